@@ -18,14 +18,18 @@ use crate::{
 };
 use std::fmt::{Debug, Display};
 use tremor_value::Value;
+pub(crate) mod avro;
 pub(crate) mod binary;
 pub(crate) mod binflux;
+pub(crate) mod cbor;
 pub(crate) mod csv;
 pub(crate) mod dogstatsd;
 pub(crate) mod influx;
 pub(crate) mod json;
+pub(crate) mod json_lines;
 pub(crate) mod msgpack;
 pub(crate) mod null;
+pub(crate) mod protobuf;
 pub(crate) mod statsd;
 pub(crate) mod string;
 pub(crate) mod syslog;
@@ -56,6 +60,14 @@ pub trait Codec: Send + Sync {
         vec![]
     }
 
+    /// Split a binary into the parts that are decoded into separate events
+    ///
+    /// Codecs doing their own framing, like `json-lines`, override this,
+    /// by default the binary is decoded as a whole.
+    fn split(&self, data: Vec<u8>) -> Vec<Vec<u8>> {
+        vec![data]
+    }
+
     /// Decode a binary, into an Value
     /// If `None` is returned, no data could be encoded, but we don't exactly triggered an error condition.
     ///
@@ -97,14 +109,18 @@ impl Debug for dyn Codec {
 ///  * if the codec doesn't exist
 pub fn resolve(config: &config::Codec) -> Result<Box<dyn Codec>> {
     match config.name.as_str() {
+        "avro" => avro::from_config(config.config.as_ref()),
         "binary" => Ok(Box::new(binary::Binary {})),
         "binflux" => Ok(Box::<binflux::BInflux>::default()),
-        "csv" => Ok(Box::new(csv::Csv {})),
+        "cbor" => Ok(Box::<cbor::Cbor>::default()),
+        "csv" => csv::from_config(config.config.as_ref()),
         "dogstatsd" => Ok(Box::<dogstatsd::DogStatsD>::default()),
         "json" => json::from_config(config.config.as_ref()),
+        "json-lines" => Ok(Box::<json_lines::JsonLines>::default()),
         "msgpack" => Ok(Box::new(msgpack::MsgPack {})),
         "influx" => Ok(Box::new(influx::Influx {})),
         "null" => Ok(Box::new(null::Null {})),
+        "protobuf" => protobuf::from_config(config.config.as_ref()),
         "statsd" => Ok(Box::<statsd::StatsD>::default()),
         "string" => Ok(Box::new(string::String {})),
         "syslog" => Ok(Box::new(syslog::Syslog::utcnow())),
@@ -121,7 +137,16 @@ mod test {
 
     #[test]
     fn lookup() {
+        // the schema is required
+        assert!(super::resolve(&"avro".into()).is_err());
         assert!(super::resolve(&"binflux".into()).is_ok());
+        assert!(super::resolve(&"cbor".into()).is_ok());
+        assert!(super::resolve(&"csv".into()).is_ok());
+        assert!(super::resolve(&NameWithConfig {
+            name: "csv".into(),
+            config: Some(literal!({"delimiter": "|", "has_header": true}))
+        })
+        .is_ok());
         assert!(super::resolve(&"dogstatsd".into()).is_ok());
         assert!(super::resolve(&"influx".into()).is_ok());
         assert!(super::resolve(&"json".into()).is_ok());
@@ -147,8 +172,11 @@ mod test {
         })
         .is_err());
         assert!(super::resolve(&"json".into()).is_ok());
+        assert!(super::resolve(&"json-lines".into()).is_ok());
         assert!(super::resolve(&"msgpack".into()).is_ok());
         assert!(super::resolve(&"null".into()).is_ok());
+        // the descriptor set is required
+        assert!(super::resolve(&"protobuf".into()).is_err());
         assert!(super::resolve(&"statsd".into()).is_ok());
         assert!(super::resolve(&"string".into()).is_ok());
         assert!(super::resolve(&"syslog".into()).is_ok());
@@ -0,0 +1,1124 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `avro` codec supports the binary encoding of [Apache Avro](https://avro.apache.org).
+//!
+//! Every event is a single datum of the configured schema, without the header of an object
+//! container file or the prefix of the single object encoding.
+//!
+//! ## Configuration
+//!
+//! | option        | description                                                   | default |
+//! |---------------|---------------------------------------------------------------|---------|
+//! | `schema`      | the schema, either inline or as a string of its JSON          |         |
+//! | `schema_file` | path to a JSON file with the schema, instead of `schema`      |         |
+//!
+//! ## Mapping
+//!
+//! Records are decoded into records keyed by the field names, maps into records, arrays into
+//! arrays, enums into the names of their symbols and `bytes` and `fixed` into binary values.
+//! Unions are decoded into the value of their branch, without naming it.
+//!
+//! `decimal`s are decoded into strings, e.g. `"12.34"`, to keep their precision. Encoding also
+//! takes integers and floats for them. The `timestamp` and `local-timestamp` types, in any
+//! precision, are decoded into nanoseconds since the epoch, like all timestamps in tremor. Other
+//! logical types are decoded as their underlying type.
+//!
+//! Encoding takes the same representation, strings for `bytes` and `fixed` as well. Missing fields
+//! of a record are encoded with their default, or as `null` if their type allows it, keys that
+//! aren't fields of the record are rejected. Values are encoded as the first branch of a union they
+//! can be encoded as, so the order of the branches matters for e.g. `["double", "long"]`.
+
+use super::prelude::*;
+use prost::encoding::{decode_varint, encode_varint};
+use simd_json::OwnedValue;
+use std::{collections::HashMap, sync::Arc};
+
+/// Nesting limit of decoded data, recursive schemas would otherwise allow overflowing the stack
+const MAX_DEPTH: usize = 128;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default = "Default::default")]
+    schema: Option<OwnedValue>,
+    #[serde(default = "Default::default")]
+    schema_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeUnit {
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl TimeUnit {
+    fn nanos(self) -> i64 {
+        match self {
+            TimeUnit::Millis => 1_000_000,
+            TimeUnit::Micros => 1_000,
+            TimeUnit::Nanos => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Decimal {
+    precision: u32,
+    scale: u32,
+}
+
+#[derive(Debug, PartialEq)]
+enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    /// a `long` with a `timestamp` or `local-timestamp` logical type
+    Timestamp(TimeUnit),
+    /// `bytes` with the `decimal` logical type
+    Decimal(Decimal),
+    Array(Box<Schema>),
+    Map(Box<Schema>),
+    Union(Vec<Schema>),
+    /// a record, enum or fixed, by its index in `Types::named`
+    Named(usize),
+}
+
+#[derive(Debug, PartialEq)]
+struct Field {
+    name: String,
+    schema: Schema,
+    default: Option<Value<'static>>,
+}
+
+#[derive(Debug, PartialEq)]
+enum NamedType {
+    Record {
+        name: String,
+        fields: Vec<Field>,
+    },
+    Enum {
+        name: String,
+        symbols: Vec<String>,
+    },
+    Fixed {
+        name: String,
+        size: usize,
+        decimal: Option<Decimal>,
+    },
+}
+
+impl NamedType {
+    fn name(&self) -> &str {
+        match self {
+            NamedType::Record { name, .. }
+            | NamedType::Enum { name, .. }
+            | NamedType::Fixed { name, .. } => name,
+        }
+    }
+}
+
+/// The configured schema with the named types it defines
+#[derive(Debug)]
+struct Types {
+    schema: Schema,
+    named: Vec<NamedType>,
+}
+
+fn invalid_schema<E: std::fmt::Display>(e: E) -> Error {
+    format!("Invalid Avro schema: {e}").into()
+}
+
+fn invalid_data<E: std::fmt::Display>(e: E) -> Error {
+    format!("Invalid Avro data: {e}").into()
+}
+
+/// Parses a schema, collecting its named types
+#[derive(Default)]
+struct Parser {
+    named: Vec<NamedType>,
+    /// index into `named` by full name
+    names: HashMap<String, usize>,
+}
+
+impl Parser {
+    fn parse(&mut self, schema: &OwnedValue, namespace: Option<&str>) -> Result<Schema> {
+        if let Some(name) = schema.as_str() {
+            return self.parse_name(name, namespace);
+        }
+        if let Some(branches) = schema.as_array() {
+            let branches = branches
+                .iter()
+                .map(|branch| self.parse(branch, namespace))
+                .collect::<Result<Vec<_>>>()?;
+            if branches.iter().any(|b| matches!(b, Schema::Union(_))) {
+                return Err(invalid_schema("unions can't contain unions"));
+            }
+            return Ok(Schema::Union(branches));
+        }
+        let ty = schema
+            .get("type")
+            .ok_or_else(|| invalid_schema(format!("missing `type` in {schema}")))?;
+        let ty_name = if let Some(ty_name) = ty.as_str() {
+            ty_name
+        } else {
+            return self.parse(ty, namespace);
+        };
+        match ty_name {
+            "record" | "error" => self.parse_record(schema, namespace),
+            "enum" => {
+                let symbols = schema
+                    .get("symbols")
+                    .and_then(OwnedValue::as_array)
+                    .and_then(|symbols| {
+                        symbols
+                            .iter()
+                            .map(|s| s.as_str().map(ToString::to_string))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .ok_or_else(|| invalid_schema("enums need an array of `symbols`"))?;
+                let name = self.full_name(schema, namespace)?;
+                self.define(NamedType::Enum { name, symbols })
+            }
+            "fixed" => {
+                let size = schema
+                    .get("size")
+                    .and_then(OwnedValue::as_usize)
+                    .ok_or_else(|| invalid_schema("fixed types need a `size`"))?;
+                let decimal = if schema.get_str("logicalType") == Some("decimal") {
+                    decimal(schema)?
+                } else {
+                    None
+                };
+                let name = self.full_name(schema, namespace)?;
+                self.define(NamedType::Fixed {
+                    name,
+                    size,
+                    decimal,
+                })
+            }
+            "array" => {
+                let items = schema
+                    .get("items")
+                    .ok_or_else(|| invalid_schema("arrays need `items`"))?;
+                Ok(Schema::Array(Box::new(self.parse(items, namespace)?)))
+            }
+            "map" => {
+                let values = schema
+                    .get("values")
+                    .ok_or_else(|| invalid_schema("maps need `values`"))?;
+                Ok(Schema::Map(Box::new(self.parse(values, namespace)?)))
+            }
+            _ => {
+                let primitive = self.parse_name(ty_name, namespace)?;
+                logical_type(primitive, schema)
+            }
+        }
+    }
+
+    fn parse_record(&mut self, schema: &OwnedValue, namespace: Option<&str>) -> Result<Schema> {
+        let name = self.full_name(schema, namespace)?;
+        // register the record before its fields, they might refer to it
+        let index = self.named.len();
+        self.define(NamedType::Record {
+            name: name.clone(),
+            fields: Vec::new(),
+        })?;
+        let namespace = name.rsplit_once('.').map(|(namespace, _)| namespace);
+        let fields = schema
+            .get("fields")
+            .and_then(OwnedValue::as_array)
+            .ok_or_else(|| invalid_schema(format!("the record `{name}` needs `fields`")))?
+            .iter()
+            .map(|field| {
+                let field_name = field
+                    .get_str("name")
+                    .ok_or_else(|| invalid_schema(format!("missing field name in `{name}`")))?;
+                let ty = field.get("type").ok_or_else(|| {
+                    invalid_schema(format!("missing type of field `{field_name}`"))
+                })?;
+                Ok(Field {
+                    name: field_name.to_string(),
+                    schema: self.parse(ty, namespace)?,
+                    default: field.get("default").cloned().map(Value::from),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(NamedType::Record { fields: f, .. }) = self.named.get_mut(index) {
+            *f = fields;
+        }
+        Ok(Schema::Named(index))
+    }
+
+    /// a primitive type or a reference to a named type
+    fn parse_name(&self, name: &str, namespace: Option<&str>) -> Result<Schema> {
+        Ok(match name {
+            "null" => Schema::Null,
+            "boolean" => Schema::Boolean,
+            "int" => Schema::Int,
+            "long" => Schema::Long,
+            "float" => Schema::Float,
+            "double" => Schema::Double,
+            "bytes" => Schema::Bytes,
+            "string" => Schema::String,
+            _ => {
+                let qualified = namespace
+                    .filter(|_| !name.contains('.'))
+                    .and_then(|namespace| self.names.get(&format!("{namespace}.{name}")));
+                let index = qualified
+                    .or_else(|| self.names.get(name))
+                    .ok_or_else(|| invalid_schema(format!("unknown type `{name}`")))?;
+                Schema::Named(*index)
+            }
+        })
+    }
+
+    /// the full name of a named type, defined within `namespace`
+    fn full_name(&self, schema: &OwnedValue, namespace: Option<&str>) -> Result<String> {
+        let name = schema
+            .get_str("name")
+            .ok_or_else(|| invalid_schema(format!("missing `name` in {schema}")))?;
+        let namespace = schema.get_str("namespace").or(namespace);
+        Ok(match namespace {
+            Some(namespace) if !name.contains('.') && !namespace.is_empty() => {
+                format!("{namespace}.{name}")
+            }
+            _ => name.to_string(),
+        })
+    }
+
+    fn define(&mut self, named: NamedType) -> Result<Schema> {
+        let index = self.named.len();
+        if self.names.insert(named.name().to_string(), index).is_some() {
+            return Err(invalid_schema(format!(
+                "`{}` is defined twice",
+                named.name()
+            )));
+        }
+        self.named.push(named);
+        Ok(Schema::Named(index))
+    }
+}
+
+/// the precision and scale of a `decimal`
+fn decimal(schema: &OwnedValue) -> Result<Option<Decimal>> {
+    let precision = schema
+        .get("precision")
+        .and_then(OwnedValue::as_u32)
+        .filter(|precision| *precision > 0);
+    let scale = schema.get("scale").map_or(Some(0), OwnedValue::as_u32);
+    match (precision, scale) {
+        (Some(precision), _) if precision > 38 => Err(invalid_schema(
+            "decimals with a precision above 38 are not supported",
+        )),
+        (Some(precision), Some(scale)) if scale <= precision => {
+            Ok(Some(Decimal { precision, scale }))
+        }
+        // invalid logical types are ignored
+        _ => Ok(None),
+    }
+}
+
+/// the logical type of a primitive, unknown or invalid ones are ignored
+fn logical_type(primitive: Schema, schema: &OwnedValue) -> Result<Schema> {
+    Ok(match (primitive, schema.get_str("logicalType")) {
+        (Schema::Long, Some("timestamp-millis" | "local-timestamp-millis")) => {
+            Schema::Timestamp(TimeUnit::Millis)
+        }
+        (Schema::Long, Some("timestamp-micros" | "local-timestamp-micros")) => {
+            Schema::Timestamp(TimeUnit::Micros)
+        }
+        (Schema::Long, Some("timestamp-nanos" | "local-timestamp-nanos")) => {
+            Schema::Timestamp(TimeUnit::Nanos)
+        }
+        (Schema::Bytes, Some("decimal")) => decimal(schema)?.map_or(Schema::Bytes, Schema::Decimal),
+        (primitive, _) => primitive,
+    })
+}
+
+// zigzag encoding of longs
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn read_long(data: &mut &[u8]) -> Result<i64> {
+    let n = decode_varint(data).map_err(invalid_data)?;
+    Ok((n >> 1) as i64 ^ -((n & 1) as i64))
+}
+
+#[allow(clippy::cast_sign_loss)]
+fn write_long(n: i64, buf: &mut Vec<u8>) {
+    encode_varint(((n << 1) ^ (n >> 63)) as u64, buf);
+}
+
+fn read_fixed<'data>(data: &mut &'data [u8], len: usize) -> Result<&'data [u8]> {
+    if data.len() < len {
+        return Err(invalid_data("unexpected end of data"));
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes)
+}
+
+fn read_bytes<'data>(data: &mut &'data [u8]) -> Result<&'data [u8]> {
+    let len = usize::try_from(read_long(data)?).map_err(invalid_data)?;
+    read_fixed(data, len)
+}
+
+fn write_bytes(bytes: &[u8], buf: &mut Vec<u8>) {
+    write_long(i64::try_from(bytes.len()).unwrap_or(i64::MAX), buf);
+    buf.extend_from_slice(bytes);
+}
+
+/// the number of items in the next block of an array or map, `0` for the last block
+fn read_block(data: &mut &[u8], items: &Schema) -> Result<usize> {
+    let count = read_long(data)?;
+    if count < 0 {
+        // followed by the size of the block in bytes
+        read_long(data)?;
+    }
+    let count = usize::try_from(count.unsigned_abs()).map_err(invalid_data)?;
+    // every item but `null` takes at least a byte, don't loop for a bogus count
+    if count > data.len() && *items != Schema::Null {
+        return Err(invalid_data("block count exceeds the data"));
+    }
+    Ok(count)
+}
+
+/// `unscaled` as a decimal number with `scale` fractional digits
+fn decimal_to_string(unscaled: i128, scale: u32) -> String {
+    let scale = scale as usize;
+    let mut digits = unscaled.unsigned_abs().to_string();
+    if digits.len() <= scale {
+        digits.insert_str(0, &"0".repeat(scale + 1 - digits.len()));
+    }
+    if scale > 0 {
+        digits.insert(digits.len() - scale, '.');
+    }
+    if unscaled < 0 {
+        digits.insert(0, '-');
+    }
+    digits
+}
+
+/// the unscaled value of a decimal, from two's complement big endian bytes
+fn decimal_from_bytes(bytes: &[u8]) -> Result<i128> {
+    let sign = match bytes.first() {
+        Some(first) if first & 0x80 != 0 => 0xff,
+        Some(_) => 0x00,
+        None => return Ok(0),
+    };
+    let mut buf = [sign; 16];
+    let significant = if bytes.len() > 16 {
+        let (extension, significant) = bytes.split_at(bytes.len() - 16);
+        if extension.iter().any(|b| *b != sign) || (significant[0] ^ sign) & 0x80 != 0 {
+            return Err(invalid_data("decimal out of range"));
+        }
+        significant
+    } else {
+        bytes
+    };
+    buf[16 - significant.len()..].copy_from_slice(significant);
+    Ok(i128::from_be_bytes(buf))
+}
+
+/// the shortest two's complement big endian bytes of `unscaled`, or sign extended to `size`
+fn decimal_to_bytes(unscaled: i128, size: Option<usize>) -> Result<Vec<u8>> {
+    let bytes = unscaled.to_be_bytes();
+    let sign = if unscaled < 0 { 0xff } else { 0x00 };
+    let mut start = 0;
+    while start < 15 && bytes[start] == sign && (bytes[start + 1] ^ sign) & 0x80 == 0 {
+        start += 1;
+    }
+    let significant = &bytes[start..];
+    match size {
+        None => Ok(significant.to_vec()),
+        Some(size) if significant.len() <= size => {
+            let mut res = vec![sign; size - significant.len()];
+            res.extend_from_slice(significant);
+            Ok(res)
+        }
+        Some(size) => Err(format!("Decimal {unscaled} doesn't fit into {size} bytes").into()),
+    }
+}
+
+/// the unscaled value of a decimal given as string, integer or float
+fn decimal_from_value(value: &Value, decimal: Decimal) -> Result<i128> {
+    let invalid = || -> Error { format!("Invalid Avro decimal: {value}").into() };
+    let factor = 10_i128.pow(decimal.scale);
+    let unscaled = if let Some(n) = value.as_i64() {
+        i128::from(n).checked_mul(factor).ok_or_else(invalid)?
+    } else if let Some(n) = value.as_u64() {
+        i128::from(n).checked_mul(factor).ok_or_else(invalid)?
+    } else {
+        let formatted;
+        let s = if let Some(s) = value.as_str() {
+            s
+        } else {
+            let f = value
+                .as_f64()
+                .filter(|f| f.is_finite())
+                .ok_or_else(invalid)?;
+            formatted = f.to_string();
+            &formatted
+        };
+        let (negative, s) = s
+            .strip_prefix('-')
+            .map_or_else(|| (false, s.strip_prefix('+').unwrap_or(s)), |s| (true, s));
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if (int.is_empty() && frac.is_empty())
+            || !all_digits(int)
+            || !all_digits(frac)
+            || frac.len() > decimal.scale as usize
+        {
+            return Err(invalid());
+        }
+        let padding = "0".repeat(decimal.scale as usize - frac.len());
+        let unscaled = format!("{int}{frac}{padding}")
+            .parse::<i128>()
+            .map_err(|_| invalid())?;
+        if negative {
+            -unscaled
+        } else {
+            unscaled
+        }
+    };
+    if unscaled.unsigned_abs() >= 10_u128.pow(decimal.precision) {
+        return Err(format!(
+            "Avro decimal {value} exceeds the precision of {}",
+            decimal.precision
+        )
+        .into());
+    }
+    Ok(unscaled)
+}
+
+impl Types {
+    fn new(schema: &OwnedValue) -> Result<Self> {
+        let mut parser = Parser::default();
+        let schema = parser.parse(schema, None)?;
+        Ok(Self {
+            schema,
+            named: parser.named,
+        })
+    }
+
+    fn named(&self, index: usize) -> Result<&NamedType> {
+        self.named
+            .get(index)
+            .ok_or_else(|| format!("Unknown Avro type {index}").into())
+    }
+
+    fn decode(&self, schema: &Schema, data: &mut &[u8], depth: usize) -> Result<Value<'static>> {
+        if depth > MAX_DEPTH {
+            return Err(invalid_data("nested too deeply"));
+        }
+        Ok(match schema {
+            Schema::Null => Value::null(),
+            Schema::Boolean => match read_fixed(data, 1)? {
+                [0] => Value::from(false),
+                [1] => Value::from(true),
+                _ => return Err(invalid_data("invalid boolean")),
+            },
+            Schema::Int => Value::from(i32::try_from(read_long(data)?).map_err(invalid_data)?),
+            Schema::Long => Value::from(read_long(data)?),
+            Schema::Float => {
+                let mut bytes = [0; 4];
+                bytes.copy_from_slice(read_fixed(data, 4)?);
+                Value::from(f64::from(f32::from_le_bytes(bytes)))
+            }
+            Schema::Double => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(read_fixed(data, 8)?);
+                Value::from(f64::from_le_bytes(bytes))
+            }
+            Schema::Bytes => Value::Bytes(read_bytes(data)?.to_vec().into()),
+            Schema::String => Value::from(std::str::from_utf8(read_bytes(data)?)?.to_string()),
+            Schema::Timestamp(unit) => Value::from(
+                read_long(data)?
+                    .checked_mul(unit.nanos())
+                    .ok_or_else(|| invalid_data("timestamp out of range"))?,
+            ),
+            Schema::Decimal(decimal) => Value::from(decimal_to_string(
+                decimal_from_bytes(read_bytes(data)?)?,
+                decimal.scale,
+            )),
+            Schema::Array(items) => {
+                let mut array = Vec::new();
+                loop {
+                    let count = read_block(data, items)?;
+                    if count == 0 {
+                        break;
+                    }
+                    for _ in 0..count {
+                        array.push(self.decode(items, data, depth + 1)?);
+                    }
+                }
+                Value::from(array)
+            }
+            Schema::Map(values) => {
+                let mut map = Object::new();
+                loop {
+                    let count = read_block(data, values)?;
+                    if count == 0 {
+                        break;
+                    }
+                    for _ in 0..count {
+                        let key = std::str::from_utf8(read_bytes(data)?)?.to_string();
+                        map.insert(key.into(), self.decode(values, data, depth + 1)?);
+                    }
+                }
+                Value::from(map)
+            }
+            Schema::Union(branches) => {
+                let branch = usize::try_from(read_long(data)?)
+                    .ok()
+                    .and_then(|index| branches.get(index))
+                    .ok_or_else(|| invalid_data("unknown union branch"))?;
+                self.decode(branch, data, depth + 1)?
+            }
+            Schema::Named(index) => match self.named(*index)? {
+                NamedType::Record { fields, .. } => {
+                    let mut record = Object::with_capacity(fields.len());
+                    for field in fields {
+                        let value = self.decode(&field.schema, data, depth + 1)?;
+                        record.insert(field.name.clone().into(), value);
+                    }
+                    Value::from(record)
+                }
+                NamedType::Enum { symbols, .. } => usize::try_from(read_long(data)?)
+                    .ok()
+                    .and_then(|index| symbols.get(index))
+                    .map(|symbol| Value::from(symbol.clone()))
+                    .ok_or_else(|| invalid_data("unknown enum symbol"))?,
+                NamedType::Fixed {
+                    size,
+                    decimal: Some(decimal),
+                    ..
+                } => Value::from(decimal_to_string(
+                    decimal_from_bytes(read_fixed(data, *size)?)?,
+                    decimal.scale,
+                )),
+                NamedType::Fixed { size, .. } => {
+                    Value::Bytes(read_fixed(data, *size)?.to_vec().into())
+                }
+            },
+        })
+    }
+
+    // floats are stored with single precision
+    #[allow(clippy::cast_possible_truncation)]
+    fn encode(&self, schema: &Schema, value: &Value, buf: &mut Vec<u8>) -> Result<()> {
+        let invalid = |expected: &str| -> Error {
+            format!("Invalid Avro value, expected {expected}: {value}").into()
+        };
+        let bytes = || match value {
+            Value::Bytes(b) => Ok(&**b),
+            _ => value
+                .as_str()
+                .map(str::as_bytes)
+                .ok_or_else(|| invalid("bytes or a string")),
+        };
+        let long = || value.as_i64().ok_or_else(|| invalid("a 64 bit integer"));
+        let float = || value.cast_f64().ok_or_else(|| invalid("a float"));
+        match schema {
+            Schema::Null if value.is_null() => (),
+            Schema::Null => return Err(invalid("null")),
+            Schema::Boolean => {
+                let b = value.as_bool().ok_or_else(|| invalid("a boolean"))?;
+                buf.push(u8::from(b));
+            }
+            Schema::Int => {
+                let n = value.as_i32().ok_or_else(|| invalid("a 32 bit integer"))?;
+                write_long(i64::from(n), buf);
+            }
+            Schema::Long => write_long(long()?, buf),
+            Schema::Float => buf.extend_from_slice(&(float()? as f32).to_le_bytes()),
+            Schema::Double => buf.extend_from_slice(&float()?.to_le_bytes()),
+            Schema::Bytes => write_bytes(bytes()?, buf),
+            Schema::String => {
+                let s = value.as_str().ok_or_else(|| invalid("a string"))?;
+                write_bytes(s.as_bytes(), buf);
+            }
+            Schema::Timestamp(unit) => write_long(long()?.div_euclid(unit.nanos()), buf),
+            Schema::Decimal(decimal) => {
+                let unscaled = decimal_from_value(value, *decimal)?;
+                write_bytes(&decimal_to_bytes(unscaled, None)?, buf);
+            }
+            Schema::Array(items) => {
+                let array = value.as_array().ok_or_else(|| invalid("an array"))?;
+                if !array.is_empty() {
+                    write_long(i64::try_from(array.len())?, buf);
+                    for item in array {
+                        self.encode(items, item, buf)?;
+                    }
+                }
+                write_long(0, buf);
+            }
+            Schema::Map(values) => {
+                let map = value.as_object().ok_or_else(|| invalid("a record"))?;
+                if !map.is_empty() {
+                    write_long(i64::try_from(map.len())?, buf);
+                    for (key, value) in map {
+                        write_bytes(key.as_bytes(), buf);
+                        self.encode(values, value, buf)?;
+                    }
+                }
+                write_long(0, buf);
+            }
+            Schema::Union(branches) => {
+                for (index, branch) in branches.iter().enumerate() {
+                    let mut encoded = Vec::new();
+                    write_long(i64::try_from(index)?, &mut encoded);
+                    if self.encode(branch, value, &mut encoded).is_ok() {
+                        buf.extend_from_slice(&encoded);
+                        return Ok(());
+                    }
+                }
+                return Err(invalid("a value of one of the union branches"));
+            }
+            Schema::Named(index) => self.encode_named(self.named(*index)?, value, buf)?,
+        }
+        Ok(())
+    }
+
+    fn encode_named(&self, named: &NamedType, value: &Value, buf: &mut Vec<u8>) -> Result<()> {
+        match named {
+            NamedType::Record { name, fields } => {
+                let record = value.as_object().ok_or_else(|| {
+                    Error::from(format!(
+                        "The Avro record `{name}` must be a record: {value}"
+                    ))
+                })?;
+                for key in record.keys() {
+                    let key: &str = key;
+                    if !fields.iter().any(|f| f.name == key) {
+                        return Err(format!("Unknown field `{key}` of Avro record `{name}`").into());
+                    }
+                }
+                for field in fields {
+                    let res = match (record.get(field.name.as_str()), field.default.as_ref()) {
+                        (Some(value), _) | (None, Some(value)) => {
+                            self.encode(&field.schema, value, buf)
+                        }
+                        (None, None) => self.encode(&field.schema, &Value::null(), buf),
+                    };
+                    res.map_err(|e| {
+                        Error::from(format!("Invalid field `{}` of `{name}`: {e}", field.name))
+                    })?;
+                }
+            }
+            NamedType::Enum { name, symbols } => {
+                let index = value
+                    .as_str()
+                    .and_then(|symbol| symbols.iter().position(|s| s == symbol))
+                    .ok_or_else(|| {
+                        Error::from(format!("Invalid symbol of Avro enum `{name}`: {value}"))
+                    })?;
+                write_long(i64::try_from(index)?, buf);
+            }
+            NamedType::Fixed {
+                size,
+                decimal: Some(decimal),
+                ..
+            } => {
+                let unscaled = decimal_from_value(value, *decimal)?;
+                buf.extend_from_slice(&decimal_to_bytes(unscaled, Some(*size))?);
+            }
+            NamedType::Fixed { name, size, .. } => {
+                let bytes = match value {
+                    Value::Bytes(b) => Some(&**b),
+                    _ => value.as_str().map(str::as_bytes),
+                }
+                .filter(|bytes| bytes.len() == *size)
+                .ok_or_else(|| {
+                    Error::from(format!(
+                        "Invalid value of Avro fixed `{name}`, expected {size} bytes: {value}"
+                    ))
+                })?;
+                buf.extend_from_slice(bytes);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Avro {
+    /// shared, so cloning the codec for every connector stream is cheap
+    types: Arc<Types>,
+}
+
+pub(crate) fn from_config(config: Option<&Value>) -> Result<Box<dyn Codec>> {
+    let invalid = |msg: String| -> Error {
+        ErrorKind::InvalidConfiguration("avro codec".to_string(), msg).into()
+    };
+    let config = config.ok_or_else(|| invalid("Missing configuration".to_string()))?;
+    let config: Config =
+        tremor_value::structurize(config.clone()).map_err(|e| invalid(e.to_string()))?;
+    let schema = match (config.schema, config.schema_file) {
+        // the JSON of a schema, as copied from an `.avsc` file
+        (Some(OwnedValue::String(json)), None)
+            if json.trim_start().starts_with(['{', '[', '"']) =>
+        {
+            let mut json = json.into_bytes();
+            simd_json::to_owned_value(&mut json)
+                .map_err(|e| invalid(format!("Invalid schema: {e}")))?
+        }
+        (Some(schema), None) => schema,
+        (None, Some(schema_file)) => {
+            let mut json = std::fs::read(&schema_file)
+                .map_err(|e| invalid(format!("Unable to read schema file {schema_file}: {e}")))?;
+            simd_json::to_owned_value(&mut json)
+                .map_err(|e| invalid(format!("Invalid schema file {schema_file}: {e}")))?
+        }
+        _ => {
+            return Err(invalid(
+                "Exactly one of `schema` and `schema_file` is required".to_string(),
+            ))
+        }
+    };
+    let types = Types::new(&schema).map_err(|e| invalid(e.to_string()))?;
+    Ok(Box::new(Avro {
+        types: Arc::new(types),
+    }))
+}
+
+impl Codec for Avro {
+    fn name(&self) -> &str {
+        "avro"
+    }
+
+    fn mime_types(&self) -> Vec<&'static str> {
+        vec!["avro/binary", "application/avro"]
+    }
+
+    fn decode<'input>(
+        &mut self,
+        data: &'input mut [u8],
+        _ingest_ns: u64,
+    ) -> Result<Option<Value<'input>>> {
+        let mut data: &[u8] = data;
+        let value = self.types.decode(&self.types.schema, &mut data, 0)?;
+        if !data.is_empty() {
+            return Err(invalid_data(format!("{} trailing bytes", data.len())));
+        }
+        Ok(Some(value))
+    }
+
+    fn encode(&mut self, data: &Value) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.types.encode(&self.types.schema, data, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Codec> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::NamedTempFile;
+    use tremor_value::literal;
+
+    fn schema() -> Value<'static> {
+        literal!({
+            "type": "record",
+            "name": "Badger",
+            "namespace": "snot",
+            "fields": [
+                {"name": "id", "type": "long"},
+                {"name": "name", "type": ["null", "string"]},
+                {"name": "scores", "type": {"type": "array", "items": "int"}},
+                {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["HONEY", "EURASIAN"]}},
+                {"name": "tags", "type": {"type": "map", "values": "string"}},
+                {"name": "hash", "type": {"type": "fixed", "name": "Hash", "size": 2}},
+                {
+                    "name": "price",
+                    "type": {"type": "bytes", "logicalType": "decimal", "precision": 6, "scale": 2}
+                },
+                {"name": "seen", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+                {"name": "weight", "type": "double"},
+                {
+                    "name": "home",
+                    "type": ["null", {
+                        "type": "record",
+                        "name": "Burrow",
+                        "fields": [
+                            {"name": "depth", "type": "int"},
+                            {"name": "next", "type": ["null", "Burrow"]}
+                        ]
+                    }],
+                    "default": null
+                },
+                {"name": "active", "type": "boolean", "default": true}
+            ]
+        })
+    }
+
+    fn avro(schema: Value<'static>) -> Result<Box<dyn Codec>> {
+        from_config(Some(&literal!({ "schema": schema })))
+    }
+
+    /// the encoding of `badger()`
+    const BADGER: &[u8] = &[
+        0xac, 0x02, // id: 150
+        0x02, 0x08, b's', b'n', b'o', b't', // name: branch 1, "snot"
+        0x06, 0x02, 0x01, 0xd8, 0x04, 0x00, // scores: a block of 3, [1, -1, 300]
+        0x02, // kind: EURASIAN
+        0x02, 0x02, b'a', 0x02, b'b', 0x00, // tags: a block of 1, {"a": "b"}
+        0xbe, 0xef, // hash
+        0x04, 0x04, 0xd2, // price: 1234 unscaled
+        0x80, 0x80, 0xf4, 0xf6, 0x90, 0x5d, // seen: 1_600_000_000_000 ms
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf8, 0x3f, // weight: 1.5
+        0x02, 0x06, 0x02, 0x01, 0x00, // home: {depth: 3, next: {depth: -1, next: null}}
+        0x01, // active: true
+    ];
+
+    fn badger() -> Result<Value<'static>> {
+        let mut badger = literal!({
+            "id": 150,
+            "name": "snot",
+            "scores": [1, -1, 300],
+            "kind": "EURASIAN",
+            "tags": {"a": "b"},
+            "price": "12.34",
+            "seen": 1_600_000_000_000_000_000_i64,
+            "weight": 1.5,
+            "home": {"depth": 3, "next": {"depth": -1, "next": null}},
+            "active": true
+        });
+        badger.insert("hash", Value::Bytes(vec![0xbe_u8, 0xef].into()))?;
+        Ok(badger)
+    }
+
+    #[test]
+    fn decode_encode() -> Result<()> {
+        let mut codec = avro(schema())?;
+        let mut data = BADGER.to_vec();
+        assert_eq!(Some(badger()?), codec.decode(&mut data, 0)?);
+        assert_eq!(BADGER, codec.encode(&badger()?)?.as_slice());
+
+        // missing fields are encoded with their defaults or as null
+        let mut badger = badger()?;
+        badger.remove("active")?;
+        badger.remove("home")?;
+        badger.remove("name")?;
+        let mut data = codec.encode(&badger)?;
+        let decoded = codec.decode(&mut data, 0)?.ok_or("no value")?;
+        assert_eq!(Some(true), decoded.get_bool("active"));
+        assert_eq!(Some(&Value::null()), decoded.get("home"));
+        assert_eq!(Some(&Value::null()), decoded.get("name"));
+        Ok(())
+    }
+
+    #[test]
+    fn unions() -> Result<()> {
+        let mut codec = avro(literal!(["null", "long", "double", "string", {
+            "type": "array", "items": ["string", "bytes"]
+        }]))?;
+        let values = [
+            (literal!(null), vec![0x00]),
+            (literal!(-1), vec![0x02, 0x01]),
+            (literal!(1.5), vec![0x04, 0, 0, 0, 0, 0, 0, 0xf8, 0x3f]),
+            (literal!("a"), vec![0x06, 0x02, b'a']),
+            (
+                Value::from(vec![Value::from("a"), Value::Bytes(vec![1_u8].into())]),
+                vec![0x08, 0x04, 0x00, 0x02, b'a', 0x02, 0x02, 0x01, 0x00],
+            ),
+        ];
+        for (value, encoded) in values {
+            assert_eq!(encoded, codec.encode(&value)?);
+            let mut data = encoded;
+            assert_eq!(Some(value), codec.decode(&mut data, 0)?);
+        }
+        assert!(codec.encode(&literal!(true)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn logical_types() -> Result<()> {
+        let mut codec = avro(literal!({
+            "type": "fixed",
+            "name": "Price",
+            "size": 3,
+            "logicalType": "decimal",
+            "precision": 5,
+            "scale": 3
+        }))?;
+        assert_eq!(vec![0xff, 0xfe, 0x0c], codec.encode(&literal!("-0.5"))?);
+        assert_eq!(vec![0xff, 0xfe, 0x0c], codec.encode(&literal!(-0.5))?);
+        assert_eq!(vec![0x00, 0x07, 0xd0], codec.encode(&literal!(2))?);
+        let mut data = vec![0xff, 0xfe, 0x0c];
+        assert_eq!(Some(literal!("-0.500")), codec.decode(&mut data, 0)?);
+        // precision and scale are enforced
+        assert!(codec.encode(&literal!("100")).is_err());
+        assert!(codec.encode(&literal!("0.0001")).is_err());
+        assert!(codec.encode(&literal!("1.2.3")).is_err());
+        assert!(codec.encode(&literal!("")).is_err());
+
+        let mut codec = avro(literal!({
+            "type": "bytes", "logicalType": "decimal", "precision": 38, "scale": 0
+        }))?;
+        let max = "99999999999999999999999999999999999999";
+        let mut data = codec.encode(&Value::from(max))?;
+        assert_eq!(Some(Value::from(max)), codec.decode(&mut data, 0)?);
+        let mut data = codec.encode(&literal!("-128"))?;
+        assert_eq!(vec![0x02, 0x80], data);
+        assert_eq!(Some(literal!("-128")), codec.decode(&mut data, 0)?);
+
+        let mut codec = avro(literal!({"type": "long", "logicalType": "timestamp-micros"}))?;
+        assert_eq!(vec![0x03], codec.encode(&literal!(-1500))?);
+        assert_eq!(Some(literal!(-2000)), codec.decode(&mut [0x03], 0)?);
+
+        // unknown or invalid logical types are ignored
+        let mut codec = avro(literal!({"type": "int", "logicalType": "date"}))?;
+        assert_eq!(Some(literal!(1)), codec.decode(&mut [0x02], 0)?);
+        let mut codec = avro(literal!({"type": "bytes", "logicalType": "decimal"}))?;
+        assert_eq!(Some(literal!("a")), codec.decode(&mut [0x02, b'a'], 0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_blocks() -> Result<()> {
+        let mut codec = avro(literal!({"type": "array", "items": "long"}))?;
+        // a block with its size in bytes, followed by a regular one
+        let mut data = vec![0x03, 0x04, 0x02, 0x04, 0x02, 0x06, 0x00];
+        assert_eq!(Some(literal!([1, 2, 3])), codec.decode(&mut data, 0)?);
+        assert_eq!(Some(literal!([])), codec.decode(&mut [0x00], 0)?);
+        // more items than bytes
+        assert!(codec.decode(&mut [0x7e, 0x02], 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_data() -> Result<()> {
+        let mut codec = avro(schema())?;
+        // truncated and trailing data
+        assert!(codec.decode(&mut BADGER[..20].to_vec(), 0).is_err());
+        let mut data = BADGER.to_vec();
+        data.push(0);
+        assert!(codec.decode(&mut data, 0).is_err());
+        // unknown union branch
+        let mut data = BADGER.to_vec();
+        data[2] = 0x04;
+        assert!(codec.decode(&mut data, 0).is_err());
+        // unknown enum symbol
+        let mut data = BADGER.to_vec();
+        data[14] = 0x04;
+        assert!(codec.decode(&mut data, 0).is_err());
+        // invalid boolean
+        let mut data = BADGER.to_vec();
+        data[45] = 0x02;
+        assert!(codec.decode(&mut data, 0).is_err());
+
+        // recursion is limited
+        let mut codec = avro(literal!({
+            "type": "record",
+            "name": "List",
+            "fields": [{"name": "next", "type": ["null", "List"]}]
+        }))?;
+        let mut data = vec![0x02; 1000];
+        data.push(0x00);
+        assert!(codec.decode(&mut data, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_values() -> Result<()> {
+        let mut codec = avro(schema())?;
+        let with = |key: &str, value: Value<'static>| -> Result<Value<'static>> {
+            let mut badger = badger()?;
+            badger.insert(key.to_string(), value)?;
+            Ok(badger)
+        };
+        assert!(codec.encode(&literal!("snot")).is_err());
+        assert!(codec.encode(&with("snot", literal!(1))?).is_err());
+        assert!(codec.encode(&with("id", literal!("1"))?).is_err());
+        assert!(codec
+            .encode(&with("scores", literal!([1, 2_147_483_648_u64]))?)
+            .is_err());
+        assert!(codec.encode(&with("kind", literal!("BADGER"))?).is_err());
+        assert!(codec.encode(&with("hash", literal!("abc"))?).is_err());
+        assert!(codec.encode(&with("tags", literal!({"a": 1}))?).is_err());
+        assert!(codec.encode(&with("home", literal!({"depth": 1}))?).is_ok());
+        assert!(codec
+            .encode(&with("home", literal!({ "next": null }))?)
+            .is_err());
+        let mut badger = badger()?;
+        badger.remove("id")?;
+        assert!(codec.encode(&badger).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn config() -> Result<()> {
+        assert!(from_config(None).is_err());
+        assert!(from_config(Some(&literal!({}))).is_err());
+        // the JSON of a schema
+        let mut codec = avro(Value::from(r#"{"type": "map", "values": "int"}"#))?;
+        assert_eq!(
+            vec![0x02, 0x02, b'a', 0x02, 0x00],
+            codec.encode(&literal!({"a": 1}))?
+        );
+
+        let file = NamedTempFile::new()?;
+        std::fs::write(file.path(), schema().encode())?;
+        let config = literal!({"schema_file": file.path().display().to_string()});
+        let mut codec = from_config(Some(&config))?;
+        assert_eq!(BADGER, codec.encode(&badger()?)?.as_slice());
+        let config = literal!({
+            "schema_file": file.path().display().to_string(),
+            "schema": "string"
+        });
+        assert!(from_config(Some(&config)).is_err());
+        assert!(from_config(Some(&literal!({"schema_file": "/does/not/exist.avsc"}))).is_err());
+
+        let invalid = [
+            literal!("snot"),
+            literal!({"type": "record", "name": "a", "fields": [{"name": "b", "type": "c"}]}),
+            literal!({"type": "record", "fields": []}),
+            literal!({"type": "enum", "name": "a"}),
+            literal!({"type": "fixed", "name": "a"}),
+            literal!({"type": "array"}),
+            literal!(["null", ["null", "int"]]),
+            literal!([
+                {"type": "fixed", "name": "a", "size": 1},
+                {"type": "enum", "name": "a", "symbols": []}
+            ]),
+            literal!({"type": "bytes", "logicalType": "decimal", "precision": 39}),
+        ];
+        for schema in invalid {
+            assert!(avro(schema.clone()).is_err(), "{schema} should be rejected");
+        }
+        // names are resolved within the namespace of their definition
+        let codec = avro(literal!({
+            "type": "record",
+            "name": "a.A",
+            "fields": [
+                {"name": "b", "type": {"type": "fixed", "name": "B", "size": 1}},
+                {"name": "c", "type": "a.B"},
+                {"name": "d", "type": "B"}
+            ]
+        }));
+        assert!(codec.is_ok());
+        Ok(())
+    }
+}
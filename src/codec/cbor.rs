@@ -0,0 +1,327 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `cbor` codec supports the Concise Binary Object Representation format.
+//!
+//! Specification: [RFC 8949](https://www.rfc-editor.org/rfc/rfc8949).
+//!
+//! Deserialization supports definite and indefinite length items. Byte strings are decoded as binary values.
+//! Tags are dropped and only the tagged item is kept, except for bignums (tags 2 and 3) fitting into 64 bits, those
+//! are decoded as integers. Map keys need to be text strings, integers, floats or booleans, non text keys are turned
+//! into their textual representation, e.g. `1` becomes `"1"`.
+//!
+//! Serialization emits definite length items only, binary values are encoded as byte strings.
+
+use super::prelude::*;
+use ciborium::value::{Integer, Value as CborValue};
+use simd_json::StaticNode;
+
+/// CBOR tag of a positive bignum
+const POSITIVE_BIGNUM: u64 = 2;
+/// CBOR tag of a negative bignum
+const NEGATIVE_BIGNUM: u64 = 3;
+
+#[derive(Clone, Default)]
+pub struct Cbor {}
+
+/// A CBOR integer as tremor value, as signed if possible, as unsigned otherwise
+fn integer(i: Integer) -> Result<Value<'static>> {
+    let i = i128::from(i);
+    if let Ok(i) = i64::try_from(i) {
+        Ok(Value::from(i))
+    } else if let Ok(u) = u64::try_from(i) {
+        Ok(Value::from(u))
+    } else {
+        Err(format!("CBOR integer {i} exceeds the 64 bit range").into())
+    }
+}
+
+/// A bignum that fits into 64 bits, `None` if it is too large
+fn bignum(tag: u64, bytes: &[u8]) -> Option<Value<'static>> {
+    let significant = bytes.iter().skip_while(|b| **b == 0).count();
+    if significant > 8 {
+        return None;
+    }
+    let n = bytes.iter().fold(0_u64, |n, b| (n << 8) | u64::from(*b));
+    if tag == POSITIVE_BIGNUM {
+        integer(Integer::from(n)).ok()
+    } else {
+        // negative bignums encode `-1 - n`
+        integer(Integer::try_from(-1 - i128::from(n)).ok()?).ok()
+    }
+}
+
+fn map_key(key: CborValue) -> Result<String> {
+    match key {
+        CborValue::Text(s) => Ok(s),
+        CborValue::Integer(i) => Ok(i128::from(i).to_string()),
+        CborValue::Float(f) => Ok(f.to_string()),
+        CborValue::Bool(b) => Ok(b.to_string()),
+        CborValue::Tag(_, key) => map_key(*key),
+        other => Err(format!("Unsupported CBOR map key: {other:?}").into()),
+    }
+}
+
+fn decode_value(cbor: CborValue) -> Result<Value<'static>> {
+    Ok(match cbor {
+        CborValue::Integer(i) => integer(i)?,
+        CborValue::Bytes(b) => Value::Bytes(b.into()),
+        CborValue::Float(f) => Value::from(f),
+        CborValue::Text(s) => Value::from(s),
+        CborValue::Bool(b) => Value::from(b),
+        CborValue::Null => Value::null(),
+        CborValue::Tag(tag @ (POSITIVE_BIGNUM | NEGATIVE_BIGNUM), inner) => match *inner {
+            CborValue::Bytes(b) => bignum(tag, &b).unwrap_or_else(|| Value::Bytes(b.into())),
+            inner => decode_value(inner)?,
+        },
+        CborValue::Tag(_, inner) => decode_value(*inner)?,
+        CborValue::Array(a) => {
+            Value::Array(a.into_iter().map(decode_value).collect::<Result<_>>()?)
+        }
+        CborValue::Map(m) => {
+            let mut o = Object::with_capacity(m.len());
+            for (k, v) in m {
+                o.insert(map_key(k)?.into(), decode_value(v)?);
+            }
+            Value::from(o)
+        }
+        other => return Err(format!("Unsupported CBOR value: {other:?}").into()),
+    })
+}
+
+/// A 128 bit integer as CBOR integer, as bignum if it exceeds the CBOR integer range
+#[cfg(feature = "128bit")]
+fn encode_i128(i: i128) -> CborValue {
+    Integer::try_from(i).map_or_else(
+        |_| {
+            let (tag, n) = if i < 0 {
+                // negative bignums encode `-1 - n`
+                (NEGATIVE_BIGNUM, (-1 - i).unsigned_abs())
+            } else {
+                (POSITIVE_BIGNUM, i.unsigned_abs())
+            };
+            encode_bignum(tag, n)
+        },
+        CborValue::Integer,
+    )
+}
+
+/// An unsigned 128 bit integer as CBOR integer, as positive bignum if it exceeds the CBOR integer range
+#[cfg(feature = "128bit")]
+fn encode_u128(u: u128) -> CborValue {
+    Integer::try_from(u).map_or_else(|_| encode_bignum(POSITIVE_BIGNUM, u), CborValue::Integer)
+}
+
+#[cfg(feature = "128bit")]
+fn encode_bignum(tag: u64, n: u128) -> CborValue {
+    let bytes = n.to_be_bytes();
+    let significant = bytes.iter().skip_while(|b| **b == 0).copied().collect();
+    CborValue::Tag(tag, Box::new(CborValue::Bytes(significant)))
+}
+
+fn encode_value(value: &Value) -> CborValue {
+    match value {
+        Value::Static(StaticNode::Null) => CborValue::Null,
+        Value::Static(StaticNode::Bool(b)) => CborValue::Bool(*b),
+        Value::Static(StaticNode::I64(i)) => CborValue::Integer(Integer::from(*i)),
+        Value::Static(StaticNode::U64(u)) => CborValue::Integer(Integer::from(*u)),
+        #[cfg(feature = "128bit")]
+        Value::Static(StaticNode::I128(i)) => encode_i128(*i),
+        #[cfg(feature = "128bit")]
+        Value::Static(StaticNode::U128(u)) => encode_u128(*u),
+        Value::Static(StaticNode::F64(f)) => CborValue::Float(*f),
+        Value::String(s) => CborValue::Text(s.to_string()),
+        Value::Array(a) => CborValue::Array(a.iter().map(encode_value).collect()),
+        Value::Object(o) => CborValue::Map(
+            o.iter()
+                .map(|(k, v)| (CborValue::Text(k.to_string()), encode_value(v)))
+                .collect(),
+        ),
+        Value::Bytes(b) => CborValue::Bytes(b.to_vec()),
+    }
+}
+
+impl Codec for Cbor {
+    fn name(&self) -> &str {
+        "cbor"
+    }
+
+    fn mime_types(&self) -> Vec<&'static str> {
+        vec!["application/cbor"]
+    }
+
+    fn decode<'input>(
+        &mut self,
+        data: &'input mut [u8],
+        _ingest_ns: u64,
+    ) -> Result<Option<Value<'input>>> {
+        let cbor: CborValue = ciborium::de::from_reader(&data[..])
+            .map_err(|e| Error::from(format!("Invalid CBOR: {e:?}")))?;
+        decode_value(cbor).map(Some)
+    }
+
+    fn encode(&mut self, data: &Value) -> Result<Vec<u8>> {
+        let mut res = Vec::new();
+        ciborium::ser::into_writer(&encode_value(data), &mut res)
+            .map_err(|e| Error::from(format!("Unable to encode CBOR: {e:?}")))?;
+        Ok(res)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Codec> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    fn decode(codec: &mut Cbor, data: &[u8]) -> Result<Option<Value<'static>>> {
+        let mut data = data.to_vec();
+        Ok(codec.decode(&mut data, 0)?.map(Value::into_static))
+    }
+
+    #[cfg(feature = "128bit")]
+    #[test]
+    fn encode_128bit() {
+        let encode = |n| encode_value(&Value::Static(n));
+        assert_eq!(
+            CborValue::Integer(Integer::from(-1)),
+            encode(StaticNode::I128(-1))
+        );
+        assert_eq!(
+            CborValue::Integer(Integer::from(u64::MAX)),
+            encode(StaticNode::U128(u128::from(u64::MAX)))
+        );
+        // out of the CBOR integer range they become bignums
+        assert_eq!(
+            CborValue::Tag(
+                POSITIVE_BIGNUM,
+                Box::new(CborValue::Bytes(vec![1, 0, 0, 0, 0, 0, 0, 0, 0]))
+            ),
+            encode(StaticNode::U128(1 << 64))
+        );
+        assert_eq!(
+            CborValue::Tag(
+                NEGATIVE_BIGNUM,
+                Box::new(CborValue::Bytes(vec![1, 0, 0, 0, 0, 0, 0, 0, 0]))
+            ),
+            encode(StaticNode::I128(-1 - (1 << 64)))
+        );
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let mut codec = Cbor {};
+        let mut seed = literal!({
+            "snot": "badger",
+            "nested": {
+                "array": [1, -1, 1.5, true, null],
+                "deeper": {"flork": "mork"}
+            },
+            "i64_min": i64::MIN,
+            "i64_max": i64::MAX,
+            "u64_max": u64::MAX
+        });
+        seed.try_insert("bytes", Value::Bytes(vec![0_u8, 1, 2, 255].into()));
+        let encoded = codec.encode(&seed)?;
+        let decoded = decode(&mut codec, &encoded)?;
+        assert_eq!(Some(seed), decoded);
+        let decoded = decoded.unwrap_or_default();
+        assert_eq!(Some(u64::MAX), decoded.get_u64("u64_max"));
+        assert_eq!(Some(i64::MIN), decoded.get_i64("i64_min"));
+        assert_eq!(Some(&[0_u8, 1, 2, 255][..]), decoded.get_bytes("bytes"));
+        Ok(())
+    }
+
+    #[test]
+    fn decode_rfc_examples() -> Result<()> {
+        let mut codec = Cbor {};
+        // 18446744073709551615
+        assert_eq!(
+            Some(Value::from(u64::MAX)),
+            decode(
+                &mut codec,
+                &[0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+            )?
+        );
+        // -18446744073709551616 doesn't fit
+        assert!(decode(
+            &mut codec,
+            &[0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+        )
+        .is_err());
+        // h'01020304'
+        assert_eq!(
+            Some(Value::Bytes(vec![1_u8, 2, 3, 4].into())),
+            decode(&mut codec, &[0x44, 0x01, 0x02, 0x03, 0x04])?
+        );
+        // 1(1363896240), an epoch based date time, the tag is dropped
+        assert_eq!(
+            Some(Value::from(1_363_896_240)),
+            decode(&mut codec, &[0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0])?
+        );
+        // 2(h'010000000000000000') doesn't fit and stays a byte string
+        assert_eq!(
+            Some(Value::Bytes(vec![1_u8, 0, 0, 0, 0, 0, 0, 0, 0].into())),
+            decode(
+                &mut codec,
+                &[0xc2, 0x49, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+            )?
+        );
+        // 3(h'ff'), -256
+        assert_eq!(
+            Some(Value::from(-256)),
+            decode(&mut codec, &[0xc3, 0x41, 0xff])?
+        );
+        // {1: 2, 3: 4}
+        assert_eq!(
+            Some(literal!({"1": 2, "3": 4})),
+            decode(&mut codec, &[0xa2, 0x01, 0x02, 0x03, 0x04])?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_indefinite_length() -> Result<()> {
+        let mut codec = Cbor {};
+        // {_ "a": 1, "b": [_ 2, 3]}
+        assert_eq!(
+            Some(literal!({"a": 1, "b": [2, 3]})),
+            decode(
+                &mut codec,
+                &[0xbf, 0x61, 0x61, 0x01, 0x61, 0x62, 0x9f, 0x02, 0x03, 0xff, 0xff]
+            )?
+        );
+        // (_ "strea", "ming")
+        assert_eq!(
+            Some(Value::from("streaming")),
+            decode(
+                &mut codec,
+                &[0x7f, 0x65, 0x73, 0x74, 0x72, 0x65, 0x61, 0x64, 0x6d, 0x69, 0x6e, 0x67, 0xff]
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_invalid() {
+        let mut codec = Cbor {};
+        // truncated array
+        assert!(decode(&mut codec, &[0x83, 0x01, 0x02]).is_err());
+        // array as map key
+        assert!(decode(&mut codec, &[0xa1, 0x80, 0x01]).is_err());
+    }
+}
@@ -17,7 +17,19 @@
 //! The codec expects a single line of [RFC-4180](https://datatracker.ietf.org/doc/html/rfc4180) CSV format data.
 //!
 //! If there is more than a single line in the message, the lines after the first will be discarded unless
-//! a `lines` preprocessor is used during deserialization.
+//! a `lines` preprocessor is used during deserialization. Quoted fields may contain the delimiter and line breaks.
+//!
+//! ## Configuration
+//!
+//! | option       | description                                                        | default |
+//! |--------------|--------------------------------------------------------------------|---------|
+//! | `delimiter`  | single ASCII character separating fields                           | `,`     |
+//! | `quote`      | single ASCII character quoting fields                              | `"`     |
+//! | `has_header` | the first record is a header row, records are decoded into records | `false` |
+//!
+//! With `has_header` the first decoded line is taken as the header and does not produce an event,
+//! every following line is decoded into a record keyed by the header fields. When encoding records,
+//! the header is taken from the keys of the first record (in sorted order) and emitted along with it.
 //!
 //! ## Example
 //!
@@ -41,7 +53,68 @@ use crate::codec::prelude::*;
 use beef::Cow;
 
 #[derive(Clone)]
-pub struct Csv {}
+pub struct Csv {
+    delimiter: u8,
+    quote: u8,
+    has_header: bool,
+    header: Option<Vec<String>>,
+}
+
+impl Default for Csv {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            has_header: false,
+            header: None,
+        }
+    }
+}
+
+fn ascii_char(config: Option<&Value>, key: &str, default: u8) -> Result<u8> {
+    match config.get_str(key) {
+        None => Ok(default),
+        Some(s) if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+        Some(s) => {
+            Err(format!("Invalid csv codec {key}: `{s}`, must be a single ASCII character").into())
+        }
+    }
+}
+
+pub(crate) fn from_config(config: Option<&Value>) -> Result<Box<dyn Codec>> {
+    Ok(Box::new(Csv {
+        delimiter: ascii_char(config, "delimiter", b',')?,
+        quote: ascii_char(config, "quote", b'"')?,
+        has_header: config.get_bool("has_header").unwrap_or_default(),
+        header: None,
+    }))
+}
+
+fn field(value: &Value) -> String {
+    value
+        .as_str()
+        .map_or_else(|| value.to_string(), ToString::to_string)
+}
+
+impl Csv {
+    fn write_records(&self, records: &[Vec<String>]) -> Result<Vec<u8>> {
+        let mut result = vec![];
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .from_writer(&mut result);
+        for record in records {
+            writer.write_record(record)?;
+        }
+        writer.flush()?;
+        drop(writer);
+
+        while result.last() == Some(&b'\n') || result.last() == Some(&b'\r') {
+            result.pop();
+        }
+        Ok(result)
+    }
+}
 
 impl Codec for Csv {
     fn name(&self) -> &str {
@@ -59,14 +132,46 @@ impl Codec for Csv {
     ) -> Result<Option<Value<'input>>> {
         let mut reader = csv::ReaderBuilder::new()
             .has_headers(false)
+            .flexible(true)
+            .delimiter(self.delimiter)
+            .quote(self.quote)
             .from_reader(&*data); // the reborrow here is needed because std::io::Read is implemented only for &[u8], not &mut [u8]
+        let mut records = reader.records();
+
+        if self.has_header && self.header.is_none() {
+            match records.next() {
+                Some(header) => {
+                    self.header = Some(header?.iter().map(ToString::to_string).collect());
+                }
+                None => return Ok(None),
+            }
+        }
 
-        let record = match reader.records().next() {
+        let record = match records.next() {
             Some(Ok(x)) => Ok(x),
             Some(Err(e)) => Err(e),
             None => return Ok(None),
         }?;
 
+        if let Some(header) = &self.header {
+            if record.len() != header.len() {
+                return Err(format!(
+                    "CSV record has {} fields, but the header has {}",
+                    record.len(),
+                    header.len()
+                )
+                .into());
+            }
+            let mut fields = Object::with_capacity(header.len());
+            for (name, field) in header.iter().zip(record.iter()) {
+                fields.insert(
+                    Cow::from(name.clone()),
+                    Value::String(Cow::from(field.to_string())),
+                );
+            }
+            return Ok(Some(Value::from(fields)));
+        }
+
         let mut fields = vec![];
         for field in record.iter() {
             fields.push(Value::String(Cow::from(field.to_string())));
@@ -77,19 +182,29 @@ impl Codec for Csv {
 
     fn encode(&mut self, data: &Value) -> Result<Vec<u8>> {
         if let Some(values) = data.as_array() {
-            let fields: Vec<String> = values.iter().map(ToString::to_string).collect();
-
-            let mut result = vec![];
-            let mut writer = csv::Writer::from_writer(&mut result);
-            writer.write_record(&fields)?;
-            writer.flush()?;
-            drop(writer);
-
-            while result.last() == Some(&b'\n') || result.last() == Some(&b'\r') {
-                result.pop();
-            }
-
-            return Ok(result);
+            let fields: Vec<String> = values.iter().map(field).collect();
+            return self.write_records(&[fields]);
+        }
+        if let Some(record) = data.as_object() {
+            let mut records = Vec::with_capacity(2);
+            let header = if let Some(header) = &self.header {
+                header.clone()
+            } else {
+                let mut keys: Vec<String> = record.keys().map(ToString::to_string).collect();
+                keys.sort();
+                if self.has_header {
+                    records.push(keys.clone());
+                    self.header = Some(keys.clone());
+                }
+                keys
+            };
+            records.push(
+                header
+                    .iter()
+                    .map(|k| record.get(k.as_str()).map(field).unwrap_or_default())
+                    .collect(),
+            );
+            return self.write_records(&records);
         }
 
         Err(
@@ -109,7 +224,7 @@ mod tests {
 
     #[test]
     fn test_can_decode_csv() {
-        let mut codec = Csv {};
+        let mut codec = Csv::default();
         let mut data = b"a,b,c,123".to_vec();
         let result = codec.decode(&mut data, 0);
 
@@ -118,11 +233,90 @@ mod tests {
 
     #[test]
     fn test_can_encode_csv() {
-        let mut codec = Csv {};
+        let mut codec = Csv::default();
         let data = literal!(["a", "b", "c", 123]);
 
         let result = codec.encode(&data).unwrap_or_default();
 
         assert_eq!(b"a,b,c,123".to_vec(), result);
     }
+
+    fn codec(config: &Value) -> Result<Box<dyn Codec>> {
+        from_config(Some(config))
+    }
+
+    #[test]
+    fn decode_edge_cases() -> Result<()> {
+        let mut codec = Csv::default();
+        let mut data = b"\"a,b\",\"snot\nbadger\",,".to_vec();
+        assert_eq!(
+            Some(literal!(["a,b", "snot\nbadger", "", ""])),
+            codec.decode(&mut data, 0)?
+        );
+        let mut data = b"".to_vec();
+        assert_eq!(None, codec.decode(&mut data, 0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_edge_cases() -> Result<()> {
+        let mut codec = Csv::default();
+        let value = literal!(["a,b", "snot\nbadger", "with \"quotes\"", ""]);
+        let mut data = codec.encode(&value)?;
+        assert_eq!(Some(value), codec.decode(&mut data, 0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn custom_delimiter_and_quote() -> Result<()> {
+        let mut codec = codec(&literal!({"delimiter": ";", "quote": "'"}))?;
+        let value = literal!(["a;b", "c,d", "e"]);
+        let mut data = codec.encode(&value)?;
+        assert_eq!(b"'a;b';c,d;e".to_vec(), data);
+        assert_eq!(Some(value), codec.decode(&mut data, 0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn header() -> Result<()> {
+        let mut codec = codec(&literal!({"has_header": true}))?;
+        let mut data = b"name,age".to_vec();
+        assert_eq!(None, codec.decode(&mut data, 0)?);
+        let mut data = b"snot,42".to_vec();
+        assert_eq!(
+            Some(literal!({"name": "snot", "age": "42"})),
+            codec.decode(&mut data, 0)?
+        );
+        let mut data = b"badger".to_vec();
+        assert!(codec.decode(&mut data, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn header_round_trip() -> Result<()> {
+        let mut encoder = codec(&literal!({"has_header": true}))?;
+        let mut decoder = codec(&literal!({"has_header": true}))?;
+
+        let mut data = encoder.encode(&literal!({"name": "snot", "age": 42}))?;
+        assert_eq!(b"age,name\n42,snot".to_vec(), data);
+        assert_eq!(
+            Some(literal!({"name": "snot", "age": "42"})),
+            decoder.decode(&mut data, 0)?
+        );
+        // the header is only emitted once, missing fields are left empty
+        let mut data = encoder.encode(&literal!({"name": "badger"}))?;
+        assert_eq!(b",badger".to_vec(), data);
+        assert_eq!(
+            Some(literal!({"name": "badger", "age": ""})),
+            decoder.decode(&mut data, 0)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config() {
+        assert!(codec(&literal!({"delimiter": ";;"})).is_err());
+        assert!(codec(&literal!({"quote": "ä"})).is_err());
+        assert!(codec(&literal!({"delimiter": "\t"})).is_ok());
+    }
 }
@@ -111,6 +111,38 @@ mod tests {
             raw
         );
     }
+    #[test]
+    fn escaped_tag_keys_and_typed_fields() -> Result<()> {
+        let mut codec = Influx {};
+        let mut data = br#"cpu,host\,name=srv\ 1,rack\=id=r1 count=3i,load=0.5,ratio=1,up=true,down=F,msg="a \"b\"" 1465839830100400200"#.to_vec();
+        let decoded = codec
+            .decode(data.as_mut_slice(), 0)?
+            .ok_or("no value")?
+            .into_static();
+        assert_eq!(Some("cpu"), decoded.get_str("measurement"));
+        assert_eq!(
+            Some(&literal!({"host,name": "srv 1", "rack=id": "r1"})),
+            decoded.get("tags")
+        );
+        let fields = decoded.get("fields");
+        // integers need the `i` suffix, all other numbers are floats
+        assert_eq!(Some(3), fields.get_i64("count"));
+        assert!(!fields.get("count").map_or(false, Value::is_f64));
+        assert!(fields.get("load").map_or(false, Value::is_f64));
+        assert!(fields.get("ratio").map_or(false, Value::is_f64));
+        assert_eq!(Some(true), fields.get_bool("up"));
+        assert_eq!(Some(false), fields.get_bool("down"));
+        assert_eq!(Some(r#"a "b""#), fields.get_str("msg"));
+
+        // fields are encoded sorted by their key
+        let encoded = codec.encode(&decoded)?;
+        assert_eq!(
+            br#"cpu,host\,name=srv\ 1,rack\=id=r1 count=3i,down=false,load=0.5,msg="a \"b\"",ratio=1.0,up=true 1465839830100400200"#.to_vec(),
+            encoded
+        );
+        Ok(())
+    }
+
     #[test]
     pub fn decode_test() {
         let mut s = b"weather,location=us-midwest temperature=82 1465839830100400200".to_vec();
@@ -0,0 +1,149 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `json-lines` codec supports newline delimited JSON, also known as [ndjson](http://ndjson.org) or [JSON Lines](https://jsonlines.org).
+//!
+//! Deserialization splits the incoming data on newlines and decodes every line as a separate event,
+//! blank lines are skipped. A line that fails to parse is reported as an error on its own, without affecting the other lines.
+//! Every chunk of incoming data is expected to contain complete lines only.
+//!
+//! Serialization emits every event as a single line of minified JSON, terminated by `\n`.
+
+use super::{
+    json::{Json, Unsorted},
+    prelude::*,
+};
+
+#[derive(Clone, Default)]
+pub struct JsonLines {
+    json: Json<Unsorted>,
+}
+
+impl Codec for JsonLines {
+    fn name(&self) -> &str {
+        "json-lines"
+    }
+
+    fn mime_types(&self) -> Vec<&'static str> {
+        vec!["application/x-ndjson", "application/jsonl"]
+    }
+
+    fn split(&self, data: Vec<u8>) -> Vec<Vec<u8>> {
+        data.split(|b| *b == b'\n')
+            .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+            .map(<[u8]>::to_vec)
+            .collect()
+    }
+
+    fn decode<'input>(
+        &mut self,
+        data: &'input mut [u8],
+        ingest_ns: u64,
+    ) -> Result<Option<Value<'input>>> {
+        if data.iter().all(u8::is_ascii_whitespace) {
+            return Ok(None);
+        }
+        self.json.decode(data, ingest_ns)
+    }
+
+    fn encode(&mut self, data: &Value) -> Result<Vec<u8>> {
+        let mut line = self.json.encode(data)?;
+        line.push(b'\n');
+        Ok(line)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Codec> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    fn decode_all(codec: &mut JsonLines, data: &[u8]) -> Vec<Result<Option<Value<'static>>>> {
+        codec
+            .split(data.to_vec())
+            .into_iter()
+            .map(|mut line| {
+                codec
+                    .decode(&mut line, 0)
+                    .map(|v| v.map(Value::into_static))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode() -> Result<()> {
+        let mut codec = JsonLines::default();
+        let res = decode_all(&mut codec, b"{\"snot\":1}\n\n  \n[1,2]\r\n\"badger\"");
+        assert_eq!(3, res.len());
+        let res = res.into_iter().collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            vec![
+                Some(literal!({"snot": 1})),
+                Some(literal!([1, 2])),
+                Some(literal!("badger"))
+            ],
+            res
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_malformed_line() {
+        let mut codec = JsonLines::default();
+        let res = decode_all(&mut codec, b"{\"snot\":1}\n{\"snot\":\n{\"badger\":2}\n");
+        assert_eq!(3, res.len());
+        assert_eq!(
+            Some(Some(literal!({"snot": 1}))),
+            res[0].as_ref().ok().cloned()
+        );
+        assert!(res[1].is_err());
+        assert_eq!(
+            Some(Some(literal!({"badger": 2}))),
+            res[2].as_ref().ok().cloned()
+        );
+    }
+
+    #[test]
+    fn decode_blank() -> Result<()> {
+        let mut codec = JsonLines::default();
+        assert!(decode_all(&mut codec, b"\n \n\t\n").is_empty());
+        let mut data = b"  ".to_vec();
+        assert_eq!(None, codec.decode(&mut data, 0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn encode() -> Result<()> {
+        let mut codec = JsonLines::default();
+        let mut data = Vec::new();
+        data.extend(codec.encode(&literal!({"snot": "bad\nger"}))?);
+        data.extend(codec.encode(&literal!([1, 2, 3]))?);
+        assert_eq!(b"{\"snot\":\"bad\\nger\"}\n[1,2,3]\n".to_vec(), data);
+        let res = decode_all(&mut codec, &data)
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            vec![
+                Some(literal!({"snot": "bad\nger"})),
+                Some(literal!([1, 2, 3]))
+            ],
+            res
+        );
+        Ok(())
+    }
+}
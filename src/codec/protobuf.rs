@@ -0,0 +1,861 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `protobuf` codec supports the [Protocol Buffers](https://protobuf.dev) binary wire format.
+//!
+//! Messages are described by a descriptor set, as generated by
+//! `protoc --include_imports --descriptor_set_out=<file> <proto files>`, so no code needs to be
+//! generated for them.
+//!
+//! ## Configuration
+//!
+//! | option            | description                                                   | default |
+//! |-------------------|---------------------------------------------------------------|---------|
+//! | `descriptor_file` | path to the binary `FileDescriptorSet` describing the message |         |
+//! | `message_type`    | fully qualified name of the message, e.g. `package.Message`   |         |
+//!
+//! ## Mapping
+//!
+//! Messages are decoded into records keyed by the field names, nested messages into nested
+//! records, repeated fields into arrays and `map` fields into records. Enums are decoded into the
+//! names of their values, or the number if the value is unknown. Only fields present on the wire
+//! are decoded, so fields with default values are usually missing. Unknown fields, e.g. of a newer
+//! version of the message, are skipped.
+//!
+//! Encoding takes the same representation, enums as names or numbers and `null` for missing fields.
+//! Keys that aren't fields of the message are rejected. Groups are not supported.
+
+use super::prelude::*;
+use prost::{
+    encoding::{decode_key, decode_varint, encode_key, encode_varint, WireType},
+    Message as _,
+};
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorSet,
+};
+use std::{collections::HashMap, sync::Arc};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    descriptor_file: String,
+    message_type: String,
+}
+
+/// A message type of the descriptor set
+#[derive(Debug)]
+struct MessageType {
+    fields: Vec<FieldDescriptorProto>,
+    /// index into `fields` by field number
+    numbers: HashMap<u32, usize>,
+    /// repeated scalar fields are packed unless configured otherwise
+    proto3: bool,
+    /// the type of the entries of a `map` field
+    map_entry: bool,
+}
+
+impl MessageType {
+    fn field(&self, number: u32) -> Option<&FieldDescriptorProto> {
+        self.numbers.get(&number).map(|i| &self.fields[*i])
+    }
+
+    fn packed(&self, field: &FieldDescriptorProto) -> bool {
+        field
+            .options
+            .as_ref()
+            .and_then(|o| o.packed)
+            .unwrap_or(self.proto3)
+    }
+
+    /// the key and value fields of a `map` entry
+    fn entry_fields(&self) -> Result<(&FieldDescriptorProto, &FieldDescriptorProto)> {
+        self.field(1)
+            .zip(self.field(2))
+            .ok_or_else(|| "Invalid protobuf map entry type".into())
+    }
+}
+
+/// An enum type of the descriptor set
+#[derive(Debug, Default)]
+struct EnumType {
+    names: HashMap<i32, String>,
+    numbers: HashMap<String, i32>,
+}
+
+/// The types of a descriptor set by their fully qualified names, like `.package.Message`
+#[derive(Debug, Default)]
+struct Types {
+    messages: HashMap<String, MessageType>,
+    enums: HashMap<String, EnumType>,
+}
+
+fn invalid_message<E: std::fmt::Display>(e: E) -> Error {
+    format!("Invalid protobuf message: {e}").into()
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u64> {
+    decode_varint(data).map_err(invalid_message)
+}
+
+fn read_fixed<const N: usize>(data: &mut &[u8]) -> Result<[u8; N]> {
+    if data.len() < N {
+        return Err(invalid_message("unexpected end of data"));
+    }
+    let (bytes, rest) = data.split_at(N);
+    *data = rest;
+    let mut res = [0; N];
+    res.copy_from_slice(bytes);
+    Ok(res)
+}
+
+fn read_bytes<'data>(data: &mut &'data [u8]) -> Result<&'data [u8]> {
+    let len = usize::try_from(read_varint(data)?)?;
+    if data.len() < len {
+        return Err(invalid_message("unexpected end of data"));
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes)
+}
+
+fn write_bytes(bytes: &[u8], buf: &mut Vec<u8>) {
+    encode_varint(bytes.len() as u64, buf);
+    buf.extend_from_slice(bytes);
+}
+
+fn skip(wire_type: WireType, data: &mut &[u8]) -> Result<()> {
+    match wire_type {
+        WireType::Varint => read_varint(data).map(|_| ()),
+        WireType::SixtyFourBit => read_fixed::<8>(data).map(|_| ()),
+        WireType::LengthDelimited => read_bytes(data).map(|_| ()),
+        WireType::ThirtyTwoBit => read_fixed::<4>(data).map(|_| ()),
+        WireType::StartGroup | WireType::EndGroup => {
+            Err(invalid_message("groups are not supported"))
+        }
+    }
+}
+
+fn wire_type(ty: Type) -> WireType {
+    match ty {
+        Type::Double | Type::Fixed64 | Type::Sfixed64 => WireType::SixtyFourBit,
+        Type::Float | Type::Fixed32 | Type::Sfixed32 => WireType::ThirtyTwoBit,
+        Type::String | Type::Bytes | Type::Message => WireType::LengthDelimited,
+        Type::Group => WireType::StartGroup,
+        Type::Int64
+        | Type::Uint64
+        | Type::Int32
+        | Type::Uint32
+        | Type::Sint32
+        | Type::Sint64
+        | Type::Bool
+        | Type::Enum => WireType::Varint,
+    }
+}
+
+/// only numeric types can be packed into a single length delimited record
+fn is_packable(ty: Type) -> bool {
+    matches!(
+        wire_type(ty),
+        WireType::Varint | WireType::SixtyFourBit | WireType::ThirtyTwoBit
+    )
+}
+
+fn field_number(field: &FieldDescriptorProto) -> Result<u32> {
+    u32::try_from(field.number())
+        .map_err(|_| format!("Invalid number of protobuf field `{}`", field.name()).into())
+}
+
+/// the key of a `map` entry, `map` fields are decoded into records so keys are strings
+fn map_key(field: &FieldDescriptorProto, key: &str) -> Result<Value<'static>> {
+    Ok(match field.r#type() {
+        Type::String => Value::from(key.to_string()),
+        Type::Bool => match key {
+            "true" => Value::from(true),
+            "false" => Value::from(false),
+            _ => return Err(format!("Invalid boolean protobuf map key `{key}`").into()),
+        },
+        Type::Uint32 | Type::Uint64 | Type::Fixed32 | Type::Fixed64 => {
+            Value::from(key.parse::<u64>()?)
+        }
+        _ => Value::from(key.parse::<i64>()?),
+    })
+}
+
+impl Types {
+    fn add_enum(&mut self, prefix: &str, enum_type: &EnumDescriptorProto) {
+        let mut res = EnumType::default();
+        for value in &enum_type.value {
+            res.names.insert(value.number(), value.name().to_string());
+            res.numbers.insert(value.name().to_string(), value.number());
+        }
+        self.enums
+            .insert(format!("{prefix}.{}", enum_type.name()), res);
+    }
+
+    fn add_message(&mut self, prefix: &str, message: DescriptorProto, proto3: bool) {
+        let name = format!("{prefix}.{}", message.name());
+        for enum_type in &message.enum_type {
+            self.add_enum(&name, enum_type);
+        }
+        for nested in message.nested_type {
+            self.add_message(&name, nested, proto3);
+        }
+        let numbers = message
+            .field
+            .iter()
+            .enumerate()
+            .filter_map(|(i, f)| Some((u32::try_from(f.number()).ok()?, i)))
+            .collect();
+        let map_entry = message.options.as_ref().map_or(false, |o| o.map_entry());
+        self.messages.insert(
+            name,
+            MessageType {
+                fields: message.field,
+                numbers,
+                proto3,
+                map_entry,
+            },
+        );
+    }
+
+    fn message(&self, name: &str) -> Result<&MessageType> {
+        self.messages
+            .get(name)
+            .ok_or_else(|| format!("Unknown protobuf message type `{name}`").into())
+    }
+
+    /// the entry type if `field` is a `map` field
+    fn map_entry(&self, field: &FieldDescriptorProto) -> Option<&MessageType> {
+        if field.label() == Label::Repeated && field.r#type() == Type::Message {
+            self.messages.get(field.type_name()).filter(|m| m.map_entry)
+        } else {
+            None
+        }
+    }
+
+    fn enum_value(&self, field: &FieldDescriptorProto, number: i32) -> Value<'static> {
+        self.enums
+            .get(field.type_name())
+            .and_then(|e| e.names.get(&number))
+            .map_or_else(|| Value::from(number), |name| Value::from(name.clone()))
+    }
+
+    /// the value of fields missing on the wire, only needed for `map` entries
+    fn default_value(&self, field: &FieldDescriptorProto) -> Value<'static> {
+        match field.r#type() {
+            Type::Double | Type::Float => Value::from(0.0),
+            Type::Bool => Value::from(false),
+            Type::String => Value::from(""),
+            Type::Bytes => Value::Bytes(Vec::new().into()),
+            Type::Enum => self.enum_value(field, 0),
+            Type::Message | Type::Group => Value::object(),
+            _ => Value::from(0),
+        }
+    }
+
+    fn decode_message(&self, message: &MessageType, mut data: &[u8]) -> Result<Object<'static>> {
+        let mut record = Object::with_capacity(message.fields.len());
+        while !data.is_empty() {
+            let (number, wire_type) = decode_key(&mut data).map_err(invalid_message)?;
+            let field = if let Some(field) = message.field(number) {
+                field
+            } else {
+                skip(wire_type, &mut data)?;
+                continue;
+            };
+            let ty = field.r#type();
+            let name = field.name();
+            if let Some(entry_type) = self.map_entry(field) {
+                let (key_field, value_field) = entry_type.entry_fields()?;
+                let mut entry = self.decode_message(entry_type, read_bytes(&mut data)?)?;
+                let key = entry
+                    .remove("key")
+                    .unwrap_or_else(|| self.default_value(key_field));
+                let key = key
+                    .as_str()
+                    .map_or_else(|| key.to_string(), ToString::to_string);
+                let value = entry
+                    .remove("value")
+                    .unwrap_or_else(|| self.default_value(value_field));
+                if let Some(Value::Object(map)) = record.get_mut(name) {
+                    map.insert(key.into(), value);
+                } else {
+                    let mut map = Object::new();
+                    map.insert(key.into(), value);
+                    record.insert(name.to_string().into(), Value::from(map));
+                }
+            } else if field.label() == Label::Repeated {
+                let values = if wire_type == WireType::LengthDelimited && is_packable(ty) {
+                    let mut packed = read_bytes(&mut data)?;
+                    let mut values = Vec::new();
+                    while !packed.is_empty() {
+                        values.push(self.decode_value(field, &mut packed)?);
+                    }
+                    values
+                } else {
+                    check_wire_type(field, wire_type)?;
+                    vec![self.decode_value(field, &mut data)?]
+                };
+                if let Some(Value::Array(array)) = record.get_mut(name) {
+                    array.extend(values);
+                } else {
+                    record.insert(name.to_string().into(), Value::from(values));
+                }
+            } else {
+                check_wire_type(field, wire_type)?;
+                let value = self.decode_value(field, &mut data)?;
+                record.insert(name.to_string().into(), value);
+            }
+        }
+        Ok(record)
+    }
+
+    // protobuf truncates varints of 32 bit types and stores signed integers as two's complement
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn decode_value(
+        &self,
+        field: &FieldDescriptorProto,
+        data: &mut &[u8],
+    ) -> Result<Value<'static>> {
+        Ok(match field.r#type() {
+            Type::Double => Value::from(f64::from_le_bytes(read_fixed(data)?)),
+            Type::Float => Value::from(f64::from(f32::from_le_bytes(read_fixed(data)?))),
+            Type::Int64 => Value::from(read_varint(data)? as i64),
+            Type::Uint64 => Value::from(read_varint(data)?),
+            Type::Int32 => Value::from(read_varint(data)? as i32),
+            Type::Uint32 => Value::from(read_varint(data)? as u32),
+            Type::Sint32 => {
+                let n = read_varint(data)? as u32;
+                Value::from((n >> 1) as i32 ^ -((n & 1) as i32))
+            }
+            Type::Sint64 => {
+                let n = read_varint(data)?;
+                Value::from((n >> 1) as i64 ^ -((n & 1) as i64))
+            }
+            Type::Fixed32 => Value::from(u32::from_le_bytes(read_fixed(data)?)),
+            Type::Fixed64 => Value::from(u64::from_le_bytes(read_fixed(data)?)),
+            Type::Sfixed32 => Value::from(i32::from_le_bytes(read_fixed(data)?)),
+            Type::Sfixed64 => Value::from(i64::from_le_bytes(read_fixed(data)?)),
+            Type::Bool => Value::from(read_varint(data)? != 0),
+            Type::String => Value::from(std::str::from_utf8(read_bytes(data)?)?.to_string()),
+            Type::Bytes => Value::Bytes(read_bytes(data)?.to_vec().into()),
+            Type::Enum => self.enum_value(field, read_varint(data)? as i32),
+            Type::Message => Value::from(
+                self.decode_message(self.message(field.type_name())?, read_bytes(data)?)?,
+            ),
+            Type::Group => return Err(invalid_message("groups are not supported")),
+        })
+    }
+
+    fn encode_message(
+        &self,
+        message: &MessageType,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        let record = value
+            .as_object()
+            .ok_or_else(|| Error::from(format!("Protobuf messages must be records: {value}")))?;
+        for key in record.keys() {
+            let key: &str = key;
+            if !message.fields.iter().any(|f| f.name() == key) {
+                return Err(format!("Unknown protobuf field `{key}`").into());
+            }
+        }
+        for field in &message.fields {
+            let value = match record.get(field.name()) {
+                Some(value) if !value.is_null() => value,
+                _ => continue,
+            };
+            let number = field_number(field)?;
+            let ty = field.r#type();
+            if let Some(entry_type) = self.map_entry(field) {
+                let (key_field, value_field) = entry_type.entry_fields()?;
+                let map = value.as_object().ok_or_else(|| {
+                    Error::from(format!("The map field `{}` must be a record", field.name()))
+                })?;
+                for (key, value) in map {
+                    let mut entry = Vec::new();
+                    encode_key(1, wire_type(key_field.r#type()), &mut entry);
+                    self.encode_value(key_field, &map_key(key_field, key)?, &mut entry)?;
+                    encode_key(2, wire_type(value_field.r#type()), &mut entry);
+                    self.encode_value(value_field, value, &mut entry)?;
+                    encode_key(number, WireType::LengthDelimited, buf);
+                    write_bytes(&entry, buf);
+                }
+            } else if field.label() == Label::Repeated {
+                let values = value.as_array().ok_or_else(|| {
+                    Error::from(format!(
+                        "The repeated field `{}` must be an array",
+                        field.name()
+                    ))
+                })?;
+                if is_packable(ty) && message.packed(field) {
+                    let mut packed = Vec::new();
+                    for value in values {
+                        self.encode_value(field, value, &mut packed)?;
+                    }
+                    encode_key(number, WireType::LengthDelimited, buf);
+                    write_bytes(&packed, buf);
+                } else {
+                    for value in values {
+                        encode_key(number, wire_type(ty), buf);
+                        self.encode_value(field, value, buf)?;
+                    }
+                }
+            } else {
+                encode_key(number, wire_type(ty), buf);
+                self.encode_value(field, value, buf)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn encode_value(
+        &self,
+        field: &FieldDescriptorProto,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        let invalid = |expected: &str| -> Error {
+            format!(
+                "Invalid value for protobuf field `{}`, expected {expected}: {value}",
+                field.name()
+            )
+            .into()
+        };
+        let float = || value.cast_f64().ok_or_else(|| invalid("a float"));
+        let int32 = || value.as_i32().ok_or_else(|| invalid("a 32 bit integer"));
+        let int64 = || value.as_i64().ok_or_else(|| invalid("a 64 bit integer"));
+        let uint32 = || {
+            value
+                .as_u32()
+                .ok_or_else(|| invalid("an unsigned 32 bit integer"))
+        };
+        let uint64 = || {
+            value
+                .as_u64()
+                .ok_or_else(|| invalid("an unsigned 64 bit integer"))
+        };
+        match field.r#type() {
+            Type::Double => buf.extend_from_slice(&float()?.to_le_bytes()),
+            Type::Float => buf.extend_from_slice(&(float()? as f32).to_le_bytes()),
+            Type::Int64 => encode_varint(int64()? as u64, buf),
+            Type::Uint64 => encode_varint(uint64()?, buf),
+            // negative values are sign extended to 64 bit
+            Type::Int32 => encode_varint(i64::from(int32()?) as u64, buf),
+            Type::Uint32 => encode_varint(u64::from(uint32()?), buf),
+            Type::Sint32 => {
+                let n = int32()?;
+                encode_varint(u64::from(((n << 1) ^ (n >> 31)) as u32), buf);
+            }
+            Type::Sint64 => {
+                let n = int64()?;
+                encode_varint(((n << 1) ^ (n >> 63)) as u64, buf);
+            }
+            Type::Fixed32 => buf.extend_from_slice(&uint32()?.to_le_bytes()),
+            Type::Fixed64 => buf.extend_from_slice(&uint64()?.to_le_bytes()),
+            Type::Sfixed32 => buf.extend_from_slice(&int32()?.to_le_bytes()),
+            Type::Sfixed64 => buf.extend_from_slice(&int64()?.to_le_bytes()),
+            Type::Bool => {
+                let b = value.as_bool().ok_or_else(|| invalid("a boolean"))?;
+                encode_varint(u64::from(b), buf);
+            }
+            Type::String => {
+                let s = value.as_str().ok_or_else(|| invalid("a string"))?;
+                write_bytes(s.as_bytes(), buf);
+            }
+            Type::Bytes => match value {
+                Value::Bytes(b) => write_bytes(b, buf),
+                _ => {
+                    let s = value.as_str().ok_or_else(|| invalid("bytes or a string"))?;
+                    write_bytes(s.as_bytes(), buf);
+                }
+            },
+            Type::Enum => {
+                let n = if let Some(name) = value.as_str() {
+                    self.enums
+                        .get(field.type_name())
+                        .and_then(|e| e.numbers.get(name))
+                        .copied()
+                        .ok_or_else(|| invalid("a value of the enum"))?
+                } else {
+                    int32()?
+                };
+                encode_varint(i64::from(n) as u64, buf);
+            }
+            Type::Message => {
+                let mut nested = Vec::new();
+                self.encode_message(self.message(field.type_name())?, value, &mut nested)?;
+                write_bytes(&nested, buf);
+            }
+            Type::Group => return Err("Protobuf groups are not supported".into()),
+        }
+        Ok(())
+    }
+}
+
+impl From<FileDescriptorSet> for Types {
+    fn from(set: FileDescriptorSet) -> Self {
+        let mut types = Self::default();
+        for file in set.file {
+            let proto3 = file.syntax() == "proto3";
+            let prefix = if file.package().is_empty() {
+                String::new()
+            } else {
+                format!(".{}", file.package())
+            };
+            for enum_type in &file.enum_type {
+                types.add_enum(&prefix, enum_type);
+            }
+            for message in file.message_type {
+                types.add_message(&prefix, message, proto3);
+            }
+        }
+        types
+    }
+}
+
+fn check_wire_type(field: &FieldDescriptorProto, wire_type: WireType) -> Result<()> {
+    if wire_type == self::wire_type(field.r#type()) {
+        Ok(())
+    } else {
+        Err(invalid_message(format!(
+            "wire type {wire_type:?} of field `{}`",
+            field.name()
+        )))
+    }
+}
+
+#[derive(Clone)]
+pub struct Protobuf {
+    /// shared, so cloning the codec for every connector stream is cheap
+    types: Arc<Types>,
+    /// fully qualified name of the message type, with a leading `.`
+    message_type: String,
+}
+
+pub(crate) fn from_config(config: Option<&Value>) -> Result<Box<dyn Codec>> {
+    let invalid = |msg: String| -> Error {
+        ErrorKind::InvalidConfiguration("protobuf codec".to_string(), msg).into()
+    };
+    let config = config.ok_or_else(|| invalid("Missing configuration".to_string()))?;
+    let config: Config =
+        tremor_value::structurize(config.clone()).map_err(|e| invalid(e.to_string()))?;
+    let data = std::fs::read(&config.descriptor_file).map_err(|e| {
+        invalid(format!(
+            "Unable to read descriptor file {}: {e}",
+            config.descriptor_file
+        ))
+    })?;
+    let set = FileDescriptorSet::decode(data.as_slice()).map_err(|e| {
+        invalid(format!(
+            "Invalid descriptor file {}: {e}",
+            config.descriptor_file
+        ))
+    })?;
+    let types = Types::from(set);
+    let message_type = if config.message_type.starts_with('.') {
+        config.message_type
+    } else {
+        format!(".{}", config.message_type)
+    };
+    if !types.messages.contains_key(&message_type) {
+        return Err(invalid(format!("Unknown message type `{message_type}`")));
+    }
+    Ok(Box::new(Protobuf {
+        types: Arc::new(types),
+        message_type,
+    }))
+}
+
+impl Codec for Protobuf {
+    fn name(&self) -> &str {
+        "protobuf"
+    }
+
+    fn mime_types(&self) -> Vec<&'static str> {
+        vec!["application/protobuf", "application/x-protobuf"]
+    }
+
+    fn decode<'input>(
+        &mut self,
+        data: &'input mut [u8],
+        _ingest_ns: u64,
+    ) -> Result<Option<Value<'input>>> {
+        let message = self.types.message(&self.message_type)?;
+        Ok(Some(Value::from(self.types.decode_message(message, data)?)))
+    }
+
+    fn encode(&mut self, data: &Value) -> Result<Vec<u8>> {
+        let message = self.types.message(&self.message_type)?;
+        let mut buf = Vec::new();
+        self.types.encode_message(message, data, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Codec> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use prost::Message;
+    use prost_types::{FileDescriptorProto, MessageOptions};
+    use tempfile::NamedTempFile;
+    use tremor_value::literal;
+
+    fn field(name: &str, number: i32, ty: Type, label: Label) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            r#type: Some(ty as i32),
+            label: Some(label as i32),
+            ..FieldDescriptorProto::default()
+        }
+    }
+
+    fn typed(mut field: FieldDescriptorProto, type_name: &str) -> FieldDescriptorProto {
+        field.type_name = Some(type_name.to_string());
+        field
+    }
+
+    /// the descriptor set of
+    ///
+    /// ```protobuf
+    /// syntax = "proto3";
+    /// package snot;
+    /// message Badger {
+    ///   enum Kind { UNKNOWN = 0; HONEY = 1; EURASIAN = 2; }
+    ///   message Burrow { string name = 1; sint32 depth = 2; }
+    ///   uint64 id = 1;
+    ///   string name = 2;
+    ///   repeated int32 scores = 3;
+    ///   Burrow home = 4;
+    ///   repeated Burrow burrows = 5;
+    ///   Kind kind = 6;
+    ///   double weight = 7;
+    ///   map<string, int64> counts = 8;
+    ///   bytes data = 9;
+    ///   bool active = 10;
+    /// }
+    /// ```
+    fn descriptor_file() -> Result<NamedTempFile> {
+        let optional = Label::Optional;
+        let burrow = DescriptorProto {
+            name: Some("Burrow".to_string()),
+            field: vec![
+                field("name", 1, Type::String, optional),
+                field("depth", 2, Type::Sint32, optional),
+            ],
+            ..DescriptorProto::default()
+        };
+        let counts_entry = DescriptorProto {
+            name: Some("CountsEntry".to_string()),
+            field: vec![
+                field("key", 1, Type::String, optional),
+                field("value", 2, Type::Int64, optional),
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..MessageOptions::default()
+            }),
+            ..DescriptorProto::default()
+        };
+        let kind = EnumDescriptorProto {
+            name: Some("Kind".to_string()),
+            value: ["UNKNOWN", "HONEY", "EURASIAN"]
+                .iter()
+                .zip(0..)
+                .map(|(name, number)| prost_types::EnumValueDescriptorProto {
+                    name: Some((*name).to_string()),
+                    number: Some(number),
+                    ..prost_types::EnumValueDescriptorProto::default()
+                })
+                .collect(),
+            ..EnumDescriptorProto::default()
+        };
+        let badger = DescriptorProto {
+            name: Some("Badger".to_string()),
+            field: vec![
+                field("id", 1, Type::Uint64, optional),
+                field("name", 2, Type::String, optional),
+                field("scores", 3, Type::Int32, Label::Repeated),
+                typed(
+                    field("home", 4, Type::Message, optional),
+                    ".snot.Badger.Burrow",
+                ),
+                typed(
+                    field("burrows", 5, Type::Message, Label::Repeated),
+                    ".snot.Badger.Burrow",
+                ),
+                typed(field("kind", 6, Type::Enum, optional), ".snot.Badger.Kind"),
+                field("weight", 7, Type::Double, optional),
+                typed(
+                    field("counts", 8, Type::Message, Label::Repeated),
+                    ".snot.Badger.CountsEntry",
+                ),
+                field("data", 9, Type::Bytes, optional),
+                field("active", 10, Type::Bool, optional),
+            ],
+            nested_type: vec![burrow, counts_entry],
+            enum_type: vec![kind],
+            ..DescriptorProto::default()
+        };
+        let set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("snot.proto".to_string()),
+                package: Some("snot".to_string()),
+                message_type: vec![badger],
+                syntax: Some("proto3".to_string()),
+                ..FileDescriptorProto::default()
+            }],
+        };
+        let file = NamedTempFile::new()?;
+        std::fs::write(file.path(), set.encode_to_vec())?;
+        Ok(file)
+    }
+
+    fn protobuf(file: &NamedTempFile, message_type: &str) -> Result<Box<dyn Codec>> {
+        from_config(Some(&literal!({
+            "descriptor_file": file.path().display().to_string(),
+            "message_type": message_type.to_string()
+        })))
+    }
+
+    /// the wire format of `badger()`
+    const BADGER: &[u8] = &[
+        0x08, 0x96, 0x01, // id: 150
+        0x12, 0x04, b's', b'n', b'o', b't', // name: "snot"
+        0x1a, 0x0d, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0xac,
+        0x02, // scores: [1, -1, 300], packed
+        0x22, 0x07, 0x0a, 0x03, b'd', b'e', b'n', 0x10,
+        0x03, // home: {name: "den", depth: -2}
+        0x2a, 0x03, 0x0a, 0x01, b'a', 0x2a, 0x02, 0x10,
+        0x02, // burrows: [{name: "a"}, {depth: 1}]
+        0x30, 0x01, // kind: HONEY
+        0x39, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf8, 0x3f, // weight: 1.5
+        0x42, 0x05, 0x0a, 0x01, b'x', 0x10, 0x02, // counts: {"x": 2}
+        0x4a, 0x01, 0xff, // data: [255]
+        0x50, 0x01, // active: true
+    ];
+
+    fn badger() -> Result<Value<'static>> {
+        let mut badger = literal!({
+            "id": 150,
+            "name": "snot",
+            "scores": [1, -1, 300],
+            "home": {"name": "den", "depth": -2},
+            "burrows": [{"name": "a"}, {"depth": 1}],
+            "kind": "HONEY",
+            "weight": 1.5,
+            "counts": {"x": 2},
+            "active": true
+        });
+        badger.insert("data", Value::Bytes(vec![0xff_u8].into()))?;
+        Ok(badger)
+    }
+
+    #[test]
+    fn decode_encode() -> Result<()> {
+        let file = descriptor_file()?;
+        let mut codec = protobuf(&file, "snot.Badger")?;
+        let mut data = BADGER.to_vec();
+        assert_eq!(Some(badger()?), codec.decode(&mut data, 0)?);
+        assert_eq!(BADGER, codec.encode(&badger()?)?.as_slice());
+
+        // nested types can be used on their own, missing fields aren't encoded
+        let mut codec = protobuf(&file, ".snot.Badger.Burrow")?;
+        let mut data = codec.encode(&literal!({"name": "den", "depth": null}))?;
+        assert_eq!(vec![0x0a, 0x03, b'd', b'e', b'n'], data);
+        assert_eq!(Some(literal!({"name": "den"})), codec.decode(&mut data, 0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_wire_variants() -> Result<()> {
+        let file = descriptor_file()?;
+        let mut codec = protobuf(&file, "snot.Badger")?;
+        let mut data = vec![
+            0x18, 0x01, 0x18, 0x02, // unpacked scores
+            0x1a, 0x01, 0x03, // followed by packed ones
+            0x30, 0x07, // unknown enum value
+            0x42, 0x02, 0x10, 0x01, // map entry without a key
+            0x60, 0x2a, // unknown varint field 12
+            0x6a, 0x02, 0x01, 0x02, // unknown length delimited field 13
+            0x75, 0x01, 0x02, 0x03, 0x04, // unknown 32 bit field 14
+            0x79, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, // unknown 64 bit field 15
+            0x12, 0x01, b'a', 0x12, 0x01, b'b', // the last value wins
+        ];
+        assert_eq!(
+            Some(literal!({
+                "scores": [1, 2, 3],
+                "kind": 7,
+                "counts": {"": 1},
+                "name": "b"
+            })),
+            codec.decode(&mut data, 0)?
+        );
+        assert_eq!(Some(literal!({})), codec.decode(&mut [], 0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_data() -> Result<()> {
+        let file = descriptor_file()?;
+        let mut codec = protobuf(&file, "snot.Badger")?;
+        // truncated
+        assert!(codec.decode(&mut [0x12, 0x04, b's'], 0).is_err());
+        assert!(codec.decode(&mut [0x08], 0).is_err());
+        assert!(codec.decode(&mut [0x39, 0x00], 0).is_err());
+        // wrong wire type
+        assert!(codec.decode(&mut [0x0a, 0x00], 0).is_err());
+        // invalid utf8
+        assert!(codec.decode(&mut [0x12, 0x01, 0xff], 0).is_err());
+        // groups
+        assert!(codec.decode(&mut [0x63, 0x64], 0).is_err());
+
+        assert!(codec.encode(&literal!("snot")).is_err());
+        assert!(codec.encode(&literal!({"snot": "badger"})).is_err());
+        assert!(codec.encode(&literal!({"id": -1})).is_err());
+        assert!(codec.encode(&literal!({"name": 1})).is_err());
+        assert!(codec.encode(&literal!({"scores": 1})).is_err());
+        assert!(codec.encode(&literal!({"kind": "BADGER"})).is_err());
+        assert!(codec
+            .encode(&literal!({"home": {"depth": "deep"}}))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config() -> Result<()> {
+        let file = descriptor_file()?;
+        assert!(from_config(None).is_err());
+        assert!(protobuf(&file, "snot.Snot").is_err());
+        assert!(from_config(Some(&literal!({
+            "descriptor_file": "/does/not/exist.desc",
+            "message_type": "snot.Badger"
+        })))
+        .is_err());
+        assert!(from_config(Some(&literal!({
+            "descriptor_file": file.path().display().to_string()
+        })))
+        .is_err());
+        Ok(())
+    }
+}
@@ -23,7 +23,7 @@
 //! The native format of a single statsd event line is as follows:
 //!
 //! ```text
-//! sam:7|c|@0.1
+//! sam:7|c|@0.1|#env:dev,region:eu
 //! ```
 //!
 //! The equivalent representation as a tremor value:
@@ -33,10 +33,13 @@
 //!   "type": "c",
 //!   "metric": "sam",
 //!   "value": 7,
-//!   "sample_rate": 0.1
+//!   "sample_rate": 0.1,
+//!   "tags": ["env:dev", "region:eu"]
 //! }
 //! ```
 //!
+//! The sample rate and the tags are optional.
+//!
 //! ## Supported types
 //!
 //! - `c` for `counter`
@@ -120,6 +123,18 @@ fn encode(value: &Value, r: &mut impl Write) -> Result<()> {
         r.write_all(ryu_buf.format(n).as_bytes())?;
     }
 
+    if let Some(tags) = value.get_array("tags") {
+        r.write_all(b"|#")?;
+        let mut tags = tags.iter().filter_map(simd_json::ValueAccess::as_str);
+        if let Some(t) = tags.next() {
+            r.write_all(t.as_bytes())?;
+        }
+        for t in tags {
+            r.write_all(b",")?;
+            r.write_all(t.as_bytes())?;
+        }
+    }
+
     Ok(())
 }
 
@@ -132,7 +147,7 @@ fn decode(data: &[u8], _ingest_ns: u64) -> Result<Value> {
     }
     let data = simdutf8::basic::from_utf8(data)?;
 
-    let mut m = Object::with_capacity(4);
+    let mut m = Object::with_capacity(6);
 
     let (metric, data) = data.split_once(':').ok_or_else(invalid)?;
     m.insert_nocheck("metric".into(), Value::from(metric));
@@ -192,12 +207,20 @@ fn decode(data: &[u8], _ingest_ns: u64) -> Result<Value> {
     } else {
         data
     };
-    if let Some(s) = data.strip_prefix("|@") {
-        let v: f64 = lexical::parse(s)?;
-        m.insert("sample_rate".into(), Value::from(v));
-    } else if !data.is_empty() {
-        return Err(invalid());
-    };
+    if !data.is_empty() {
+        let sections = data.strip_prefix('|').ok_or_else(invalid)?;
+        for section in sections.split('|') {
+            if let Some(s) = section.strip_prefix('@') {
+                let v: f64 = lexical::parse(s)?;
+                m.insert("sample_rate".into(), Value::from(v));
+            } else if let Some(tags) = section.strip_prefix('#') {
+                let tags: Vec<&str> = tags.split(',').collect();
+                m.insert("tags".into(), Value::from(tags));
+            } else {
+                return Err(invalid());
+            }
+        }
+    }
 
     m.insert("value".into(), value);
     Ok(Value::from(m))
@@ -353,6 +376,53 @@ mod test {
         assert_eq!(encoded, data);
     }
 
+    #[test]
+    fn tags() {
+        let data = b"tagged:-12.5|g|@0.5|#env:dev,region:eu";
+        let parsed = decode(data, 0).expect("failed to decode");
+        let expected = literal!({
+            "type": "g",
+            "metric": "tagged",
+            "action": "sub",
+            "value": 12.5,
+            "sample_rate": 0.5,
+            "tags": ["env:dev", "region:eu"]
+        });
+        assert_eq!(parsed, expected);
+        let mut encoded = Vec::new();
+        encode(&parsed, &mut encoded).expect("failed to encode");
+        assert_eq!(encoded, data);
+
+        // tags without a sample rate, before or after the sample rate
+        for (data, expected) in [
+            (&b"timed:320|ms|#snot"[..], &b"timed:320|ms|#snot"[..]),
+            (b"hist:7|h|#snot,badger:1", b"hist:7|h|#snot,badger:1"),
+            (b"counted:2|c|#snot|@0.1", b"counted:2|c|@0.1|#snot"),
+            (b"unique:42|s|#", b"unique:42|s|#"),
+        ] {
+            let parsed = decode(data, 0).expect("failed to decode");
+            assert!(parsed.get_array("tags").is_some());
+            let mut encoded = Vec::new();
+            encode(&parsed, &mut encoded).expect("failed to encode");
+            assert_eq!(encoded, expected);
+        }
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert!(decode(b"snot", 0).is_err());
+        assert!(decode(b"snot:1", 0).is_err());
+        assert!(decode(b"snot:badger|c", 0).is_err());
+        assert!(decode(b"snot:1|c|badger", 0).is_err());
+        assert!(decode(b"snot:1|c|@badger", 0).is_err());
+        assert!(decode(b"snot:1|c#tag", 0).is_err());
+        assert!(encode(
+            &literal!({"metric": "snot", "type": "c", "value": "1"}),
+            &mut Vec::new()
+        )
+        .is_err());
+    }
+
     #[test]
     fn bench() {
         let data = b"foo:1620649445.3351967|h";
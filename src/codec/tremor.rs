@@ -64,6 +64,10 @@ impl Tremor {
     const BYTES: u8 = 7;
     const ARRAY: u8 = 8;
     const OBJECT: u8 = 9;
+    #[cfg(feature = "128bit")]
+    const I128: u8 = 10;
+    #[cfg(feature = "128bit")]
+    const U128: u8 = 11;
     const VERSION: u16 = 0;
     fn decode(data: &[u8]) -> Result<Value> {
         let mut cursor = Cursor::new(data);
@@ -162,6 +166,22 @@ impl Tremor {
                 let mut cursor = Cursor::new(data);
                 Ok((Value::Static(StaticNode::F64(cursor.read_f64::<E>()?)), 9))
             }
+            #[cfg(feature = "128bit")]
+            Self::I128 => {
+                let mut cursor = Cursor::new(data);
+                Ok((
+                    Value::Static(StaticNode::I128(cursor.read_i128::<E>()?)),
+                    17,
+                ))
+            }
+            #[cfg(feature = "128bit")]
+            Self::U128 => {
+                let mut cursor = Cursor::new(data);
+                Ok((
+                    Value::Static(StaticNode::U128(cursor.read_u128::<E>()?)),
+                    17,
+                ))
+            }
             Self::STRING => Self::decode_string::<E>(t, data),
             Self::BYTES => Self::decode_bytes::<E>(t, data),
             Self::ARRAY => Self::decode_array::<E>(t, data),
@@ -184,6 +204,16 @@ impl Tremor {
                 w.write_u8(Self::F64)?;
                 w.write_f64::<E>(*n)?;
             }
+            #[cfg(feature = "128bit")]
+            StaticNode::I128(n) => {
+                w.write_u8(Self::I128)?;
+                w.write_i128::<E>(*n)?;
+            }
+            #[cfg(feature = "128bit")]
+            StaticNode::U128(n) => {
+                w.write_u8(Self::U128)?;
+                w.write_u128::<E>(*n)?;
+            }
             StaticNode::Bool(true) => w.write_u8(Self::BOOL_TRUE)?,
             StaticNode::Bool(false) => w.write_u8(Self::BOOL_FALSE)?,
             StaticNode::Null => w.write_u8(Self::NULL)?,
@@ -326,6 +356,23 @@ mod test {
 
         Ok(())
     }
+    #[cfg(feature = "128bit")]
+    #[test]
+    fn test_128bit() -> Result<()> {
+        let mut v: Vec<u8> = Vec::new();
+        let min = Value::Static(StaticNode::I128(i128::MIN));
+        Tremor::encode(&min, &mut v)?;
+        let val = Tremor::decode(&v)?;
+        assert_eq!(val, min);
+
+        let mut v: Vec<u8> = Vec::new();
+        let max = Value::Static(StaticNode::U128(u128::MAX));
+        Tremor::encode(&max, &mut v)?;
+        let val = Tremor::decode(&v)?;
+        assert_eq!(val, max);
+
+        Ok(())
+    }
     #[test]
     fn test_f64() -> Result<()> {
         let mut v: Vec<u8> = Vec::new();
@@ -15,12 +15,77 @@
 //! The `yaml` codec supports marshalling the `YAML Ain't Markup Language` format.
 //!
 //! Specification: [YAML 1.2](https://yaml.org).
+//!
+//! Deserialization resolves anchors and aliases. Multi-document streams, separated by `---`, are decoded into
+//! one event per document.
+//!
+//! Serialization emits a single document with the keys of every map sorted, for a stable output.
 
 use super::prelude::*;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Number, Value as YamlValue};
+use simd_json::StaticNode;
+
+/// Converts `value` to a yaml value, with sorted map keys
+fn to_yaml(value: &Value) -> Result<YamlValue> {
+    Ok(match value {
+        Value::Static(StaticNode::Null) => YamlValue::Null,
+        Value::Static(StaticNode::Bool(b)) => YamlValue::Bool(*b),
+        Value::Static(StaticNode::I64(i)) => YamlValue::Number(Number::from(*i)),
+        Value::Static(StaticNode::U64(u)) => YamlValue::Number(Number::from(*u)),
+        #[cfg(feature = "128bit")]
+        Value::Static(StaticNode::I128(i)) => YamlValue::Number(
+            i64::try_from(*i)
+                .map(Number::from)
+                .or_else(|_| u64::try_from(*i).map(Number::from))
+                .map_err(|_| format!("{i} exceeds the 64 bit range of YAML numbers"))?,
+        ),
+        #[cfg(feature = "128bit")]
+        Value::Static(StaticNode::U128(u)) => YamlValue::Number(
+            u64::try_from(*u)
+                .map(Number::from)
+                .map_err(|_| format!("{u} exceeds the 64 bit range of YAML numbers"))?,
+        ),
+        Value::Static(StaticNode::F64(f)) => YamlValue::Number(Number::from(*f)),
+        Value::String(s) => YamlValue::String(s.to_string()),
+        Value::Array(a) => YamlValue::Sequence(a.iter().map(to_yaml).collect::<Result<_>>()?),
+        Value::Object(o) => {
+            let mut entries: Vec<_> = o.iter().collect();
+            entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            let mut m = Mapping::with_capacity(entries.len());
+            for (k, v) in entries {
+                m.insert(YamlValue::String(k.to_string()), to_yaml(v)?);
+            }
+            YamlValue::Mapping(m)
+        }
+        Value::Bytes(_) => return Err("Binary values can't be encoded as YAML".into()),
+    })
+}
 
 #[derive(Clone)]
 pub struct Yaml {}
 
+impl Yaml {
+    /// Splits `data` into its documents, `None` if it isn't valid yaml
+    fn documents(data: &[u8]) -> Option<Vec<Vec<u8>>> {
+        serde_yaml::Deserializer::from_slice(data)
+            .map(|doc| {
+                let doc = YamlValue::deserialize(doc).ok()?;
+                serde_yaml::to_string(&doc).ok().map(String::into_bytes)
+            })
+            .collect()
+    }
+
+    /// `true` if a line of `data` starts with a document marker, `---` or `...`,
+    /// followed by whitespace or the end of the line
+    fn has_document_marker(data: &[u8]) -> bool {
+        data.split(|b| *b == b'\n').any(|line| {
+            (line.starts_with(b"---") || line.starts_with(b"..."))
+                && line.get(3).map_or(true, u8::is_ascii_whitespace)
+        })
+    }
+}
+
 impl Codec for Yaml {
     fn name(&self) -> &str {
         "yaml"
@@ -30,6 +95,15 @@ impl Codec for Yaml {
         vec!["application/yaml"]
     }
 
+    fn split(&self, data: Vec<u8>) -> Vec<Vec<u8>> {
+        // only a document marker can start a second document
+        if !Self::has_document_marker(&data) {
+            return vec![data];
+        }
+        // on invalid yaml we let `decode` report the error
+        Self::documents(&data).unwrap_or_else(|| vec![data])
+    }
+
     fn decode<'input>(
         &mut self,
         data: &'input mut [u8],
@@ -41,7 +115,7 @@ impl Codec for Yaml {
             .map_err(Error::from)
     }
     fn encode(&mut self, data: &Value) -> Result<Vec<u8>> {
-        Ok(serde_yaml::to_string(data)?.into_bytes())
+        Ok(serde_yaml::to_string(&to_yaml(data)?)?.into_bytes())
     }
 
     fn boxed_clone(&self) -> Box<dyn Codec> {
@@ -54,6 +128,18 @@ mod test {
     use super::*;
     use tremor_value::literal;
 
+    #[cfg(feature = "128bit")]
+    #[test]
+    fn to_yaml_128bit() -> Result<()> {
+        let small = to_yaml(&Value::Static(StaticNode::I128(-1)))?;
+        assert_eq!(YamlValue::Number(Number::from(-1)), small);
+        let small = to_yaml(&Value::Static(StaticNode::U128(u128::from(u64::MAX))))?;
+        assert_eq!(YamlValue::Number(Number::from(u64::MAX)), small);
+        assert!(to_yaml(&Value::Static(StaticNode::U128(u128::MAX))).is_err());
+        assert!(to_yaml(&Value::Static(StaticNode::I128(i128::MIN))).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_yaml_codec() -> Result<()> {
         let seed = literal!({ "snot": "badger" });
@@ -66,4 +152,78 @@ mod test {
 
         Ok(())
     }
+
+    fn decode_all(codec: &mut Yaml, data: &[u8]) -> Result<Vec<Value<'static>>> {
+        codec
+            .split(data.to_vec())
+            .into_iter()
+            .map(|mut doc| Ok(codec.decode(&mut doc, 0)?.ok_or("no value")?.into_static()))
+            .collect()
+    }
+
+    #[test]
+    fn multi_document() -> Result<()> {
+        let mut codec = Yaml {};
+        let data = b"---\nsnot: badger\n---\n- 1\n- 2\n...\n--- flork\n";
+        assert_eq!(
+            vec![
+                literal!({"snot": "badger"}),
+                literal!([1, 2]),
+                literal!("flork")
+            ],
+            decode_all(&mut codec, data)?
+        );
+        // a single document stays as is
+        assert_eq!(1, codec.split(b"snot: badger\n".to_vec()).len());
+        // markers only count at the start of a line
+        assert!(!Yaml::has_document_marker(b"snot: badger---\nflork: ...\n"));
+        assert!(!Yaml::has_document_marker(
+            b"snot: |\n  ---\n----: badger\n"
+        ));
+        assert!(Yaml::has_document_marker(b"snot: badger\n...\r\n"));
+        assert!(Yaml::has_document_marker(b"--- flork"));
+        // invalid documents are reported by decode
+        let res = decode_all(&mut codec, b"---\nsnot: badger\n---\n[1, 2\n");
+        assert!(res.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn anchors_and_aliases() -> Result<()> {
+        let mut codec = Yaml {};
+        let data = b"base: &base\n  snot: badger\ncopy: *base\n";
+        assert_eq!(
+            vec![literal!({"base": {"snot": "badger"}, "copy": {"snot": "badger"}})],
+            decode_all(&mut codec, data)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_nested() -> Result<()> {
+        let mut codec = Yaml {};
+        let seed = literal!({
+            "snot": "badger",
+            "nested": {
+                "array": [1, -1, 1.5, true, null, "---"],
+                "deeper": {"flork": "mork", "empty": {}}
+            },
+            "u64_max": u64::MAX
+        });
+        let encoded = codec.encode(&seed)?;
+        assert_eq!(vec![seed], decode_all(&mut codec, &encoded)?);
+        Ok(())
+    }
+
+    #[test]
+    fn encode_sorted() -> Result<()> {
+        let mut codec = Yaml {};
+        let encoded = codec.encode(&literal!({"c": 1, "a": {"z": 1, "b": 2}, "b": 3}))?;
+        assert_eq!(
+            "a:\n  b: 2\n  z: 1\nb: 3\nc: 1\n",
+            String::from_utf8_lossy(&encoded)
+        );
+        assert!(codec.encode(&Value::Bytes(vec![1_u8, 2].into())).is_err());
+        Ok(())
+    }
 }
@@ -135,6 +135,16 @@ impl ConnectorBuilder for Builder {
                     Auth::ElasticsearchApiKey { id, api_key } => {
                         Some(Credentials::ApiKey(id.clone(), api_key.clone()))
                     }
+                    Auth::BearerFile { .. }
+                    | Auth::OAuth2 { .. }
+                    | Auth::AwsSigV4 { .. }
+                    | Auth::Digest { .. }
+                    | Auth::Jwt { .. } => {
+                        return Err(err_connector_def(
+                            id,
+                            "Bearer file, OAuth2, AWS SigV4, Digest and JWT auth are not supported by the elastic connector",
+                        ));
+                    }
                     // Gcp Auth is handled in sink connect
                     Auth::Gcp | Auth::None => None,
                 }
@@ -15,7 +15,6 @@
 use crate::channel::{bounded, Receiver, Sender};
 use crate::connectors::google::{AuthInterceptor, TokenProvider};
 use crate::connectors::prelude::*;
-use beef::generic::Cow;
 use futures::StreamExt;
 use googapis::google::pubsub::v1::subscriber_client::SubscriberClient;
 use googapis::google::pubsub::v1::{
@@ -251,12 +250,7 @@ fn pubsub_metadata(
     publish_time: Option<Duration>,
     attributes: HashMap<String, String>,
 ) -> Value<'static> {
-    let mut attributes_value = Value::object_with_capacity(attributes.len());
-    for (name, value) in attributes {
-        attributes_value
-            .as_object_mut()
-            .map(|x| x.insert(Cow::from(name), Value::from(value)));
-    }
+    let attributes_value = Value::object_from(attributes);
     literal!({
         "gpubsub_consumer": {
             "message_id": id,
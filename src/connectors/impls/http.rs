@@ -13,7 +13,18 @@
 // limitations under the License.
 
 pub(crate) mod auth;
+pub(crate) mod cache;
+pub(crate) mod circuit_breaker;
 pub(crate) mod client;
+pub(crate) mod compression;
+pub(crate) mod cookies;
+pub(crate) mod dns;
 pub(crate) mod meta;
+pub(crate) mod multipart;
+pub(crate) mod pool;
+pub(crate) mod proxy;
+pub(crate) mod rate_limit;
+pub(crate) mod redirect;
+pub(crate) mod retry;
 pub(crate) mod server;
 pub(crate) mod utils;
@@ -12,9 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::errors::Result;
+mod digest;
+mod jwt;
+mod sigv4;
+
+use crate::connectors::google::{GouthTokenProvider, TokenProvider};
+use crate::errors::{Error, Result};
 use base64::Engine;
-use std::io::Write;
+use chrono::{DateTime, Utc};
+use http::{header, Method, Request, Response, StatusCode, Uri};
+use rand::Rng;
+use simd_json::OwnedValue;
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::sync::Mutex;
 use tremor_common::base64::BASE64;
 
 /// Authorization methods
@@ -25,22 +40,115 @@ pub(crate) enum Auth {
     Basic { username: String, password: String },
     #[serde(alias = "bearer")]
     Bearer(String),
+    /// Bearer token read from `path`, e.g. a short lived token written by a sidecar
+    ///
+    /// The token is re-read once `reload_interval_ms` elapsed or when the file changed.
+    #[serde(alias = "bearer_file")]
+    BearerFile {
+        path: String,
+        #[serde(default = "default_reload_interval_ms")]
+        reload_interval_ms: u64,
+        #[serde(skip)]
+        token: FileTokenCache,
+    },
     #[serde(alias = "elastic_api_key")]
     ElasticsearchApiKey { id: String, api_key: String },
     #[serde(alias = "gcp")]
     Gcp,
+    /// OAuth2 client credentials grant, see <https://www.rfc-editor.org/rfc/rfc6749#section-4.4>
+    #[serde(alias = "oauth2")]
+    OAuth2 {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        #[serde(default = "Default::default")]
+        scopes: Vec<String>,
+        #[serde(skip)]
+        token: TokenCache,
+    },
+    /// AWS Signature Version 4, see <https://docs.aws.amazon.com/general/latest/gr/signature-version-4.html>
+    #[serde(alias = "aws_sigv4")]
+    AwsSigV4 {
+        access_key_id: String,
+        secret_access_key: String,
+        region: String,
+        service: String,
+        session_token: Option<String>,
+    },
+    /// HTTP Digest access authentication, see <https://www.rfc-editor.org/rfc/rfc7616>
+    #[serde(alias = "digest")]
+    Digest { username: String, password: String },
+    /// Self-signed JSON Web Token, carrying the `claims` plus `iat` and `exp`
+    ///
+    /// `key` is the shared secret for `HS256` and the path to a PEM encoded RSA private key for `RS256`.
+    /// Tokens are valid for `ttl_secs` and re-signed before they expire.
+    #[serde(alias = "jwt")]
+    Jwt {
+        alg: jwt::Algorithm,
+        key: String,
+        #[serde(default = "Default::default")]
+        claims: BTreeMap<String, OwnedValue>,
+        #[serde(default = "default_jwt_ttl_secs")]
+        ttl_secs: u64,
+        #[serde(skip)]
+        token: jwt::TokenCache,
+    },
     #[serde(alias = "none")]
     None,
 }
 
 impl Auth {
-    /// Prepare a HTTP autheorization header value given the auth strategy
-    pub fn as_header_value(&self) -> Result<Option<String>> {
+    /// Validate the auth config, loading the key for `Jwt`
+    pub(crate) fn validate(&self) -> Result<()> {
+        if let Auth::Jwt { alg, key, .. } = self {
+            jwt::Key::load(*alg, key)?;
+        }
+        Ok(())
+    }
+
+    /// Prepare a HTTP authorization header value given the auth strategy
+    ///
+    /// This might need to fetch a token first. `OAuth2` tokens are fetched asynchronously, GCP tokens,
+    /// token files and signing keys are read on the blocking thread pool, so they don't stall the runtime.
+    /// `AwsSigV4` and `Digest` provide no header value, they need to `sign` the whole request
+    /// or `answer_challenge`s respectively.
+    pub(crate) async fn header_value(&self) -> Result<Option<String>> {
         match self {
-            Auth::Gcp => {
-                let t = gouth::Token::new()?;
-                Ok(Some(t.header_value()?.to_string()))
+            Auth::OAuth2 {
+                token_url,
+                client_id,
+                client_secret,
+                scopes,
+                token,
+            } => {
+                let token = token
+                    .get_or_refresh(token_url, client_id, client_secret, scopes)
+                    .await?;
+                Ok(Some(format!("Bearer {token}")))
+            }
+            Auth::Gcp | Auth::BearerFile { .. } | Auth::Jwt { .. } => {
+                // clones share the token caches
+                let auth = self.clone();
+                tokio::task::spawn_blocking(move || auth.blocking_header_value()).await?
             }
+            _ => self.blocking_header_value(),
+        }
+    }
+
+    /// Prepare a HTTP authorization header value given the auth strategy
+    ///
+    /// Fails for `OAuth2`, which needs to fetch its token via `header_value`.
+    #[deprecated(
+        note = "blocks while acquiring `gcp` tokens, use the async `header_value` instead"
+    )]
+    pub fn as_header_value(&self) -> Result<Option<String>> {
+        self.blocking_header_value()
+    }
+
+    /// The header value, acquiring tokens on the calling thread
+    fn blocking_header_value(&self) -> Result<Option<String>> {
+        match self {
+            Auth::Gcp => Ok(Some(GCP_TOKEN.header_value()?)),
             Auth::Basic {
                 ref username,
                 ref password,
@@ -49,6 +157,25 @@ impl Auth {
                 Ok(Some(format!("Basic {}", &encoded)))
             }
             Auth::Bearer(token) => Ok(Some(format!("Bearer {}", &token))),
+            Auth::BearerFile {
+                path,
+                reload_interval_ms,
+                token,
+            } => {
+                let token =
+                    token.get_or_reload(path, Duration::from_millis(*reload_interval_ms))?;
+                Ok(Some(format!("Bearer {token}")))
+            }
+            Auth::Jwt {
+                alg,
+                key,
+                claims,
+                ttl_secs,
+                token,
+            } => {
+                let token = token.get_or_sign(*alg, key, claims, *ttl_secs)?;
+                Ok(Some(format!("Bearer {token}")))
+            }
             Auth::ElasticsearchApiKey { id, api_key } => {
                 let mut header_value = "ApiKey ".to_string();
                 let mut writer =
@@ -58,9 +185,255 @@ impl Auth {
                 writer.into_inner(); // release the reference, so header-value is accessible again
                 Ok(Some(header_value))
             }
-            Auth::None => Ok(None),
+            Auth::OAuth2 { .. } => {
+                Err("OAuth2 tokens can only be fetched via `header_value`".into())
+            }
+            Auth::AwsSigV4 { .. } | Auth::Digest { .. } | Auth::None => Ok(None),
         }
     }
+
+    /// Whether this strategy needs the whole request body, to `sign` it or to replay it for `answer_challenge`
+    pub(crate) fn needs_buffered_body(&self) -> bool {
+        matches!(self, Auth::AwsSigV4 { .. } | Auth::Digest { .. })
+    }
+
+    /// Sign the full `request` with its `body`, a no-op for all strategies but `AwsSigV4`
+    pub(crate) fn sign<B>(
+        &self,
+        request: &mut Request<B>,
+        body: &[u8],
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        if let Auth::AwsSigV4 {
+            access_key_id,
+            secret_access_key,
+            region,
+            service,
+            session_token,
+        } = self
+        {
+            let credentials = sigv4::Credentials {
+                access_key_id,
+                secret_access_key,
+                session_token: session_token.as_deref(),
+                region,
+                service,
+            };
+            sigv4::sign(request, body, &credentials, now)?;
+        }
+        Ok(())
+    }
+
+    /// The `Authorization` header value answering the challenge of a `401 Unauthorized` `response`
+    /// to a request with `method` to `uri`, only for `Digest` auth
+    ///
+    /// `answered` is the number of challenges already answered for this request. A challenge is only
+    /// answered again if the server flagged our previous nonce as `stale`.
+    pub(crate) fn answer_challenge<B>(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        response: &Response<B>,
+        answered: usize,
+    ) -> Result<Option<String>> {
+        let (username, password) = match self {
+            Auth::Digest { username, password } => (username, password),
+            _ => return Ok(None),
+        };
+        if response.status() != StatusCode::UNAUTHORIZED || answered > 1 {
+            return Ok(None);
+        }
+        for header in response.headers().get_all(header::WWW_AUTHENTICATE) {
+            if let Some(challenge) = digest::Challenge::parse(header.to_str()?) {
+                if answered > 0 && !challenge.stale {
+                    // our credentials were rejected
+                    return Ok(None);
+                }
+                let cnonce = hex::encode(rand::thread_rng().gen::<[u8; 16]>());
+                let uri = uri.path_and_query().map_or("/", |pq| pq.as_str());
+                return Ok(Some(challenge.authorization(
+                    username,
+                    password,
+                    method.as_str(),
+                    uri,
+                    &cnonce,
+                )));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// tokens are refreshed this long before they expire
+const OAUTH2_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+struct CachedToken {
+    access_token: String,
+    refresh_at: Option<Instant>,
+}
+
+/// Cache for `OAuth2` access tokens, shared between all clones of an `Auth`
+#[derive(Clone, Default)]
+pub(crate) struct TokenCache(Arc<Mutex<Option<CachedToken>>>);
+
+impl TokenCache {
+    async fn get_or_refresh(
+        &self,
+        token_url: &str,
+        client_id: &str,
+        client_secret: &str,
+        scopes: &[String],
+    ) -> Result<String> {
+        // holding the lock while fetching ensures we only fetch one token at a time
+        let mut cached = self.0.lock().await;
+        if let Some(token) = cached.as_ref().filter(|token| {
+            token
+                .refresh_at
+                .map_or(true, |refresh_at| Instant::now() < refresh_at)
+        }) {
+            return Ok(token.access_token.clone());
+        }
+        let mut form = vec![("grant_type", "client_credentials".to_string())];
+        if !scopes.is_empty() {
+            form.push(("scope", scopes.join(" ")));
+        }
+        let response = reqwest::Client::new()
+            .post(token_url)
+            .basic_auth(client_id, Some(client_secret))
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?;
+        let mut body = response.bytes().await?.to_vec();
+        let TokenResponse {
+            access_token,
+            expires_in,
+        } = simd_json::from_slice(&mut body)?;
+        let refresh_at = expires_in.map(|expires_in| {
+            Instant::now() + Duration::from_secs(expires_in).saturating_sub(OAUTH2_EXPIRY_MARGIN)
+        });
+        *cached = Some(CachedToken {
+            access_token: access_token.clone(),
+            refresh_at,
+        });
+        Ok(access_token)
+    }
+}
+
+impl std::fmt::Debug for TokenCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TokenCache")
+    }
+}
+
+/// The cache is runtime state, not part of the configuration
+impl PartialEq for TokenCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+fn default_reload_interval_ms() -> u64 {
+    60_000
+}
+
+fn default_jwt_ttl_secs() -> u64 {
+    300
+}
+
+struct FileToken {
+    token: String,
+    read_at: Instant,
+    /// modification time and length of the file when the token was read
+    version: Option<(SystemTime, u64)>,
+}
+
+/// Cache for `BearerFile` tokens, shared between all clones of an `Auth`
+#[derive(Clone, Default)]
+pub(crate) struct FileTokenCache(Arc<std::sync::Mutex<Option<FileToken>>>);
+
+impl FileTokenCache {
+    fn get_or_reload(&self, path: &str, reload_interval: Duration) -> Result<String> {
+        let mut cached = self
+            .0
+            .lock()
+            .map_err(|_| Error::from("Bearer token cache poisoned"))?;
+        let version = std::fs::metadata(path)
+            .ok()
+            .and_then(|meta| Some((meta.modified().ok()?, meta.len())));
+        if let Some(cached) = cached.as_ref() {
+            let fresh = cached.read_at.elapsed() < reload_interval && version == cached.version;
+            // the file might be missing while it is rotated, we stick to the last good token
+            if fresh || version.is_none() {
+                return Ok(cached.token.clone());
+            }
+        }
+        match read_token(path) {
+            Ok(token) => {
+                *cached = Some(FileToken {
+                    token: token.clone(),
+                    read_at: Instant::now(),
+                    version,
+                });
+                Ok(token)
+            }
+            Err(e) => {
+                let token = cached.as_ref().map(|cached| cached.token.clone());
+                if token.is_some() {
+                    warn!("Unable to reload bearer token from {path}, using the last one: {e}");
+                }
+                token.ok_or(e)
+            }
+        }
+    }
+}
+
+fn read_token(path: &str) -> Result<String> {
+    let token = std::fs::read_to_string(path)?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(format!("Empty bearer token file {path}").into());
+    }
+    Ok(token.to_string())
+}
+
+impl std::fmt::Debug for FileTokenCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FileTokenCache")
+    }
+}
+
+/// The cache is runtime state, not part of the configuration
+impl PartialEq for FileTokenCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+lazy_static! {
+    /// The GCP token of all `Gcp` auths, they all use the credentials of the environment
+    static ref GCP_TOKEN: GcpTokenCache<GouthTokenProvider> = GcpTokenCache::default();
+}
+
+/// Cache for the provider of GCP tokens, so credentials are only read once and the token is refreshed
+/// by the provider before it expires
+#[derive(Default)]
+struct GcpTokenCache<P: TokenProvider>(std::sync::Mutex<P>);
+
+impl<P: TokenProvider> GcpTokenCache<P> {
+    fn header_value(&self) -> Result<String> {
+        let mut provider = self
+            .0
+            .lock()
+            .map_err(|_| Error::from("GCP token cache poisoned"))?;
+        Ok(provider.get_token()?.to_string())
+    }
 }
 
 impl Default for Auth {
@@ -70,8 +443,20 @@ impl Default for Auth {
 }
 
 #[cfg(test)]
+#[allow(deprecated)] // the blocking `as_header_value` is tested alongside `header_value`
 mod tests {
     use super::*;
+    use crate::connectors::tests::free_port::find_free_tcp_port;
+    use hyper::{
+        body::to_bytes,
+        server::conn::AddrStream,
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, StatusCode,
+    };
+    use std::{
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
 
     #[test]
     fn header_value_basic() {
@@ -91,6 +476,51 @@ mod tests {
         assert_eq!(Ok(Some("Bearer token".to_string())), auth.as_header_value());
     }
 
+    #[test]
+    fn header_value_bearer_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("token");
+        std::fs::write(&path, "token-1\n")?;
+        let auth = Auth::BearerFile {
+            path: path.display().to_string(),
+            reload_interval_ms: 3_600_000,
+            token: FileTokenCache::default(),
+        };
+        assert_eq!(Some("Bearer token-1".to_string()), auth.as_header_value()?);
+
+        // a changed file is picked up before the reload interval elapsed
+        std::fs::write(&path, "rotated-token-2\n")?;
+        assert_eq!(
+            Some("Bearer rotated-token-2".to_string()),
+            auth.as_header_value()?
+        );
+
+        // the last token is kept while the file is missing
+        std::fs::remove_file(&path)?;
+        assert_eq!(
+            Some("Bearer rotated-token-2".to_string()),
+            auth.as_header_value()?
+        );
+        // clones share the cache
+        assert_eq!(
+            Some("Bearer rotated-token-2".to_string()),
+            auth.clone().as_header_value()?
+        );
+
+        // without any token read yet a missing file is an error
+        let auth = Auth::BearerFile {
+            path: path.display().to_string(),
+            reload_interval_ms: 0,
+            token: FileTokenCache::default(),
+        };
+        assert!(auth.as_header_value().is_err());
+        std::fs::write(&path, "  \n")?;
+        assert!(auth.as_header_value().is_err());
+        std::fs::write(&path, "token-3")?;
+        assert_eq!(Some("Bearer token-3".to_string()), auth.as_header_value()?);
+        Ok(())
+    }
+
     #[test]
     fn header_value_elastic_api_key() {
         let auth = Auth::ElasticsearchApiKey {
@@ -102,4 +532,269 @@ mod tests {
             auth.as_header_value()
         );
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn header_value_async_matches_blocking() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("token");
+        std::fs::write(&path, "token-1\n")?;
+        let auths = [
+            Auth::Basic {
+                username: "badger".to_string(),
+                password: "snot".to_string(),
+            },
+            Auth::Bearer("token".to_string()),
+            Auth::BearerFile {
+                path: path.display().to_string(),
+                reload_interval_ms: 3_600_000,
+                token: FileTokenCache::default(),
+            },
+            Auth::ElasticsearchApiKey {
+                id: "badger".to_string(),
+                api_key: "snot".to_string(),
+            },
+            Auth::AwsSigV4 {
+                access_key_id: "snot".to_string(),
+                secret_access_key: "badger".to_string(),
+                region: "eu-west-1".to_string(),
+                service: "s3".to_string(),
+                session_token: None,
+            },
+            Auth::None,
+        ];
+        for auth in auths {
+            assert_eq!(auth.as_header_value()?, auth.header_value().await?);
+        }
+        assert_eq!(
+            Some("Basic YmFkZ2VyOnNub3Q=".to_string()),
+            Auth::Basic {
+                username: "badger".to_string(),
+                password: "snot".to_string(),
+            }
+            .header_value()
+            .await?
+        );
+        Ok(())
+    }
+
+    /// Provider creating its token on first use, counting how often it did
+    #[derive(Clone, Default)]
+    struct CountingTokenProvider {
+        token: Option<Arc<String>>,
+        created: Arc<AtomicUsize>,
+    }
+
+    impl TokenProvider for CountingTokenProvider {
+        fn get_token(&mut self) -> std::result::Result<Arc<String>, tonic::Status> {
+            let created = self.created.clone();
+            Ok(self
+                .token
+                .get_or_insert_with(|| {
+                    let n = created.fetch_add(1, Ordering::AcqRel) + 1;
+                    Arc::new(format!("Bearer gcp-token-{n}"))
+                })
+                .clone())
+        }
+    }
+
+    #[test]
+    fn gcp_token_cached() -> Result<()> {
+        let provider = CountingTokenProvider::default();
+        let created = provider.created.clone();
+        let cache = GcpTokenCache(std::sync::Mutex::new(provider));
+        for _ in 0..100 {
+            assert_eq!("Bearer gcp-token-1", cache.header_value()?);
+        }
+        assert_eq!(1, created.load(Ordering::Acquire));
+        Ok(())
+    }
+
+    /// token endpoint handing out `token-<n>` for the n-th request, valid for `expires_in` seconds
+    async fn token_endpoint(
+        expires_in: u64,
+    ) -> Result<(String, Arc<AtomicUsize>, tokio::task::JoinHandle<()>)> {
+        let port = find_free_tcp_port().await?;
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let counter = counter.clone();
+            let service = service_fn(move |req: Request<Body>| {
+                let counter = counter.clone();
+                async move {
+                    let authorized = req
+                        .headers()
+                        .get(hyper::header::AUTHORIZATION)
+                        .map_or(false, |v| v == "Basic c25vdDpiYWRnZXI=");
+                    let body = to_bytes(req.into_body()).await?;
+                    let res = if authorized
+                        && body.as_ref() == b"grant_type=client_credentials&scope=read+write"
+                    {
+                        let n = counter.fetch_add(1, Ordering::AcqRel) + 1;
+                        let token = format!(
+                            r#"{{"access_token":"token-{n}","token_type":"Bearer","expires_in":{expires_in}}}"#
+                        );
+                        Response::builder()
+                            .status(StatusCode::OK)
+                            .body(Body::from(token))
+                    } else {
+                        Response::builder()
+                            .status(StatusCode::BAD_REQUEST)
+                            .body(Body::empty())
+                    };
+                    res.map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+                }
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        let server = hyper::Server::bind(&([127, 0, 0, 1], port).into()).serve(make_service);
+        let handle = tokio::task::spawn(async move {
+            if let Err(e) = server.await {
+                error!("Token endpoint error: {e}");
+            }
+        });
+        Ok((format!("http://127.0.0.1:{port}/token"), requests, handle))
+    }
+
+    fn oauth2(token_url: String) -> Auth {
+        Auth::OAuth2 {
+            token_url,
+            client_id: "snot".to_string(),
+            client_secret: "badger".to_string(),
+            scopes: vec!["read".to_string(), "write".to_string()],
+            token: TokenCache::default(),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn header_value_oauth2_cached() -> Result<()> {
+        let (token_url, requests, server) = token_endpoint(3600).await?;
+        let auth = oauth2(token_url);
+        assert!(auth.as_header_value().is_err());
+        assert_eq!(
+            Some("Bearer token-1".to_string()),
+            auth.header_value().await?
+        );
+        // clones share the cache
+        let clone = auth.clone();
+        assert_eq!(
+            Some("Bearer token-1".to_string()),
+            clone.header_value().await?
+        );
+        assert_eq!(1, requests.load(Ordering::Acquire));
+        server.abort();
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn header_value_oauth2_refresh() -> Result<()> {
+        // tokens expiring within the margin are refreshed on every use
+        let (token_url, requests, server) = token_endpoint(10).await?;
+        let auth = oauth2(token_url);
+        assert_eq!(
+            Some("Bearer token-1".to_string()),
+            auth.header_value().await?
+        );
+        assert_eq!(
+            Some("Bearer token-2".to_string()),
+            auth.header_value().await?
+        );
+        assert_eq!(2, requests.load(Ordering::Acquire));
+        server.abort();
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_oauth2() -> Result<()> {
+        let config = tremor_value::literal!({
+            "oauth2": {
+                "token_url": "http://localhost/token",
+                "client_id": "snot",
+                "client_secret": "badger"
+            }
+        });
+        let auth: Auth = tremor_value::structurize(config)?;
+        assert_eq!(
+            Auth::OAuth2 {
+                token_url: "http://localhost/token".to_string(),
+                client_id: "snot".to_string(),
+                client_secret: "badger".to_string(),
+                scopes: vec![],
+                token: TokenCache::default(),
+            },
+            auth
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn header_value_jwt() -> Result<()> {
+        let config = tremor_value::literal!({
+            "jwt": {
+                "alg": "HS256",
+                "key": "snot",
+                "claims": {"sub": "badger"}
+            }
+        });
+        let auth: Auth = tremor_value::structurize(config)?;
+        assert!(auth.validate().is_ok());
+        let header_value = auth.as_header_value()?.unwrap_or_default();
+        assert!(header_value.starts_with("Bearer eyJ"));
+        // the token is cached
+        assert_eq!(Some(header_value), auth.as_header_value()?);
+
+        let config = tremor_value::literal!({
+            "jwt": {
+                "alg": "RS256",
+                "key": "/snot/badger.pem"
+            }
+        });
+        let auth: Auth = tremor_value::structurize(config)?;
+        assert!(auth.validate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn answer_digest_challenge() -> Result<()> {
+        let auth = Auth::Digest {
+            username: "snot".to_string(),
+            password: "badger".to_string(),
+        };
+        let uri: Uri = "http://localhost/snot?badger=1".parse()?;
+        let challenge = |stale: bool| {
+            Response::builder()
+                .status(StatusCode::UNAUTHORIZED)
+                .header(header::WWW_AUTHENTICATE, r#"Basic realm="tremor""#)
+                .header(
+                    header::WWW_AUTHENTICATE,
+                    format!(r#"Digest realm="tremor", nonce="flork", qop="auth", stale={stale}"#),
+                )
+                .body(())
+        };
+        let authorization = auth
+            .answer_challenge(&Method::GET, &uri, &challenge(false)?, 0)?
+            .unwrap_or_default();
+        assert!(authorization.starts_with(
+            r#"Digest username="snot", realm="tremor", nonce="flork", uri="/snot?badger=1""#
+        ));
+        // rejected credentials are not retried, stale nonces are
+        assert_eq!(
+            None,
+            auth.answer_challenge(&Method::GET, &uri, &challenge(false)?, 1)?
+        );
+        assert!(auth
+            .answer_challenge(&Method::GET, &uri, &challenge(true)?, 1)?
+            .is_some());
+        assert_eq!(
+            None,
+            auth.answer_challenge(&Method::GET, &uri, &challenge(true)?, 2)?
+        );
+        // no challenge
+        let ok = Response::builder().status(StatusCode::OK).body(())?;
+        assert_eq!(None, auth.answer_challenge(&Method::GET, &uri, &ok, 0)?);
+        assert_eq!(
+            None,
+            Auth::None.answer_challenge(&Method::GET, &uri, &challenge(false)?, 0)?
+        );
+        Ok(())
+    }
 }
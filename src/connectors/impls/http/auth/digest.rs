@@ -0,0 +1,274 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP Digest access authentication
+//!
+//! See <https://www.rfc-editor.org/rfc/rfc7616>
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl Algorithm {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "MD5" => Some(Self::Md5),
+            "MD5-SESS" => Some(Self::Md5Sess),
+            "SHA-256" => Some(Self::Sha256),
+            "SHA-256-SESS" => Some(Self::Sha256Sess),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Sess => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn hash(self, data: &str) -> String {
+        match self {
+            Self::Md5 | Self::Md5Sess => hex::encode(Md5::digest(data.as_bytes())),
+            Self::Sha256 | Self::Sha256Sess => hex::encode(Sha256::digest(data.as_bytes())),
+        }
+    }
+
+    fn is_sess(self) -> bool {
+        matches!(self, Self::Md5Sess | Self::Sha256Sess)
+    }
+}
+
+/// A `Digest` challenge from a `WWW-Authenticate` header
+#[derive(Debug, PartialEq)]
+pub(super) struct Challenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: Algorithm,
+    /// `true` if the server supports `qop=auth`, `false` for legacy RFC 2069 challenges
+    qop_auth: bool,
+    /// the previous nonce was rejected for being stale, not for wrong credentials
+    pub(super) stale: bool,
+}
+
+impl Challenge {
+    /// Parse a `WWW-Authenticate` header value
+    ///
+    /// Returns `None` if it is no `Digest` challenge or one we cannot answer.
+    pub(super) fn parse(header: &str) -> Option<Self> {
+        let (scheme, params) = header.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+        let mut params = parse_params(params);
+        let algorithm = params
+            .get("algorithm")
+            .map_or(Some(Algorithm::Md5), |a| Algorithm::parse(a))?;
+        let qop_auth = if let Some(qop) = params.get("qop") {
+            // we only support `auth`, not `auth-int`
+            if !qop.split(',').any(|q| q.trim() == "auth") {
+                return None;
+            }
+            true
+        } else {
+            false
+        };
+        Some(Self {
+            realm: params.remove("realm")?,
+            nonce: params.remove("nonce")?,
+            opaque: params.remove("opaque"),
+            algorithm,
+            qop_auth,
+            stale: params
+                .get("stale")
+                .map_or(false, |s| s.eq_ignore_ascii_case("true")),
+        })
+    }
+
+    /// The `Authorization` header value answering this challenge for a request with `method` to `uri`
+    pub(super) fn authorization(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> String {
+        // every challenge comes with a fresh nonce, so this is always our first use of it
+        const NC: &str = "00000001";
+        let algorithm = self.algorithm;
+        let mut ha1 = algorithm.hash(&format!("{username}:{}:{password}", self.realm));
+        if algorithm.is_sess() {
+            ha1 = algorithm.hash(&format!("{ha1}:{}:{cnonce}", self.nonce));
+        }
+        let ha2 = algorithm.hash(&format!("{method}:{uri}"));
+        let response = if self.qop_auth {
+            algorithm.hash(&format!("{ha1}:{}:{NC}:{cnonce}:auth:{ha2}", self.nonce))
+        } else {
+            algorithm.hash(&format!("{ha1}:{}:{ha2}", self.nonce))
+        };
+        let mut header = format!(
+            r#"Digest username="{username}", realm="{}", nonce="{}", uri="{uri}", algorithm={}, response="{response}""#,
+            self.realm,
+            self.nonce,
+            algorithm.name()
+        );
+        if self.qop_auth {
+            header.push_str(&format!(r#", qop=auth, nc={NC}, cnonce="{cnonce}""#));
+        }
+        if let Some(opaque) = &self.opaque {
+            header.push_str(&format!(r#", opaque="{opaque}""#));
+        }
+        header
+    }
+}
+
+/// Parse comma separated `key=value` or `key="quoted value"` pairs, keys are lowercased
+fn parse_params(mut rest: &str) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().to_ascii_lowercase();
+        let after = after.trim_start();
+        let (value, remainder) = if let Some(quoted) = after.strip_prefix('"') {
+            let mut value = String::new();
+            let mut end = quoted.len();
+            let mut escaped = false;
+            for (i, c) in quoted.char_indices() {
+                if escaped {
+                    value.push(c);
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    end = i + 1;
+                    break;
+                } else {
+                    value.push(c);
+                }
+            }
+            (value, &quoted[end..])
+        } else {
+            let end = after.find(',').unwrap_or(after.len());
+            (after[..end].trim().to_string(), &after[end..])
+        };
+        params.insert(key, value);
+        rest = remainder.trim_start().trim_start_matches(',');
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_challenge() {
+        let challenge = Challenge::parse(
+            r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS", stale=TRUE"#,
+        );
+        assert_eq!(
+            Some(Challenge {
+                realm: "http-auth@example.org".to_string(),
+                nonce: "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v".to_string(),
+                opaque: Some("FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS".to_string()),
+                algorithm: Algorithm::Sha256,
+                qop_auth: true,
+                stale: true,
+            }),
+            challenge
+        );
+        assert_eq!(None, Challenge::parse(r#"Basic realm="snot""#));
+        assert_eq!(
+            None,
+            Challenge::parse(r#"Digest realm="snot", nonce="badger", qop="auth-int""#)
+        );
+        assert_eq!(
+            None,
+            Challenge::parse(r#"Digest realm="snot", nonce="badger", algorithm=SHA-512"#)
+        );
+        assert_eq!(None, Challenge::parse(r#"Digest realm="snot""#));
+    }
+
+    #[test]
+    fn parse_quoted_params() {
+        let params = parse_params(r#"realm="snot, \"badger\"", Nonce=abc ,qop=auth"#);
+        assert_eq!(Some(&r#"snot, "badger""#.to_string()), params.get("realm"));
+        assert_eq!(Some(&"abc".to_string()), params.get("nonce"));
+        assert_eq!(Some(&"auth".to_string()), params.get("qop"));
+    }
+
+    // RFC 2617 section 3.5
+    #[test]
+    fn rfc2617_md5() {
+        let challenge = Challenge::parse(
+            r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+        );
+        let header = challenge.map(|c| {
+            c.authorization(
+                "Mufasa",
+                "Circle Of Life",
+                "GET",
+                "/dir/index.html",
+                "0a4f113b",
+            )
+        });
+        assert_eq!(
+            Some(r#"Digest username="Mufasa", realm="testrealm@host.com", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", uri="/dir/index.html", algorithm=MD5, response="6629fae49393a05397450978507c4ef1", qop=auth, nc=00000001, cnonce="0a4f113b", opaque="5ccc069c403ebaf9f0171e9517f40e41""#.to_string()),
+            header
+        );
+    }
+
+    // RFC 7616 section 3.9.1
+    #[test]
+    fn rfc7616_sha256_and_md5() {
+        let challenge = |algorithm| {
+            Challenge::parse(&format!(
+                r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm={algorithm}, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#
+            ))
+        };
+        let response = |challenge: Option<Challenge>| {
+            challenge.map(|c| {
+                let header = c.authorization(
+                    "Mufasa",
+                    "Circle of Life",
+                    "GET",
+                    "/dir/index.html",
+                    "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+                );
+                parse_params(header.trim_start_matches("Digest "))
+                    .remove("response")
+                    .unwrap_or_default()
+            })
+        };
+        assert_eq!(
+            Some("753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1".to_string()),
+            response(challenge("SHA-256"))
+        );
+        assert_eq!(
+            Some("8ca523f5e9506fed4657c9700eebdbec".to_string()),
+            response(challenge("MD5"))
+        );
+    }
+}
@@ -0,0 +1,292 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Self-signed JSON Web Tokens, see <https://www.rfc-editor.org/rfc/rfc7519>
+
+use crate::connectors::utils::tls::load_keys;
+use crate::errors::{Error, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use ring::{
+    rand::SystemRandom,
+    signature::{RsaKeyPair, RSA_PKCS1_SHA256},
+};
+use sha2::Sha256;
+use simd_json::OwnedValue;
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// tokens are re-signed this long before they expire
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// Signing algorithm of a JWT
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Algorithm {
+    /// HMAC with SHA-256, signed with a shared secret
+    #[serde(rename = "HS256")]
+    Hs256,
+    /// RSASSA-PKCS1-v1_5 with SHA-256, signed with an RSA private key
+    #[serde(rename = "RS256")]
+    Rs256,
+}
+
+impl Algorithm {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hs256 => "HS256",
+            Self::Rs256 => "RS256",
+        }
+    }
+}
+
+/// A key to sign tokens with
+pub(super) enum Key {
+    Hmac(Vec<u8>),
+    Rsa(RsaKeyPair),
+}
+
+impl Key {
+    /// Load the key for `alg`, `key` is the shared secret for `HS256` and the path to a PEM file for `RS256`
+    pub(super) fn load(alg: Algorithm, key: &str) -> Result<Self> {
+        match alg {
+            Algorithm::Hs256 if key.is_empty() => Err("Empty JWT secret".into()),
+            Algorithm::Hs256 => Ok(Self::Hmac(key.as_bytes().to_vec())),
+            Algorithm::Rs256 => {
+                let der = load_keys(Path::new(key))?.0;
+                // `load_keys` prefers PKCS8 keys and falls back to PKCS1 RSA keys
+                let key_pair = RsaKeyPair::from_pkcs8(&der)
+                    .or_else(|_| RsaKeyPair::from_der(&der))
+                    .map_err(|e| format!("Invalid RSA private key in {key}: {e}"))?;
+                Ok(Self::Rsa(key_pair))
+            }
+        }
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Hmac(secret) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret).map_err(|e| e.to_string())?;
+                mac.update(message);
+                Ok(mac.finalize().into_bytes().to_vec())
+            }
+            Self::Rsa(key_pair) => {
+                let mut signature = vec![0; key_pair.public_modulus_len()];
+                key_pair
+                    .sign(
+                        &RSA_PKCS1_SHA256,
+                        &SystemRandom::new(),
+                        message,
+                        &mut signature,
+                    )
+                    .map_err(|_| Error::from("Unable to sign JWT"))?;
+                Ok(signature)
+            }
+        }
+    }
+}
+
+/// A signed token with the given `claims` plus `iat` and `exp`, issued at `now` seconds since the epoch
+fn mint(
+    alg: Algorithm,
+    key: &Key,
+    claims: &BTreeMap<String, OwnedValue>,
+    now: u64,
+    ttl_secs: u64,
+) -> Result<String> {
+    let header = format!(r#"{{"alg":"{}","typ":"JWT"}}"#, alg.name());
+    let mut claims = claims.clone();
+    claims.insert("iat".to_string(), OwnedValue::from(now));
+    claims.insert("exp".to_string(), OwnedValue::from(now + ttl_secs));
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header),
+        URL_SAFE_NO_PAD.encode(simd_json::to_string(&claims)?)
+    );
+    let signature = key.sign(signing_input.as_bytes())?;
+    Ok(format!(
+        "{signing_input}.{}",
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+struct SignedToken {
+    token: String,
+    refresh_at: Instant,
+}
+
+/// Cache for signed tokens, shared between all clones of an `Auth`
+#[derive(Clone, Default)]
+pub(crate) struct TokenCache(Arc<Mutex<Option<SignedToken>>>);
+
+impl TokenCache {
+    pub(super) fn get_or_sign(
+        &self,
+        alg: Algorithm,
+        key: &str,
+        claims: &BTreeMap<String, OwnedValue>,
+        ttl_secs: u64,
+    ) -> Result<String> {
+        let mut cached = self
+            .0
+            .lock()
+            .map_err(|_| Error::from("JWT cache poisoned"))?;
+        if let Some(token) = cached
+            .as_ref()
+            .filter(|token| Instant::now() < token.refresh_at)
+        {
+            return Ok(token.token.clone());
+        }
+        // the key is loaded anew for every token, so rotated key files are picked up
+        let key = Key::load(alg, key)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+        let token = mint(alg, &key, claims, now, ttl_secs)?;
+        *cached = Some(SignedToken {
+            token: token.clone(),
+            refresh_at: Instant::now()
+                + Duration::from_secs(ttl_secs).saturating_sub(EXPIRY_MARGIN),
+        });
+        Ok(token)
+    }
+}
+
+impl std::fmt::Debug for TokenCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("JwtTokenCache")
+    }
+}
+
+/// The cache is runtime state, not part of the configuration
+impl PartialEq for TokenCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::tests::setup_for_tls;
+    use ring::signature::{KeyPair, UnparsedPublicKey, RSA_PKCS1_2048_8192_SHA256};
+    use simd_json::prelude::*;
+
+    fn claims() -> BTreeMap<String, OwnedValue> {
+        let mut claims = BTreeMap::new();
+        claims.insert("iss".to_string(), OwnedValue::from("tremor"));
+        claims.insert("aud".to_string(), OwnedValue::from(vec!["snot", "badger"]));
+        claims
+    }
+
+    /// The decoded header and claims of `token` and the signature over the signing input
+    fn decode(token: &str) -> Result<(OwnedValue, OwnedValue, String, Vec<u8>)> {
+        let parts: Vec<&str> = token.split('.').collect();
+        if let [header, claims, signature] = parts.as_slice() {
+            let mut header_json = URL_SAFE_NO_PAD.decode(header)?;
+            let mut claims_json = URL_SAFE_NO_PAD.decode(claims)?;
+            Ok((
+                simd_json::to_owned_value(&mut header_json)?,
+                simd_json::to_owned_value(&mut claims_json)?,
+                format!("{header}.{claims}"),
+                URL_SAFE_NO_PAD.decode(signature)?,
+            ))
+        } else {
+            Err(format!("Invalid JWT {token}").into())
+        }
+    }
+
+    #[test]
+    fn hs256() -> Result<()> {
+        let key = Key::load(Algorithm::Hs256, "snot")?;
+        let token = mint(Algorithm::Hs256, &key, &claims(), 1_000_000, 60)?;
+        let (header, claims, signing_input, signature) = decode(&token)?;
+        assert_eq!(Some("HS256"), header.get_str("alg"));
+        assert_eq!(Some("JWT"), header.get_str("typ"));
+        assert_eq!(Some("tremor"), claims.get_str("iss"));
+        assert_eq!(Some(2), claims.get_array("aud").map(Vec::len));
+        assert_eq!(Some(1_000_000), claims.get_u64("iat"));
+        assert_eq!(Some(1_000_060), claims.get_u64("exp"));
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"snot").map_err(|e| e.to_string())?;
+        mac.update(signing_input.as_bytes());
+        assert!(mac.verify_slice(&signature).is_ok());
+        // a different secret doesn't verify
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"badger").map_err(|e| e.to_string())?;
+        mac.update(signing_input.as_bytes());
+        assert!(mac.verify_slice(&signature).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rs256() -> Result<()> {
+        setup_for_tls();
+        let key = Key::load(Algorithm::Rs256, "./tests/localhost.key")?;
+        let token = mint(Algorithm::Rs256, &key, &claims(), 1_000_000, 60)?;
+        let (header, claims, signing_input, signature) = decode(&token)?;
+        assert_eq!(Some("RS256"), header.get_str("alg"));
+        assert_eq!(Some("tremor"), claims.get_str("iss"));
+        assert_eq!(Some(1_000_060), claims.get_u64("exp"));
+
+        let public_key = if let Key::Rsa(key_pair) = &key {
+            key_pair.public_key().as_ref().to_vec()
+        } else {
+            return Err("Expected an RSA key".into());
+        };
+        let public_key = UnparsedPublicKey::new(&RSA_PKCS1_2048_8192_SHA256, public_key);
+        assert!(public_key
+            .verify(signing_input.as_bytes(), &signature)
+            .is_ok());
+        assert!(public_key.verify(b"snot.badger", &signature).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_keys() {
+        assert!(Key::load(Algorithm::Hs256, "").is_err());
+        assert!(Key::load(Algorithm::Rs256, "snot").is_err());
+        // not a private key
+        setup_for_tls();
+        assert!(Key::load(Algorithm::Rs256, "./tests/localhost.cert").is_err());
+    }
+
+    #[test]
+    fn cached() -> Result<()> {
+        let cache = TokenCache::default();
+        let token = cache.get_or_sign(Algorithm::Hs256, "snot", &claims(), 3600)?;
+        // clones share the cache
+        assert_eq!(
+            token,
+            cache
+                .clone()
+                .get_or_sign(Algorithm::Hs256, "snot", &claims(), 3600)?
+        );
+
+        // tokens expiring within the margin are re-signed on every use
+        let cache = TokenCache::default();
+        cache.get_or_sign(Algorithm::Hs256, "snot", &claims(), 10)?;
+        let refresh_at = cache
+            .0
+            .lock()
+            .map_err(|_| Error::from("JWT cache poisoned"))?
+            .as_ref()
+            .map(|token| token.refresh_at);
+        assert!(refresh_at.map_or(false, |refresh_at| refresh_at <= Instant::now()));
+        Ok(())
+    }
+}
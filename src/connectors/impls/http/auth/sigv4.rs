@@ -0,0 +1,313 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! AWS Signature Version 4 request signing
+//!
+//! See <https://docs.aws.amazon.com/general/latest/gr/sigv4_signing.html>
+
+use crate::errors::Result;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use http::{
+    header::{self, HeaderMap, HeaderValue},
+    Request,
+};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const X_AMZ_DATE: &str = "x-amz-date";
+const X_AMZ_SECURITY_TOKEN: &str = "x-amz-security-token";
+const X_AMZ_CONTENT_SHA256: &str = "x-amz-content-sha256";
+
+/// Everything needed to sign a request
+pub(super) struct Credentials<'a> {
+    pub(super) access_key_id: &'a str,
+    pub(super) secret_access_key: &'a str,
+    pub(super) session_token: Option<&'a str>,
+    pub(super) region: &'a str,
+    pub(super) service: &'a str,
+}
+
+/// Sign `request` with its `body` at time `now`, adding the `Authorization` and `x-amz-*` headers
+pub(super) fn sign<B>(
+    request: &mut Request<B>,
+    body: &[u8],
+    credentials: &Credentials,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = &amz_date[..8];
+    let payload_hash = hex::encode(Sha256::digest(body));
+    let is_s3 = credentials.service == "s3";
+
+    let host = request
+        .uri()
+        .authority()
+        .map(|authority| HeaderValue::from_str(authority.as_str()))
+        .transpose()?;
+    let headers = request.headers_mut();
+    if let Some(host) = host {
+        // hyper would add it anyways, but we need it to be signed
+        headers.entry(header::HOST).or_insert(host);
+    }
+    headers.insert(X_AMZ_DATE, HeaderValue::from_str(&amz_date)?);
+    if let Some(session_token) = credentials.session_token {
+        headers.insert(X_AMZ_SECURITY_TOKEN, HeaderValue::from_str(session_token)?);
+    }
+    if is_s3 {
+        headers.insert(X_AMZ_CONTENT_SHA256, HeaderValue::from_str(&payload_hash)?);
+    }
+
+    let (canonical_headers, signed_headers) = canonical_headers(request.headers())?;
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method(),
+        canonical_uri(request.uri().path(), is_s3),
+        canonical_query(request.uri().query().unwrap_or_default()),
+        canonical_headers,
+        signed_headers,
+        payload_hash
+    );
+    let scope = format!(
+        "{date}/{}/{}/aws4_request",
+        credentials.region, credentials.service
+    );
+    let string_to_sign = format!(
+        "{ALGORITHM}\n{amz_date}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = hmac(
+        format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+        date.as_bytes(),
+    )?;
+    let key = hmac(&key, credentials.region.as_bytes())?;
+    let key = hmac(&key, credentials.service.as_bytes())?;
+    let key = hmac(&key, b"aws4_request")?;
+    let signature = hex::encode(hmac(&key, string_to_sign.as_bytes())?);
+
+    let authorization = format!(
+        "{ALGORITHM} Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        credentials.access_key_id
+    );
+    request.headers_mut().insert(
+        header::AUTHORIZATION,
+        HeaderValue::from_str(&authorization)?,
+    );
+    Ok(())
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| e.to_string())?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+/// Percent-encode everything but unreserved characters (and `/` if `keep_slash` is set)
+fn uri_encode(input: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for b in input.bytes() {
+        if b.is_ascii_alphanumeric()
+            || matches!(b, b'-' | b'_' | b'.' | b'~')
+            || (keep_slash && b == b'/')
+        {
+            encoded.push(char::from(b));
+        } else {
+            encoded.push('%');
+            encoded.push_str(&hex::encode_upper([b]));
+        }
+    }
+    encoded
+}
+
+/// S3 expects the path as it was sent, all other services expect it to be encoded twice
+fn canonical_uri(path: &str, is_s3: bool) -> String {
+    if path.is_empty() {
+        "/".to_string()
+    } else if is_s3 {
+        path.to_string()
+    } else {
+        uri_encode(path, true)
+    }
+}
+
+fn canonical_query(query: &str) -> String {
+    let mut params: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes())
+        .map(|(k, v)| (uri_encode(&k, false), uri_encode(&v, false)))
+        .collect();
+    params.sort();
+    params
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Returns the canonical headers and the list of signed headers
+fn canonical_headers(headers: &HeaderMap) -> Result<(String, String)> {
+    let mut sorted: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (name, value) in headers {
+        // values are trimmed and sequential spaces are collapsed
+        let value = value
+            .to_str()?
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        sorted.entry(name.as_str()).or_default().push(value);
+    }
+    let canonical = sorted
+        .iter()
+        .map(|(name, values)| format!("{name}:{}\n", values.join(",")))
+        .collect();
+    let signed = sorted.keys().copied().collect::<Vec<_>>().join(";");
+    Ok((canonical, signed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // test vectors from the AWS SigV4 test suite
+    const CREDENTIALS: Credentials = Credentials {
+        access_key_id: "AKIDEXAMPLE",
+        secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        session_token: None,
+        region: "us-east-1",
+        service: "service",
+    };
+
+    fn now() -> Result<DateTime<Utc>> {
+        Ok(Utc
+            .with_ymd_and_hms(2015, 8, 30, 12, 36, 0)
+            .single()
+            .ok_or("invalid date")?)
+    }
+
+    fn signature(
+        mut request: Request<()>,
+        body: &[u8],
+        credentials: &Credentials,
+    ) -> Result<String> {
+        sign(&mut request, body, credentials, now()?)?;
+        assert_eq!(
+            Some("20150830T123600Z"),
+            request
+                .headers()
+                .get(X_AMZ_DATE)
+                .and_then(|v| v.to_str().ok())
+        );
+        Ok(request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .ok_or("no authorization header")?
+            .to_str()?
+            .to_string())
+    }
+
+    #[test]
+    fn get_vanilla() -> Result<()> {
+        let request = Request::get("https://example.amazonaws.com/").body(())?;
+        assert_eq!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31",
+            signature(request, b"", &CREDENTIALS)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn get_vanilla_query_order_key_case() -> Result<()> {
+        let request =
+            Request::get("https://example.amazonaws.com/?Param2=value2&Param1=value1").body(())?;
+        assert_eq!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500",
+            signature(request, b"", &CREDENTIALS)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn post_vanilla() -> Result<()> {
+        let request = Request::post("https://example.amazonaws.com/").body(())?;
+        assert_eq!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b",
+            signature(request, b"", &CREDENTIALS)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn post_x_www_form_urlencoded() -> Result<()> {
+        let request = Request::post("https://example.amazonaws.com/")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(())?;
+        assert_eq!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature=ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a",
+            signature(request, b"Param1=value1", &CREDENTIALS)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn get_header_value_trim() -> Result<()> {
+        let request = Request::get("https://example.amazonaws.com/")
+            .header("My-Header1", " value1")
+            .header("My-Header2", " \"a   b   c\"")
+            .body(())?;
+        assert_eq!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;my-header1;my-header2;x-amz-date, Signature=acc3ed3afb60bb290fc8d2dd0098b9911fcaa05412b367055dee359757a9c736",
+            signature(request, b"", &CREDENTIALS)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn post_sts_token() -> Result<()> {
+        let credentials = Credentials {
+            session_token: Some("AQoDYXdzEPT//////////wEXAMPLEtc764bNrC9SAPBSM22wDOk4x4HIZ8j4FZTwdQWLWsKWHGBuFqwAeMicRXmxfpSPfIeoIYRqTflfKD8YUuwthAx7mSEI/qkPpKPi/kMcGdQrmGdeehM4IC1NtBmUpp2wUE8phUZampKsburEDy0KPkyQDYwT7WZ0wq5VSXDvp75YU9HFvlRd8Tx6q6fE8YQcHNVXAkiY9q6d+xo0rKwT38xVqr7ZD0u0iPPkUL64lIZbqBAz+scqKmlzm8FDrypNC9Yjc8fPOLn9FX9KSYvKTr4rvx3iSIlTJabIQwj2ICCR/oLxBA=="),
+            ..CREDENTIALS
+        };
+        let request = Request::post("https://example.amazonaws.com/").body(())?;
+        assert_eq!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date;x-amz-security-token, Signature=85d96828115b5dc0cfc3bd16ad9e210dd772bbebba041836c64533a82be05ead",
+            signature(request, b"", &credentials)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn s3_content_hash() -> Result<()> {
+        let credentials = Credentials {
+            service: "s3",
+            ..CREDENTIALS
+        };
+        let mut request =
+            Request::put("https://examplebucket.s3.amazonaws.com/snot%20badger").body(())?;
+        sign(&mut request, b"", &credentials, now()?)?;
+        assert_eq!(
+            Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            request
+                .headers()
+                .get(X_AMZ_CONTENT_SHA256)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_eq!("/snot%20badger", canonical_uri(request.uri().path(), true));
+        assert_eq!(
+            "/snot%2520badger",
+            canonical_uri(request.uri().path(), false)
+        );
+        Ok(())
+    }
+}
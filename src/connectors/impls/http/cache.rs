@@ -0,0 +1,506 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! In-memory cache for the responses to `GET` requests of the `http_client`
+//!
+//! `200 OK` responses are stored unless their `Cache-Control` contains `no-store` or they carry a `Vary`
+//! header. They are fresh for their `max-age`, minus their `Age`, and returned without sending a request.
+//! Responses without a `max-age` or with `no-cache` are stored if they carry an `ETag` or `Last-Modified`
+//! validator, as are responses that became stale: they are revalidated with a conditional request using
+//! `If-None-Match` or `If-Modified-Since`. A `304 Not Modified` answer renews the stored response, any other
+//! answer replaces it.
+//!
+//! Responses are stored per url and per value of the request headers that might change them, like
+//! `Authorization` or `Accept`, so a response is never answered to a request with other credentials.
+//!
+//! The size of the cache is bounded by `cache_max_bytes`, the least recently used responses are evicted first.
+
+use crate::connectors::prelude::*;
+use halfbrown::HashMap;
+use hyper::{
+    body::Bytes,
+    header::{self, HeaderMap},
+    Body, Request, Response, StatusCode,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Request headers that might change the response, their values are part of the cache key
+const KEY_HEADERS: [header::HeaderName; 5] = [
+    header::AUTHORIZATION,
+    header::ACCEPT,
+    header::ACCEPT_ENCODING,
+    header::ACCEPT_LANGUAGE,
+    header::COOKIE,
+];
+
+/// The `Cache-Control` directives relevant for a private cache
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    max_age: Option<u64>,
+}
+
+impl CacheControl {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let mut cache_control = Self::default();
+        let directives = headers
+            .get_all(header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','));
+        for directive in directives {
+            let (name, value) = directive
+                .split_once('=')
+                .map_or((directive, None), |(name, value)| (name, Some(value)));
+            match name.trim().to_ascii_lowercase().as_str() {
+                "no-store" => cache_control.no_store = true,
+                "no-cache" => cache_control.no_cache = true,
+                "max-age" => {
+                    cache_control.max_age =
+                        value.and_then(|v| v.trim().trim_matches('"').parse().ok());
+                }
+                _ => (),
+            }
+        }
+        cache_control
+    }
+
+    /// How long a response with `headers` is fresh after it was received
+    fn freshness(&self, headers: &HeaderMap) -> Duration {
+        if self.no_cache {
+            return Duration::ZERO;
+        }
+        let age = headers
+            .get(header::AGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or_default();
+        Duration::from_secs(self.max_age.unwrap_or_default().saturating_sub(age))
+    }
+}
+
+fn has_validator(headers: &HeaderMap) -> bool {
+    headers.contains_key(header::ETAG) || headers.contains_key(header::LAST_MODIFIED)
+}
+
+struct Entry {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    /// time the response was received or last revalidated
+    stored: Instant,
+    freshness: Duration,
+    size: usize,
+    last_used: u64,
+}
+
+impl Entry {
+    fn response(&self) -> Response<Body> {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        response
+    }
+
+    fn is_fresh(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.stored) < self.freshness
+    }
+}
+
+#[derive(Default)]
+struct Entries {
+    entries: HashMap<String, Entry>,
+    /// size of all entries in bytes
+    size: usize,
+    /// counter to track the use of entries
+    tick: u64,
+}
+
+impl Entries {
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.size -= entry.size;
+        }
+    }
+
+    fn insert(&mut self, key: String, mut entry: Entry, max_bytes: usize) {
+        self.remove(&key);
+        self.tick += 1;
+        entry.last_used = self.tick;
+        self.size += entry.size;
+        self.entries.insert(key, entry);
+        while self.size > max_bytes {
+            let lru = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(lru) = lru {
+                self.remove(&lru);
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// Result of looking up a request in the cache
+pub(super) enum Lookup {
+    /// a fresh response, to be used without sending the request
+    Fresh(Response<Body>),
+    /// a stale response, the request was turned into a conditional one to revalidate it
+    Stale,
+    /// nothing stored for the request
+    Miss,
+}
+
+/// Bounded in-memory cache for responses to `GET` requests
+pub(crate) struct ResponseCache {
+    max_bytes: usize,
+    entries: Mutex<Entries>,
+}
+
+impl ResponseCache {
+    pub(super) fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// The cache key of `request`, its url and a hash of the `KEY_HEADERS` it carries
+    ///
+    /// The headers are hashed to keep credentials out of the keys.
+    pub(super) fn key(request: &Request<Body>) -> String {
+        let mut key = request.uri().to_string();
+        let mut hasher = Sha256::new();
+        let mut hashed = false;
+        for name in &KEY_HEADERS {
+            for value in request.headers().get_all(name) {
+                hasher.update(name.as_str());
+                hasher.update(b":");
+                hasher.update(value.as_bytes());
+                hasher.update(b"\n");
+                hashed = true;
+            }
+        }
+        if hashed {
+            key.push(' ');
+            key.push_str(&hex::encode(hasher.finalize()));
+        }
+        key
+    }
+
+    fn entries(&self) -> Result<std::sync::MutexGuard<Entries>> {
+        self.entries
+            .lock()
+            .map_err(|_| Error::from("Poisoned response cache"))
+    }
+
+    /// Look up the response to `request`
+    ///
+    /// If the stored response is stale, the validators of it are added to `request`.
+    pub(super) fn lookup(&self, request: &mut Request<Body>, now: Instant) -> Result<Lookup> {
+        let mut entries = self.entries()?;
+        entries.tick += 1;
+        let tick = entries.tick;
+        let entry = if let Some(entry) = entries.entries.get_mut(&Self::key(request)) {
+            entry
+        } else {
+            return Ok(Lookup::Miss);
+        };
+        entry.last_used = tick;
+        if entry.is_fresh(now) {
+            return Ok(Lookup::Fresh(entry.response()));
+        }
+        let headers = request.headers_mut();
+        if let Some(etag) = entry.headers.get(header::ETAG) {
+            headers.insert(header::IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = entry.headers.get(header::LAST_MODIFIED) {
+            headers.insert(header::IF_MODIFIED_SINCE, last_modified.clone());
+        }
+        Ok(Lookup::Stale)
+    }
+
+    /// The stored response for a `304 Not Modified` answer to a conditional request, renewed with the
+    /// headers of the answer
+    pub(super) fn revalidated(
+        &self,
+        key: &str,
+        not_modified: &Response<Body>,
+        now: Instant,
+    ) -> Result<Option<Response<Body>>> {
+        let mut entries = self.entries()?;
+        let entry = if let Some(entry) = entries.entries.get_mut(key) {
+            entry
+        } else {
+            return Ok(None);
+        };
+        for name in not_modified.headers().keys() {
+            entry.headers.remove(name);
+        }
+        for (name, value) in not_modified.headers() {
+            entry.headers.append(name, value.clone());
+        }
+        entry.stored = now;
+        entry.freshness = CacheControl::from_headers(&entry.headers).freshness(&entry.headers);
+        Ok(Some(entry.response()))
+    }
+
+    /// Store `response` if it can be cached, buffering its body
+    ///
+    /// Returns the response to use instead of the given one.
+    pub(super) async fn store(
+        &self,
+        key: String,
+        response: Response<Body>,
+        now: Instant,
+    ) -> Result<Response<Body>> {
+        let cache_control = CacheControl::from_headers(response.headers());
+        let freshness = cache_control.freshness(response.headers());
+        let cacheable = response.status() == StatusCode::OK
+            && !cache_control.no_store
+            && !response.headers().contains_key(header::VARY)
+            && (freshness > Duration::ZERO || has_validator(response.headers()));
+        if !cacheable {
+            self.entries()?.remove(&key);
+            return Ok(response);
+        }
+        let (parts, body) = response.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        let size = key.len()
+            + body.len()
+            + parts
+                .headers
+                .iter()
+                .map(|(name, value)| name.as_str().len() + value.len())
+                .sum::<usize>();
+        let entry = Entry {
+            status: parts.status,
+            headers: parts.headers.clone(),
+            body: body.clone(),
+            stored: now,
+            freshness,
+            size,
+            last_used: 0,
+        };
+        let mut entries = self.entries()?;
+        if size <= self.max_bytes {
+            entries.insert(key, entry, self.max_bytes);
+        } else {
+            entries.remove(&key);
+        }
+        Ok(Response::from_parts(parts, Body::from(body)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(cache_control: &str, body: &str) -> Result<Response<Body>> {
+        Ok(Response::builder()
+            .header(header::CACHE_CONTROL, cache_control)
+            .header(header::ETAG, "\"v1\"")
+            .body(Body::from(body.to_string()))?)
+    }
+
+    fn get(uri: &str) -> Result<Request<Body>> {
+        Ok(Request::get(uri).body(Body::empty())?)
+    }
+
+    #[test]
+    fn cache_control() -> Result<()> {
+        let headers = |cache_control: &str| -> Result<HeaderMap> {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::CACHE_CONTROL, cache_control.parse()?);
+            Ok(headers)
+        };
+        assert_eq!(
+            CacheControl {
+                no_store: false,
+                no_cache: false,
+                max_age: Some(60)
+            },
+            CacheControl::from_headers(&headers("public, Max-Age=\"60\"")?)
+        );
+        assert!(CacheControl::from_headers(&headers("no-store")?).no_store);
+
+        let mut aged = headers("max-age=60")?;
+        aged.insert(header::AGE, "20".parse()?);
+        let cache_control = CacheControl::from_headers(&aged);
+        assert_eq!(Duration::from_secs(40), cache_control.freshness(&aged));
+        let no_cache = headers("no-cache, max-age=60")?;
+        assert_eq!(
+            Duration::ZERO,
+            CacheControl::from_headers(&no_cache).freshness(&no_cache)
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn fresh_and_stale() -> Result<()> {
+        let cache = ResponseCache::new(1024);
+        let now = Instant::now();
+        let mut request = get("http://snot/badger")?;
+        assert!(matches!(cache.lookup(&mut request, now)?, Lookup::Miss));
+        let key = ResponseCache::key(&request);
+        let response = cache
+            .store(key.clone(), response("max-age=10", "snot")?, now)
+            .await?;
+        assert_eq!(
+            &b"snot"[..],
+            &hyper::body::to_bytes(response.into_body()).await?[..]
+        );
+
+        let cached = if let Lookup::Fresh(cached) = cache.lookup(&mut request, now)? {
+            cached
+        } else {
+            return Err("expected a fresh response".into());
+        };
+        assert_eq!(
+            &b"snot"[..],
+            &hyper::body::to_bytes(cached.into_body()).await?[..]
+        );
+        assert!(request.headers().get(header::IF_NONE_MATCH).is_none());
+
+        // stale responses turn the request into a conditional one
+        let later = now + Duration::from_secs(10);
+        assert!(matches!(cache.lookup(&mut request, later)?, Lookup::Stale));
+        assert_eq!(
+            Some("\"v1\""),
+            request
+                .headers()
+                .get(header::IF_NONE_MATCH)
+                .and_then(|v| v.to_str().ok())
+        );
+        let not_modified = Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::CACHE_CONTROL, "max-age=20")
+            .body(Body::empty())?;
+        let renewed = cache
+            .revalidated(&key, &not_modified, later)?
+            .ok_or("no cached response")?;
+        assert_eq!(StatusCode::OK, renewed.status());
+        assert!(matches!(
+            cache.lookup(
+                &mut get("http://snot/badger")?,
+                later + Duration::from_secs(19)
+            )?,
+            Lookup::Fresh(_)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn not_cacheable() -> Result<()> {
+        let cache = ResponseCache::new(1024);
+        let now = Instant::now();
+        let no_store = response("no-store", "snot")?;
+        cache.store("a".to_string(), no_store, now).await?;
+        let vary = Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=10")
+            .header(header::VARY, "accept")
+            .body(Body::from("snot"))?;
+        cache.store("b".to_string(), vary, now).await?;
+        let error = Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header(header::CACHE_CONTROL, "max-age=10")
+            .body(Body::from("snot"))?;
+        cache.store("c".to_string(), error, now).await?;
+        // too large
+        cache
+            .store(
+                "d".to_string(),
+                response("max-age=10", &"x".repeat(2000))?,
+                now,
+            )
+            .await?;
+        assert!(cache.entries()?.entries.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn keyed_by_credentials() -> Result<()> {
+        let cache = ResponseCache::new(1024);
+        let now = Instant::now();
+        let request = |authorization: &str| -> Result<Request<Body>> {
+            Ok(Request::get("http://snot/badger")
+                .header(header::AUTHORIZATION, authorization)
+                .body(Body::empty())?)
+        };
+        let mut snot = request("Bearer snot")?;
+        let key = ResponseCache::key(&snot);
+        assert!(!key.contains("Bearer"), "credentials leaked into {key}");
+        cache
+            .store(key, response("max-age=10", "snot")?, now)
+            .await?;
+        assert!(matches!(cache.lookup(&mut snot, now)?, Lookup::Fresh(_)));
+        // the response is not shared with other or missing credentials
+        assert!(matches!(
+            cache.lookup(&mut request("Bearer badger")?, now)?,
+            Lookup::Miss
+        ));
+        assert!(matches!(
+            cache.lookup(&mut get("http://snot/badger")?, now)?,
+            Lookup::Miss
+        ));
+        // neither with another representation
+        let mut gzip = request("Bearer snot")?;
+        gzip.headers_mut()
+            .insert(header::ACCEPT_ENCODING, "gzip".parse()?);
+        assert!(matches!(cache.lookup(&mut gzip, now)?, Lookup::Miss));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn eviction() -> Result<()> {
+        let cache = ResponseCache::new(300);
+        let now = Instant::now();
+        let body = "x".repeat(50);
+        for uri in ["http://snot/1", "http://snot/2", "http://snot/3"] {
+            let key = ResponseCache::key(&get(uri)?);
+            cache
+                .store(key, response("max-age=10", &body)?, now)
+                .await?;
+        }
+        // using the first response makes the second one the least recently used
+        assert!(matches!(
+            cache.lookup(&mut get("http://snot/1")?, now)?,
+            Lookup::Fresh(_)
+        ));
+        let key = ResponseCache::key(&get("http://snot/4")?);
+        cache
+            .store(key, response("max-age=10", &body)?, now)
+            .await?;
+        assert!(cache.entries()?.size <= 300);
+        assert!(matches!(
+            cache.lookup(&mut get("http://snot/2")?, now)?,
+            Lookup::Miss
+        ));
+        for uri in ["http://snot/1", "http://snot/4"] {
+            assert!(matches!(
+                cache.lookup(&mut get(uri)?, now)?,
+                Lookup::Fresh(_)
+            ));
+        }
+        Ok(())
+    }
+}
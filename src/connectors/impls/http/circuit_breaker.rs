@@ -0,0 +1,325 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per host circuit breakers, keeping the `http_client` from sending requests to a failing host
+//!
+//! A request fails if it couldn't be sent or was answered with a `5xx` status, after all retries.
+//! The breaker of a host opens after `max_consecutive_failures` failed requests in a row, or when the ratio
+//! of failed requests among the last `window` requests reaches `error_rate`. While it is open, requests to
+//! the host fail right away. After `cooldown_ms` it half-opens and lets a single probe request through,
+//! which closes the breaker again if it succeeds and re-opens it for another cooldown otherwise.
+
+use crate::connectors::prelude::*;
+use halfbrown::HashMap;
+use hyper::Uri;
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Configuration for the circuit breakers of the `http_client`
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CircuitBreaker {
+    /// number of failed requests in a row that open the breaker
+    #[serde(default = "default_max_consecutive_failures")]
+    pub(super) max_consecutive_failures: u32,
+    /// ratio of failed requests among the last `window` requests that opens the breaker
+    #[serde(default = "Default::default")]
+    pub(super) error_rate: Option<f64>,
+    /// number of most recent requests the `error_rate` is calculated over
+    #[serde(default = "default_window")]
+    pub(super) window: usize,
+    /// time in milliseconds an open breaker waits before letting a probe request through
+    #[serde(default = "default_cooldown_ms")]
+    pub(super) cooldown_ms: u64,
+}
+
+fn default_max_consecutive_failures() -> u32 {
+    5
+}
+
+fn default_window() -> usize {
+    20
+}
+
+fn default_cooldown_ms() -> u64 {
+    10_000
+}
+
+impl CircuitBreaker {
+    /// Validate the circuit breaker config
+    pub(super) fn validate(&self) -> std::result::Result<(), &'static str> {
+        if self.max_consecutive_failures == 0 || self.window == 0 {
+            return Err(
+                "`circuit_breaker.max_consecutive_failures` and `circuit_breaker.window` must be > 0",
+            );
+        }
+        if let Some(error_rate) = self.error_rate {
+            if error_rate <= 0.0 || error_rate > 1.0 {
+                return Err("`circuit_breaker.error_rate` must be > 0 and <= 1");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Closed,
+    Open {
+        since: Instant,
+    },
+    /// a single probe request is in flight
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Host {
+    state: State,
+    consecutive_failures: u32,
+    /// outcomes of the most recent requests, `true` for failures
+    recent: VecDeque<bool>,
+}
+
+impl Default for Host {
+    fn default() -> Self {
+        Self {
+            state: State::Closed,
+            consecutive_failures: 0,
+            recent: VecDeque::new(),
+        }
+    }
+}
+
+impl Host {
+    fn trip(&mut self, now: Instant) {
+        self.state = State::Open { since: now };
+        self.consecutive_failures = 0;
+        self.recent.clear();
+    }
+}
+
+/// The circuit breakers of all hosts, a breaker is created with the first request to its host
+#[derive(Debug, Default)]
+pub(crate) struct CircuitBreakers {
+    config: Option<CircuitBreaker>,
+    hosts: Mutex<HashMap<String, Host>>,
+}
+
+fn host_of(uri: &Uri) -> String {
+    uri.authority().map(ToString::to_string).unwrap_or_default()
+}
+
+impl CircuitBreakers {
+    pub(super) fn new(config: Option<CircuitBreaker>) -> Self {
+        Self {
+            config,
+            hosts: Mutex::default(),
+        }
+    }
+
+    /// Whether a request to the host of `uri` may be sent at `now`
+    ///
+    /// The request allowed by a breaker that just half-opened is its probe, its outcome needs to be recorded.
+    pub(super) fn allow(&self, uri: &Uri, now: Instant) -> Result<bool> {
+        let cooldown = if let Some(config) = self.config.as_ref() {
+            Duration::from_millis(config.cooldown_ms)
+        } else {
+            return Ok(true);
+        };
+        let mut hosts = self
+            .hosts
+            .lock()
+            .map_err(|_| Error::from("Poisoned circuit breakers"))?;
+        let host = if let Some(host) = hosts.get_mut(&host_of(uri)) {
+            host
+        } else {
+            return Ok(true);
+        };
+        Ok(match host.state {
+            State::Closed => true,
+            State::Open { since } if now.saturating_duration_since(since) >= cooldown => {
+                host.state = State::HalfOpen;
+                true
+            }
+            State::Open { .. } | State::HalfOpen => false,
+        })
+    }
+
+    /// Record the outcome of a request to the host of `uri` that was allowed by `allow`
+    pub(super) fn record(&self, uri: &Uri, failed: bool, now: Instant) -> Result<()> {
+        let config = if let Some(config) = self.config.as_ref() {
+            config
+        } else {
+            return Ok(());
+        };
+        let mut hosts = self
+            .hosts
+            .lock()
+            .map_err(|_| Error::from("Poisoned circuit breakers"))?;
+        let host = hosts.entry(host_of(uri)).or_insert_with(Host::default);
+        match host.state {
+            State::HalfOpen if failed => host.trip(now),
+            State::HalfOpen => *host = Host::default(),
+            // a request sent before the breaker opened
+            State::Open { .. } => (),
+            State::Closed => {
+                if failed {
+                    host.consecutive_failures += 1;
+                } else {
+                    host.consecutive_failures = 0;
+                }
+                host.recent.push_back(failed);
+                if host.recent.len() > config.window {
+                    host.recent.pop_front();
+                }
+                let failures = host.recent.iter().filter(|failed| **failed).count();
+                #[allow(clippy::cast_precision_loss)] // precision is fine for a ratio
+                let error_rate_reached = config.error_rate.map_or(false, |error_rate| {
+                    host.recent.len() == config.window
+                        && failures as f64 / config.window as f64 >= error_rate
+                });
+                if host.consecutive_failures >= config.max_consecutive_failures
+                    || error_rate_reached
+                {
+                    host.trip(now);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of open and half-open breakers
+    pub(super) fn open(&self) -> (u64, u64) {
+        self.hosts.lock().map_or((0, 0), |hosts| {
+            hosts
+                .values()
+                .fold((0, 0), |(open, half_open), host| match host.state {
+                    State::Closed => (open, half_open),
+                    State::Open { .. } => (open + 1, half_open),
+                    State::HalfOpen => (open, half_open + 1),
+                })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breakers(error_rate: Option<f64>) -> CircuitBreakers {
+        CircuitBreakers::new(Some(CircuitBreaker {
+            max_consecutive_failures: 3,
+            error_rate,
+            window: 4,
+            cooldown_ms: 100,
+        }))
+    }
+
+    #[test]
+    fn validate() {
+        let config = |max_consecutive_failures, error_rate, window| CircuitBreaker {
+            max_consecutive_failures,
+            error_rate,
+            window,
+            cooldown_ms: 100,
+        };
+        assert!(config(3, None, 10).validate().is_ok());
+        assert!(config(3, Some(1.0), 10).validate().is_ok());
+        assert!(config(0, None, 10).validate().is_err());
+        assert!(config(3, None, 0).validate().is_err());
+        assert!(config(3, Some(0.0), 10).validate().is_err());
+        assert!(config(3, Some(1.5), 10).validate().is_err());
+    }
+
+    #[test]
+    fn disabled() -> Result<()> {
+        let breakers = CircuitBreakers::default();
+        let uri = Uri::from_static("http://snot:8080/badger");
+        let now = Instant::now();
+        for _ in 0..10 {
+            breakers.record(&uri, true, now)?;
+            assert!(breakers.allow(&uri, now)?);
+        }
+        assert_eq!((0, 0), breakers.open());
+        Ok(())
+    }
+
+    #[test]
+    fn consecutive_failures() -> Result<()> {
+        let breakers = breakers(None);
+        let snot = Uri::from_static("http://snot:8080/badger");
+        let other = Uri::from_static("http://flork:8080/badger");
+        let now = Instant::now();
+        breakers.record(&snot, true, now)?;
+        breakers.record(&snot, true, now)?;
+        // a success resets the consecutive failures
+        breakers.record(&snot, false, now)?;
+        breakers.record(&snot, true, now)?;
+        breakers.record(&snot, true, now)?;
+        assert!(breakers.allow(&snot, now)?);
+        breakers.record(&snot, true, now)?;
+        assert!(!breakers.allow(&snot, now)?);
+        assert_eq!((1, 0), breakers.open());
+        // other hosts are not affected
+        assert!(breakers.allow(&other, now)?);
+        Ok(())
+    }
+
+    #[test]
+    fn error_rate() -> Result<()> {
+        let breakers = breakers(Some(0.5));
+        let uri = Uri::from_static("http://snot:8080/badger");
+        let now = Instant::now();
+        for failed in [true, false, true] {
+            breakers.record(&uri, failed, now)?;
+            // the window isn't full yet
+            assert!(breakers.allow(&uri, now)?);
+        }
+        breakers.record(&uri, false, now)?;
+        assert!(!breakers.allow(&uri, now)?);
+        Ok(())
+    }
+
+    #[test]
+    fn half_open() -> Result<()> {
+        let breakers = breakers(None);
+        let uri = Uri::from_static("http://snot:8080/badger");
+        let start = Instant::now();
+        for _ in 0..3 {
+            breakers.record(&uri, true, start)?;
+        }
+        assert!(!breakers.allow(&uri, start + Duration::from_millis(99))?);
+
+        // a single probe after the cooldown
+        let probe = start + Duration::from_millis(100);
+        assert!(breakers.allow(&uri, probe)?);
+        assert_eq!((0, 1), breakers.open());
+        assert!(!breakers.allow(&uri, probe)?);
+        // a failed probe re-opens the breaker for another cooldown
+        breakers.record(&uri, true, probe)?;
+        assert!(!breakers.allow(&uri, probe + Duration::from_millis(99))?);
+
+        let probe = probe + Duration::from_millis(100);
+        assert!(breakers.allow(&uri, probe)?);
+        // a successful probe closes it
+        breakers.record(&uri, false, probe)?;
+        assert!(breakers.allow(&uri, probe)?);
+        assert!(breakers.allow(&uri, probe)?);
+        assert_eq!((0, 0), breakers.open());
+        Ok(())
+    }
+}
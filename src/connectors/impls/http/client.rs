@@ -13,8 +13,21 @@
 // limitations under the License.
 
 use super::auth::Auth;
-use super::meta::{extract_request_meta, extract_response_meta, HttpRequestBuilder};
-use super::utils::{Header, RequestId};
+use super::cache::{Lookup, ResponseCache};
+use super::circuit_breaker::{CircuitBreaker, CircuitBreakers};
+use super::compression::{self, Compression};
+use super::cookies::CookieJar;
+use super::dns::{Dns, Resolver};
+use super::meta::{
+    extract_headers, extract_request_meta, parse_method, HttpRequestBuilder, HttpResponseMeta,
+};
+use super::pool::Pool;
+use super::proxy::{Proxy, ProxyConnector};
+use super::rate_limit::{OnLimit, RateLimit, RateLimiter};
+use super::redirect;
+use super::retry::Retry;
+use super::utils::{rebuild_request, Header, RequestId};
+use crate::connectors::utils::metrics;
 use crate::connectors::utils::mime::MimeCodecMap;
 use crate::connectors::utils::tls::TLSClientConfig;
 use crate::{
@@ -23,19 +36,31 @@ use crate::{
 };
 use crate::{config::NameWithConfig, connectors::sink::concurrency_cap::ConcurrencyCap};
 use crate::{connectors::prelude::*, errors::err_connector_def};
+use beef::Cow;
+use chrono::Utc;
 use either::Either;
 use halfbrown::HashMap;
+use hdrhistogram::Histogram;
 use http_body::Body;
 use hyper::{
-    client::{Client as HyperClient, HttpConnector},
-    Method,
+    client::Client as HyperClient, header::HeaderValue, HeaderMap, Method, Request, Response,
+    StatusCode, Uri,
 };
 use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
 use serde::{Deserialize, Deserializer};
 use std::sync::Arc;
-use std::{sync::atomic::AtomicBool, time::Duration};
-use tokio::time::timeout;
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, AtomicU64},
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{oneshot, OwnedSemaphorePermit, Semaphore},
+    task::JoinHandle,
+    time::{interval, sleep, timeout},
+};
 use tremor_common::time::nanotime;
+use tremor_pipeline::{SignalKind, METRICS_CHANNEL};
 
 //  pipeline -> Sink -> http client
 //                          |
@@ -68,7 +93,144 @@ pub(crate) struct Config {
     /// custom codecs mapping from mime_type to custom codec name
     /// e.g. for handling `application/json` with the `binary` codec, if desired
     /// the mime type of `*/*` serves as a default / fallback
+    /// it is merged onto the builtin mapping, entries given here take precedence
     mime_mapping: Option<HashMap<String, NameWithConfig>>,
+    /// replace the builtin mime type mapping with `mime_mapping` instead of merging onto it
+    #[serde(default = "Default::default")]
+    mime_mapping_override: bool,
+    /// optional retry config, if not set requests are not retried
+    #[serde(default = "Default::default")]
+    retry: Option<Retry>,
+    /// maximum number of redirects to follow, if `0` redirect responses are emitted as is
+    #[serde(default = "Default::default")]
+    max_redirects: usize,
+    /// compression of request bodies, responses are decompressed according to their `Content-Encoding`
+    #[serde(default = "Default::default")]
+    compression: Compression,
+    /// maximum time in nanoseconds to wait for in-flight requests to finish when stopping
+    #[serde(default = "default_drain_timeout")]
+    drain_timeout: u64,
+    /// emit responses with a status of 400 or above via the `err` port instead of `out`
+    #[serde(default = "Default::default")]
+    split_errors: bool,
+    /// optional proxy to send requests through
+    #[serde(default = "Default::default")]
+    proxy: Option<Proxy>,
+    /// optional path of a unix socket all requests are sent over, instead of connecting to the host of
+    /// their url, which is still sent as `Host` header
+    #[serde(default = "Default::default")]
+    unix_socket: Option<String>,
+    /// what to do with events arriving while `concurrency` requests are in flight
+    #[serde(default = "Default::default")]
+    overflow: Overflow,
+    /// emit the response body chunk by chunk as it arrives, as a stream through the preprocessors
+    /// instead of buffering it completely
+    #[serde(default = "Default::default")]
+    stream_response: bool,
+    /// HTTP version to send requests with
+    #[serde(default = "Default::default")]
+    http_version: HttpVersion,
+    /// maximum number of requests in flight to a single host, requests to other hosts are not held up
+    /// by a host at this limit. The overall number of requests in flight is capped by `concurrency`.
+    #[serde(default = "Default::default")]
+    max_connections_per_host: Option<usize>,
+    /// optional per host circuit breaker, if not set requests are sent to failing hosts as well
+    #[serde(default = "Default::default")]
+    circuit_breaker: Option<CircuitBreaker>,
+    /// maximum size in bytes of the in-memory cache for responses to `GET` requests, if not set responses
+    /// are not cached
+    #[serde(default = "Default::default")]
+    cache_max_bytes: Option<usize>,
+    /// caching of resolved hostnames and static hostname to IP address overrides
+    #[serde(default = "Default::default")]
+    dns: Dns,
+    /// send event payloads, arrays of parts, as `multipart/form-data` instead of encoding them with the codec
+    #[serde(default = "Default::default")]
+    pub(super) multipart: bool,
+    /// `Host` header sent with every request instead of the host of the request url, unless the event sets one
+    #[serde(default = "Default::default")]
+    pub(super) host_header: Option<String>,
+    /// concatenate the payloads of consecutive events of the same stream into the chunked body of a single request,
+    /// which is finished by an event with `$http_client.request.end_stream` set, by an event of another stream
+    /// or by stopping the connector
+    #[serde(default = "Default::default")]
+    stream_requests: bool,
+    /// limits of idle connections and the time to wait for connections to be established
+    #[serde(default = "Default::default")]
+    pool: Pool,
+    /// optional limit of the requests sent per second, if not set requests are only limited by `concurrency`
+    #[serde(default = "Default::default")]
+    rate_limit: Option<RateLimit>,
+    /// how much of the request is echoed as `$http_client.request` into response and error events
+    #[serde(default = "Default::default")]
+    echo_request: EchoRequest,
+    /// named upstreams with their own `url`, `auth` and `tls`, selected per event via `$http_client.profile`,
+    /// events without a profile are sent with the top level `url`, `auth` and `tls`
+    #[serde(default = "Default::default")]
+    profiles: HashMap<String, Profile>,
+    /// interval in milliseconds in which the client metrics are emitted, independent of events arriving,
+    /// if not set they are emitted along with the other connector metrics every `metrics_interval_s`
+    #[serde(default = "Default::default")]
+    metrics_interval_ms: Option<u64>,
+    /// store the cookies set by responses and send them with subsequent requests to matching urls,
+    /// the cookie jar is shared by all profiles
+    #[serde(default = "Default::default")]
+    cookies: bool,
+}
+
+/// A named upstream of the `http_client`, selected per event via `$http_client.profile`
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Profile {
+    /// Target URL
+    pub(super) url: Url,
+    /// Authorization method
+    #[serde(default = "Default::default")]
+    pub(super) auth: Auth,
+    /// optional tls client config
+    #[serde(with = "either::serde_untagged_optional", default = "Default::default")]
+    tls: Option<Either<TLSClientConfig, bool>>,
+}
+
+/// HTTP version used by the `http_client`
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum HttpVersion {
+    /// HTTP/1.1 only
+    #[serde(rename = "1.1")]
+    Http11,
+    /// HTTP/2 only, negotiated via ALPN for `https`, with prior knowledge for `http` urls
+    #[serde(rename = "2")]
+    Http2,
+    /// HTTP/2 if the server supports it via ALPN, HTTP/1.1 otherwise and for plain `http` urls
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+}
+
+/// Handling of events exceeding the `concurrency` of the `http_client`
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Overflow {
+    /// send the request and close the circuit breaker until in-flight requests finish
+    #[default]
+    Block,
+    /// drop the event, emit a synthetic `429 Too Many Requests` error event and ack it
+    Drop,
+    /// drop the event and fail it
+    Fail,
+}
+
+/// Request metadata attached to the response and error events of the `http_client`
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum EchoRequest {
+    /// method, uri, version and headers, along with the body sent as `raw_body`
+    Full,
+    /// method, uri, version and headers
+    #[default]
+    Headers,
+    /// no request metadata, only the `request_id`
+    None,
 }
 
 /// Just a wrapper
@@ -81,7 +243,7 @@ impl<'de> Deserialize<'de> for SerdeishMethod {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        let method = Method::from_bytes(s.as_bytes()).map_err(serde::de::Error::custom)?;
+        let method = parse_method(&s).map_err(serde::de::Error::custom)?;
         Ok(Self(method))
     }
 }
@@ -92,6 +254,10 @@ fn default_concurrency() -> usize {
     DEFAULT_CONCURRENCY
 }
 
+fn default_drain_timeout() -> u64 {
+    10_000_000_000 // 10s
+}
+
 fn default_method() -> SerdeishMethod {
     SerdeishMethod(Method::POST)
 }
@@ -99,6 +265,135 @@ fn default_method() -> SerdeishMethod {
 // for new
 impl ConfigImpl for Config {}
 
+impl Config {
+    /// The profile of events without `$http_client.profile`, made up of the top level `url`, `auth` and `tls`
+    pub(super) fn default_profile(&self) -> Profile {
+        Profile {
+            url: self.url.clone(),
+            auth: self.auth.clone(),
+            tls: self.tls.clone(),
+        }
+    }
+}
+
+impl Profile {
+    /// The server name overriding the url host for SNI and certificate verification, `tls.server_name`
+    fn server_name(&self) -> Option<&str> {
+        match self.tls.as_ref() {
+            Some(Either::Left(tls_config)) => tls_config.domain.as_deref(),
+            Some(Either::Right(_)) | None => None,
+        }
+    }
+}
+
+/// A profile with its loaded TLS client config
+#[derive(Clone)]
+struct Upstream {
+    profile: Profile,
+    tls_client_config: Option<rustls::ClientConfig>,
+}
+
+impl Upstream {
+    /// Validate `profile` and load its TLS client config, `prefix` locates the profile in the config
+    fn load(id: &Alias, profile: Profile, prefix: &str) -> Result<Self> {
+        if let Some(server_name) = profile.server_name() {
+            if rustls::ServerName::try_from(server_name).is_err() {
+                return Err(err_connector_def(
+                    id,
+                    &format!("`{prefix}tls.server_name` must be a valid DNS name or IP address"),
+                ));
+            }
+            if matches!(
+                profile.tls,
+                Some(Either::Left(TLSClientConfig {
+                    insecure_skip_verify: true,
+                    ..
+                }))
+            ) {
+                warn!("[Connector::{id}] `{prefix}tls.server_name` is only sent via SNI, the server certificate is not verified");
+            }
+        }
+        let tls_client_config = match profile.tls.as_ref() {
+            Some(Either::Right(true)) => {
+                // default config
+                Some(TLSClientConfig::default().to_client_config()?)
+            }
+            Some(Either::Left(tls_config)) => Some(tls_config.to_client_config()?),
+            Some(Either::Right(false)) | None => None,
+        };
+        if profile.url.scheme() == "https" && tls_client_config.is_none() {
+            return Err(err_connector_def(
+                id,
+                &format!("missing tls config with 'https' url. Set '{prefix}tls' to 'true' or provide a full tls config."),
+            ));
+        }
+        Ok(Self {
+            profile,
+            tls_client_config,
+        })
+    }
+
+    /// The scheme of the request metadata
+    fn scheme(&self) -> &'static str {
+        if self.tls_client_config.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+}
+
+/// The default profile, from the top level `url`, `auth` and `tls`, and the named `profiles`
+#[derive(Clone)]
+struct Profiles<T> {
+    default: T,
+    named: HashMap<String, T>,
+}
+
+impl<T> Profiles<T> {
+    /// The profile named `name`, the default one for `None`
+    fn get(&self, name: Option<&str>) -> Option<&T> {
+        match name {
+            Some(name) => self.named.get(name),
+            None => Some(&self.default),
+        }
+    }
+
+    fn values(&self) -> impl Iterator<Item = &T> {
+        std::iter::once(&self.default).chain(self.named.values())
+    }
+
+    fn map<U, F>(&self, mut f: F) -> Profiles<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Profiles {
+            default: f(&self.default),
+            named: self
+                .named
+                .iter()
+                .map(|(name, profile)| (name.clone(), f(profile)))
+                .collect(),
+        }
+    }
+}
+
+impl Profiles<Upstream> {
+    /// Validate all profiles of `config` and load their TLS client configs
+    fn load(id: &Alias, config: &Config) -> Result<Self> {
+        let default = Upstream::load(id, config.default_profile(), "")?;
+        let named = config
+            .profiles
+            .iter()
+            .map(|(name, profile)| {
+                let prefix = format!("profiles.{name}.");
+                Ok((name.clone(), Upstream::load(id, profile.clone(), &prefix)?))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { default, named })
+    }
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct Builder {}
 
@@ -116,33 +411,80 @@ impl ConnectorBuilder for Builder {
         _kill_switch: &KillSwitch,
     ) -> Result<Box<dyn Connector>> {
         let config = Config::new(config)?;
-
-        let tls_client_config = match config.tls.as_ref() {
-            Some(Either::Right(true)) => {
-                // default config
-                Some(TLSClientConfig::default().to_client_config()?)
+        if let Some(retry) = config.retry.as_ref() {
+            retry.validate().map_err(|e| err_connector_def(id, e))?;
+        }
+        if let Some(proxy) = config.proxy.as_ref() {
+            proxy.validate().map_err(|e| err_connector_def(id, e))?;
+        }
+        if let Some(unix_socket) = config.unix_socket.as_deref() {
+            if cfg!(not(unix)) {
+                return Err(err_connector_def(
+                    id,
+                    "`unix_socket` is only supported on unix platforms",
+                ));
             }
-            Some(Either::Left(tls_config)) => Some(tls_config.to_client_config()?),
-            Some(Either::Right(false)) | None => None,
-        };
-        if config.url.scheme() == "https" && tls_client_config.is_none() {
-            return Err(err_connector_def(
+            if unix_socket.is_empty() {
+                return Err(err_connector_def(id, "`unix_socket` must not be empty"));
+            }
+            if config.proxy.is_some() {
+                return Err(err_connector_def(
                     id,
-                    "missing tls config with 'https' url. Set 'tls' to 'true' or provide a full tls config.",
+                    "`unix_socket` and `proxy` can't be used together",
                 ));
+            }
         }
+        if let Some(circuit_breaker) = config.circuit_breaker.as_ref() {
+            circuit_breaker
+                .validate()
+                .map_err(|e| err_connector_def(id, e))?;
+        }
+        if let Some(rate_limit) = config.rate_limit.as_ref() {
+            rate_limit
+                .validate()
+                .map_err(|e| err_connector_def(id, e))?;
+        }
+        config
+            .dns
+            .validate()
+            .map_err(|e| err_connector_def(id, e))?;
+        config
+            .pool
+            .validate()
+            .map_err(|e| err_connector_def(id, e))?;
+        if let Some(host_header) = config.host_header.as_deref() {
+            if host_header.is_empty() || HeaderValue::from_str(host_header).is_err() {
+                return Err(err_connector_def(
+                    id,
+                    "`host_header` must be a valid header value",
+                ));
+            }
+        }
+        if config.max_connections_per_host == Some(0) {
+            return Err(err_connector_def(
+                id,
+                "`max_connections_per_host` must be > 0",
+            ));
+        }
+        if config.cache_max_bytes == Some(0) {
+            return Err(err_connector_def(id, "`cache_max_bytes` must be > 0"));
+        }
+        if config.metrics_interval_ms == Some(0) {
+            return Err(err_connector_def(id, "`metrics_interval_ms` must be > 0"));
+        }
+
+        let upstreams = Profiles::load(id, &config)?;
         let (response_tx, response_rx) = bounded(qsize());
-        let mime_codec_map = Arc::new(if let Some(codec_map) = config.mime_mapping.clone() {
-            MimeCodecMap::from_custom(codec_map)
-        } else {
-            MimeCodecMap::new()
-        });
+        let mime_codec_map = Arc::new(MimeCodecMap::from_config(
+            config.mime_mapping.clone(),
+            config.mime_mapping_override,
+        ));
 
         Ok(Box::new(Client {
             response_tx,
             response_rx: Some(response_rx),
             config,
-            tls_client_config,
+            upstreams,
             mime_codec_map,
             source_is_connected: Arc::new(AtomicBool::new(false)),
         }))
@@ -154,7 +496,7 @@ pub(crate) struct Client {
     response_tx: Sender<SourceReply>,
     response_rx: Option<Receiver<SourceReply>>,
     config: Config,
-    tls_client_config: Option<rustls::ClientConfig>,
+    upstreams: Profiles<Upstream>,
     // this is basically an immutable map, we use arc to share it across tasks (e.g. for each request sending)
     mime_codec_map: Arc<MimeCodecMap>,
     source_is_connected: Arc<AtomicBool>,
@@ -187,14 +529,9 @@ impl Connector for Client {
             self.response_tx.clone(),
             builder.reply_tx(),
             self.config.clone(),
-            self.tls_client_config.clone(),
+            self.upstreams.clone(),
             self.mime_codec_map.clone(),
             self.source_is_connected.clone(),
-            if self.tls_client_config.is_some() {
-                "https"
-            } else {
-                "http"
-            },
         );
         Ok(Some(builder.spawn(sink, ctx)))
     }
@@ -228,42 +565,353 @@ impl Source for HttpRequestSource {
     }
 }
 
+/// The hyper client used by the `http_client`
+pub(super) type HttpClient = HyperClient<HttpsConnector<ProxyConnector>>;
+
+/// Limits the requests in flight to each host, the limit for a host is created with its first request
+#[derive(Debug, Default)]
+struct HostLimits {
+    max_per_host: Option<usize>,
+    hosts: std::sync::Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HostLimits {
+    fn new(max_per_host: Option<usize>) -> Self {
+        Self {
+            max_per_host,
+            hosts: std::sync::Mutex::default(),
+        }
+    }
+
+    /// Wait until a request to the host of `uri` is allowed
+    ///
+    /// The returned permit needs to be held until the response is consumed.
+    /// Returns `None` if there is no limit.
+    async fn acquire(&self, uri: &Uri) -> Result<Option<OwnedSemaphorePermit>> {
+        let max_per_host = if let Some(max_per_host) = self.max_per_host {
+            max_per_host
+        } else {
+            return Ok(None);
+        };
+        let host = uri.authority().map(ToString::to_string).unwrap_or_default();
+        let mut hosts = self
+            .hosts
+            .lock()
+            .map_err(|_| Error::from("Poisoned host limits"))?;
+        // permits hold on to their semaphore, so only semaphores of hosts without requests are dropped
+        hosts.retain(|_, semaphore| Arc::strong_count(semaphore) > 1);
+        let semaphore = hosts
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(max_per_host)))
+            .clone();
+        drop(hosts);
+        // the semaphores are never closed
+        let permit = semaphore
+            .acquire_owned()
+            .await
+            .map_err(|e| Error::from(format!("Error waiting for a host limit: {e}")))?;
+        Ok(Some(permit))
+    }
+}
+
+/// Sends requests from within the per-request tasks
+struct RequestSender {
+    client: HttpClient,
+    host_limits: HostLimits,
+    circuit_breakers: CircuitBreakers,
+    cache: Option<ResponseCache>,
+    proxy: Option<Proxy>,
+    timeout: Duration,
+    retry: Option<Retry>,
+    max_redirects: usize,
+    /// the upstream all requests of this sender are sent to
+    profile: Profile,
+    /// scheme of the request metadata
+    scheme: &'static str,
+    compression: Compression,
+    cookies: Option<Arc<CookieJar>>,
+}
+
+impl RequestSender {
+    /// Send a request unless the circuit breaker of its host is open, recording its outcome
+    async fn send_guarded(
+        &self,
+        request: Request<hyper::Body>,
+        uri: &Uri,
+        now: Instant,
+        ctx: &SinkContext,
+    ) -> Result<(Response<hyper::Body>, Uri)> {
+        if !self.circuit_breakers.allow(uri, now)? {
+            let host = uri.authority().map(ToString::to_string);
+            return Err(ErrorKind::CircuitBreakerOpen(host.unwrap_or_default()).into());
+        }
+        let res = self.send(request, ctx).await;
+        let failed = res
+            .as_ref()
+            .map_or(true, |(response, _)| response.status().is_server_error());
+        self.circuit_breakers.record(uri, failed, Instant::now())?;
+        res
+    }
+
+    /// Send a request, answering `GET` requests from the response cache if configured
+    ///
+    /// Returns the final response and the url it was received from.
+    async fn send(
+        &self,
+        mut request: Request<hyper::Body>,
+        ctx: &SinkContext,
+    ) -> Result<(Response<hyper::Body>, Uri)> {
+        let cache = if let Some(cache) = self.cache.as_ref() {
+            cache
+        } else {
+            return self.fetch(request, ctx).await;
+        };
+        if request.method() != Method::GET {
+            return self.fetch(request, ctx).await;
+        }
+        let key = ResponseCache::key(&request);
+        let uri = request.uri().clone();
+        let lookup = cache.lookup(&mut request, Instant::now())?;
+        let stale = match lookup {
+            Lookup::Fresh(response) => {
+                debug!("{ctx} Answering request to {uri} from the cache");
+                return Ok((response, uri));
+            }
+            Lookup::Stale => true,
+            Lookup::Miss => false,
+        };
+        let (response, url) = self.fetch(request, ctx).await?;
+        if stale && response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(response) = cache.revalidated(&key, &response, Instant::now())? {
+                debug!("{ctx} Revalidated cached response to {uri}");
+                return Ok((response, url));
+            }
+        }
+        let response = cache.store(key, response, Instant::now()).await?;
+        Ok((response, url))
+    }
+
+    /// Send a request, following up to `max_redirects` redirects
+    ///
+    /// Requests are only signed and authentication challenges only answered for the origin of `request`,
+    /// never after a redirect to another origin.
+    /// Returns the final response and the url it was received from.
+    async fn fetch(
+        &self,
+        request: Request<hyper::Body>,
+        ctx: &SinkContext,
+    ) -> Result<(Response<hyper::Body>, Uri)> {
+        if self.max_redirects == 0
+            && !self.profile.auth.needs_buffered_body()
+            && !self.compression.is_enabled()
+        {
+            let uri = request.uri().clone();
+            return Ok((self.send_once(request, ctx).await?, uri));
+        }
+        // redirects and auth challenges might need to replay the body, signing needs to hash it
+        // and compression needs to compress it, so we need to buffer it
+        let (mut parts, body) = request.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        let mut body = self.compression.compress(&mut parts, body)?;
+        let origin = parts.uri.clone();
+        let mut redirects = 0;
+        loop {
+            // credentials are only ever sent to the origin of the request
+            let authorize = redirect::same_origin(&origin, &parts.uri);
+            let mut request = rebuild_request(&parts, body.clone());
+            if authorize {
+                self.profile.auth.sign(&mut request, &body, Utc::now())?;
+            }
+            let mut response = self.send_once(request, ctx).await?;
+            let mut answered = 0;
+            while let Some(authorization) = self
+                .profile
+                .auth
+                .answer_challenge(&parts.method, &parts.uri, &response, answered)?
+                .filter(|_| authorize)
+            {
+                debug!("{ctx} Answering authentication challenge");
+                let mut request = rebuild_request(&parts, body.clone());
+                request
+                    .headers_mut()
+                    .insert(hyper::header::AUTHORIZATION, authorization.parse()?);
+                response = self.send_once(request, ctx).await?;
+                answered += 1;
+            }
+            match redirect::location(&parts.uri, &response)? {
+                Some(location) if self.max_redirects > 0 => {
+                    if redirects >= self.max_redirects {
+                        return Err(ErrorKind::TooManyRedirects(self.max_redirects).into());
+                    }
+                    debug!(
+                        "{ctx} Following {} redirect to {location}",
+                        response.status()
+                    );
+                    redirects += 1;
+                    redirect::follow(&mut parts, &mut body, response.status(), location);
+                }
+                _ => return Ok((response, parts.uri)),
+            }
+        }
+    }
+
+    /// Send a single request, retrying it if configured
+    async fn send_once(
+        &self,
+        mut request: Request<hyper::Body>,
+        ctx: &SinkContext,
+    ) -> Result<Response<hyper::Body>> {
+        if let Some(proxy) = self.proxy.as_ref() {
+            proxy.authorize(&mut request)?;
+        }
+        let cookies = if let Some(cookies) = self.cookies.as_ref() {
+            cookies.attach(&mut request, Utc::now())?;
+            Some((cookies, request.uri().clone()))
+        } else {
+            None
+        };
+        let response = if let Some(retry) = self.retry.as_ref() {
+            retry.send(&self.client, request, self.timeout, ctx).await?
+        } else {
+            timeout(self.timeout, self.client.request(request)).await??
+        };
+        if let Some((cookies, uri)) = cookies {
+            cookies.store(&uri, &response, Utc::now())?;
+        }
+        Ok(response)
+    }
+}
+
+/// Counters of the requests sent by the `http_client` sink, reported as its metrics
+#[derive(Debug)]
+struct RequestStats {
+    /// requests that were sent or failed to be sent
+    requests: AtomicU64,
+    /// requests that failed to be sent, e.g. due to a timeout or an unreachable host
+    failures: AtomicU64,
+    /// latencies in nanoseconds of the requests sent since the last report
+    latencies: std::sync::Mutex<Histogram<u64>>,
+    /// `requests`, `failures` and the timestamp of the last report
+    last_reported: std::sync::Mutex<(u64, u64, u64)>,
+}
+
+impl RequestStats {
+    fn new(now: u64) -> Self {
+        Self {
+            requests: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            // ALLOW: 2 significant figures are always valid
+            latencies: std::sync::Mutex::new(Histogram::new(2).expect("valid histogram")),
+            last_reported: std::sync::Mutex::new((0, 0, now)),
+        }
+    }
+
+    /// Record the time from sending a request until its response arrived or it failed
+    fn record_latency(&self, latency: Duration) {
+        if let Ok(mut latencies) = self.latencies.lock() {
+            latencies.saturating_record(u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX));
+        }
+    }
+}
+
 struct HttpRequestSink {
     request_counter: u64,
-    client: Option<Arc<HyperClient<HttpsConnector<HttpConnector>>>>,
+    /// a sender per profile, created on connect
+    senders: Option<Profiles<Arc<RequestSender>>>,
     response_tx: Sender<SourceReply>,
     reply_tx: ReplySender,
     config: Config,
-    tls_client_config: Option<rustls::ClientConfig>,
+    upstreams: Profiles<Upstream>,
     // reply_tx: ReplySender,
     concurrency_cap: ConcurrencyCap,
     origin_uri: EventOriginUri,
     codec_map: Arc<MimeCodecMap>,
-    scheme: &'static str,
     // we should only send responses down the channel when we know there is a source consuming them
     // otherwise the channel would fill up and we'd be stuck
     // TODO: find/implement a channel that just throws away the oldest message when it is full, like a ring-buffer
     source_is_connected: Arc<AtomicBool>,
+    stats: Arc<RequestStats>,
+    /// emits the metrics every `metrics_interval_ms`, started on connect
+    metrics_task: Option<JoinHandle<()>>,
+    /// shared by the senders of all profiles and kept across reconnects, with `cookies`
+    cookies: Option<Arc<CookieJar>>,
+    /// the request the events of the current stream are appended to, with `stream_requests`
+    streamed_request: Option<StreamedRequest>,
+    /// limits the requests sent per second, with `rate_limit`
+    rate_limiter: Option<RateLimiter>,
+}
+
+/// A request with a body made up of the payloads of consecutive events of the same stream
+struct StreamedRequest {
+    /// source and stream id of the events
+    stream: (u64, u64),
+    builder: HttpRequestBuilder,
+    /// the events appended so far, acked or failed with the response
+    contraflow_data: Option<ContraflowData>,
+    /// hands the events over to the sending task once the stream is finished
+    contraflow_tx: oneshot::Sender<Option<ContraflowData>>,
+}
+
+/// Hand the events carried by a request over to its sending task, which acks or fails them with the response
+fn hand_over(
+    contraflow_tx: oneshot::Sender<Option<ContraflowData>>,
+    contraflow_data: Option<ContraflowData>,
+    ctx: &SinkContext,
+) {
+    if contraflow_tx.send(contraflow_data).is_err() {
+        debug!("{ctx} The request was finished before all its events were handed over");
+    }
+}
+
+/// Append the payloads of `event` to the request body
+async fn append_event(
+    builder: &mut HttpRequestBuilder,
+    event: &Event,
+    serializer: &mut EventSerializer,
+) -> Result<()> {
+    for value in event.value_iter() {
+        builder.append(value, event.ingest_ns, serializer).await?;
+    }
+    Ok(())
 }
 
 impl HttpRequestSink {
+    const IN_FLIGHT: Cow<'static, str> = Cow::const_str("in_flight");
+    const CONCURRENCY: Cow<'static, str> = Cow::const_str("concurrency");
+    const REQUESTS: Cow<'static, str> = Cow::const_str("requests");
+    const FAILURES: Cow<'static, str> = Cow::const_str("failures");
+    const ERROR_RATE: Cow<'static, str> = Cow::const_str("error_rate");
+    const CIRCUITS_OPEN: Cow<'static, str> = Cow::const_str("circuits_open");
+    const CIRCUITS_HALF_OPEN: Cow<'static, str> = Cow::const_str("circuits_half_open");
+    const CLIENTS: Cow<'static, str> = Cow::const_str("clients");
+    const REQUESTS_PER_SEC: Cow<'static, str> = Cow::const_str("requests_per_sec");
+    const LATENCY_P50: Cow<'static, str> = Cow::const_str("latency_p50");
+    const LATENCY_P99: Cow<'static, str> = Cow::const_str("latency_p99");
+    const FLOW: Cow<'static, str> = Cow::const_str("flow");
+    const CONNECTOR: Cow<'static, str> = Cow::const_str("connector");
+    const HTTP_CLIENT_STATS: &'static str = "http_client_stats";
+
     fn new(
         response_tx: Sender<SourceReply>,
         reply_tx: ReplySender,
         config: Config,
-        tls_client_config: Option<rustls::ClientConfig>,
+        upstreams: Profiles<Upstream>,
         codec_map: Arc<MimeCodecMap>,
         source_is_connected: Arc<AtomicBool>,
-        scheme: &'static str,
     ) -> Self {
         let concurrency_cap = ConcurrencyCap::new(config.concurrency, reply_tx.clone());
+        let rate_limiter = config
+            .rate_limit
+            .clone()
+            .map(|rate_limit| RateLimiter::new(rate_limit, Instant::now()));
+        let cookies = config.cookies.then(|| Arc::new(CookieJar::default()));
         Self {
             request_counter: 1, // always start by 1, 0 is DEFAULT_STREAM_ID and this might interfere with custom codecs
-            client: None,
+            senders: None,
             response_tx,
             reply_tx,
             config,
-            tls_client_config,
+            upstreams,
             concurrency_cap,
             origin_uri: EventOriginUri {
                 scheme: String::from("http_client"),
@@ -273,33 +921,404 @@ impl HttpRequestSink {
             },
             codec_map,
             source_is_connected,
-            scheme,
+            stats: Arc::new(RequestStats::new(nanotime())),
+            metrics_task: None,
+            cookies,
+            streamed_request: None,
+            rate_limiter,
         }
     }
 }
 
-#[async_trait::async_trait()]
-impl Sink for HttpRequestSink {
-    async fn connect(&mut self, _ctx: &SinkContext, _attempt: &Attempt) -> Result<bool> {
-        let https = if let Some(tls_config) = self.tls_client_config.clone() {
-            HttpsConnectorBuilder::new()
-                .with_tls_config(tls_config)
-                .https_or_http()
-                .enable_http1()
-                .enable_http2()
-                .build()
+impl HttpRequestSink {
+    /// Reports the requests in flight, the `concurrency` they are capped at, the number of clients, the
+    /// total number of requests and failures, the open and half-open circuit breakers and, since the last
+    /// report, the ratio of failed requests, the requests per second and the 50th and 99th percentile of
+    /// the request latencies in nanoseconds
+    fn metrics_payload(
+        stats: &RequestStats,
+        concurrency_cap: &ConcurrencyCap,
+        senders: Option<&Profiles<Arc<RequestSender>>>,
+        alias: &Alias,
+        timestamp: u64,
+    ) -> EventPayload {
+        let requests = stats.requests.load(Ordering::Relaxed);
+        let failures = stats.failures.load(Ordering::Relaxed);
+        let (last_requests, last_failures, last_timestamp) = stats
+            .last_reported
+            .lock()
+            .map_or((requests, failures, timestamp), |mut last| {
+                std::mem::replace(&mut *last, (requests, failures, timestamp))
+            });
+        let interval_requests = requests.saturating_sub(last_requests);
+        let interval_ns = timestamp.saturating_sub(last_timestamp);
+        #[allow(clippy::cast_precision_loss)] // precision is fine for a ratio
+        let error_rate = if interval_requests == 0 {
+            0.0
+        } else {
+            failures.saturating_sub(last_failures) as f64 / interval_requests as f64
+        };
+        #[allow(clippy::cast_precision_loss)] // precision is fine for a rate
+        let requests_per_sec = if interval_ns == 0 {
+            0.0
+        } else {
+            interval_requests as f64 * 1_000_000_000.0 / interval_ns as f64
+        };
+        let (latency_p50, latency_p99) = stats.latencies.lock().map_or((0, 0), |mut latencies| {
+            let percentiles = (
+                latencies.value_at_quantile(0.5),
+                latencies.value_at_quantile(0.99),
+            );
+            latencies.reset();
+            percentiles
+        });
+
+        let (circuits_open, circuits_half_open) = senders
+            .into_iter()
+            .flat_map(Profiles::values)
+            .map(|sender| sender.circuit_breakers.open())
+            .fold((0, 0), |(open, half_open), (o, h)| {
+                (open + o, half_open + h)
+            });
+        let clients = senders.map_or(0, |senders| senders.values().count());
+
+        let mut fields = HashMap::with_capacity(11);
+        fields.insert(Self::IN_FLIGHT, Value::from(concurrency_cap.get_counter()));
+        fields.insert(Self::CONCURRENCY, Value::from(concurrency_cap.cap()));
+        fields.insert(Self::CLIENTS, Value::from(clients));
+        fields.insert(Self::REQUESTS, Value::from(requests));
+        fields.insert(Self::FAILURES, Value::from(failures));
+        fields.insert(Self::ERROR_RATE, Value::from(error_rate));
+        fields.insert(Self::REQUESTS_PER_SEC, Value::from(requests_per_sec));
+        fields.insert(Self::LATENCY_P50, Value::from(latency_p50));
+        fields.insert(Self::LATENCY_P99, Value::from(latency_p99));
+        fields.insert(Self::CIRCUITS_OPEN, Value::from(circuits_open));
+        fields.insert(Self::CIRCUITS_HALF_OPEN, Value::from(circuits_half_open));
+
+        let mut tags = HashMap::with_capacity(2);
+        tags.insert(Self::FLOW, Value::from(alias.flow_alias().to_string()));
+        tags.insert(Self::CONNECTOR, Value::from(alias.to_string()));
+
+        make_metrics_payload(Self::HTTP_CLIENT_STATS, fields, tags, timestamp)
+    }
+
+    /// Emit the metrics every `metrics_interval`, until aborted
+    fn spawn_metrics_task(
+        &self,
+        metrics_interval: Duration,
+        senders: Profiles<Arc<RequestSender>>,
+        alias: Alias,
+    ) -> JoinHandle<()> {
+        let stats = self.stats.clone();
+        let concurrency_cap = self.concurrency_cap.clone();
+        tokio::task::spawn(async move {
+            let tx = METRICS_CHANNEL.tx();
+            let mut ticks = interval(metrics_interval);
+            // the first tick completes immediately
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                let payload = Self::metrics_payload(
+                    &stats,
+                    &concurrency_cap,
+                    Some(&senders),
+                    &alias,
+                    nanotime(),
+                );
+                metrics::send(&tx, payload, &alias);
+            }
+        })
+    }
+
+    /// Create the sender of the requests to `upstream`, with a client of its own as the TLS config is part
+    /// of its connector
+    fn request_sender(&self, upstream: &Upstream) -> RequestSender {
+        let connector = ProxyConnector::new(
+            self.config.proxy.clone(),
+            self.config.unix_socket.as_ref().map(PathBuf::from),
+            Resolver::new(self.config.dns.clone()),
+            self.config.pool.connect_timeout(),
+        );
+        let builder = if let Some(tls_config) = upstream.tls_client_config.clone() {
+            HttpsConnectorBuilder::new().with_tls_config(tls_config)
         } else {
-            HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .https_or_http()
+            HttpsConnectorBuilder::new().with_native_roots()
+        }
+        .https_or_http();
+        // verify certificates of all hosts against the configured server name and send it via SNI
+        let builder = if let Some(server_name) = upstream.profile.server_name() {
+            builder.with_server_name(server_name.to_string())
+        } else {
+            builder
+        };
+        // the enabled protocols determine the ALPN protocols offered during the TLS handshake
+        let https = match self.config.http_version {
+            HttpVersion::Http11 => builder.enable_http1().wrap_connector(connector),
+            HttpVersion::Http2 => builder.enable_http2().wrap_connector(connector),
+            HttpVersion::Auto => builder
                 .enable_http1()
                 .enable_http2()
-                .build()
+                .wrap_connector(connector),
         };
-        let client = HyperClient::builder().build(https);
+        // a single client is shared by all requests to the upstream, its connection pool only opens new
+        // connections when all pooled ones are busy, up to `concurrency`, and closes them again once they idle.
+        // HTTP/2 connections multiplex all concurrent requests to the same host over a single connection.
+        let mut client = HyperClient::builder();
+        client.http2_only(self.config.http_version == HttpVersion::Http2);
+        self.config.pool.apply(&mut client);
+        let client = client.build(https);
 
-        self.client = Some(Arc::new(client));
+        RequestSender {
+            client,
+            host_limits: HostLimits::new(self.config.max_connections_per_host),
+            circuit_breakers: CircuitBreakers::new(self.config.circuit_breaker.clone()),
+            cache: self.config.cache_max_bytes.map(ResponseCache::new),
+            proxy: self.config.proxy.clone(),
+            timeout: self
+                .config
+                .timeout
+                .map_or_else(|| Duration::from_secs(60), Duration::from_nanos),
+            retry: self.config.retry.clone(),
+            max_redirects: self.config.max_redirects,
+            profile: upstream.profile.clone(),
+            scheme: upstream.scheme(),
+            compression: self.config.compression,
+            cookies: self.cookies.clone(),
+        }
+    }
 
+    /// Append `event` to the streamed request of its stream, if there is one
+    ///
+    /// Returns `false` if the event starts a new stream, finishing the streamed request of the previous one.
+    async fn append_to_stream(
+        &mut self,
+        event: &Event,
+        end_stream: bool,
+        ctx: &SinkContext,
+        serializer: &mut EventSerializer,
+    ) -> bool {
+        let stream = (event.id.source_id(), event.id.stream_id());
+        if let Some(streamed) = self
+            .streamed_request
+            .as_mut()
+            .filter(|streamed| streamed.stream == stream)
+        {
+            if event.transactional {
+                if let Some(contraflow_data) = streamed.contraflow_data.as_mut() {
+                    contraflow_data.track(event);
+                } else {
+                    streamed.contraflow_data = Some(ContraflowData::from(event));
+                }
+            }
+            let res = append_event(&mut streamed.builder, event, serializer).await;
+            // a failed append means the request failed already, its task fails the events with it
+            let failed = res.is_err();
+            ctx.swallow_err(res, "Error serializing event into streamed request body");
+            if failed || end_stream {
+                self.finish_streamed_request(ctx, serializer).await;
+            }
+            true
+        } else {
+            self.finish_streamed_request(ctx, serializer).await;
+            false
+        }
+    }
+
+    /// Finish the body of the streamed request, if there is one
+    async fn finish_streamed_request(
+        &mut self,
+        ctx: &SinkContext,
+        serializer: &mut EventSerializer,
+    ) {
+        if let Some(mut streamed) = self.streamed_request.take() {
+            let res = streamed.builder.finalize(serializer).await;
+            hand_over(streamed.contraflow_tx, streamed.contraflow_data, ctx);
+            ctx.swallow_err(
+                res,
+                "Error serializing final parts of the stream into request body",
+            );
+        }
+    }
+
+    /// Handle an event exceeding the configured concurrency with a non-blocking `overflow`
+    async fn overflow(&self, event: &Event, url: &Url, ctx: &SinkContext) -> Result<SinkReply> {
+        debug!(
+            "{ctx} Dropping event, {} requests are in flight",
+            self.config.concurrency
+        );
+        if self.config.overflow == Overflow::Fail {
+            return Ok(SinkReply::FAIL);
+        }
+        let error = format!(
+            "Concurrency limit of {} requests reached",
+            self.config.concurrency
+        );
+        self.too_many_requests(event, url, error, ctx).await
+    }
+
+    /// Drop `event` because of the `rate_limit`, emitting a synthetic `429` and acking it
+    async fn rate_limited(&self, event: &Event, url: &Url, ctx: &SinkContext) -> Result<SinkReply> {
+        let max_per_sec = self
+            .rate_limiter
+            .as_ref()
+            .map(RateLimiter::max_per_sec)
+            .unwrap_or_default();
+        debug!("{ctx} Dropping event, rate limit of {max_per_sec} requests per second reached");
+        let error = format!("Rate limit of {max_per_sec} requests per second reached");
+        self.too_many_requests(event, url, error, ctx).await
+    }
+
+    /// Emit a synthetic `429 Too Many Requests` error event with `error` for the dropped `event` and ack it
+    async fn too_many_requests(
+        &self,
+        event: &Event,
+        url: &Url,
+        error: String,
+        ctx: &SinkContext,
+    ) -> Result<SinkReply> {
+        let response_meta =
+            HttpResponseMeta::synthetic(StatusCode::TOO_MANY_REQUESTS, url.as_str().parse()?);
+        self.send_error(
+            event,
+            literal!({ "error": error }),
+            literal!({ "response": Value::from(response_meta) }),
+            ctx,
+        )
+        .await;
+        Ok(SinkReply::ACK)
+    }
+
+    /// Emit an error event for `event`, which selected a `profile` that isn't configured, and fail it
+    async fn unknown_profile(
+        &self,
+        event: &Event,
+        profile: &Value<'_>,
+        ctx: &SinkContext,
+    ) -> Result<SinkReply> {
+        debug!("{ctx} Dropping event with unknown profile {profile}");
+        self.send_error(
+            event,
+            literal!({ "error": format!("Unknown profile {profile}"), "kind": "profile" }),
+            literal!({ "profile": profile.clone_static() }),
+            ctx,
+        )
+        .await;
+        Ok(SinkReply::FAIL)
+    }
+
+    /// Emit an error event with `payload` and `http_meta` for the dropped `event`, carrying its correlation
+    async fn send_error(
+        &self,
+        event: &Event,
+        payload: Value<'static>,
+        http_meta: Value<'static>,
+        ctx: &SinkContext,
+    ) {
+        if self.source_is_connected.load(Ordering::Acquire) {
+            let mut meta = ctx.meta(http_meta);
+            if let Some(corr_meta) = event
+                .value_meta_iter()
+                .next()
+                .and_then(|(_, meta)| meta.get("correlation"))
+            {
+                meta.try_insert("correlation", corr_meta.clone_static());
+            }
+            let reply = SourceReply::Structured {
+                origin_uri: self.origin_uri.clone(),
+                payload: (payload, meta).into(),
+                stream: DEFAULT_STREAM_ID,
+                port: Some(ERR),
+            };
+            ctx.swallow_err(
+                self.response_tx.send(reply).await,
+                "Error sending error event to source",
+            );
+        }
+    }
+}
+
+/// The request metadata of response and error events, with the body for `echo_request: "full"`
+fn echoed_request(
+    req_meta: Option<Value<'static>>,
+    echoed_body: Option<&std::sync::Mutex<Vec<u8>>>,
+) -> Result<Option<Value<'static>>> {
+    let mut req_meta = if let Some(req_meta) = req_meta {
+        req_meta
+    } else {
+        return Ok(None);
+    };
+    if let Some(body) = echoed_body {
+        let body = body.lock()?.clone();
+        req_meta.try_insert("raw_body", Value::Bytes(body.into()));
+    }
+    Ok(Some(req_meta))
+}
+
+/// Read the whole body of `response`, decoded according to its `Content-Encoding`,
+/// and the trailers following it, if any
+async fn read_body(response: &mut Response<hyper::Body>) -> Result<(Vec<u8>, Option<HeaderMap>)> {
+    let mut data = Vec::new();
+    while let Some(chunk) = response.data().await.transpose()? {
+        data.extend_from_slice(&chunk);
+    }
+    let trailers = response.trailers().await?;
+    Ok((
+        compression::decompress(response.headers_mut(), data)?,
+        trailers,
+    ))
+}
+
+/// Classify why sending a request failed, for the `kind` of the resulting error event
+fn failure_kind(e: &Error) -> &'static str {
+    match &e.0 {
+        ErrorKind::Timeout(_) => "timeout",
+        ErrorKind::TooManyRedirects(_) => "redirect",
+        ErrorKind::CircuitBreakerOpen(_) => "circuit_breaker",
+        ErrorKind::Hyper(hyper_error) => {
+            let mut source: Option<&(dyn std::error::Error + 'static)> = Some(hyper_error);
+            while let Some(cause) = source {
+                // hypers connector doesn't expose its error type, only the message
+                if cause.to_string() == "dns error" {
+                    return "dns";
+                }
+                let is_tls = cause.is::<rustls::Error>()
+                    || cause
+                        .downcast_ref::<std::io::Error>()
+                        .and_then(std::io::Error::get_ref)
+                        .map_or(false, |inner| inner.is::<rustls::Error>());
+                if is_tls {
+                    return "tls";
+                }
+                source = cause.source();
+            }
+            if hyper_error.is_connect() {
+                "connect"
+            } else {
+                "request"
+            }
+        }
+        ErrorKind::Io(_) => "connect",
+        _ => "request",
+    }
+}
+
+#[async_trait::async_trait()]
+impl Sink for HttpRequestSink {
+    async fn connect(&mut self, ctx: &SinkContext, _attempt: &Attempt) -> Result<bool> {
+        let senders = self
+            .upstreams
+            .map(|upstream| Arc::new(self.request_sender(upstream)));
+        if let Some(metrics_interval_ms) = self.config.metrics_interval_ms {
+            if let Some(metrics_task) = self.metrics_task.take() {
+                metrics_task.abort();
+            }
+            self.metrics_task = Some(self.spawn_metrics_task(
+                Duration::from_millis(metrics_interval_ms),
+                senders.clone(),
+                ctx.alias().clone(),
+            ));
+        }
+        self.senders = Some(senders);
         Ok(true)
     }
 
@@ -312,10 +1331,69 @@ impl Sink for HttpRequestSink {
         serializer: &mut EventSerializer,
         start: u64,
     ) -> Result<SinkReply> {
+        let end_stream = event.value_meta_iter().any(|(_, meta)| {
+            ctx.extract_meta(meta)
+                .get("request")
+                .get_bool("end_stream")
+                .unwrap_or_default()
+        });
+        if self.config.stream_requests
+            && self
+                .append_to_stream(&event, end_stream, ctx, serializer)
+                .await
+        {
+            return Ok(SinkReply::NONE);
+        }
+
+        // the upstream selected by the event, unknown profiles are rejected before taking any limits
+        let profile = event
+            .value_meta_iter()
+            .next()
+            .and_then(|(_, meta)| ctx.extract_meta(meta))
+            .get("profile");
+        let profile_name = profile.map(|profile| profile.as_str().unwrap_or_default());
+        let url = if let Some(upstream) = self.upstreams.get(profile_name) {
+            upstream.profile.url.clone()
+        } else {
+            return self
+                .unknown_profile(&event, profile.unwrap_or(&Value::const_null()), ctx)
+                .await;
+        };
+
+        // hold back or drop requests exceeding the rate limit, only the sink itself takes tokens,
+        // so waiting here never waits on a request in flight
+        if let Some(rate_limiter) = self.rate_limiter.as_mut() {
+            let now = Instant::now();
+            match rate_limiter.on_limit() {
+                OnLimit::Delay => {
+                    let wait = rate_limiter.reserve(now);
+                    if !wait.is_zero() {
+                        sleep(wait).await;
+                    }
+                }
+                OnLimit::Drop => {
+                    if !rate_limiter.try_acquire(now) {
+                        return self.rate_limited(&event, &url, ctx).await;
+                    }
+                }
+            }
+        }
+
         // constrain to max concurrency - propagate CB close on hitting limit
-        let guard = self.concurrency_cap.inc_for(&event)?;
+        let guard = if self.config.overflow == Overflow::Block {
+            self.concurrency_cap.inc_for(&event)?
+        } else if let Some(guard) = self.concurrency_cap.try_inc_for(&event) {
+            guard
+        } else {
+            return self.overflow(&event, &url, ctx).await;
+        };
 
-        if let Some(client) = self.client.as_ref().cloned() {
+        if let Some(sender) = self
+            .senders
+            .as_ref()
+            .and_then(|senders| senders.get(profile_name))
+            .cloned()
+        {
             // TODO: think about making ctx an Arc so it doesn't have to be cloned deep
             let task_ctx = ctx.clone();
 
@@ -325,13 +1403,14 @@ impl Sink for HttpRequestSink {
                 .then(|| self.response_tx.clone());
 
             let reply_tx = self.reply_tx.clone();
-            let contraflow_data = if event.transactional {
+            let mut contraflow_data = if event.transactional {
                 Some(ContraflowData::from(&event))
             } else {
                 None
             };
             let mut origin_uri = self.origin_uri.clone();
-            let ingest_ns = event.ingest_ns;
+            let split_errors = self.config.split_errors;
+            let stream_response = self.config.stream_response;
 
             // take the metadata from the first element of the batch
             let event_meta = event.value_meta_iter().next().map(|t| t.1);
@@ -343,17 +1422,35 @@ impl Sink for HttpRequestSink {
 
             let http_meta = event_meta.and_then(|meta| ctx.extract_meta(meta));
             let mut builder = ctx.bail_err(
-                HttpRequestBuilder::new(request_id, http_meta, &self.codec_map, &self.config),
+                HttpRequestBuilder::new(
+                    request_id,
+                    http_meta,
+                    &self.codec_map,
+                    &self.config,
+                    &sender.profile,
+                )
+                .await,
                 "Error turning event into an HTTP Request",
             )?;
             let codec_map = self.codec_map.clone();
+            let stats = self.stats.clone();
+            let echo_request = self.config.echo_request;
+            let echoed_body = (echo_request == EchoRequest::Full).then(|| builder.echo_body());
             let request = builder.take_request()?;
 
-            let req_meta = extract_request_meta(&request, self.scheme)?;
-            let t = self
-                .config
-                .timeout
-                .map_or_else(|| Duration::from_secs(60), Duration::from_nanos);
+            let req_meta = if echo_request == EchoRequest::None {
+                None
+            } else {
+                Some(extract_request_meta(&request, sender.scheme)?)
+            };
+            // the events of a streamed request are handed over once the stream is finished
+            let (contraflow_tx, contraflow_rx) = oneshot::channel();
+            let contraflow_tx = if self.config.stream_requests && !end_stream {
+                Some(contraflow_tx)
+            } else {
+                hand_over(contraflow_tx, contraflow_data.take(), ctx);
+                None
+            };
             // spawn the sending task
             tokio::task::spawn(async move {
                 // extract request meta for the response metadata from the finally prepared request
@@ -368,49 +1465,168 @@ impl Sink for HttpRequestSink {
                     .split('/')
                     .map(ToString::to_string)
                     .collect();
-                match timeout(t, client.request(request)).await {
-                    Ok(Ok(mut response)) => {
-                        let mut data: Vec<u8> = Vec::new();
-                        while let Some(chunk) = response.data().await.transpose()? {
-                            data.extend_from_slice(&chunk);
+                let request_url = request.uri().clone();
+                // all errors from here on fail the event, like errors sending the request
+                let host_permit = sender.host_limits.acquire(&request_url).await;
+                let sent = Instant::now();
+                let (host_permit, res) = match host_permit {
+                    Ok(host_permit) => {
+                        let res = sender
+                            .send_guarded(request, &request_url, sent, &task_ctx)
+                            .await;
+                        (host_permit, res)
+                    }
+                    Err(e) => (None, Err(e)),
+                };
+                stats.requests.fetch_add(1, Ordering::Relaxed);
+                stats.record_latency(sent.elapsed());
+                // responses whose body can't be read or decoded fail like requests without a response
+                let mut undecodable_response = None;
+                let res = match res {
+                    Ok((mut response, url)) if !stream_response => {
+                        match read_body(&mut response).await {
+                            Ok((data, trailers)) => Ok((response, url, data, trailers)),
+                            Err(e) => {
+                                // the response metadata of the error event is best effort
+                                undecodable_response =
+                                    HttpResponseMeta::from_response(&response, url).ok();
+                                Err(e)
+                            }
                         }
+                    }
+                    res => res.map(|(response, url)| (response, url, Vec::new(), None)),
+                };
+                let (res, req_meta) = match echoed_request(req_meta, echoed_body.as_deref()) {
+                    Ok(req_meta) => (res, req_meta),
+                    Err(e) => (Err(e), None),
+                };
+                let res = match res {
+                    Ok((mut response, url, data, trailers)) => {
+                        async {
+                            if let Some(response_tx) = &response_tx {
+                                let response_meta =
+                                    HttpResponseMeta::from_response(&response, url)?
+                                        .with_trailers(trailers.as_ref())?
+                                        .with_duration(sent.elapsed());
+                                let mut http_meta = literal!({
+                                    "request_id": request_id.get(),
+                                    "response": Value::from(response_meta)
+                                });
+                                if let Some(req_meta) = req_meta.clone() {
+                                    http_meta.try_insert("request", req_meta);
+                                }
+                                let mut meta = task_ctx.meta(http_meta);
 
-                        if let Some(response_tx) = response_tx {
-                            let response_meta = extract_response_meta(&response)?;
-                            let mut meta = task_ctx.meta(literal!({
-                                "request": req_meta,
-                                "request_id": request_id.get(),
-                                "response": response_meta
-                            }));
-
-                            if let Some(corr_meta) = correlation_meta {
-                                meta.try_insert("correlation", corr_meta);
+                                if let Some(corr_meta) = correlation_meta.clone() {
+                                    meta.try_insert("correlation", corr_meta);
+                                }
+                                // the codec is picked by the content type of each response, e.g. error pages
+                                // are decoded as text, responses without a valid one use the `*/*` codec
+                                let mime = response
+                                    .headers()
+                                    .get(hyper::header::CONTENT_TYPE)
+                                    .and_then(|mime_header| mime_header.to_str().ok())
+                                    // https://static.wikia.nocookie.net/disney-fan-fiction/images/9/99/Nemo-Seagulls_.jpg/revision/latest?cb=20130722023815
+                                    .and_then(|mime_header| mime_header.parse::<mime::Mime>().ok());
+                                let codec_overwrite = codec_map
+                                    .get_codec_name(
+                                        mime.as_ref().map_or("*/*", mime::Mime::essence_str),
+                                    )
+                                    .cloned();
+                                let port = if split_errors
+                                    && (response.status().is_client_error()
+                                        || response.status().is_server_error())
+                                {
+                                    Some(ERR)
+                                } else {
+                                    None
+                                };
+                                if stream_response {
+                                    // every response is its own stream, identified by its request id
+                                    let stream = request_id.get();
+                                    while let Some(chunk) = response.data().await {
+                                        let chunk = match chunk {
+                                            Ok(chunk) => chunk,
+                                            Err(e) => {
+                                                task_ctx.swallow_err(
+                                                    response_tx
+                                                        .send(SourceReply::StreamFail(stream))
+                                                        .await,
+                                                    "Error sending stream fail to source",
+                                                );
+                                                return Err(e.into());
+                                            }
+                                        };
+                                        let reply = SourceReply::Data {
+                                            origin_uri: origin_uri.clone(),
+                                            data: chunk.to_vec(),
+                                            meta: Some(meta.clone()),
+                                            stream: Some(stream),
+                                            port: port.clone(),
+                                            codec_overwrite: codec_overwrite.clone(),
+                                        };
+                                        task_ctx.swallow_err(
+                                            response_tx.send(reply).await,
+                                            "Error sending response chunk to source",
+                                        );
+                                    }
+                                    // trailers follow the body, so only the end of the stream carries them
+                                    let trailers = match response.trailers().await {
+                                        Ok(trailers) => trailers,
+                                        Err(e) => {
+                                            task_ctx.swallow_err(
+                                                response_tx
+                                                    .send(SourceReply::StreamFail(stream))
+                                                    .await,
+                                                "Error sending stream fail to source",
+                                            );
+                                            return Err(e.into());
+                                        }
+                                    };
+                                    if let Some(trailers) = trailers {
+                                        if let Some(response_meta) = meta
+                                            .get_mut("http_client")
+                                            .and_then(|http_meta| http_meta.get_mut("response"))
+                                        {
+                                            response_meta.try_insert(
+                                                "trailers",
+                                                extract_headers(&trailers)?,
+                                            );
+                                        }
+                                    }
+                                    let reply = SourceReply::EndStream {
+                                        origin_uri: origin_uri.clone(),
+                                        stream,
+                                        meta: Some(meta),
+                                    };
+                                    task_ctx.swallow_err(
+                                        response_tx.send(reply).await,
+                                        "Error sending end of response stream to source",
+                                    );
+                                } else {
+                                    let reply = SourceReply::Data {
+                                        origin_uri: origin_uri.clone(),
+                                        data,
+                                        meta: Some(meta),
+                                        stream: None, // a response (as well as a request) is a discrete unit and not part of a stream
+                                        port,
+                                        codec_overwrite,
+                                    };
+                                    task_ctx.swallow_err(
+                                        response_tx.send(reply).await,
+                                        "Error sending response to source",
+                                    );
+                                }
                             }
-                            let codec_name = if let Some(mime_header) =
-                                response.headers().get(hyper::header::CONTENT_TYPE)
-                            {
-                                // https://static.wikia.nocookie.net/disney-fan-fiction/images/9/99/Nemo-Seagulls_.jpg/revision/latest?cb=20130722023815
-                                let mime: mime::Mime = mime_header.to_str()?.parse()?;
-
-                                codec_map.get_codec_name(mime.essence_str())
-                            } else {
-                                None
-                            };
-                            let codec_overwrite = codec_name.cloned();
-                            let reply = SourceReply::Data {
-                                origin_uri,
-                                data,
-                                meta: Some(meta),
-                                stream: None, // a response (as well as a request) is a discrete unit and not part of a stream
-                                port: None,
-                                codec_overwrite,
-                            };
-                            task_ctx.swallow_err(
-                                response_tx.send(reply).await,
-                                "Error sending response to source",
-                            );
+                            Result::Ok(())
                         }
-                        if let Some(contraflow_data) = contraflow_data {
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+                match res {
+                    Ok(()) => {
+                        if let Some(contraflow_data) = contraflow_rx.await.ok().flatten() {
                             task_ctx.swallow_err(
                                 reply_tx
                                     .send(AsyncSinkReply::Ack(contraflow_data, nanotime() - start)),
@@ -418,18 +1634,56 @@ impl Sink for HttpRequestSink {
                             );
                         }
                     }
-                    Ok(Err(e)) => {
+                    Err(e) => {
                         error!("{task_ctx} Error sending HTTP request: {e}");
-                        if let Some(contraflow_data) = contraflow_data {
+                        stats.failures.fetch_add(1, Ordering::Relaxed);
+                        // failed requests are reported as error events via the `err` port
+                        if let Some(response_tx) = response_tx {
+                            let mut http_meta = literal!({
+                                "request_id": request_id.get(),
+                            });
+                            if let Some(req_meta) = req_meta {
+                                http_meta.try_insert("request", req_meta);
+                            }
+                            // a synthetic `504 Gateway Timeout` or `503 Service Unavailable` response
+                            let synthetic_status = match e.0 {
+                                ErrorKind::Timeout(_) => Some(StatusCode::GATEWAY_TIMEOUT),
+                                ErrorKind::CircuitBreakerOpen(_) => {
+                                    Some(StatusCode::SERVICE_UNAVAILABLE)
+                                }
+                                _ => None,
+                            };
+                            let response_meta = undecodable_response.or_else(|| {
+                                synthetic_status.map(|status| {
+                                    HttpResponseMeta::synthetic(status, request_url.clone())
+                                })
+                            });
+                            if let Some(response_meta) = response_meta {
+                                let response_meta = response_meta.with_duration(sent.elapsed());
+                                http_meta.try_insert("response", Value::from(response_meta));
+                            }
+                            let error = if let ErrorKind::Timeout(_) = e.0 {
+                                format!("Request timed out after {:?}", sender.timeout)
+                            } else {
+                                e.to_string()
+                            };
+                            let mut meta = task_ctx.meta(http_meta);
+                            if let Some(corr_meta) = correlation_meta {
+                                meta.try_insert("correlation", corr_meta);
+                            }
+                            let kind = failure_kind(&e);
+                            let reply = SourceReply::Structured {
+                                origin_uri,
+                                payload: (literal!({ "error": error, "kind": kind }), meta).into(),
+                                stream: DEFAULT_STREAM_ID,
+                                port: Some(ERR),
+                            };
                             task_ctx.swallow_err(
-                                reply_tx.send(AsyncSinkReply::Fail(contraflow_data)),
-                                "Error sending fail contraflow",
+                                response_tx.send(reply).await,
+                                "Error sending error response to source",
                             );
                         }
-                    }
-                    Err(e) => {
-                        error!("{task_ctx} Error sending HTTP request: {e}");
-                        if let Some(contraflow_data) = contraflow_data {
+                        if let Some(contraflow_data) = contraflow_rx.await.ok().flatten() {
                             task_ctx.swallow_err(
                                 reply_tx.send(AsyncSinkReply::Fail(contraflow_data)),
                                 "Error sending fail contraflow",
@@ -437,21 +1691,29 @@ impl Sink for HttpRequestSink {
                         }
                     }
                 }
+                drop(host_permit);
                 drop(guard);
                 Result::Ok(())
             });
 
             // if we have a chunked request we still gotta do some work (sending the chunks)
-            for value in event.value_iter() {
+            ctx.bail_err(
+                append_event(&mut builder, &event, serializer).await,
+                "Error serializing event into request body",
+            )?;
+            if let Some(contraflow_tx) = contraflow_tx {
+                self.streamed_request = Some(StreamedRequest {
+                    stream: (event.id.source_id(), event.id.stream_id()),
+                    builder,
+                    contraflow_data,
+                    contraflow_tx,
+                });
+            } else {
                 ctx.bail_err(
-                    builder.append(value, ingest_ns, serializer).await,
-                    "Error serializing event into request body",
+                    builder.finalize(serializer).await,
+                    "Error serializing final parts of the event into request body",
                 )?;
             }
-            ctx.bail_err(
-                builder.finalize(serializer).await,
-                "Error serializing final parts of the event into request body",
-            )?;
         } else {
             error!("{ctx} No http client available.");
             return Ok(SinkReply::FAIL);
@@ -464,8 +1726,171 @@ impl Sink for HttpRequestSink {
         true
     }
 
+    async fn metrics(&mut self, timestamp: u64, ctx: &SinkContext) -> Vec<EventPayload> {
+        // with `metrics_interval_ms` they are emitted by the metrics task instead
+        if self.config.metrics_interval_ms.is_some() {
+            return vec![];
+        }
+        vec![Self::metrics_payload(
+            &self.stats,
+            &self.concurrency_cap,
+            self.senders.as_ref(),
+            ctx.alias(),
+            timestamp,
+        )]
+    }
+
+    async fn on_signal(
+        &mut self,
+        signal: Event,
+        ctx: &SinkContext,
+        serializer: &mut EventSerializer,
+    ) -> Result<SinkReply> {
+        // no more events of the current stream arrive from a draining upstream
+        if let Some(SignalKind::Drain(_)) = signal.kind {
+            self.finish_streamed_request(ctx, serializer).await;
+        }
+        Ok(SinkReply::NONE)
+    }
+
+    async fn on_stop(&mut self, ctx: &SinkContext) -> Result<()> {
+        if let Some(metrics_task) = self.metrics_task.take() {
+            metrics_task.abort();
+        }
+        // without the serializer the body of a streamed request ends with the chunks appended so far
+        if let Some(streamed) = self.streamed_request.take() {
+            hand_over(streamed.contraflow_tx, streamed.contraflow_data, ctx);
+        }
+        // wait for in-flight requests, so their responses and acks aren't lost
+        let drain_timeout = Duration::from_nanos(self.config.drain_timeout);
+        if !self.concurrency_cap.wait_for_idle(drain_timeout).await {
+            warn!("{ctx} In-flight requests did not finish within {drain_timeout:?}");
+        }
+        Ok(())
+    }
+
     // we do ack when the response is sent
     fn auto_ack(&self) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::unbounded;
+    use crate::connectors::{
+        reconnect::ConnectionLostNotifier, utils::quiescence::QuiescenceBeacon,
+    };
+    use tremor_common::ids::SinkId;
+    use tremor_value::literal;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn host_limits() -> Result<()> {
+        let snot = Uri::from_static("http://snot:8080/");
+        let badger = Uri::from_static("http://badger/");
+        assert!(HostLimits::new(None).acquire(&snot).await?.is_none());
+
+        let limits = HostLimits::new(Some(1));
+        let permit = limits.acquire(&snot).await?;
+        assert!(permit.is_some());
+        // a second request to the same host waits for the permit
+        let waiting = tokio::time::timeout(Duration::from_millis(100), limits.acquire(&snot));
+        assert!(waiting.await.is_err());
+        assert!(limits.acquire(&badger).await?.is_some());
+        drop(permit);
+        // hosts without requests are evicted
+        let _permit = limits.acquire(&badger).await?;
+        let hosts = limits.hosts.lock().map(|hosts| hosts.len()).ok();
+        assert_eq!(Some(1), hosts);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn metrics() -> Result<()> {
+        let (response_tx, _response_rx) = bounded(qsize());
+        let (reply_tx, _reply_rx) = unbounded();
+        let (connection_lost_tx, _connection_lost_rx) = bounded(1);
+        let config = Config::new(&literal!({"url": "http://localhost:65535", "concurrency": 2}))?;
+        let alias = Alias::new("flow", "snot");
+        let upstreams = Profiles::load(&alias, &config)?;
+        let mut sink = HttpRequestSink::new(
+            response_tx,
+            reply_tx,
+            config,
+            upstreams,
+            Arc::new(MimeCodecMap::with_builtin()),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let ctx = SinkContext::new(
+            SinkId::default(),
+            alias,
+            CONNECTOR_TYPE.into(),
+            QuiescenceBeacon::default(),
+            ConnectionLostNotifier::new(connection_lost_tx),
+        );
+
+        let _guard = sink.concurrency_cap.inc_for(&Event::default())?;
+        sink.stats.requests.store(4, Ordering::Relaxed);
+        sink.stats.failures.store(1, Ordering::Relaxed);
+        for _ in 0..99 {
+            sink.stats.record_latency(Duration::from_millis(1));
+        }
+        sink.stats.record_latency(Duration::from_millis(100));
+        let metrics = sink.metrics(42, &ctx).await;
+        assert_eq!(1, metrics.len());
+        let metric = metrics[0].suffix().value();
+        assert_eq!(Some("http_client_stats"), metric.get_str("measurement"));
+        assert_eq!(
+            Some(&literal!({"flow": "flow", "connector": "flow::snot"})),
+            metric.get("tags")
+        );
+        let fields = metric.get("fields");
+        // latencies are recorded with 2 significant figures
+        let latency_p50 = fields.get_u64("latency_p50").unwrap_or_default();
+        assert!((990_000..1_010_000).contains(&latency_p50), "{latency_p50}");
+        let latency_p99 = fields.get_u64("latency_p99").unwrap_or_default();
+        assert!((990_000..1_010_000).contains(&latency_p99), "{latency_p99}");
+        let mut fields = fields.cloned().unwrap_or_default();
+        fields.remove("latency_p50")?;
+        fields.remove("latency_p99")?;
+        assert_eq!(
+            literal!({
+                "in_flight": 1,
+                "concurrency": 2,
+                "clients": 0,
+                "requests": 4,
+                "failures": 1,
+                "error_rate": 0.25,
+                // the first report has no interval to compute a rate for
+                "requests_per_sec": 0.0,
+                "circuits_open": 0,
+                "circuits_half_open": 0
+            }),
+            fields
+        );
+        assert_eq!(Some(42), metric.get_u64("timestamp"));
+
+        // rates and latencies only cover the requests since the last report
+        sink.connect(&ctx, &Attempt::default()).await?;
+        sink.stats.requests.store(6, Ordering::Relaxed);
+        sink.stats.record_latency(Duration::from_millis(100));
+        let metrics = sink.metrics(2_000_000_042, &ctx).await;
+        let fields = metrics[0].suffix().value().get("fields");
+        assert_eq!(Some(0.0), fields.get_f64("error_rate"));
+        assert_eq!(Some(6), fields.get_u64("requests"));
+        assert_eq!(Some(1.0), fields.get_f64("requests_per_sec"));
+        assert_eq!(Some(1), fields.get_u64("clients"));
+        let latency_p50 = fields.get_u64("latency_p50").unwrap_or_default();
+        assert!(
+            (99_000_000..101_000_000).contains(&latency_p50),
+            "{latency_p50}"
+        );
+        let metrics = sink.metrics(3_000_000_042, &ctx).await;
+        let fields = metrics[0].suffix().value().get("fields");
+        assert_eq!(Some(0.0), fields.get_f64("error_rate"));
+        assert_eq!(Some(0.0), fields.get_f64("requests_per_sec"));
+        assert_eq!(Some(0), fields.get_u64("latency_p99"));
+        Ok(())
+    }
+}
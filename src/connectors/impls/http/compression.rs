@@ -0,0 +1,271 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transparent compression of request bodies and decompression of responses
+//!
+//! Response bodies are decoded according to their `Content-Encoding`, `gzip`, `deflate`, `br` and `zstd`
+//! are supported. Bodies with any other encoding are rejected, so they don't reach the codec undecoded.
+
+use crate::errors::Result;
+use hyper::{body::Bytes, header, http::request::Parts, HeaderMap};
+use libflate::{
+    gzip::{Encoder, MultiDecoder},
+    zlib,
+};
+use std::io::{Read, Write};
+
+const GZIP: &str = "gzip";
+
+/// Compression of request and response bodies of the `http_client`
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Compression {
+    /// compress request bodies with gzip and accept gzipped responses
+    Gzip,
+    /// send request bodies as is
+    #[default]
+    None,
+}
+
+impl Compression {
+    pub(super) fn is_enabled(self) -> bool {
+        self != Self::None
+    }
+
+    /// Compress the request `body` and set the `Content-Encoding` and `Accept-Encoding` headers in `parts`
+    ///
+    /// Empty bodies and bodies that already have a `Content-Encoding` are left untouched.
+    pub(super) fn compress(self, parts: &mut Parts, body: Bytes) -> Result<Bytes> {
+        if self == Self::None {
+            return Ok(body);
+        }
+        if !parts.headers.contains_key(header::ACCEPT_ENCODING) {
+            parts.headers.insert(
+                header::ACCEPT_ENCODING,
+                header::HeaderValue::from_static(GZIP),
+            );
+        }
+        if body.is_empty() || parts.headers.contains_key(header::CONTENT_ENCODING) {
+            return Ok(body);
+        }
+        let mut encoder = Encoder::new(Vec::with_capacity(body.len()))?;
+        encoder.write_all(&body)?;
+        let compressed = encoder.finish().into_result()?;
+        parts.headers.insert(
+            header::CONTENT_ENCODING,
+            header::HeaderValue::from_static(GZIP),
+        );
+        // the length of the uncompressed body, hyper sets the correct one
+        parts.headers.remove(header::CONTENT_LENGTH);
+        Ok(Bytes::from(compressed))
+    }
+}
+
+/// Decompress a response body `data` according to the `Content-Encoding` in its `headers`
+///
+/// The `Content-Encoding` and `Content-Length` headers are removed if the body was decompressed,
+/// so they describe the body as it is emitted.
+pub(super) fn decompress(headers: &mut HeaderMap, data: Vec<u8>) -> Result<Vec<u8>> {
+    if data.is_empty() || !headers.contains_key(header::CONTENT_ENCODING) {
+        return Ok(data);
+    }
+    let mut codings = Vec::new();
+    for value in headers.get_all(header::CONTENT_ENCODING) {
+        let value = value
+            .to_str()
+            .map_err(|_| "Invalid Content-Encoding header")?;
+        codings.extend(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|coding| !coding.is_empty() && !coding.eq_ignore_ascii_case("identity"))
+                .map(str::to_ascii_lowercase),
+        );
+    }
+    // codings are listed in the order they were applied
+    let mut data = data;
+    for coding in codings.iter().rev() {
+        data = decode(coding, &data)?;
+    }
+    headers.remove(header::CONTENT_ENCODING);
+    headers.remove(header::CONTENT_LENGTH);
+    Ok(data)
+}
+
+/// Decode `data` encoded with the lower case content `coding`
+fn decode(coding: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    let res = match coding {
+        // `x-gzip` is an alias of `gzip`, see <https://www.rfc-editor.org/rfc/rfc9110#section-8.4.1.3>
+        GZIP | "x-gzip" => MultiDecoder::new(data).and_then(|mut d| d.read_to_end(&mut decoded)),
+        // `deflate` is the zlib format, not raw deflate
+        "deflate" => zlib::Decoder::new(data).and_then(|mut d| d.read_to_end(&mut decoded)),
+        "br" => brotli::Decompressor::new(data, 4096).read_to_end(&mut decoded),
+        "zstd" => {
+            zstd::stream::read::Decoder::new(data).and_then(|mut d| d.read_to_end(&mut decoded))
+        }
+        other => return Err(format!("Unsupported Content-Encoding {other}").into()),
+    };
+    res.map_err(|e| format!("Invalid {coding} encoded response body: {e}"))?;
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::Request;
+
+    fn request_parts() -> Result<Parts> {
+        Ok(Request::builder()
+            .uri("http://localhost/snot")
+            .header(header::CONTENT_LENGTH, "11")
+            .body(())?
+            .into_parts()
+            .0)
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let mut parts = request_parts()?;
+        let compressed = Compression::Gzip.compress(&mut parts, Bytes::from("snot badger"))?;
+        assert_eq!(Some(&[0x1f_u8, 0x8b][..]), compressed.get(0..2));
+        assert_eq!(
+            Some(GZIP),
+            parts
+                .headers
+                .get(header::CONTENT_ENCODING)
+                .and_then(|v| v.to_str().ok())
+        );
+        assert!(parts.headers.get(header::CONTENT_LENGTH).is_none());
+
+        let mut headers = parts.headers;
+        let decompressed = decompress(&mut headers, compressed.to_vec())?;
+        assert_eq!(b"snot badger".to_vec(), decompressed);
+        assert!(headers.get(header::CONTENT_ENCODING).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn empty_body() -> Result<()> {
+        let mut parts = request_parts()?;
+        let body = Compression::Gzip.compress(&mut parts, Bytes::new())?;
+        assert!(body.is_empty());
+        assert!(parts.headers.get(header::CONTENT_ENCODING).is_none());
+        assert!(parts.headers.get(header::ACCEPT_ENCODING).is_some());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_ENCODING,
+            header::HeaderValue::from_static(GZIP),
+        );
+        assert!(decompress(&mut headers, Vec::new())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn disabled() -> Result<()> {
+        let mut parts = request_parts()?;
+        let body = Compression::None.compress(&mut parts, Bytes::from("snot"))?;
+        assert_eq!(Bytes::from("snot"), body);
+        assert!(parts.headers.get(header::ACCEPT_ENCODING).is_none());
+        // not encoded
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            b"badger".to_vec(),
+            decompress(&mut headers, b"badger".to_vec())?
+        );
+        Ok(())
+    }
+
+    fn encoded(encoding: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_ENCODING,
+            header::HeaderValue::from_static(encoding),
+        );
+        headers.insert(header::CONTENT_LENGTH, header::HeaderValue::from(42));
+        headers
+    }
+
+    /// `data` encoded with the content `coding`
+    fn encode(coding: &str, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(match coding {
+            "gzip" => {
+                let mut encoder = Encoder::new(Vec::new())?;
+                encoder.write_all(data)?;
+                encoder.finish().into_result()?
+            }
+            "deflate" => {
+                let mut encoder = zlib::Encoder::new(Vec::new())?;
+                encoder.write_all(data)?;
+                encoder.finish().into_result()?
+            }
+            "br" => {
+                let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+                encoder.write_all(data)?;
+                encoder.into_inner()
+            }
+            "zstd" => zstd::stream::encode_all(data, 3)?,
+            other => return Err(format!("unknown coding {other}").into()),
+        })
+    }
+
+    #[test]
+    fn content_encodings() -> Result<()> {
+        let data = b"snot badger".repeat(10);
+        for coding in ["gzip", "deflate", "br", "zstd"] {
+            let mut headers = encoded(coding);
+            assert_eq!(
+                data,
+                decompress(&mut headers, encode(coding, &data)?)?,
+                "{coding}"
+            );
+            assert!(headers.get(header::CONTENT_ENCODING).is_none());
+            assert!(headers.get(header::CONTENT_LENGTH).is_none());
+        }
+        // codings are case insensitive
+        let mut headers = encoded("ZSTD");
+        assert_eq!(data, decompress(&mut headers, encode("zstd", &data)?)?);
+
+        let mut headers = encoded("identity");
+        assert_eq!(data, decompress(&mut headers, data.clone())?);
+        Ok(())
+    }
+
+    #[test]
+    fn stacked_encodings() -> Result<()> {
+        let body = encode("br", &encode("gzip", b"snot badger")?)?;
+        // gzip was applied first
+        let mut headers = encoded("gzip, identity, br");
+        assert_eq!(b"snot badger".to_vec(), decompress(&mut headers, body)?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_encodings() -> Result<()> {
+        // unknown encodings are not passed on as garbage
+        let mut headers = encoded("compress");
+        assert!(decompress(&mut headers, b"snot".to_vec()).is_err());
+        assert!(headers.get(header::CONTENT_ENCODING).is_some());
+        // truncated bodies
+        let data = b"snot badger".repeat(10);
+        for coding in ["gzip", "deflate", "br", "zstd"] {
+            let mut body = encode(coding, &data)?;
+            body.truncate(body.len() / 2);
+            let mut headers = encoded(coding);
+            assert!(decompress(&mut headers, body).is_err(), "{coding}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,487 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cookie jar of the `http_client`, shared by the clients of all profiles
+//!
+//! Cookies from the `Set-Cookie` headers of responses are stored following RFC 6265: without a `Domain`
+//! they are only sent back to the host that set them, with one also to its subdomains, as long as the host
+//! is within that domain. They are sent to urls within their `Path`, which defaults to the directory of
+//! the url that set them. `Secure` cookies are only accepted from and sent to `https` urls. Cookies expire
+//! after their `Max-Age` or at their `Expires` date, cookies with neither last as long as the connector.
+//! A `Domain` that is a public suffix, like `co.uk` or `github.io`, or a single label is ignored and the
+//! cookie is only sent back to the host that set it, so hosts can't share cookies with unrelated sites.
+//!
+//! At most `MAX_COOKIES` are kept, the oldest ones are evicted first.
+
+use crate::connectors::prelude::*;
+use chrono::{DateTime, Duration, Utc};
+use hyper::{
+    header::{self, HeaderValue},
+    Body, Request, Response, Uri,
+};
+use std::{net::IpAddr, sync::Mutex};
+
+/// Maximum number of cookies kept in the jar
+const MAX_COOKIES: usize = 3000;
+/// `Max-Age` is capped to 400 days, as recommended by RFC 6265bis
+const MAX_AGE_SECS: i64 = 400 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cookie {
+    name: String,
+    value: String,
+    /// lowercase, without a leading dot
+    domain: String,
+    /// only sent to `domain` itself, not to its subdomains
+    host_only: bool,
+    path: String,
+    secure: bool,
+    expires: Option<DateTime<Utc>>,
+    /// order of creation, replaced cookies keep theirs
+    created: u64,
+}
+
+impl Cookie {
+    /// Parse a `Set-Cookie` header received from `uri`
+    ///
+    /// Returns `None` for malformed cookies and cookies `uri` isn't allowed to set.
+    fn parse(set_cookie: &str, uri: &Uri, now: DateTime<Utc>) -> Option<Self> {
+        let host = host_of(uri)?;
+        let mut attributes = set_cookie.split(';');
+        let (name, value) = attributes.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let mut domain = None;
+        let mut path = None;
+        let mut secure = false;
+        let mut max_age = None;
+        let mut expires = None;
+        for attribute in attributes {
+            let (key, value) = attribute
+                .split_once('=')
+                .map_or((attribute, ""), |(key, value)| (key, value.trim()));
+            match key.trim().to_ascii_lowercase().as_str() {
+                "domain" => {
+                    let value = value.trim_start_matches('.').to_ascii_lowercase();
+                    domain = (!value.is_empty()).then_some(value);
+                }
+                "path" => path = value.starts_with('/').then(|| value.to_string()),
+                "secure" => secure = true,
+                "max-age" => {
+                    max_age = value
+                        .parse::<i64>()
+                        .ok()
+                        .map(|secs| now + Duration::seconds(secs.clamp(0, MAX_AGE_SECS)));
+                }
+                "expires" => {
+                    expires = DateTime::parse_from_rfc2822(value)
+                        .ok()
+                        .map(|expires| expires.with_timezone(&Utc));
+                }
+                _ => (),
+            }
+        }
+        if secure && uri.scheme_str() != Some("https") {
+            return None;
+        }
+        let domain = domain.filter(|domain| !is_public_suffix(domain));
+        let host_only = domain.is_none();
+        let domain = match domain {
+            Some(domain) if domain_matches(&host, &domain) => domain,
+            Some(_) => return None,
+            None => host,
+        };
+        Some(Self {
+            name: name.to_string(),
+            value: value.trim().to_string(),
+            domain,
+            host_only,
+            path: path.unwrap_or_else(|| default_path(uri)),
+            secure,
+            expires: max_age.or(expires),
+            created: 0,
+        })
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.map_or(false, |expires| expires <= now)
+    }
+
+    /// Whether the cookie is sent with requests to `uri`
+    fn matches(&self, uri: &Uri, now: DateTime<Utc>) -> bool {
+        let host = if let Some(host) = host_of(uri) {
+            host
+        } else {
+            return false;
+        };
+        let domain_match = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(&host, &self.domain)
+        };
+        domain_match
+            && path_matches(uri.path(), &self.path)
+            && (!self.secure || uri.scheme_str() == Some("https"))
+            && !self.is_expired(now)
+    }
+
+    /// Whether this replaces `other`, according to RFC 6265 section 5.3
+    fn replaces(&self, other: &Self) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+}
+
+fn host_of(uri: &Uri) -> Option<String> {
+    uri.host()
+        .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+        .map(str::to_ascii_lowercase)
+}
+
+/// Whether `domain` is a single label or on the public suffix list, so that it is shared by unrelated sites
+fn is_public_suffix(domain: &str) -> bool {
+    !domain.contains('.') || psl::suffix_str(domain) == Some(domain)
+}
+
+/// RFC 6265 section 5.1.3, IP addresses only match themselves
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<IpAddr>().is_err())
+}
+
+/// RFC 6265 section 5.1.4, the directory of the path of `uri`
+fn default_path(uri: &Uri) -> String {
+    match uri.path().rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(end) => uri.path()[..end].to_string(),
+    }
+}
+
+/// RFC 6265 section 5.1.4
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+#[derive(Debug, Default)]
+struct Cookies {
+    cookies: Vec<Cookie>,
+    created: u64,
+}
+
+impl Cookies {
+    fn store(&mut self, mut cookie: Cookie, now: DateTime<Utc>) {
+        let replaced = self
+            .cookies
+            .iter()
+            .position(|stored| cookie.replaces(stored));
+        if let Some(replaced) = replaced {
+            cookie.created = self.cookies.swap_remove(replaced).created;
+        } else {
+            self.created += 1;
+            cookie.created = self.created;
+        }
+        // an expired cookie only removes the one it replaces
+        if cookie.is_expired(now) {
+            return;
+        }
+        self.cookies.push(cookie);
+        if self.cookies.len() > MAX_COOKIES {
+            self.cookies.retain(|cookie| !cookie.is_expired(now));
+        }
+        while self.cookies.len() > MAX_COOKIES {
+            let oldest = self
+                .cookies
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, cookie)| cookie.created)
+                .map(|(i, _)| i);
+            if let Some(oldest) = oldest {
+                self.cookies.swap_remove(oldest);
+            }
+        }
+    }
+}
+
+/// Cookies received with responses, sent with subsequent requests to matching urls
+#[derive(Debug, Default)]
+pub(crate) struct CookieJar {
+    cookies: Mutex<Cookies>,
+}
+
+impl CookieJar {
+    fn cookies(&self) -> Result<std::sync::MutexGuard<Cookies>> {
+        self.cookies
+            .lock()
+            .map_err(|_| Error::from("Poisoned cookie jar"))
+    }
+
+    /// Add the cookies matching the url of `request` to its `Cookie` header
+    ///
+    /// Cookies with longer paths come first, cookies with the same path in the order they were created in.
+    /// Cookies already in the header are kept in front.
+    pub(super) fn attach(&self, request: &mut Request<Body>, now: DateTime<Utc>) -> Result<()> {
+        let mut matching: Vec<_> = self
+            .cookies()?
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.matches(request.uri(), now))
+            .cloned()
+            .collect();
+        if matching.is_empty() {
+            return Ok(());
+        }
+        matching.sort_by(|a, b| {
+            b.path
+                .len()
+                .cmp(&a.path.len())
+                .then(a.created.cmp(&b.created))
+        });
+        let mut header = request
+            .headers()
+            .get(header::COOKIE)
+            .and_then(|cookie| cookie.to_str().ok())
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        for cookie in matching {
+            if !header.is_empty() {
+                header.push_str("; ");
+            }
+            header.push_str(&cookie.name);
+            header.push('=');
+            header.push_str(&cookie.value);
+        }
+        request
+            .headers_mut()
+            .insert(header::COOKIE, HeaderValue::from_str(&header)?);
+        Ok(())
+    }
+
+    /// Store the cookies set by `response` to a request to `uri`
+    pub(super) fn store(
+        &self,
+        uri: &Uri,
+        response: &Response<Body>,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let set_cookies = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|set_cookie| set_cookie.to_str().ok())
+            .filter_map(|set_cookie| Cookie::parse(set_cookie, uri, now));
+        let mut cookies = self.cookies()?;
+        for cookie in set_cookies {
+            cookies.store(cookie, now);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(uri: &str) -> Result<Uri> {
+        Ok(uri.parse()?)
+    }
+
+    fn cookie_header(jar: &CookieJar, url: &str, now: DateTime<Utc>) -> Result<Option<String>> {
+        let mut request = Request::get(url).body(Body::empty())?;
+        jar.attach(&mut request, now)?;
+        Ok(request
+            .headers()
+            .get(header::COOKIE)
+            .and_then(|cookie| cookie.to_str().ok())
+            .map(ToString::to_string))
+    }
+
+    fn set_cookies(set_cookies: &[&str]) -> Result<Response<Body>> {
+        let mut response = Response::builder();
+        for set_cookie in set_cookies {
+            response = response.header(header::SET_COOKIE, *set_cookie);
+        }
+        Ok(response.body(Body::empty())?)
+    }
+
+    #[test]
+    fn parse() -> Result<()> {
+        let now = Utc::now();
+        let from = uri("http://www.example.com/snot/badger")?;
+        let cookie = Cookie::parse("session=snot; HttpOnly", &from, now).ok_or("no cookie")?;
+        assert_eq!("session", cookie.name);
+        assert_eq!("snot", cookie.value);
+        assert_eq!("www.example.com", cookie.domain);
+        assert!(cookie.host_only);
+        assert_eq!("/snot", cookie.path);
+        assert_eq!(None, cookie.expires);
+
+        let cookie = Cookie::parse(
+            "session=snot; Domain=.Example.com; Path=/; Max-Age=60; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+            &from,
+            now,
+        )
+        .ok_or("no cookie")?;
+        assert_eq!("example.com", cookie.domain);
+        assert!(!cookie.host_only);
+        assert_eq!("/", cookie.path);
+        // `Max-Age` takes precedence
+        assert_eq!(Some(now + Duration::seconds(60)), cookie.expires);
+
+        let cookie = Cookie::parse("a=b; Expires=Wed, 21 Oct 2015 07:28:00 GMT", &from, now)
+            .ok_or("no cookie")?;
+        assert!(cookie.is_expired(now));
+
+        // malformed or not allowed for the host
+        assert_eq!(None, Cookie::parse("snot", &from, now));
+        assert_eq!(None, Cookie::parse("=badger", &from, now));
+        assert_eq!(None, Cookie::parse("a=b; Domain=other.com", &from, now));
+        assert_eq!(
+            None,
+            Cookie::parse("a=b; Domain=ww.example.com", &from, now)
+        );
+        assert_eq!(None, Cookie::parse("a=b; Secure", &from, now));
+        let ip = uri("http://127.0.0.1/")?;
+        assert_eq!(None, Cookie::parse("a=b; Domain=0.0.1", &ip, now));
+        Ok(())
+    }
+
+    #[test]
+    fn public_suffixes() -> Result<()> {
+        let jar = CookieJar::default();
+        let now = Utc::now();
+        jar.store(
+            &uri("http://www.example.co.uk/")?,
+            &set_cookies(&[
+                "super=snot; Domain=co.uk",
+                "shared=badger; Domain=example.co.uk",
+            ])?,
+            now,
+        )?;
+        // the public suffix `Domain` is ignored, the cookie is host-only
+        assert_eq!(
+            Some("super=snot; shared=badger".to_string()),
+            cookie_header(&jar, "http://www.example.co.uk/", now)?
+        );
+        assert_eq!(
+            Some("shared=badger".to_string()),
+            cookie_header(&jar, "http://api.example.co.uk/", now)?
+        );
+        assert_eq!(None, cookie_header(&jar, "http://other.co.uk/", now)?);
+
+        let from = uri("http://snot.github.io/")?;
+        let cookie = Cookie::parse("a=b; Domain=github.io", &from, now).ok_or("no cookie")?;
+        assert!(cookie.host_only);
+        assert_eq!("snot.github.io", cookie.domain);
+        let from = uri("http://snot.localhost/")?;
+        let cookie = Cookie::parse("a=b; Domain=localhost", &from, now).ok_or("no cookie")?;
+        assert!(cookie.host_only);
+        assert_eq!("snot.localhost", cookie.domain);
+        Ok(())
+    }
+
+    #[test]
+    fn paths() {
+        assert!(path_matches("/", "/"));
+        assert!(path_matches("/snot", "/"));
+        assert!(path_matches("/snot", "/snot"));
+        assert!(path_matches("/snot/badger", "/snot"));
+        assert!(path_matches("/snot/badger", "/snot/"));
+        assert!(!path_matches("/snotbadger", "/snot"));
+        assert!(!path_matches("/", "/snot"));
+    }
+
+    #[test]
+    fn attach() -> Result<()> {
+        let jar = CookieJar::default();
+        let now = Utc::now();
+        jar.store(
+            &uri("https://api.example.com/login")?,
+            &set_cookies(&[
+                "session=snot; Path=/",
+                "token=badger; Path=/v1; Secure",
+                "shared=flork; Domain=example.com; Path=/",
+                "short=lived; Path=/; Max-Age=10",
+            ])?,
+            now,
+        )?;
+        assert_eq!(
+            Some("token=badger; session=snot; shared=flork; short=lived".to_string()),
+            cookie_header(&jar, "https://api.example.com/v1/users", now)?
+        );
+        // `Secure` cookies are only sent over https
+        assert_eq!(
+            Some("session=snot; shared=flork; short=lived".to_string()),
+            cookie_header(&jar, "http://api.example.com/v1/users", now)?
+        );
+        // host-only cookies aren't sent to other hosts of the domain
+        assert_eq!(
+            Some("shared=flork".to_string()),
+            cookie_header(&jar, "https://www.example.com/", now)?
+        );
+        assert_eq!(None, cookie_header(&jar, "https://example.org/", now)?);
+        // expired cookies aren't sent
+        let later = now + Duration::seconds(10);
+        assert_eq!(
+            Some("session=snot; shared=flork".to_string()),
+            cookie_header(&jar, "https://api.example.com/", later)?
+        );
+
+        // cookies are replaced in place and removed by expired ones
+        jar.store(
+            &uri("https://api.example.com/")?,
+            &set_cookies(&[
+                "session=badger; Path=/",
+                "shared=gone; Domain=example.com; Path=/; Max-Age=0",
+            ])?,
+            now,
+        )?;
+        assert_eq!(
+            Some("session=badger; short=lived".to_string()),
+            cookie_header(&jar, "https://api.example.com/", now)?
+        );
+
+        // cookies set by the event are kept
+        let mut request = Request::get("https://api.example.com/")
+            .header(header::COOKIE, "mine=1")
+            .body(Body::empty())?;
+        jar.attach(&mut request, now)?;
+        assert_eq!(
+            Some("mine=1; session=badger; short=lived"),
+            request
+                .headers()
+                .get(header::COOKIE)
+                .and_then(|cookie| cookie.to_str().ok())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn bounded() -> Result<()> {
+        let mut cookies = Cookies::default();
+        let now = Utc::now();
+        let from = uri("http://example.com/")?;
+        for i in 0..=MAX_COOKIES {
+            let cookie = Cookie::parse(&format!("c{i}=v"), &from, now).ok_or("no cookie")?;
+            cookies.store(cookie, now);
+        }
+        assert_eq!(MAX_COOKIES, cookies.cookies.len());
+        assert!(!cookies.cookies.iter().any(|cookie| cookie.name == "c0"));
+        Ok(())
+    }
+}
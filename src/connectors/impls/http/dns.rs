@@ -0,0 +1,216 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! DNS resolution of the `http_client`
+//!
+//! Hostnames in `hosts` always resolve to the given IP address. All other hostnames are resolved via the
+//! system resolver, and if `cache_ttl_ms` is set successful lookups are cached for that long.
+//! IP addresses in urls are connected to directly and never resolved.
+
+use futures::future::BoxFuture;
+use halfbrown::HashMap;
+use hyper::{
+    client::connect::dns::{GaiResolver, Name},
+    service::Service,
+};
+use std::{
+    collections::HashMap as StdHashMap,
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// DNS configuration of the `http_client`
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Dns {
+    /// time in milliseconds a resolved hostname is cached for, if not set every connection resolves it anew
+    #[serde(default = "Default::default")]
+    cache_ttl_ms: Option<u64>,
+    /// static mapping from hostname to IP address, taking precedence over the system resolver
+    #[serde(default = "Default::default")]
+    hosts: HashMap<String, IpAddr>,
+}
+
+impl Dns {
+    /// Validate the dns config
+    pub(super) fn validate(&self) -> std::result::Result<(), &'static str> {
+        if self.cache_ttl_ms == Some(0) {
+            return Err("`dns.cache_ttl_ms` must be > 0");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Cached {
+    addrs: Vec<SocketAddr>,
+    expires: Instant,
+}
+
+#[derive(Debug)]
+struct Inner {
+    ttl: Option<Duration>,
+    hosts: StdHashMap<String, IpAddr>,
+    cache: Mutex<StdHashMap<String, Cached>>,
+}
+
+impl Inner {
+    /// The addresses for `host` from the static hosts or the cache, if they are still valid at `now`
+    fn lookup(&self, host: &str, now: Instant) -> Option<Vec<SocketAddr>> {
+        if let Some(ip) = self.hosts.get(host) {
+            // the port is set by the connector
+            return Some(vec![SocketAddr::new(*ip, 0)]);
+        }
+        let cache = self.cache.lock().ok()?;
+        cache
+            .get(host)
+            .filter(|cached| cached.expires > now)
+            .map(|cached| cached.addrs.clone())
+    }
+
+    fn store(&self, host: &str, addrs: &[SocketAddr], now: Instant) {
+        if let (Some(ttl), Ok(mut cache)) = (self.ttl, self.cache.lock()) {
+            // drop expired entries so hosts that are no longer requested don't pile up
+            cache.retain(|_, cached| cached.expires > now);
+            cache.insert(
+                host.to_string(),
+                Cached {
+                    addrs: addrs.to_vec(),
+                    expires: now + ttl,
+                },
+            );
+        }
+    }
+}
+
+/// Resolver applying the static hosts and caching lookups of the system resolver
+#[derive(Debug, Clone)]
+pub(crate) struct Resolver {
+    gai: GaiResolver,
+    inner: Arc<Inner>,
+}
+
+impl Resolver {
+    pub(super) fn new(config: Dns) -> Self {
+        Self {
+            gai: GaiResolver::new(),
+            inner: Arc::new(Inner {
+                ttl: config.cache_ttl_ms.map(Duration::from_millis),
+                hosts: config
+                    .hosts
+                    .into_iter()
+                    .map(|(host, ip)| (host.to_ascii_lowercase(), ip))
+                    .collect(),
+                cache: Mutex::default(),
+            }),
+        }
+    }
+}
+
+impl Service<Name> for Resolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future = BoxFuture<'static, io::Result<Self::Response>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let mut gai = self.gai.clone();
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_ascii_lowercase();
+            if let Some(addrs) = inner.lookup(&host, Instant::now()) {
+                return Ok(addrs.into_iter());
+            }
+            let addrs: Vec<SocketAddr> = gai.call(name).await?.collect();
+            inner.store(&host, &addrs, Instant::now());
+            Ok(addrs.into_iter())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Result;
+    use std::str::FromStr;
+    use tremor_value::literal;
+
+    fn resolver(config: &tremor_value::Value) -> Result<Resolver> {
+        Ok(Resolver::new(tremor_value::structurize(config.clone())?))
+    }
+
+    fn name(host: &str) -> Result<Name> {
+        Name::from_str(host).map_err(|e| e.to_string().into())
+    }
+
+    #[test]
+    fn validate() -> Result<()> {
+        let dns: Dns = tremor_value::structurize(literal!({"cache_ttl_ms": 0}))?;
+        assert!(dns.validate().is_err());
+        let dns: Dns = tremor_value::structurize(literal!({"hosts": {"snot": "127.0.0.1"}}))?;
+        assert!(dns.validate().is_ok());
+        assert!(tremor_value::structurize::<Dns>(literal!({"hosts": {"snot": "badger"}})).is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn static_hosts() -> Result<()> {
+        let mut resolver = resolver(&literal!({
+            "hosts": {"Snot.Badger": "10.0.0.1", "ipv6.snot": "::1"}
+        }))?;
+        let addrs: Vec<_> = resolver.call(name("snot.badger")?).await?.collect();
+        assert_eq!(vec![SocketAddr::from(([10, 0, 0, 1], 0))], addrs);
+        let addrs: Vec<_> = resolver.call(name("IPV6.snot")?).await?.collect();
+        assert_eq!(vec![SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 1], 0))], addrs);
+        Ok(())
+    }
+
+    #[test]
+    fn cache_expiry() -> Result<()> {
+        let resolver = resolver(&literal!({"cache_ttl_ms": 100}))?;
+        let addrs = vec![SocketAddr::from(([10, 0, 0, 2], 0))];
+        let start = Instant::now();
+        assert_eq!(None, resolver.inner.lookup("snot", start));
+        resolver.inner.store("snot", &addrs, start);
+        assert_eq!(
+            Some(addrs.clone()),
+            resolver
+                .inner
+                .lookup("snot", start + Duration::from_millis(99))
+        );
+        // expired entries are resolved again
+        let expired = start + Duration::from_millis(100);
+        assert_eq!(None, resolver.inner.lookup("snot", expired));
+        let renewed = vec![SocketAddr::from(([10, 0, 0, 3], 0))];
+        resolver.inner.store("snot", &renewed, expired);
+        assert_eq!(
+            Some(renewed),
+            resolver
+                .inner
+                .lookup("snot", expired + Duration::from_millis(1))
+        );
+
+        // without a ttl nothing is cached
+        let uncached = self::resolver(&literal!({}))?;
+        uncached.inner.store("snot", &addrs, start);
+        assert_eq!(None, uncached.inner.lookup("snot", start));
+        Ok(())
+    }
+}
@@ -12,21 +12,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::convert::Infallible;
+use std::{
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use super::{client, utils::RequestId};
+use super::{client, multipart, utils::RequestId};
 use crate::channel::{bounded, Sender};
 use crate::{
     config::NameWithConfig,
     connectors::{prelude::*, utils::mime::MimeCodecMap},
 };
+use base64::Engine;
 use either::Either;
 use http::{
     header::{self, HeaderName},
     HeaderMap, Uri,
 };
-use hyper::{header::HeaderValue, Body, Method, Request, Response};
+use hyper::{header::HeaderValue, Body, Method, Request, Response, StatusCode, Version};
 use mime::Mime;
+use tremor_common::base64::BASE64;
 use tremor_value::Value;
 use value_trait::{Builder, ValueAccess};
 
@@ -37,6 +43,12 @@ pub(crate) struct HttpRequestBuilder {
     request: Option<hyper::Request<Body>>,
     chunk_tx: Sender<Vec<u8>>,
     codec_overwrite: Option<NameWithConfig>,
+    /// pre-serialized body from the metadata, sent instead of the event payload
+    raw_body: Option<Vec<u8>>,
+    /// boundary of the `multipart/form-data` body the event payloads are encoded into
+    multipart_boundary: Option<String>,
+    /// copy of the body sent so far, for echoing it into the response metadata
+    echoed_body: Option<Arc<Mutex<Vec<u8>>>>,
 }
 
 #[derive(Clone)]
@@ -89,29 +101,114 @@ impl<'v> Iterator for HeaderValueValue<'v> {
     }
 }
 
+/// The HTTP method named `method`, case insensitively, rejecting methods `http_types::Method` doesn't know
+pub(super) fn parse_method(method: &str) -> Result<Method> {
+    let known: http_types::Method = method
+        .to_ascii_uppercase()
+        .parse()
+        .map_err(|_| format!("Unknown HTTP Method: {method}"))?;
+    Method::from_bytes(known.to_string().as_bytes())
+        .map_err(|e| format!("Invalid HTTP Method {method}: {e}").into())
+}
+
+/// The method override from the request metadata, rejecting unknown methods
+fn method_override(method: &Value) -> Result<Method> {
+    parse_method(method.as_str().ok_or("Invalid HTTP Method")?)
+}
+
+/// A pre-serialized request body, given as bytes or as a base64 encoded string
+fn raw_body(raw_body: &Value) -> Result<Vec<u8>> {
+    match raw_body {
+        Value::Bytes(bytes) => Ok(bytes.to_vec()),
+        Value::String(encoded) => Ok(BASE64.decode(encoded.as_bytes())?),
+        other => Err(format!(
+            "Invalid raw_body, expected bytes or a base64 encoded string: {other}"
+        )
+        .into()),
+    }
+}
+
+/// The request url from the configured `url` and the `url` and `query` overrides from the request metadata
+///
+/// A relative `url` override is resolved against the configured `url`.
+/// Parameters in the `query` override replace parameters of the same name in the url.
+fn request_uri(config_url: &url::Url, request_meta: Option<&Value>) -> Result<Uri> {
+    let mut url = if let Some(url_v) = request_meta.get("url") {
+        config_url.join(url_v.as_str().ok_or("Invalid HTTP URL")?)?
+    } else {
+        config_url.clone()
+    };
+    if let Some(query) = request_meta.get("query") {
+        let query = query
+            .as_object()
+            .ok_or("Invalid HTTP query, expected a record")?;
+        let params: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| !query.contains_key(name.as_ref()))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        if !(params.is_empty() && query.is_empty()) {
+            let mut serializer = url.query_pairs_mut();
+            serializer.clear().extend_pairs(params);
+            for (name, values) in query.iter() {
+                let values = values
+                    .as_array()
+                    .map_or_else(|| vec![values], |values| values.iter().collect::<Vec<_>>());
+                for value in values {
+                    let value = value
+                        .as_str()
+                        .map_or_else(|| value.encode(), ToString::to_string);
+                    serializer.append_pair(name, &value);
+                }
+            }
+        }
+    }
+    Ok(url.as_str().parse()?)
+}
+
 // TODO: do some deduplication with SinkResponse
 impl HttpRequestBuilder {
-    pub(super) fn new(
+    /// Build a request from the event metadata and connector config
+    ///
+    /// The `method`, `url`, `query` and `headers` from `$http_client.request` take precedence
+    /// over the connector config, which is used for everything not set in the metadata.
+    /// Headers set in the metadata replace configured headers of the same name, a `host` header
+    /// replaces the configured `host_header` as well.
+    ///
+    /// If `raw_body` is set, as bytes or as a base64 encoded string, it is sent verbatim as the request
+    /// body and the event payload is ignored, it doesn't pass through the codec and postprocessors.
+    ///
+    /// With `multipart` configured the event payloads are encoded as `multipart/form-data` instead of
+    /// using the codec and postprocessors, replacing any configured `Content-Type`.
+    ///
+    /// The request is sent to the `url` of the selected `profile`, with its `auth`.
+    pub(super) async fn new(
         request_id: RequestId,
         meta: Option<&Value>,
         codec_map: &MimeCodecMap,
         config: &client::Config,
+        profile: &client::Profile,
     ) -> Result<Self> {
         let request_meta = meta.get("request");
         let method = if let Some(method_v) = request_meta.get("method") {
-            Method::from_bytes(method_v.as_bytes().ok_or("Invalid HTTP Method")?)?
+            method_override(method_v)?
         } else {
             config.method.0.clone()
         };
-        let uri: Uri = if let Some(url_v) = request_meta.get("url") {
-            url_v.as_str().ok_or("Invalid HTTP URL")?.parse()?
-        } else {
-            config.url.to_string().parse()?
-        };
+        let uri = request_uri(&profile.url, request_meta)?;
+        let raw_body = request_meta.get("raw_body").map(raw_body).transpose()?;
         let mut request = Request::builder().method(method).uri(uri);
+        let headers = request_meta.get("headers").as_object();
 
-        // first insert config headers
+        // first insert config headers, unless they are overridden by the event
         for (config_header_name, config_header_values) in &config.headers {
+            if headers.map_or(false, |headers| {
+                headers
+                    .keys()
+                    .any(|name| name.eq_ignore_ascii_case(config_header_name))
+            }) {
+                continue;
+            }
             match &config_header_values.0 {
                 Either::Left(config_header_values) => {
                     for header_value in config_header_values {
@@ -124,10 +221,9 @@ impl HttpRequestBuilder {
                 }
             }
         }
-        let headers = request_meta.get("headers");
 
         // build headers
-        if let Some(headers) = headers.as_object() {
+        if let Some(headers) = headers {
             for (name, values) in headers {
                 let name = HeaderName::from_bytes(name.as_bytes())?;
                 for value in HeaderValueValue::new(values) {
@@ -136,6 +232,24 @@ impl HttpRequestBuilder {
             }
         }
 
+        if let (Some(host_header), Some(headers)) =
+            (config.host_header.as_deref(), request.headers_mut())
+        {
+            if !headers.contains_key(header::HOST) {
+                headers.insert(header::HOST, HeaderValue::from_str(host_header)?);
+            }
+        }
+
+        let multipart_boundary = (config.multipart && raw_body.is_none()).then(multipart::boundary);
+        if let (Some(boundary), Some(headers)) =
+            (multipart_boundary.as_ref(), request.headers_mut())
+        {
+            headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_str(&multipart::content_type(boundary))?,
+            );
+        }
+
         let header_content_type = content_type(request.headers_ref())?;
 
         let (codec_overwrite, content_type) =
@@ -148,7 +262,7 @@ impl HttpRequestBuilder {
             }
         }
         // handle AUTH
-        if let Some(auth_header) = config.auth.as_header_value()? {
+        if let Some(auth_header) = profile.auth.header_value().await? {
             request = request.header(hyper::header::AUTHORIZATION, auth_header);
         }
 
@@ -167,6 +281,9 @@ impl HttpRequestBuilder {
             request: Some(request),
             chunk_tx,
             codec_overwrite,
+            raw_body,
+            multipart_boundary,
+            echoed_body: None,
         })
     }
 
@@ -176,6 +293,13 @@ impl HttpRequestBuilder {
         ingest_ns: u64,
         serializer: &mut EventSerializer,
     ) -> Result<()> {
+        if self.raw_body.is_some() {
+            return Ok(());
+        }
+        if let Some(boundary) = self.multipart_boundary.as_ref() {
+            let parts = multipart::encode_parts(value, boundary)?;
+            return self.append_data(vec![parts]).await;
+        }
         let chunks = serializer.serialize_for_stream_with_codec(
             value,
             ingest_ns,
@@ -187,11 +311,19 @@ impl HttpRequestBuilder {
 
     async fn append_data(&mut self, chunks: Vec<Vec<u8>>) -> Result<()> {
         for chunk in chunks {
+            if let Some(echoed_body) = self.echoed_body.as_ref() {
+                echoed_body.lock()?.extend_from_slice(&chunk);
+            }
             self.chunk_tx.send(chunk).await?;
         }
         Ok(())
     }
 
+    /// Keep a copy of the body from now on, it is complete once the request is finalized
+    pub(super) fn echo_body(&mut self) -> Arc<Mutex<Vec<u8>>> {
+        self.echoed_body.get_or_insert_with(Arc::default).clone()
+    }
+
     pub(super) fn take_request(&mut self) -> Result<Request<Body>> {
         Ok(self.request.take().ok_or("Request already consumed")?)
     }
@@ -200,6 +332,12 @@ impl HttpRequestBuilder {
     ///
     /// After calling this function this instance shouldn't be used anymore
     pub(super) async fn finalize(&mut self, serializer: &mut EventSerializer) -> Result<()> {
+        if let Some(raw_body) = self.raw_body.take() {
+            return self.append_data(vec![raw_body]).await;
+        }
+        if let Some(boundary) = self.multipart_boundary.take() {
+            return self.append_data(vec![multipart::closing(&boundary)]).await;
+        }
         // finalize the stream
         let rest = serializer.finish_stream(self.request_id.get())?;
         if !rest.is_empty() {
@@ -252,7 +390,8 @@ pub(crate) fn consolidate_mime(
     (codec_overwrite, content_type)
 }
 
-fn extract_headers(headers: &HeaderMap) -> Result<Value<'static>> {
+/// Collect the values of each header, or trailer, into an array per name
+pub(super) fn extract_headers(headers: &HeaderMap) -> Result<Value<'static>> {
     headers
         .keys()
         .map(|name| {
@@ -286,16 +425,81 @@ pub(super) fn extract_request_meta(
     }))
 }
 
-/// extract response metadata
-pub(super) fn extract_response_meta<B>(response: &Response<B>) -> Result<Value<'static>> {
-    // collect header values into an array for each header
-    let headers = extract_headers(response.headers())?;
+/// Metadata of a response to an `http_client` request, emitted as `$http_client.response`
+///
+/// Pipelines can rely on `status` and `headers` always being present, header values are
+/// always collected into an array per header name. Trailers, sent after the body e.g. by gRPC,
+/// are kept apart from the headers in `trailers`, which is only present if the response had any.
+pub(super) struct HttpResponseMeta {
+    status: u16,
+    headers: Value<'static>,
+    trailers: Option<Value<'static>>,
+    version: Option<Version>,
+    url: Uri,
+    duration_ns: Option<u64>,
+}
 
-    let mut meta = Value::object_with_capacity(3);
-    meta.try_insert("status", response.status().as_u16());
-    meta.try_insert("headers", headers);
-    meta.try_insert("version", format!("{:?}", response.version()));
-    Ok(meta)
+impl HttpResponseMeta {
+    /// metadata of the `response` received from `url`
+    ///
+    /// The url might differ from the request url when following redirects.
+    pub(super) fn from_response<B>(response: &Response<B>, url: Uri) -> Result<Self> {
+        Ok(Self {
+            status: response.status().as_u16(),
+            headers: extract_headers(response.headers())?,
+            trailers: None,
+            version: Some(response.version()),
+            url,
+            duration_ns: None,
+        })
+    }
+
+    /// metadata for a response that was not received from `url`, but synthesized by the connector
+    pub(super) fn synthetic(status: StatusCode, url: Uri) -> Self {
+        Self {
+            status: status.as_u16(),
+            headers: Value::object(),
+            trailers: None,
+            version: None,
+            url,
+            duration_ns: None,
+        }
+    }
+
+    /// record the time it took to receive the response,
+    /// from sending the request, including all retries and redirects, until the whole body was received,
+    /// or until the headers were received for streamed responses
+    #[must_use]
+    pub(super) fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration_ns = Some(u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX));
+        self
+    }
+
+    /// record the trailers received after the body of the response
+    pub(super) fn with_trailers(mut self, trailers: Option<&HeaderMap>) -> Result<Self> {
+        self.trailers = trailers.map(extract_headers).transpose()?;
+        Ok(self)
+    }
+}
+
+impl From<HttpResponseMeta> for Value<'static> {
+    fn from(meta: HttpResponseMeta) -> Self {
+        let mut value = literal!({
+            "status": meta.status,
+            "headers": meta.headers,
+            "url": meta.url.to_string(),
+        });
+        if let Some(trailers) = meta.trailers {
+            value.try_insert("trailers", trailers);
+        }
+        if let Some(version) = meta.version {
+            value.try_insert("version", format!("{version:?}"));
+        }
+        if let Some(duration_ns) = meta.duration_ns {
+            value.try_insert("duration_ns", duration_ns);
+        }
+        value
+    }
 }
 
 #[cfg(test)]
@@ -319,7 +523,9 @@ mod test {
         )?;
         let config = client::Config::new(&c)?;
 
-        let mut b = HttpRequestBuilder::new(request_id, meta, &codec_map, &config)?;
+        let profile = config.default_profile();
+        let mut b =
+            HttpRequestBuilder::new(request_id, meta, &codec_map, &config, &profile).await?;
 
         let r = b.take_request()?;
         b.finalize(&mut s).await?;
@@ -327,4 +533,131 @@ mod test {
         assert_eq!(r.headers().get_all("cake").iter().count(), 2);
         Ok(())
     }
+
+    #[test]
+    fn raw_bodies() -> Result<()> {
+        assert_eq!(
+            b"snot".to_vec(),
+            raw_body(&Value::Bytes(b"snot".to_vec().into()))?
+        );
+        assert_eq!(b"snot".to_vec(), raw_body(&Value::from("c25vdA=="))?);
+        assert!(raw_body(&Value::from("not base64!")).is_err());
+        assert!(raw_body(&literal!([1, 2, 3])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn method_overrides() -> Result<()> {
+        assert_eq!(Method::PATCH, method_override(&Value::from("PATCH"))?);
+        assert_eq!(Method::DELETE, method_override(&Value::from("delete"))?);
+        assert!(method_override(&Value::from("SNOT")).is_err());
+        assert!(method_override(&Value::from("")).is_err());
+        assert_eq!(
+            "PROPFIND",
+            method_override(&Value::from("PropFind"))?.as_str()
+        );
+        assert!(method_override(&Value::from(42)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn uri_overrides() -> Result<()> {
+        let config_url = url::Url::parse("http://localhost:8080/snot/?badger=1&flork=2")?;
+        assert_eq!(
+            "http://localhost:8080/snot/?badger=1&flork=2",
+            request_uri(&config_url, None)?.to_string()
+        );
+        let meta = literal!({"url": "badger"});
+        assert_eq!(
+            "http://localhost:8080/snot/badger",
+            request_uri(&config_url, Some(&meta))?.to_string()
+        );
+        let meta = literal!({"url": "https://example.com/mork?a=b"});
+        assert_eq!(
+            "https://example.com/mork?a=b",
+            request_uri(&config_url, Some(&meta))?.to_string()
+        );
+        let meta = literal!({"query": {"badger": "snot badger", "x": [1, 2]}});
+        assert_eq!(
+            "http://localhost:8080/snot/?flork=2&badger=snot+badger&x=1&x=2",
+            request_uri(&config_url, Some(&meta))?.to_string()
+        );
+        let meta = literal!({"url": "/mork", "query": {}});
+        assert_eq!(
+            "http://localhost:8080/mork",
+            request_uri(&config_url, Some(&meta))?.to_string()
+        );
+        let meta = literal!({"query": "badger=2"});
+        assert!(request_uri(&config_url, Some(&meta)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn uri_query_encoding() -> Result<()> {
+        let config_url = url::Url::parse("http://localhost:8080/snot?q=old&page=1")?;
+        let meta = literal!({
+            "query": {
+                "q": "snot & badger=1/2?ü",
+                "snot key": "+%",
+                "tags": ["a b", "c#d"],
+                "n": 42,
+                "empty": []
+            }
+        });
+        assert_eq!(
+            "http://localhost:8080/snot?page=1&q=snot+%26+badger%3D1%2F2%3F%C3%BC&snot+key=%2B%25&tags=a+b&tags=c%23d&n=42",
+            request_uri(&config_url, Some(&meta))?.to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn response_meta() -> Result<()> {
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header("x-snot", "badger")
+            .header("x-snot", "badger2")
+            .header("x-flork", "mork")
+            .body(())?;
+        let url: Uri = "http://localhost:8080/snot".parse()?;
+        let meta: Value = HttpResponseMeta::from_response(&response, url.clone())?.into();
+        assert_eq!(
+            literal!({
+                "status": 200,
+                "headers": {
+                    "x-snot": ["badger", "badger2"],
+                    "x-flork": ["mork"]
+                },
+                "url": "http://localhost:8080/snot",
+                "version": "HTTP/1.1"
+            }),
+            meta
+        );
+
+        // trailers are kept apart from the headers
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", HeaderValue::from_static("0"));
+        let meta: Value = HttpResponseMeta::from_response(&response, url.clone())?
+            .with_trailers(Some(&trailers))?
+            .into();
+        assert_eq!(
+            Some(&literal!({"grpc-status": ["0"]})),
+            meta.get("trailers")
+        );
+        assert_eq!(None, meta.get("headers").get("grpc-status"));
+
+        let meta: Value = HttpResponseMeta::synthetic(StatusCode::GATEWAY_TIMEOUT, url)
+            .with_duration(Duration::from_millis(100))
+            .into();
+        assert_eq!(
+            literal!({
+                "status": 504,
+                "headers": {},
+                "url": "http://localhost:8080/snot",
+                "duration_ns": 100_000_000
+            }),
+            meta
+        );
+        Ok(())
+    }
 }
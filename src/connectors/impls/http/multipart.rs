@@ -0,0 +1,166 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `multipart/form-data` request bodies, see <https://www.rfc-editor.org/rfc/rfc7578>
+//!
+//! Each event payload is an array of parts, records with a `name`, the `data` and an optional `filename`
+//! and `content_type`. Bytes and strings are sent as they are, all other data is sent JSON encoded.
+//! Parts with a `filename` default to a content type of `application/octet-stream`.
+
+use crate::connectors::prelude::*;
+use rand::Rng;
+
+/// A random boundary, unlikely to occur in any part
+pub(super) fn boundary() -> String {
+    format!(
+        "tremor-{}",
+        hex::encode(rand::thread_rng().gen::<[u8; 16]>())
+    )
+}
+
+/// The `Content-Type` header value for a body delimited by `boundary`
+pub(super) fn content_type(boundary: &str) -> String {
+    format!("multipart/form-data; boundary={boundary}")
+}
+
+/// Quote a `name` or `filename` parameter, percent-encoding the characters that would end it
+fn quoted(value: &str) -> String {
+    let escaped = value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+    format!("\"{escaped}\"")
+}
+
+/// Encode the `parts` of an event, each part starts with the delimiter for `boundary`
+pub(super) fn encode_parts(parts: &Value, boundary: &str) -> Result<Vec<u8>> {
+    let parts = parts
+        .as_array()
+        .ok_or("Invalid multipart body, expected an array of parts")?;
+    let mut body = Vec::new();
+    for part in parts {
+        let name = part
+            .get_str("name")
+            .ok_or("Invalid multipart part, expected a string `name`")?;
+        let filename = part.get_str("filename");
+        let content_type = part
+            .get_str("content_type")
+            .or_else(|| filename.map(|_| "application/octet-stream"));
+        body.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+        body.extend_from_slice(
+            format!("Content-Disposition: form-data; name={}", quoted(name)).as_bytes(),
+        );
+        if let Some(filename) = filename {
+            body.extend_from_slice(format!("; filename={}", quoted(filename)).as_bytes());
+        }
+        body.extend_from_slice(b"\r\n");
+        if let Some(content_type) = content_type {
+            body.extend_from_slice(format!("Content-Type: {content_type}\r\n").as_bytes());
+        }
+        body.extend_from_slice(b"\r\n");
+        match part.get("data") {
+            Some(Value::Bytes(data)) => body.extend_from_slice(data),
+            Some(Value::String(data)) => body.extend_from_slice(data.as_bytes()),
+            Some(data) => body.extend_from_slice(data.encode().as_bytes()),
+            None => return Err(format!("Invalid multipart part {name}, missing `data`").into()),
+        }
+        body.extend_from_slice(b"\r\n");
+    }
+    Ok(body)
+}
+
+/// The closing delimiter ending the body
+pub(super) fn closing(boundary: &str) -> Vec<u8> {
+    format!("--{boundary}--\r\n").into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tremor_value::literal;
+
+    #[test]
+    fn boundaries() {
+        let boundary = boundary();
+        assert!(boundary.starts_with("tremor-"));
+        assert_eq!(39, boundary.len());
+        assert_ne!(boundary, super::boundary());
+        assert_eq!(
+            format!("multipart/form-data; boundary={boundary}"),
+            content_type(&boundary)
+        );
+    }
+
+    #[test]
+    fn encode() -> Result<()> {
+        let binary: Vec<u8> = vec![0, 159, 146, 150, b'\r', b'\n', 255];
+        let parts = literal!([
+            {"name": "snot", "data": "badger"},
+            {"name": "meta\"data", "data": {"flork": [1, 2]}},
+            {
+                "name": "file",
+                "filename": "snot.bin",
+                "data": Value::Bytes(binary.clone().into())
+            },
+            {
+                "name": "doc",
+                "filename": "badger.csv",
+                "content_type": "text/csv",
+                "data": "a,b\n1,2"
+            }
+        ]);
+        let mut body = encode_parts(&parts, "BOUNDARY")?;
+        body.extend(closing("BOUNDARY"));
+
+        let mut expected = b"--BOUNDARY\r\n\
+            Content-Disposition: form-data; name=\"snot\"\r\n\
+            \r\n\
+            badger\r\n\
+            --BOUNDARY\r\n\
+            Content-Disposition: form-data; name=\"meta%22data\"\r\n\
+            \r\n\
+            {\"flork\":[1,2]}\r\n\
+            --BOUNDARY\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"snot.bin\"\r\n\
+            Content-Type: application/octet-stream\r\n\
+            \r\n"
+            .to_vec();
+        // the bytes of binary parts are preserved
+        expected.extend_from_slice(&binary);
+        expected.extend_from_slice(
+            b"\r\n\
+            --BOUNDARY\r\n\
+            Content-Disposition: form-data; name=\"doc\"; filename=\"badger.csv\"\r\n\
+            Content-Type: text/csv\r\n\
+            \r\n\
+            a,b\n1,2\r\n\
+            --BOUNDARY--\r\n",
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&expected),
+            String::from_utf8_lossy(&body)
+        );
+        assert_eq!(expected, body);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_parts() {
+        assert!(encode_parts(&literal!({"name": "snot", "data": "badger"}), "b").is_err());
+        assert!(encode_parts(&literal!([{"data": "badger"}]), "b").is_err());
+        assert!(encode_parts(&literal!([{"name": 1, "data": "badger"}]), "b").is_err());
+        assert!(encode_parts(&literal!([{"name": "snot"}]), "b").is_err());
+        assert!(encode_parts(&literal!(["snot"]), "b").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Connection pool of the `http_client`
+//!
+//! Connections are kept open for reuse once their request finished. Without any configuration
+//! the number of idle connections per host is not limited, they are closed after 90 seconds
+//! and connecting is only limited by the request `timeout`.
+
+use hyper::client::Builder;
+use std::time::Duration;
+
+/// upper bound of all timeouts, anything longer is most likely a unit mixup
+const MAX_TIMEOUT_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Connection pool configuration of the `http_client`
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Pool {
+    /// maximum number of idle connections kept open per host, `0` disables reusing connections
+    #[serde(default = "Default::default")]
+    max_idle_per_host: Option<usize>,
+    /// time in milliseconds idle connections are kept open for
+    #[serde(default = "Default::default")]
+    idle_timeout_ms: Option<u64>,
+    /// time in milliseconds to wait for a connection to be established
+    #[serde(default = "Default::default")]
+    connect_timeout_ms: Option<u64>,
+}
+
+impl Pool {
+    /// Validate the pool config
+    pub(super) fn validate(&self) -> std::result::Result<(), &'static str> {
+        if let Some(idle_timeout_ms) = self.idle_timeout_ms {
+            if idle_timeout_ms == 0 || idle_timeout_ms > MAX_TIMEOUT_MS {
+                return Err("`pool.idle_timeout_ms` must be > 0 and at most a day");
+            }
+        }
+        if let Some(connect_timeout_ms) = self.connect_timeout_ms {
+            if connect_timeout_ms == 0 || connect_timeout_ms > MAX_TIMEOUT_MS {
+                return Err("`pool.connect_timeout_ms` must be > 0 and at most a day");
+            }
+        }
+        Ok(())
+    }
+
+    /// time to wait for a connection to be established
+    pub(super) fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Apply the idle connection settings to the client `builder`
+    pub(super) fn apply(&self, builder: &mut Builder) {
+        if let Some(max_idle_per_host) = self.max_idle_per_host {
+            builder.pool_max_idle_per_host(max_idle_per_host);
+        }
+        if let Some(idle_timeout_ms) = self.idle_timeout_ms {
+            builder.pool_idle_timeout(Duration::from_millis(idle_timeout_ms));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Result;
+    use tremor_value::literal;
+
+    #[test]
+    fn validate() -> Result<()> {
+        let pool: Pool = tremor_value::structurize(literal!({
+            "max_idle_per_host": 0,
+            "idle_timeout_ms": 1000,
+            "connect_timeout_ms": 100
+        }))?;
+        assert!(pool.validate().is_ok());
+        assert_eq!(Some(Duration::from_millis(100)), pool.connect_timeout());
+        assert!(Pool::default().validate().is_ok());
+        assert_eq!(None, Pool::default().connect_timeout());
+
+        let invalid = [
+            literal!({"idle_timeout_ms": 0}),
+            literal!({"connect_timeout_ms": 0}),
+            literal!({ "connect_timeout_ms": MAX_TIMEOUT_MS + 1 }),
+        ];
+        for config in invalid {
+            let pool: Pool = tremor_value::structurize(config)?;
+            assert!(pool.validate().is_err(), "{pool:?}");
+        }
+        assert!(tremor_value::structurize::<Pool>(literal!({"max_idle_per_host": -1})).is_err());
+        assert!(tremor_value::structurize::<Pool>(literal!({"snot": 1})).is_err());
+        Ok(())
+    }
+}
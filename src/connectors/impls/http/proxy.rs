@@ -0,0 +1,499 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Routing requests through an HTTP proxy
+//!
+//! Plain `http` requests are sent to the proxy with an absolute request target,
+//! `https` requests are tunneled through the proxy via `CONNECT`.
+//!
+//! With a `unix_socket` configured all connections are opened to that socket instead, the
+//! requests are still sent with the host of their url.
+
+use super::dns::Resolver;
+use crate::connectors::utils::url::Url;
+use crate::errors::Result;
+use base64::Engine;
+use futures::future::BoxFuture;
+use hyper::{
+    client::{
+        connect::{Connected, Connection},
+        HttpConnector,
+    },
+    header::{self, HeaderValue},
+    http::uri::Scheme,
+    service::Service,
+    Request, Uri,
+};
+use std::{
+    io,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::TcpStream,
+    time::timeout,
+};
+use tremor_common::base64::BASE64;
+
+/// upper bound for the response header of a `CONNECT` request
+const MAX_CONNECT_RESPONSE_LEN: usize = 8192;
+
+/// Proxy configuration of the `http_client`
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Proxy {
+    /// url of the proxy, only `http` proxies are supported
+    url: Url,
+    /// optional username for basic proxy authentication
+    #[serde(default = "Default::default")]
+    username: Option<String>,
+    /// optional password for basic proxy authentication
+    #[serde(default = "Default::default")]
+    password: Option<String>,
+    /// hosts that are connected to directly, `example.com` also matches all its subdomains, `*` matches all hosts
+    #[serde(default = "Default::default")]
+    no_proxy: Vec<String>,
+}
+
+impl Proxy {
+    /// Validate the proxy config
+    pub(super) fn validate(&self) -> std::result::Result<(), &'static str> {
+        if self.url.scheme() != "http" {
+            return Err("`proxy.url` must be an `http` url");
+        }
+        if self.url.host_str().is_none() {
+            return Err("`proxy.url` is missing a host");
+        }
+        if self.password.is_some() && self.username.is_none() {
+            return Err("`proxy.password` requires a `proxy.username`");
+        }
+        Ok(())
+    }
+
+    /// `true` if requests to `uri` are to be sent via the proxy
+    fn applies_to(&self, uri: &Uri) -> bool {
+        let host = match uri.host() {
+            Some(host) => host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_ascii_lowercase(),
+            None => return false,
+        };
+        !self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim().to_ascii_lowercase();
+            let domain = entry.trim_start_matches("*.").trim_start_matches('.');
+            entry == "*" || host == domain || host.ends_with(&format!(".{domain}"))
+        })
+    }
+
+    /// The `Proxy-Authorization` header value, if credentials are configured
+    fn authorization(&self) -> Option<String> {
+        self.username.as_ref().map(|username| {
+            let password = self.password.as_deref().unwrap_or_default();
+            format!("Basic {}", BASE64.encode(format!("{username}:{password}")))
+        })
+    }
+
+    /// Add the `Proxy-Authorization` header to plain `http` requests sent via the proxy
+    ///
+    /// Tunneled `https` requests are authorized in the `CONNECT` request instead.
+    pub(super) fn authorize<B>(&self, request: &mut Request<B>) -> Result<()> {
+        if request.uri().scheme() == Some(&Scheme::HTTP) && self.applies_to(request.uri()) {
+            if let Some(authorization) = self.authorization() {
+                request.headers_mut().insert(
+                    header::PROXY_AUTHORIZATION,
+                    HeaderValue::from_str(&authorization)?,
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A connector connecting either directly, via the configured proxy or to a unix socket
+#[derive(Clone)]
+pub(crate) struct ProxyConnector {
+    http: HttpConnector<Resolver>,
+    proxy: Option<Arc<Proxy>>,
+    /// socket all connections are opened to, regardless of the host of the request
+    unix_socket: Option<Arc<PathBuf>>,
+    connect_timeout: Option<Duration>,
+}
+
+impl ProxyConnector {
+    pub(super) fn new(
+        proxy: Option<Proxy>,
+        unix_socket: Option<PathBuf>,
+        resolver: Resolver,
+        connect_timeout: Option<Duration>,
+    ) -> Self {
+        let mut http = HttpConnector::new_with_resolver(resolver);
+        // we are also connecting for https urls, tls is handled by the wrapping `HttpsConnector`
+        http.enforce_http(false);
+        http.set_connect_timeout(connect_timeout);
+        Self {
+            http,
+            proxy: proxy.map(Arc::new),
+            unix_socket: unix_socket.map(Arc::new),
+            connect_timeout,
+        }
+    }
+}
+
+impl Service<Uri> for ProxyConnector {
+    type Response = ProxyStream;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Future = BoxFuture<'static, std::result::Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.http.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        if let Some(unix_socket) = self.unix_socket.clone() {
+            let connect_timeout = self.connect_timeout;
+            return Box::pin(async move {
+                let connect = connect_unix(unix_socket);
+                let stream = if let Some(connect_timeout) = connect_timeout {
+                    timeout(connect_timeout, connect).await??
+                } else {
+                    connect.await?
+                };
+                Ok(ProxyStream {
+                    stream,
+                    proxied: false,
+                })
+            });
+        }
+        let mut http = self.http.clone();
+        let proxy = self.proxy.clone().filter(|proxy| proxy.applies_to(&dst));
+        Box::pin(async move {
+            if let Some(proxy) = proxy {
+                let stream = http.call(proxy.url.as_str().parse()?).await?;
+                if dst.scheme() == Some(&Scheme::HTTPS) {
+                    let stream = tunnel(stream, &dst, proxy.authorization()).await?;
+                    Ok(ProxyStream {
+                        stream: Transport::Tcp(stream),
+                        proxied: false,
+                    })
+                } else {
+                    Ok(ProxyStream {
+                        stream: Transport::Tcp(stream),
+                        proxied: true,
+                    })
+                }
+            } else {
+                Ok(ProxyStream {
+                    stream: Transport::Tcp(http.call(dst).await?),
+                    proxied: false,
+                })
+            }
+        })
+    }
+}
+
+#[cfg(unix)]
+async fn connect_unix(path: Arc<PathBuf>) -> io::Result<Transport> {
+    Ok(Transport::Unix(UnixStream::connect(path.as_path()).await?))
+}
+
+#[cfg(not(unix))]
+async fn connect_unix(_path: Arc<PathBuf>) -> io::Result<Transport> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "unix sockets are not supported on this platform",
+    ))
+}
+
+/// Open a tunnel to `dst` via `CONNECT` on the connection to the proxy
+async fn tunnel(
+    mut stream: TcpStream,
+    dst: &Uri,
+    authorization: Option<String>,
+) -> io::Result<TcpStream> {
+    let host = dst
+        .host()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "url without host"))?;
+    let port = dst.port_u16().unwrap_or(443);
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if let Some(authorization) = authorization {
+        request.push_str(&format!("Proxy-Authorization: {authorization}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // the proxy won't send anything beyond the response header before we start the tls handshake
+    let mut response = Vec::with_capacity(128);
+    let mut buf = [0_u8; 128];
+    while !response.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "proxy closed the connection during CONNECT",
+            ));
+        }
+        response.extend_from_slice(&buf[..read]);
+        if response.len() > MAX_CONNECT_RESPONSE_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "proxy response to CONNECT is too long",
+            ));
+        }
+    }
+    let status_line = response
+        .split(|b| *b == b'\r')
+        .next()
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    if status_line.split(' ').nth(1) == Some("200") {
+        Ok(stream)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("proxy CONNECT to {host}:{port} failed: {status_line}"),
+        ))
+    }
+}
+
+/// The transport of a connection
+enum Transport {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+/// A connection, either directly to the target, to a proxy or to a unix socket
+pub(crate) struct ProxyStream {
+    stream: Transport,
+    /// requests need to be sent with an absolute url to the proxy
+    proxied: bool,
+}
+
+impl Connection for ProxyStream {
+    fn connected(&self) -> Connected {
+        match &self.stream {
+            Transport::Tcp(stream) => stream.connected().proxy(self.proxied),
+            #[cfg(unix)]
+            Transport::Unix(_) => Connected::new(),
+        }
+    }
+}
+
+impl AsyncRead for ProxyStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().stream {
+            Transport::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            Transport::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for ProxyStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match &mut self.get_mut().stream {
+            Transport::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            Transport::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().stream {
+            Transport::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            Transport::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.get_mut().stream {
+            Transport::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            Transport::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match &mut self.get_mut().stream {
+            Transport::Tcp(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            #[cfg(unix)]
+            Transport::Unix(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match &self.stream {
+            Transport::Tcp(stream) => stream.is_write_vectored(),
+            #[cfg(unix)]
+            Transport::Unix(stream) => stream.is_write_vectored(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tremor_value::literal;
+
+    fn proxy(config: &tremor_value::Value) -> Result<Proxy> {
+        Ok(tremor_value::structurize(config.clone())?)
+    }
+
+    #[test]
+    fn validate() -> Result<()> {
+        assert!(proxy(&literal!({"url": "http://localhost:3128"}))?
+            .validate()
+            .is_ok());
+        assert!(proxy(&literal!({"url": "https://localhost:3128"}))?
+            .validate()
+            .is_err());
+        assert!(
+            proxy(&literal!({"url": "http://localhost:3128", "password": "snot"}))?
+                .validate()
+                .is_err()
+        );
+        assert!(proxy(&literal!({"url": "http://localhost:3128", "snot": "badger"})).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn no_proxy() -> Result<()> {
+        let proxy = proxy(&literal!({
+            "url": "http://localhost:3128",
+            "no_proxy": ["example.com", ".internal", "*.snot.org", "::1"]
+        }))?;
+        for uri in [
+            "http://example.com/",
+            "https://api.EXAMPLE.com/",
+            "http://db.internal:8080/",
+            "http://snot.org/",
+            "http://badger.snot.org/",
+            "http://[::1]:8080/",
+        ] {
+            assert!(!proxy.applies_to(&uri.parse()?), "{uri}");
+        }
+        for uri in [
+            "http://badexample.com/",
+            "https://example.com.evil.org/",
+            "http://internal.org/",
+            "http://127.0.0.1/",
+        ] {
+            assert!(proxy.applies_to(&uri.parse()?), "{uri}");
+        }
+        let all = self::proxy(&literal!({"url": "http://localhost:3128", "no_proxy": ["*"]}))?;
+        assert!(!all.applies_to(&"http://example.com".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn authorize() -> Result<()> {
+        let proxy = proxy(&literal!({
+            "url": "http://localhost:3128",
+            "username": "snot",
+            "password": "badger",
+            "no_proxy": ["localhost"]
+        }))?;
+        let mut request = Request::get("http://example.com/").body(())?;
+        proxy.authorize(&mut request)?;
+        assert_eq!(
+            Some("Basic c25vdDpiYWRnZXI="),
+            request
+                .headers()
+                .get(header::PROXY_AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+        );
+        // bypassing the proxy or tunneling must not leak the proxy credentials
+        for uri in ["http://localhost/", "https://example.com/"] {
+            let mut request = Request::get(uri).body(())?;
+            proxy.authorize(&mut request)?;
+            assert!(request.headers().get(header::PROXY_AUTHORIZATION).is_none());
+        }
+        Ok(())
+    }
+
+    /// Accept a single connection, answer the `CONNECT` request with `status` and echo afterwards
+    async fn spawn_connect_proxy(
+        status: &'static str,
+    ) -> Result<(String, tokio::task::JoinHandle<Result<String>>)> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?.to_string();
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await?;
+            let mut request = Vec::new();
+            let mut buf = [0_u8; 128];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let read = stream.read(&mut buf).await?;
+                request.extend_from_slice(&buf[..read]);
+            }
+            stream
+                .write_all(format!("HTTP/1.1 {status}\r\n\r\n").as_bytes())
+                .await?;
+            let read = stream.read(&mut buf).await?;
+            stream.write_all(&buf[..read]).await?;
+            Ok(String::from_utf8_lossy(&request).to_string())
+        });
+        Ok((addr, handle))
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connect_tunnel() -> Result<()> {
+        let (addr, proxy) = spawn_connect_proxy("200 Connection established").await?;
+        let stream = TcpStream::connect(&addr).await?;
+        let mut stream = tunnel(
+            stream,
+            &"https://example.com/snot".parse()?,
+            Some("Basic c25vdDpiYWRnZXI=".to_string()),
+        )
+        .await?;
+        stream.write_all(b"snot").await?;
+        let mut buf = [0_u8; 4];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(b"snot", &buf);
+        let request = proxy.await??;
+        assert!(request.starts_with("CONNECT example.com:443 HTTP/1.1\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic c25vdDpiYWRnZXI=\r\n"));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connect_tunnel_rejected() -> Result<()> {
+        let (addr, proxy) = spawn_connect_proxy("407 Proxy Authentication Required").await?;
+        let stream = TcpStream::connect(&addr).await?;
+        let res = tunnel(stream, &"https://example.com:8443/".parse()?, None).await;
+        assert!(res.is_err());
+        proxy.abort();
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rate limiting of the requests sent by the `http_client`, to respect the quotas of upstream services
+//!
+//! A token bucket holding up to `burst` tokens is refilled with `max_per_sec` tokens per second, every
+//! request takes one. When the bucket is empty, the request is delayed until the next token is available
+//! or dropped, depending on `on_limit`.
+
+use std::time::{Duration, Instant};
+
+/// Rate limit configuration of the `http_client`
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RateLimit {
+    /// requests per second, fractions like `0.5` are allowed
+    pub(super) max_per_sec: f64,
+    /// number of requests that may be sent at once after the limiter was idle
+    #[serde(default = "default_burst")]
+    pub(super) burst: u32,
+    /// what to do with requests exceeding the limit
+    #[serde(default = "Default::default")]
+    pub(super) on_limit: OnLimit,
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+/// Handling of requests exceeding the `rate_limit` of the `http_client`
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OnLimit {
+    /// hold the request back until it may be sent, which holds back all following events as well
+    #[default]
+    Delay,
+    /// drop the event, emit a synthetic `429 Too Many Requests` error event and ack it
+    Drop,
+}
+
+impl RateLimit {
+    /// Validate the rate limit config
+    pub(super) fn validate(&self) -> std::result::Result<(), &'static str> {
+        if !self.max_per_sec.is_finite() || self.max_per_sec <= 0.0 {
+            return Err("`rate_limit.max_per_sec` must be > 0");
+        }
+        if self.burst == 0 {
+            return Err("`rate_limit.burst` must be > 0");
+        }
+        Ok(())
+    }
+}
+
+/// Token bucket enforcing a `RateLimit`
+///
+/// It is only used by the sink itself, not by the tasks sending the requests, so it is never shared.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    config: RateLimit,
+    /// tokens available at `refilled`, negative if requests are waiting for tokens
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    pub(super) fn new(config: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(config.burst),
+            config,
+            refilled: now,
+        }
+    }
+
+    pub(super) fn on_limit(&self) -> OnLimit {
+        self.config.on_limit
+    }
+
+    pub(super) fn max_per_sec(&self) -> f64 {
+        self.config.max_per_sec
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.config.max_per_sec).min(f64::from(self.config.burst));
+        self.refilled = now;
+    }
+
+    /// Take a token if one is available at `now`
+    pub(super) fn try_acquire(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Take the next token, returning how long to wait from `now` until it becomes available
+    pub(super) fn reserve(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.config.max_per_sec)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(max_per_sec: f64, burst: u32, now: Instant) -> RateLimiter {
+        RateLimiter::new(
+            RateLimit {
+                max_per_sec,
+                burst,
+                on_limit: OnLimit::Delay,
+            },
+            now,
+        )
+    }
+
+    #[test]
+    fn validate() {
+        let config = |max_per_sec, burst| RateLimit {
+            max_per_sec,
+            burst,
+            on_limit: OnLimit::Drop,
+        };
+        assert!(config(10.0, 1).validate().is_ok());
+        assert!(config(0.1, 5).validate().is_ok());
+        assert!(config(0.0, 1).validate().is_err());
+        assert!(config(-1.0, 1).validate().is_err());
+        assert!(config(f64::NAN, 1).validate().is_err());
+        assert!(config(f64::INFINITY, 1).validate().is_err());
+        assert!(config(10.0, 0).validate().is_err());
+    }
+
+    #[test]
+    fn try_acquire() {
+        let now = Instant::now();
+        let mut limiter = limiter(8.0, 2, now);
+        // the burst is available right away
+        assert!(limiter.try_acquire(now));
+        assert!(limiter.try_acquire(now));
+        assert!(!limiter.try_acquire(now));
+        // a token every 125ms
+        assert!(!limiter.try_acquire(now + Duration::from_micros(62_500)));
+        assert!(limiter.try_acquire(now + Duration::from_millis(125)));
+        assert!(!limiter.try_acquire(now + Duration::from_millis(125)));
+        // idle time refills no more than the burst
+        let later = now + Duration::from_secs(10);
+        assert!(limiter.try_acquire(later));
+        assert!(limiter.try_acquire(later));
+        assert!(!limiter.try_acquire(later));
+    }
+
+    #[test]
+    fn reserve() {
+        let now = Instant::now();
+        let mut limiter = limiter(4.0, 1, now);
+        assert_eq!(Duration::ZERO, limiter.reserve(now));
+        // requests reserve the following tokens, one every 250ms
+        assert_eq!(Duration::from_millis(250), limiter.reserve(now));
+        assert_eq!(Duration::from_millis(500), limiter.reserve(now));
+        let later = now + Duration::from_millis(500);
+        assert_eq!(Duration::from_millis(250), limiter.reserve(later));
+        assert!(!limiter.try_acquire(later));
+        assert_eq!(
+            Duration::ZERO,
+            limiter.reserve(now + Duration::from_millis(1000))
+        );
+    }
+
+    #[test]
+    fn throughput() {
+        // over a second no more than `burst` plus `max_per_sec` requests are sent
+        let now = Instant::now();
+        let mut limiter = limiter(100.0, 10, now);
+        let sent = (0..1000)
+            .filter(|i| limiter.try_acquire(now + Duration::from_millis(*i)))
+            .count();
+        assert!((100..=110).contains(&sent), "{sent}");
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Following HTTP redirects
+
+use crate::errors::Result;
+use hyper::{body::Bytes, header, http::request::Parts, Method, Response, StatusCode, Uri};
+
+/// The absolute target of the redirect `response` to a request to `base`
+///
+/// Returns `None` if `response` is not a redirect we can follow.
+pub(super) fn location<B>(base: &Uri, response: &Response<B>) -> Result<Option<Uri>> {
+    if !matches!(
+        response.status(),
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    ) {
+        return Ok(None);
+    }
+    if let Some(location) = response.headers().get(header::LOCATION) {
+        // the location might be relative to the request url
+        let base = url::Url::parse(&base.to_string())?;
+        let location = base.join(location.to_str()?)?;
+        Ok(Some(location.as_str().parse()?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Adapt the request `parts` and `body` for following a redirect with `status` to `location`
+///
+/// `303 See Other` is always followed with a `GET`, `301` and `302` only turn `POST` into `GET`,
+/// like all major clients do. `307` and `308` replay the request as is.
+/// The `Authorization` header is dropped when redirecting to another origin.
+pub(super) fn follow(parts: &mut Parts, body: &mut Bytes, status: StatusCode, location: Uri) {
+    let to_get = match status {
+        StatusCode::SEE_OTHER => parts.method != Method::HEAD,
+        StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => parts.method == Method::POST,
+        _ => false,
+    };
+    if to_get {
+        parts.method = Method::GET;
+        *body = Bytes::new();
+        parts.headers.remove(header::CONTENT_TYPE);
+        parts.headers.remove(header::CONTENT_LENGTH);
+        parts.headers.remove(header::TRANSFER_ENCODING);
+    }
+    if !same_origin(&parts.uri, &location) {
+        parts.headers.remove(header::AUTHORIZATION);
+        parts.headers.remove(header::HOST);
+    }
+    parts.uri = location;
+}
+
+/// Whether `a` and `b` share scheme, host and port
+pub(super) fn same_origin(a: &Uri, b: &Uri) -> bool {
+    a.scheme() == b.scheme() && a.authority() == b.authority()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{Body, Request};
+
+    fn redirect(status: StatusCode, location: &str) -> Result<Response<Body>> {
+        Ok(Response::builder()
+            .status(status)
+            .header(header::LOCATION, location)
+            .body(Body::empty())?)
+    }
+
+    fn request_parts(method: Method, uri: &str) -> Result<Parts> {
+        Ok(Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, "Bearer snot")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(())?
+            .into_parts()
+            .0)
+    }
+
+    #[test]
+    fn location_resolution() -> Result<()> {
+        let base: Uri = "http://localhost:8080/snot/badger".parse()?;
+        let res = redirect(StatusCode::FOUND, "/flork")?;
+        assert_eq!(
+            Some("http://localhost:8080/flork".parse::<Uri>()?),
+            location(&base, &res)?
+        );
+        let res = redirect(StatusCode::TEMPORARY_REDIRECT, "mork")?;
+        assert_eq!(
+            Some("http://localhost:8080/snot/mork".parse::<Uri>()?),
+            location(&base, &res)?
+        );
+        let res = redirect(StatusCode::PERMANENT_REDIRECT, "https://example.com/")?;
+        assert_eq!(
+            Some("https://example.com/".parse::<Uri>()?),
+            location(&base, &res)?
+        );
+        // not a redirect
+        let res = redirect(StatusCode::OK, "/flork")?;
+        assert_eq!(None, location(&base, &res)?);
+        // no location
+        let res = Response::builder()
+            .status(StatusCode::FOUND)
+            .body(Body::empty())?;
+        assert_eq!(None, location(&base, &res)?);
+        Ok(())
+    }
+
+    #[test]
+    fn follow_see_other() -> Result<()> {
+        let mut parts = request_parts(Method::PUT, "http://localhost/snot")?;
+        let mut body = Bytes::from("badger");
+        follow(
+            &mut parts,
+            &mut body,
+            StatusCode::SEE_OTHER,
+            "http://localhost/badger".parse()?,
+        );
+        assert_eq!(Method::GET, parts.method);
+        assert!(body.is_empty());
+        assert!(parts.headers.get(header::CONTENT_TYPE).is_none());
+        assert!(parts.headers.get(header::AUTHORIZATION).is_some());
+        assert_eq!("/badger", parts.uri.path());
+        Ok(())
+    }
+
+    #[test]
+    fn follow_found() -> Result<()> {
+        let mut parts = request_parts(Method::PUT, "http://localhost/snot")?;
+        let mut body = Bytes::from("badger");
+        follow(
+            &mut parts,
+            &mut body,
+            StatusCode::FOUND,
+            "http://localhost/badger".parse()?,
+        );
+        assert_eq!(Method::PUT, parts.method);
+        assert_eq!(Bytes::from("badger"), body);
+
+        let mut parts = request_parts(Method::POST, "http://localhost/snot")?;
+        follow(
+            &mut parts,
+            &mut body,
+            StatusCode::FOUND,
+            "http://localhost/badger".parse()?,
+        );
+        assert_eq!(Method::GET, parts.method);
+        assert!(body.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn follow_cross_origin() -> Result<()> {
+        let mut parts = request_parts(Method::POST, "http://localhost/snot")?;
+        let mut body = Bytes::from("badger");
+        follow(
+            &mut parts,
+            &mut body,
+            StatusCode::TEMPORARY_REDIRECT,
+            "http://example.com/badger".parse()?,
+        );
+        assert_eq!(Method::POST, parts.method);
+        assert_eq!(Bytes::from("badger"), body);
+        assert!(parts.headers.get(header::AUTHORIZATION).is_none());
+        assert!(parts.headers.get(header::CONTENT_TYPE).is_some());
+        Ok(())
+    }
+}
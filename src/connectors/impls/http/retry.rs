@@ -0,0 +1,265 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Retrying failed HTTP requests with exponential backoff and jitter
+//!
+//! Responses with `429 Too Many Requests` are always retried. A `Retry-After` header on a retried response
+//! overrides the backoff, capped at `max_retry_after_ms`.
+//!
+//! Timeouts and connections closed before the response arrived are only retried for idempotent methods,
+//! as the server might have processed the request already, unless `non_idempotent` is set.
+
+use super::{client::HttpClient, utils::rebuild_request};
+use crate::connectors::prelude::*;
+use chrono::{DateTime, Utc};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use rand::Rng;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+/// Configuration for retrying requests of the `http_client`
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Retry {
+    /// maximum number of retries after the initial request
+    #[serde(default = "default_max_retries")]
+    pub(super) max_retries: u32,
+    /// backoff before the first retry in milliseconds, doubled for every further retry
+    #[serde(default = "default_initial_backoff_ms")]
+    pub(super) initial_backoff_ms: u64,
+    /// upper bound for the backoff between two retries in milliseconds
+    #[serde(default = "default_max_backoff_ms")]
+    pub(super) max_backoff_ms: u64,
+    /// response status codes that are considered transient and will be retried
+    #[serde(default = "default_retry_on")]
+    pub(super) retry_on: Vec<u16>,
+    /// upper bound for a delay requested via a `Retry-After` header in milliseconds
+    #[serde(default = "default_max_retry_after_ms")]
+    pub(super) max_retry_after_ms: u64,
+    /// also retry requests with non idempotent methods, like `POST`, after timeouts or closed connections
+    #[serde(default = "Default::default")]
+    pub(super) non_idempotent: bool,
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    100
+}
+
+fn default_max_backoff_ms() -> u64 {
+    10_000
+}
+
+fn default_retry_on() -> Vec<u16> {
+    vec![502, 503, 504]
+}
+
+fn default_max_retry_after_ms() -> u64 {
+    60_000
+}
+
+/// Whether a request with `method` can be sent again if it is unknown whether the server processed it
+fn idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
+
+/// The delay requested by the `Retry-After` header of `response`, given in seconds or as an HTTP date
+///
+/// A date in the past results in no delay.
+fn retry_after(response: &Response<Body>, now: DateTime<Utc>) -> Option<Duration> {
+    let value = response
+        .headers()
+        .get(hyper::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+impl Retry {
+    /// Validate the retry config
+    pub(super) fn validate(&self) -> std::result::Result<(), &'static str> {
+        if self.initial_backoff_ms > self.max_backoff_ms {
+            return Err(
+                "`retry.initial_backoff_ms` must not be greater than `retry.max_backoff_ms`",
+            );
+        }
+        Ok(())
+    }
+
+    fn should_retry(&self, status: StatusCode) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || self.retry_on.contains(&status.as_u16())
+    }
+
+    /// Backoff before retry number `retry` (starting at 0)
+    ///
+    /// The exponential backoff is capped at `max_backoff_ms`, jitter picks a random
+    /// duration between half and the full backoff.
+    fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff_ms
+            .saturating_mul(2_u64.saturating_pow(retry))
+            .min(self.max_backoff_ms);
+        let jittered = rand::thread_rng().gen_range(backoff / 2..=backoff);
+        Duration::from_millis(jittered)
+    }
+
+    /// Send the request, retrying on connection errors, timeouts and on responses with a status in `retry_on`.
+    ///
+    /// Timeouts and connections closed while waiting for the response are only retried for idempotent methods
+    /// or if `non_idempotent` is set. The request body is buffered, so it can be replayed for each attempt.
+    /// When all retries are exhausted the last response is returned, or the last error if there was none.
+    pub(super) async fn send(
+        &self,
+        client: &HttpClient,
+        request: Request<Body>,
+        request_timeout: Duration,
+        ctx: &SinkContext,
+    ) -> Result<Response<Body>> {
+        let (parts, body) = request.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        // the request might have reached the server when it timed out or the connection was closed
+        let replayable = self.non_idempotent || idempotent(&parts.method);
+        let mut retry = 0;
+        loop {
+            let request = rebuild_request(&parts, body.clone());
+            let res = timeout(request_timeout, client.request(request)).await;
+            let should_retry = match &res {
+                Ok(Ok(response)) => self.should_retry(response.status()),
+                Ok(Err(e)) if e.is_connect() => true,
+                Ok(Err(e)) => replayable && (e.is_closed() || e.is_incomplete_message()),
+                Err(_) => replayable,
+            };
+            if !should_retry || retry >= self.max_retries {
+                return Ok(res??);
+            }
+            let backoff = match &res {
+                Ok(Ok(response)) => retry_after(response, Utc::now()).map_or_else(
+                    || self.backoff(retry),
+                    |delay| delay.min(Duration::from_millis(self.max_retry_after_ms)),
+                ),
+                _ => self.backoff(retry),
+            };
+            match &res {
+                Ok(Ok(response)) => debug!(
+                    "{ctx} Retrying HTTP request in {backoff:?} after status {}",
+                    response.status()
+                ),
+                Ok(Err(e)) => debug!("{ctx} Retrying HTTP request in {backoff:?} after error: {e}"),
+                Err(e) => debug!("{ctx} Retrying HTTP request in {backoff:?} after {e}"),
+            }
+            retry += 1;
+            sleep(backoff).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retry(initial_backoff_ms: u64, max_backoff_ms: u64) -> Retry {
+        Retry {
+            max_retries: 5,
+            initial_backoff_ms,
+            max_backoff_ms,
+            retry_on: default_retry_on(),
+            max_retry_after_ms: default_max_retry_after_ms(),
+            non_idempotent: false,
+        }
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let retry = retry(100, 1000);
+        for i in 0..10 {
+            let expected = (100 * 2_u64.pow(i)).min(1000);
+            let backoff = retry.backoff(i);
+            assert!(backoff <= Duration::from_millis(expected));
+            assert!(backoff >= Duration::from_millis(expected / 2));
+        }
+        // no overflow for large retry counts
+        assert!(retry.backoff(u32::MAX) <= Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn validate() {
+        assert!(retry(100, 1000).validate().is_ok());
+        assert!(retry(1000, 1000).validate().is_ok());
+        assert!(retry(1001, 1000).validate().is_err());
+    }
+
+    #[test]
+    fn should_retry() {
+        let retry = retry(100, 1000);
+        assert!(retry.should_retry(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(retry.should_retry(StatusCode::BAD_GATEWAY));
+        assert!(retry.should_retry(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!retry.should_retry(StatusCode::OK));
+        assert!(!retry.should_retry(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+
+    #[test]
+    fn idempotent_methods() {
+        assert!(idempotent(&Method::GET));
+        assert!(idempotent(&Method::PUT));
+        assert!(idempotent(&Method::DELETE));
+        assert!(!idempotent(&Method::POST));
+        assert!(!idempotent(&Method::PATCH));
+    }
+
+    #[test]
+    fn parse_retry_after() -> Result<()> {
+        let response = |retry_after: &str| -> Result<Response<Body>> {
+            Ok(Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header(hyper::header::RETRY_AFTER, retry_after)
+                .body(Body::empty())?)
+        };
+        let now =
+            DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:27:55 GMT")?.with_timezone(&Utc);
+        assert_eq!(
+            Some(Duration::from_secs(2)),
+            retry_after(&response("2")?, now)
+        );
+        assert_eq!(
+            Some(Duration::from_secs(5)),
+            retry_after(&response("Wed, 21 Oct 2015 07:28:00 GMT")?, now)
+        );
+        // dates in the past don't delay
+        assert_eq!(
+            Some(Duration::ZERO),
+            retry_after(&response("Wed, 21 Oct 2015 07:00:00 GMT")?, now)
+        );
+        assert_eq!(None, retry_after(&response("-1")?, now));
+        assert_eq!(None, retry_after(&response("snot")?, now));
+        assert_eq!(None, retry_after(&Response::new(Body::empty()), now));
+        Ok(())
+    }
+}
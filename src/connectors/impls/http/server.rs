@@ -60,7 +60,11 @@ pub(crate) struct Config {
     /// custom codecs mapping from mime_type to custom codec name
     /// e.g. for handling `application/json` with the `binary` codec, if desired
     /// the mime type of `*/*` serves as a default / fallback
+    /// it is merged onto the builtin mapping, entries given here take precedence
     mime_mapping: Option<HashMap<String, NameWithConfig>>,
+    /// replace the builtin mime type mapping with `mime_mapping` instead of merging onto it
+    #[serde(default = "Default::default")]
+    mime_mapping_override: bool,
 }
 
 impl ConfigImpl for Config {}
@@ -99,11 +103,8 @@ impl ConnectorBuilder for Builder {
             path: vec![],
         };
         let inflight = Arc::default();
-        let codec_map = if let Some(custom_codecs) = config.mime_mapping.clone() {
-            MimeCodecMap::from_custom(custom_codecs)
-        } else {
-            MimeCodecMap::new()
-        };
+        let codec_map =
+            MimeCodecMap::from_config(config.mime_mapping.clone(), config.mime_mapping_override);
 
         Ok(Box::new(HttpServer {
             config,
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use either::Either;
+use hyper::{body::Bytes, http::request::Parts, Body, Request};
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(transparent)]
@@ -37,3 +38,15 @@ impl std::fmt::Display for RequestId {
         write!(f, "{}", self.0)
     }
 }
+
+/// Build a new request from the `parts` of a previous one, for sending it again
+///
+/// `Parts` is not `Clone`, so we need to copy the relevant bits ourselves
+pub(crate) fn rebuild_request(parts: &Parts, body: Bytes) -> Request<Body> {
+    let mut request = Request::new(Body::from(body));
+    *request.method_mut() = parts.method.clone();
+    *request.uri_mut() = parts.uri.clone();
+    *request.version_mut() = parts.version;
+    *request.headers_mut() = parts.headers.clone();
+    request
+}
@@ -80,6 +80,21 @@ pub(crate) fn any_value_to_pb(data: &Value<'_>) -> AnyValue {
                 value: Some(Inner::IntValue(v)),
             }
         }
+        // otel integers are 64 bit, larger ones are sent as doubles
+        #[cfg(feature = "128bit")]
+        #[allow(clippy::cast_precision_loss)]
+        Value::Static(StaticNode::I128(v)) => AnyValue {
+            value: Some(
+                i64::try_from(*v).map_or_else(|_| Inner::DoubleValue(*v as f64), Inner::IntValue),
+            ),
+        },
+        #[cfg(feature = "128bit")]
+        #[allow(clippy::cast_precision_loss)]
+        Value::Static(StaticNode::U128(v)) => AnyValue {
+            value: Some(
+                i64::try_from(*v).map_or_else(|_| Inner::DoubleValue(*v as f64), Inner::IntValue),
+            ),
+        },
         Value::Static(StaticNode::F64(v)) => AnyValue {
             value: Some(Inner::DoubleValue(*v)),
         },
@@ -263,6 +278,22 @@ mod tests {
         assert_eq!(pb, back_again);
     }
 
+    #[cfg(feature = "128bit")]
+    #[test]
+    fn any_value_128bit() {
+        let json = Value::Static(StaticNode::I128(-42));
+        let pb = AnyValue {
+            value: Some(any_value::Value::IntValue(-42)),
+        };
+        assert_eq!(pb, any_value_to_pb(&json));
+
+        let json = Value::Static(StaticNode::U128(u128::MAX));
+        let pb = AnyValue {
+            value: Some(any_value::Value::DoubleValue(2_f64.powi(128))),
+        };
+        assert_eq!(pb, any_value_to_pb(&json));
+    }
+
     #[test]
     fn any_value_double() {
         let pb = AnyValue {
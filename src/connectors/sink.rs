@@ -27,7 +27,7 @@ use crate::connectors::utils::reconnect::{Attempt, ConnectionLostNotifier};
 use crate::connectors::{Alias, ConnectorType, Context, Msg, QuiescenceBeacon, StreamDone};
 use crate::errors::Result;
 use crate::pipeline;
-use crate::postprocessor::{finish, make_postprocessors, postprocess, Postprocessors};
+use crate::postprocessor::{finish, make_postprocessors, postprocess, tick, Postprocessors};
 use crate::primerge::PriorityMerge;
 use crate::{
     channel::{bounded, unbounded, Receiver, Sender, UnboundedReceiver, UnboundedSender},
@@ -46,7 +46,7 @@ use tokio_stream::wrappers::{ReceiverStream, UnboundedReceiverStream};
 use tremor_common::time::nanotime;
 use tremor_common::{
     ids::{SinkId, SourceId},
-    ports::Port,
+    ports::{Port, IN},
 };
 use tremor_pipeline::{CbAction, Event, EventId, OpMeta, SignalKind, DEFAULT_STREAM_ID};
 use tremor_script::{ast::DeployEndpoint, EventPayload};
@@ -233,6 +233,29 @@ pub(crate) trait Sink: Send {
     fn asynchronous(&self) -> bool {
         false
     }
+
+    /// called on every tick, to flush out the data postprocessors emit without new input,
+    /// e.g. a `batch` whose `timeout_ms` elapsed.
+    ///
+    /// By default the data of the default stream is handed to `on_event` with an event without a value,
+    /// whose serialization yields the flushed data. Sinks serializing into streams of their own need to
+    /// flush those themselves.
+    async fn on_tick(&mut self, ctx: &SinkContext, serializer: &mut EventSerializer) -> Result<()> {
+        if !serializer.tick_default_stream(nanotime())? {
+            return Ok(());
+        }
+        let event = Event {
+            ingest_ns: nanotime(),
+            ..Event::default()
+        };
+        let res = self
+            .on_event(IN.borrow(), event, ctx, serializer, nanotime())
+            .await;
+        if serializer.discard_flushed() {
+            warn!("{ctx} Dropped postprocessor data that was not serialized");
+        }
+        res.map(|_| ())
+    }
 }
 
 /// handles writing to 1 stream (e.g. file or TCP connection)
@@ -452,6 +475,8 @@ pub(crate) struct EventSerializer {
     // stream data
     // TODO: clear out state from codec, postprocessors and enable reuse
     streams: BTreeMap<u64, (Box<dyn Codec>, Postprocessors)>,
+    // data the postprocessors of the default stream emitted on a tick, returned by its next serialization
+    flushed: Option<Vec<Vec<u8>>>,
 }
 
 impl EventSerializer {
@@ -486,6 +511,7 @@ impl EventSerializer {
             codec_config,
             postprocessor_configs,
             streams: BTreeMap::new(),
+            flushed: None,
         })
     }
 
@@ -536,6 +562,10 @@ impl EventSerializer {
         codec_overwrite: Option<&NameWithConfig>,
     ) -> Result<Vec<Vec<u8>>> {
         if stream_id == DEFAULT_STREAM_ID {
+            if let Some(data) = self.flushed.take() {
+                // the serialization of the event flushing the data emitted on a tick
+                return Ok(data);
+            }
             // no codec_overwrite for the default stream
             postprocess(
                 &mut self.postprocessors,
@@ -564,6 +594,49 @@ impl EventSerializer {
         }
     }
 
+    /// data the postprocessors of each stream emit on their own at `now_ns`, e.g. batches whose timeout elapsed
+    ///
+    /// # Errors
+    ///   * if a postprocessor fails
+    pub(crate) fn tick(&mut self, now_ns: u64) -> Result<Vec<(u64, Vec<Vec<u8>>)>> {
+        let mut res = Vec::new();
+        let data = tick(&mut self.postprocessors, now_ns, &self.alias)?;
+        if !data.is_empty() {
+            res.push((DEFAULT_STREAM_ID, data));
+        }
+        for (stream_id, (_codec, postprocessors)) in &mut self.streams {
+            let data = tick(postprocessors, now_ns, &self.alias)?;
+            if !data.is_empty() {
+                res.push((*stream_id, data));
+            }
+        }
+        Ok(res)
+    }
+
+    /// tick the postprocessors of the default stream, the data they emit is returned by the next
+    /// serialization of the default stream instead of the serialized value, see `Sink::on_tick`
+    ///
+    /// Returns `true` if there is data to flush.
+    ///
+    /// # Errors
+    ///   * if a postprocessor fails
+    pub(crate) fn tick_default_stream(&mut self, now_ns: u64) -> Result<bool> {
+        let data = tick(&mut self.postprocessors, now_ns, &self.alias)?;
+        if data.is_empty() {
+            Ok(false)
+        } else {
+            self.flushed = Some(data);
+            Ok(true)
+        }
+    }
+
+    /// drop the data flushed by `tick_default_stream`, if it wasn't serialized
+    ///
+    /// Returns `true` if there was data left.
+    pub(crate) fn discard_flushed(&mut self) -> bool {
+        self.flushed.take().is_some()
+    }
+
     /// remove and flush out any pending data from the stream identified by the given `stream_id`
     pub(crate) fn finish_stream(&mut self, stream_id: u64) -> Result<Vec<Vec<u8>>> {
         if let Some((mut _codec, mut postprocessors)) = self.streams.remove(&stream_id) {
@@ -815,6 +888,7 @@ where
                             };
                         }
                         SinkMsg::Signal { signal } => {
+                            let is_tick = matches!(signal.kind, Some(SignalKind::Tick));
                             // special treatment
                             match signal.kind {
                                 Some(SignalKind::Drain(source_uid)) => {
@@ -868,6 +942,13 @@ where
                                     error!("{} Error handling signal: {e}", self.ctx);
                                 }
                             }
+                            if is_tick {
+                                // flush out data postprocessors held back, e.g. batches whose timeout elapsed
+                                let res = self.sink.on_tick(&self.ctx, &mut self.serializer).await;
+                                if let Err(e) = res {
+                                    error!("{} Error flushing postprocessors: {e}", self.ctx);
+                                }
+                            }
                         }
                         st @ (SinkMsg::Start | SinkMsg::Resume | SinkMsg::Pause) => {
                             info!("{} Ignoring {st:?} message in {}", self.ctx, self.state);
@@ -922,6 +1003,11 @@ impl ContraflowData {
     pub(crate) fn into_fail(self) -> Event {
         Event::cb_fail(self.ingest_ns, self.event_id, self.op_meta)
     }
+    /// Track `event` as well, so it is acked or failed together with the events tracked already
+    pub(crate) fn track(&mut self, event: &Event) {
+        self.event_id.track(&event.id);
+        self.op_meta.merge(event.op_meta.clone());
+    }
     fn cb(&self, cb: CbAction) -> Event {
         Event::insight(
             cb,
@@ -1013,4 +1099,32 @@ mod test {
         assert_eq!(SinkReply::ack_or_none(true), SinkReply::ACK);
         assert_eq!(SinkReply::ack_or_none(false), SinkReply::NONE);
     }
+
+    #[test]
+    fn serializer_flushes_default_stream_on_tick() -> Result<()> {
+        let batch = tremor_value::literal!({"name": "batch", "config": {"timeout_ms": 1}});
+        let mut serializer = EventSerializer::new(
+            Some(CodecConfig::from("json")),
+            CodecReq::Required,
+            vec![NameWithConfig::try_from(&batch)?],
+            &ConnectorType("test".into()),
+            &Alias::new("flow", "test"),
+        )?;
+        assert!(!serializer.tick_default_stream(nanotime())?);
+        assert!(serializer.serialize(&Value::from(1), 0)?.is_empty());
+        assert!(serializer.serialize(&Value::from(2), 0)?.is_empty());
+        assert!(serializer.tick_default_stream(u64::MAX)?);
+        // the next serialization hands out the flushed batch
+        assert_eq!(
+            vec![b"[1,2]".to_vec()],
+            serializer.serialize(&Value::const_null(), 0)?
+        );
+        assert!(!serializer.discard_flushed());
+
+        assert!(serializer.serialize(&Value::from(3), 0)?.is_empty());
+        assert!(serializer.tick_default_stream(u64::MAX)?);
+        assert!(serializer.discard_flushed());
+        assert!(!serializer.tick_default_stream(u64::MAX)?);
+        Ok(())
+    }
 }
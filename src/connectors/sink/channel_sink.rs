@@ -468,6 +468,26 @@ where
         Ok(SinkReply::default())
     }
 
+    async fn on_tick(&mut self, ctx: &SinkContext, serializer: &mut EventSerializer) -> Result<()> {
+        // flush out data postprocessors held back on any stream, e.g. batches
+        for (stream_id, data) in serializer.tick(nanotime())? {
+            if let Some(sender) = self.streams.get(&stream_id) {
+                let sink_data = SinkData {
+                    meta: None,
+                    data,
+                    contraflow: None,
+                    start: nanotime(),
+                };
+                if sender.send(sink_data).await.is_err() {
+                    error!("{ctx} Error sending to closed stream {stream_id}.");
+                    self.remove_stream(stream_id);
+                    serializer.drop_stream(stream_id);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn asynchronous(&self) -> bool {
         // events are delivered asynchronously on their stream tasks
         true
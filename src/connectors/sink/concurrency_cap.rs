@@ -17,6 +17,8 @@ use crate::connectors::sink::ContraflowData;
 use crate::errors::Result;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
 use tremor_pipeline::{CbAction, Event};
 
 /// Utility for limiting concurrency in a sink to a certain `cap` value
@@ -26,6 +28,7 @@ pub(crate) struct ConcurrencyCap {
     cap: usize,
     reply_tx: ReplySender,
     counter: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
 }
 
 impl ConcurrencyCap {
@@ -34,14 +37,38 @@ impl ConcurrencyCap {
             cap,
             reply_tx,
             counter: Arc::new(AtomicUsize::new(0)),
+            idle: Arc::new(Notify::new()),
         }
     }
 
-    #[cfg(test)]
-    fn get_counter(&self) -> usize {
+    /// Wait until all guards are dropped, i.e. there are no more in-flight tasks
+    ///
+    /// Returns `false` if there are still tasks in flight after `max_wait`.
+    pub(crate) async fn wait_for_idle(&self, max_wait: Duration) -> bool {
+        tokio::time::timeout(max_wait, async {
+            loop {
+                // register before checking, so we don't miss a notification in between
+                let idle = self.idle.notified();
+                if self.counter.load(Ordering::Acquire) == 0 {
+                    break;
+                }
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    /// the number of tasks currently in flight
+    pub(crate) fn get_counter(&self) -> usize {
         self.counter.load(Ordering::Acquire)
     }
 
+    /// the maximum number of tasks in flight
+    pub(crate) fn cap(&self) -> usize {
+        self.cap
+    }
+
     /// increment the counter and return a guard for safely counting down
     /// wrapped inside an enum to check whether we exceeded the maximum or not
     pub(crate) fn inc_for(&self, event: &Event) -> Result<CounterGuard> {
@@ -57,8 +84,23 @@ impl ConcurrencyCap {
         Ok(guard)
     }
 
+    /// increment the counter only if it is below the `cap`, never issuing a `CB::Close`
+    ///
+    /// Returns `None` if `cap` tasks are already in flight.
+    pub(crate) fn try_inc_for(&self, event: &Event) -> Option<CounterGuard> {
+        self.counter
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |num| {
+                (num < self.cap).then_some(num + 1)
+            })
+            .ok()
+            .map(|num| CounterGuard(num, self.clone(), ContraflowData::from(event)))
+    }
+
     fn dec_with(&self, cf_data: &ContraflowData) -> Result<()> {
         let num = self.counter.fetch_sub(1, Ordering::AcqRel);
+        if num == 1 {
+            self.idle.notify_waiters();
+        }
         if num == self.cap {
             // we crossed max - send an open
             self.reply_tx
@@ -117,4 +159,67 @@ mod tests {
         assert_eq!(0, cap.get_counter());
         Ok(())
     }
+
+    #[test]
+    fn concurrency_cap_of_one() -> Result<()> {
+        let (tx, mut rx) = unbounded();
+        let cap = ConcurrencyCap::new(1, tx);
+        let event = Event::default();
+        // a single in-flight request is within the cap
+        let guard1 = cap.inc_for(&event)?;
+        assert!(rx.try_recv().is_err());
+        let guard2 = cap.inc_for(&event)?;
+        let reply = rx.try_recv()?;
+        assert!(matches!(reply, AsyncSinkReply::CB(_, CbAction::Trigger)));
+        drop(guard2);
+        let reply = rx.try_recv()?;
+        assert!(matches!(reply, AsyncSinkReply::CB(_, CbAction::Restore)));
+        drop(guard1);
+        assert_eq!(0, cap.get_counter());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn wait_for_idle() -> Result<()> {
+        let (tx, _rx) = unbounded();
+        let cap = ConcurrencyCap::new(2, tx);
+        assert!(cap.wait_for_idle(Duration::from_millis(10)).await);
+
+        let event = Event::default();
+        let guard1 = cap.inc_for(&event)?;
+        let guard2 = cap.inc_for(&event)?;
+        assert!(!cap.wait_for_idle(Duration::from_millis(10)).await);
+
+        let waiter = cap.clone();
+        let idle = tokio::spawn(async move { waiter.wait_for_idle(Duration::from_secs(5)).await });
+        drop(guard1);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!idle.is_finished());
+        drop(guard2);
+        assert!(idle.await?);
+        Ok(())
+    }
+
+    #[test]
+    fn try_inc_for() -> Result<()> {
+        let (tx, mut rx) = unbounded();
+        let cap = ConcurrencyCap::new(2, tx);
+        let event = Event::default();
+        let guard1 = cap.try_inc_for(&event);
+        assert!(guard1.is_some());
+        let guard2 = cap.try_inc_for(&event);
+        assert!(guard2.is_some());
+        assert_eq!(2, cap.get_counter());
+        // at the cap, no guard is handed out and no CB Close is issued
+        assert!(cap.try_inc_for(&event).is_none());
+        assert_eq!(2, cap.get_counter());
+        assert!(rx.try_recv().is_err());
+
+        drop(guard2);
+        assert_eq!(1, cap.get_counter());
+        assert!(cap.try_inc_for(&event).is_some());
+        drop(guard1);
+        assert_eq!(0, cap.get_counter());
+        Ok(())
+    }
 }
@@ -110,7 +110,7 @@ pub(crate) enum SourceReply {
         /// Port to send to, defaults to `out`
         port: Option<Port<'static>>,
         /// Overwrite the codec being used for deserializing this data.
-        /// Should only be used when setting `stream` to `None`
+        /// For a `stream` only the overwrite given with its first data is used.
         codec_overwrite: Option<NameWithConfig>,
     },
     /// an already structured event payload
@@ -461,6 +461,7 @@ impl Streams {
     fn get_or_create_stream<C: Context>(
         &mut self,
         stream_id: u64,
+        codec_overwrite: Option<NameWithConfig>,
         ctx: &C,
     ) -> Result<&mut StreamState> {
         Ok(match self.states.entry(stream_id) {
@@ -471,7 +472,7 @@ impl Streams {
                     self.uid,
                     stream_id,
                     &self.codec_config,
-                    None,
+                    codec_overwrite,
                     &self.preprocessor_configs,
                 )?;
                 e.insert(state)
@@ -1048,7 +1049,7 @@ where
         origin_uri: EventOriginUri,
     ) -> Result<()> {
         let ingest_ns = nanotime();
-        let stream_state = self.streams.get_or_create_stream(stream, &self.ctx)?;
+        let stream_state = self.streams.get_or_create_stream(stream, None, &self.ctx)?;
         let event = build_event(
             stream_state,
             pull_id,
@@ -1080,7 +1081,9 @@ where
     ) -> Result<()> {
         let mut ingest_ns = nanotime();
         if let Some(stream) = stream {
-            let stream_state = self.streams.get_or_create_stream(stream, &self.ctx)?;
+            let stream_state =
+                self.streams
+                    .get_or_create_stream(stream, codec_overwrite, &self.ctx)?;
             let results = build_events(
                 &self.ctx.alias,
                 stream_state,
@@ -1258,8 +1261,12 @@ fn build_events(
         alias,
     ) {
         Ok(processed) => {
-            let mut res = Vec::with_capacity(processed.len());
-            for chunk in processed {
+            let chunks: Vec<Vec<u8>> = processed
+                .into_iter()
+                .flat_map(|chunk| stream_state.codec.split(chunk))
+                .collect();
+            let mut res = Vec::with_capacity(chunks.len());
+            for chunk in chunks {
                 let line_value = EventPayload::try_new::<Option<Error>, _>(chunk, |mut_data| {
                     match stream_state.codec.decode(mut_data, *ingest_ns) {
                         Ok(None) => Err(None),
@@ -1319,10 +1326,14 @@ fn build_last_events(
     meta: &Value<'static>,
     is_transactional: bool,
 ) -> Vec<(Port<'static>, Event)> {
-    match finish(stream_state.preprocessors.as_mut_slice(), alias) {
+    match finish(stream_state.preprocessors.as_mut_slice(), ingest_ns, alias) {
         Ok(processed) => {
-            let mut res = Vec::with_capacity(processed.len());
-            for chunk in processed {
+            let chunks: Vec<Vec<u8>> = processed
+                .into_iter()
+                .flat_map(|chunk| stream_state.codec.split(chunk))
+                .collect();
+            let mut res = Vec::with_capacity(chunks.len());
+            for chunk in chunks {
                 let line_value = EventPayload::try_new::<Option<Error>, _>(chunk, |mut_data| {
                     match stream_state.codec.decode(mut_data, *ingest_ns) {
                         Ok(None) => Err(None),
@@ -1017,7 +1017,8 @@ async fn auth_client_cert() -> Result<()> {
         username: "elastic".to_string(),
         password: "snot".to_string(),
     }
-    .as_header_value()?
+    .header_value()
+    .await?
     .expect("auth header");
 
     let connector_config = literal!({
@@ -13,28 +13,42 @@
 // limitations under the License.
 
 use crate::{
+    codec::{msgpack::MsgPack, Codec},
     connectors::{
         impls::http::{self as http_impl, meta::content_type},
         prelude::Url,
-        tests::{free_port::find_free_tcp_port, ConnectorHarness},
-        utils::url::HttpDefaults,
+        tests::{free_port::find_free_tcp_port, setup_for_tls, ConnectorHarness},
+        utils::{
+            tls::{self, Acceptor, TLSServerConfig},
+            url::HttpDefaults,
+        },
     },
     errors::Result,
 };
 use hyper::StatusCode;
 use hyper::{
     body::to_bytes,
-    server::conn::AddrStream,
+    server::conn::{AddrIncoming, AddrStream},
     service::{make_service_fn, service_fn},
     Body, Response,
 };
 use std::{
     convert::Infallible,
     net::{SocketAddr, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    net::{TcpSocket, TcpStream},
+    sync::broadcast::error::RecvError,
+    task::{spawn, JoinHandle},
+    time::{sleep, timeout},
 };
-use tokio::task::{spawn, JoinHandle};
 use tremor_common::ports::IN;
-use tremor_pipeline::Event;
+use tremor_pipeline::{CbAction, Event, EventId, METRICS_CHANNEL};
 use tremor_script::ValueAndMeta;
 use tremor_value::{literal, Value};
 use value_trait::{Mutable, ValueAccess};
@@ -128,6 +142,648 @@ impl TestHttpServer {
     }
 }
 
+/// Spawn a server that answers the first `failures` requests with `503 Service Unavailable`
+/// and echoes the request body with `200 OK` afterwards
+fn spawn_flaky_server(
+    target: &str,
+    failures: usize,
+    requests: Arc<AtomicUsize>,
+) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let requests = requests.clone();
+            let service = service_fn(move |req: hyper::Request<Body>| {
+                let requests = requests.clone();
+                async move {
+                    let data = to_bytes(req.into_body()).await?;
+                    let status = if requests.fetch_add(1, Ordering::AcqRel) < failures {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    } else {
+                        StatusCode::OK
+                    };
+                    Response::builder()
+                        .status(status)
+                        .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                        .body(Body::from(data))
+                        .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+                }
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server that answers the first request with `429 Too Many Requests` and the given `Retry-After` header
+/// and echoes the request body with `200 OK` afterwards
+fn spawn_retry_after_server(
+    target: &str,
+    retry_after: String,
+    requests: Arc<AtomicUsize>,
+) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let requests = requests.clone();
+            let retry_after = retry_after.clone();
+            let service = service_fn(move |req: hyper::Request<Body>| {
+                let requests = requests.clone();
+                let retry_after = retry_after.clone();
+                async move {
+                    let data = to_bytes(req.into_body()).await?;
+                    let response = if requests.fetch_add(1, Ordering::AcqRel) == 0 {
+                        Response::builder()
+                            .status(StatusCode::TOO_MANY_REQUESTS)
+                            .header(hyper::header::RETRY_AFTER, retry_after)
+                            .body(Body::empty())
+                    } else {
+                        Response::builder()
+                            .status(StatusCode::OK)
+                            .body(Body::from(data))
+                    };
+                    response
+                        .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+                }
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server that echoes the request body after sleeping for `delay`
+fn spawn_slow_server(
+    target: &str,
+    delay: Duration,
+    responses: Arc<AtomicUsize>,
+) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let responses = responses.clone();
+            let service = service_fn(move |req: hyper::Request<Body>| {
+                let responses = responses.clone();
+                async move {
+                    let data = to_bytes(req.into_body()).await?;
+                    sleep(delay).await;
+                    responses.fetch_add(1, Ordering::AcqRel);
+                    Response::builder()
+                        .status(StatusCode::OK)
+                        .body(Body::from(data))
+                        .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+                }
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server counting the requests it received, that never responds
+fn spawn_stalling_server(
+    target: &str,
+    requests: Arc<AtomicUsize>,
+) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let requests = requests.clone();
+            let service = service_fn(move |_req: hyper::Request<Body>| {
+                requests.fetch_add(1, Ordering::AcqRel);
+                async move {
+                    futures::future::pending::<()>().await;
+                    Ok::<_, hyper::Error>(Response::new(Body::empty()))
+                }
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server that responds with a chunked body of newline separated lines,
+/// split across chunks in the middle of a line
+fn spawn_streaming_server(target: &str) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(|_conn: &AddrStream| async {
+            Ok::<_, Infallible>(service_fn(|_req: hyper::Request<Body>| async {
+                let (mut tx, body) = Body::channel();
+                spawn(async move {
+                    for chunk in ["snot\nbad", "ger\n", "flork\nmork"] {
+                        sleep(Duration::from_millis(50)).await;
+                        tx.send_data(chunk.into()).await?;
+                    }
+                    Result::Ok(())
+                });
+                Ok::<_, Infallible>(Response::new(body))
+            }))
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server echoing the request body after `delay` and counting the connections it accepted,
+/// only speaking HTTP/2 with prior knowledge if `http2_only` is set
+fn spawn_counting_server(
+    target: &str,
+    delay: Duration,
+    http2_only: bool,
+    connections: Arc<AtomicUsize>,
+) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            connections.fetch_add(1, Ordering::AcqRel);
+            let service = service_fn(move |req: hyper::Request<Body>| async move {
+                let data = to_bytes(req.into_body()).await?;
+                sleep(delay).await;
+                Ok::<_, hyper::Error>(Response::new(Body::from(data)))
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr)
+            .http2_only(http2_only)
+            .serve(make_service)
+            .await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server only speaking HTTP/2 with prior knowledge, responding with a `grpc-status`
+/// trailer after the body, like gRPC does
+fn spawn_trailer_server(target: &str) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(|_conn: &AddrStream| async {
+            Ok::<_, Infallible>(service_fn(|_req: hyper::Request<Body>| async {
+                let (mut tx, body) = Body::channel();
+                spawn(async move {
+                    tx.send_data("snot".into()).await?;
+                    let mut trailers = hyper::HeaderMap::new();
+                    trailers.insert("grpc-status", hyper::header::HeaderValue::from_static("0"));
+                    tx.send_trailers(trailers).await?;
+                    Result::Ok(())
+                });
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .header("x-snot", "badger")
+                    .body(body)
+                    .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+            }))
+        });
+        hyper::Server::bind(&addr)
+            .http2_only(true)
+            .serve(make_service)
+            .await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server that redirects `/a` -> `/b` -> `/c` and `/loop` to itself,
+/// echoing the request body on any other path
+fn spawn_redirect_server(target: &str) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let service = service_fn(move |req: hyper::Request<Body>| async move {
+                let redirect = match req.uri().path() {
+                    "/a" => Some((StatusCode::FOUND, "/b")),
+                    "/b" => Some((StatusCode::TEMPORARY_REDIRECT, "/c")),
+                    "/loop" => Some((StatusCode::FOUND, "/loop")),
+                    _ => None,
+                };
+                let res = if let Some((status, location)) = redirect {
+                    Response::builder()
+                        .status(status)
+                        .header(hyper::header::LOCATION, location)
+                        .body(Body::empty())
+                } else {
+                    let data = to_bytes(req.into_body()).await?;
+                    Response::builder()
+                        .status(StatusCode::OK)
+                        .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                        .body(Body::from(data))
+                };
+                res.map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server that redirects every request to `location` with a `307 Temporary Redirect`
+fn spawn_redirect_to_server(target: &str, location: String) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let location = location.clone();
+            let service = service_fn(move |_req: hyper::Request<Body>| {
+                let location = location.clone();
+                async move {
+                    Response::builder()
+                        .status(StatusCode::TEMPORARY_REDIRECT)
+                        .header(hyper::header::LOCATION, location)
+                        .body(Body::empty())
+                }
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server that responds with the status given as request path, e.g. `/404`
+fn spawn_status_server(target: &str) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let service = service_fn(move |req: hyper::Request<Body>| async move {
+                let status =
+                    StatusCode::from_bytes(req.uri().path().trim_start_matches('/').as_bytes())
+                        .unwrap_or(StatusCode::OK);
+                Response::builder()
+                    .status(status)
+                    .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                    .body(Body::from(status.to_string()))
+                    .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a https server with a certificate signed by the private test CA `./tests/ca.cert`
+fn spawn_tls_server(target: &str) -> Result<JoinHandle<Result<()>>> {
+    setup_for_tls();
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    let config = TLSServerConfig {
+        cert: "./tests/localhost-ca.cert".into(),
+        key: "./tests/localhost-ca.key".into(),
+    }
+    .to_server_config()?;
+    let incoming = AddrIncoming::bind(&addr)?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(|_conn: &tls::Stream| async {
+            Ok::<_, Infallible>(service_fn(|_req: hyper::Request<Body>| async {
+                Ok::<_, Infallible>(Response::new(Body::from("secure")))
+            }))
+        });
+        hyper::Server::builder(Acceptor::new(Arc::new(config), incoming))
+            .serve(make_service)
+            .await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a fake forward proxy that responds with the request target and `Proxy-Authorization` header it received
+fn spawn_proxy_server(target: &str) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let service = service_fn(move |req: hyper::Request<Body>| async move {
+                let authorization = req
+                    .headers()
+                    .get(hyper::header::PROXY_AUTHORIZATION)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("none");
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                    .body(Body::from(format!("{} {authorization}", req.uri())))
+                    .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server on the unix socket at `path`, answering with the method, `Host` and url
+#[cfg(unix)]
+fn spawn_unix_socket_server(path: &std::path::Path) -> Result<JoinHandle<Result<()>>> {
+    let listener = tokio::net::UnixListener::bind(path)?;
+    Ok(spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let service = service_fn(|req: hyper::Request<Body>| async move {
+                let host = req
+                    .headers()
+                    .get(hyper::header::HOST)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("none")
+                    .to_string();
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                    .body(Body::from(format!("{} {host} {}", req.method(), req.uri())))
+                    .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+            });
+            spawn(hyper::server::conn::Http::new().serve_connection(stream, service));
+        }
+        Ok(())
+    }))
+}
+
+/// Spawn a server that responds with its `name` and the `Authorization` header it received
+fn spawn_named_server(target: &str, name: &'static str) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| async move {
+            Ok::<_, Infallible>(service_fn(move |req: hyper::Request<Body>| async move {
+                let authorization = req
+                    .headers()
+                    .get(hyper::header::AUTHORIZATION)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("none")
+                    .to_string();
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                    .body(Body::from(format!("{name} {authorization}")))
+                    .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+            }))
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+// Sets a session cookie on `/login`, answers other requests with the `Cookie` header they carry
+fn spawn_cookie_server(target: &str) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(|_conn: &AddrStream| async {
+            Ok::<_, Infallible>(service_fn(|req: hyper::Request<Body>| async move {
+                let response = Response::builder()
+                    .status(StatusCode::OK)
+                    .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string());
+                let response = if req.uri().path() == "/login" {
+                    response
+                        .header(hyper::header::SET_COOKIE, "session=snot; Path=/; HttpOnly")
+                        .header(hyper::header::SET_COOKIE, "admin=badger; Path=/admin")
+                        .body(Body::from("logged in"))
+                } else {
+                    let cookie = req
+                        .headers()
+                        .get(hyper::header::COOKIE)
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or("none")
+                        .to_string();
+                    response.body(Body::from(cookie))
+                };
+                response.map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+            }))
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+// Challenges with nonce `n1`, then rejects that nonce as stale and challenges with `n2`
+fn spawn_digest_server(target: &str, requests: Arc<AtomicUsize>) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let requests = requests.clone();
+            let service = service_fn(move |req: hyper::Request<Body>| {
+                let requests = requests.clone();
+                async move {
+                    requests.fetch_add(1, Ordering::AcqRel);
+                    let authorization = req
+                        .headers()
+                        .get(hyper::header::AUTHORIZATION)
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string();
+                    let challenge = if authorization.contains(r#"nonce="n2""#) {
+                        None
+                    } else if authorization.contains(r#"nonce="n1""#) {
+                        Some(r#"Digest realm="tremor", nonce="n2", qop="auth", stale=true"#)
+                    } else {
+                        Some(r#"Digest realm="tremor", nonce="n1", qop="auth""#)
+                    };
+                    let res = if let Some(challenge) = challenge {
+                        Response::builder()
+                            .status(StatusCode::UNAUTHORIZED)
+                            .header(hyper::header::WWW_AUTHENTICATE, challenge)
+                            .body(Body::empty())
+                    } else {
+                        let data = to_bytes(req.into_body()).await?;
+                        Response::builder()
+                            .status(StatusCode::OK)
+                            .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                            .body(Body::from(data))
+                    };
+                    res.map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+                }
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+// Decompresses gzipped request bodies and responds with the gzipped body,
+// fails requests that don't accept gzip
+fn spawn_gzip_server(target: &str) -> Result<JoinHandle<Result<()>>> {
+    use libflate::gzip::{Decoder, Encoder};
+    use std::io::{Read, Write};
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let service = service_fn(move |req: hyper::Request<Body>| async move {
+                let accepts_gzip = req
+                    .headers()
+                    .get(hyper::header::ACCEPT_ENCODING)
+                    .map_or(false, |v| v == "gzip");
+                let is_gzip = req
+                    .headers()
+                    .get(hyper::header::CONTENT_ENCODING)
+                    .map_or(false, |v| v == "gzip");
+                let data = to_bytes(req.into_body()).await?;
+                let mut body = Vec::new();
+                if is_gzip {
+                    Decoder::new(data.as_ref())?.read_to_end(&mut body)?;
+                } else {
+                    body.extend_from_slice(&data);
+                }
+                let res = if accepts_gzip {
+                    let mut encoder = Encoder::new(Vec::new())?;
+                    encoder.write_all(&body)?;
+                    Response::builder()
+                        .status(StatusCode::OK)
+                        .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                        .header(hyper::header::CONTENT_ENCODING, "gzip")
+                        .body(Body::from(encoder.finish().into_result()?))
+                } else {
+                    Response::builder()
+                        .status(StatusCode::NOT_ACCEPTABLE)
+                        .body(Body::empty())
+                };
+                res.map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server that answers with `snot badger`, encoded with the `Content-Encoding` named by
+/// the request path, e.g. `/br`, and sent as is with an unknown encoding for `/unknown`
+fn spawn_encoding_server(target: &str) -> Result<JoinHandle<Result<()>>> {
+    use std::io::Write;
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let service = service_fn(move |req: hyper::Request<Body>| async move {
+                let data = b"snot badger";
+                let (encoding, body) = match req.uri().path() {
+                    "/gzip" => {
+                        let mut encoder = libflate::gzip::Encoder::new(Vec::new())?;
+                        encoder.write_all(data)?;
+                        ("gzip", encoder.finish().into_result()?)
+                    }
+                    "/br" => {
+                        let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+                        encoder.write_all(data)?;
+                        ("br", encoder.into_inner())
+                    }
+                    _ => ("snot", data.to_vec()),
+                };
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                    .header(hyper::header::CONTENT_ENCODING, encoding)
+                    .body(Body::from(body))
+                    .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server that answers with `snot badger`, the given `Cache-Control` and an `ETag`,
+/// conditional requests for this `ETag` are answered with `304 Not Modified`
+fn spawn_caching_server(
+    target: &str,
+    cache_control: &'static str,
+    requests: Arc<AtomicUsize>,
+    not_modified: Arc<AtomicUsize>,
+) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let requests = requests.clone();
+            let not_modified = not_modified.clone();
+            let service = service_fn(move |req: hyper::Request<Body>| {
+                requests.fetch_add(1, Ordering::AcqRel);
+                let revalidated = req
+                    .headers()
+                    .get(hyper::header::IF_NONE_MATCH)
+                    .map_or(false, |etag| etag == "\"v1\"");
+                let response = Response::builder()
+                    .header(hyper::header::CACHE_CONTROL, cache_control)
+                    .header(hyper::header::ETAG, "\"v1\"");
+                let response = if revalidated {
+                    not_modified.fetch_add(1, Ordering::AcqRel);
+                    response
+                        .status(StatusCode::NOT_MODIFIED)
+                        .body(Body::empty())
+                } else {
+                    response
+                        .status(StatusCode::OK)
+                        .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                        .body(Body::from("snot badger"))
+                };
+                async move {
+                    response
+                        .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+                }
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn a server echoing the request body and its `Transfer-Encoding` via `x-transfer-encoding`,
+/// counting the requests it received
+fn spawn_upload_server(target: &str, requests: Arc<AtomicUsize>) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let requests = requests.clone();
+            let service = service_fn(move |req: hyper::Request<Body>| {
+                let requests = requests.clone();
+                async move {
+                    requests.fetch_add(1, Ordering::AcqRel);
+                    let transfer_encoding = req
+                        .headers()
+                        .get(hyper::header::TRANSFER_ENCODING)
+                        .cloned()
+                        .unwrap_or_else(|| hyper::header::HeaderValue::from_static("none"));
+                    let data = to_bytes(req.into_body()).await?;
+                    Response::builder()
+                        .status(StatusCode::OK)
+                        .header(hyper::header::CONTENT_TYPE, mime::TEXT_PLAIN.to_string())
+                        .header("x-transfer-encoding", transfer_encoding)
+                        .body(Body::from(data))
+                        .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+                }
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
+/// Spawn an API server answering `/ok` with JSON, `/error` with a `500` HTML error page and
+/// `/invalid` with an invalid content type
+fn spawn_api_server(target: &str) -> Result<JoinHandle<Result<()>>> {
+    let addr = target.to_socket_addrs()?.next().ok_or("no address")?;
+    Ok(spawn(async move {
+        let make_service = make_service_fn(move |_conn: &AddrStream| {
+            let service = service_fn(move |req: hyper::Request<Body>| async move {
+                let (status, content_type, body) = match req.uri().path() {
+                    "/ok" => (StatusCode::OK, "application/json", r#"{"snot":"badger"}"#),
+                    "/invalid" => (StatusCode::OK, "snot", "badger"),
+                    _ => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "text/html; charset=utf-8",
+                        "<h1>Internal Server Error</h1>",
+                    ),
+                };
+                Response::builder()
+                    .status(status)
+                    .header(hyper::header::CONTENT_TYPE, content_type)
+                    .body(Body::from(body))
+                    .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { Box::new(e) })
+            });
+            async move { Ok::<_, Infallible>(service) }
+        });
+        hyper::Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }))
+}
+
 // Convenience template for a round trip HTTP request/response interaction
 async fn rtt(
     scheme: &'static str,
@@ -153,6 +809,16 @@ async fn rtt(
     let defn = literal!({
       "config": config,
     });
+    rtt_with_defn(url, defn, event).await
+}
+
+// Round trip HTTP request/response interaction for a full connector definition
+async fn rtt_with_defn(
+    url: String,
+    defn: Value<'static>,
+    event: Event,
+) -> Result<ValueAndMeta<'static>> {
+    let _ = env_logger::try_init();
     let mut fake = TestHttpServer::new(url.clone()).await?;
     let mut harness = ConnectorHarness::new(
         function_name!(),
@@ -252,6 +918,19 @@ async fn http_client_request_with_defaults() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_response_duration() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let res = rtt("http", &target, "string", None, Event::default()).await?;
+    let duration_ns = res
+        .meta()
+        .get("http_client")
+        .get("response")
+        .get_u64("duration_ns");
+    assert!(duration_ns.map_or(false, |d| d > 0));
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn http_client_request_with_defaults_post() -> Result<()> {
     let target = find_free_tcp_endpoint_str().await?;
@@ -416,6 +1095,154 @@ async fn http_client_request_override_headers() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_delete_with_body() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let url = format!("http://{target}");
+    // configured methods are case insensitive
+    let defn = literal!({
+      "config": {
+        "url": url.clone(),
+        "method": "delete",
+        "headers": {"content-type": "application/json"},
+        "mime_mapping": {
+            "application/json": "json",
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!({"snot": ["badger", 42]}), literal!({})).into(),
+        ..Default::default()
+    };
+    let res = rtt_with_defn(url, defn, event).await?;
+    assert_with_request_meta!(res, meta, {
+        assert_eq!(Some("DELETE"), meta.get_str("method"));
+    });
+    // the body was sent and echoed back
+    assert_eq!(&literal!({"snot": ["badger", 42]}), res.value());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_method() -> Result<()> {
+    // extension methods, valid tokens for `hyper::Method`, are rejected as well
+    for method in ["SNOT", "", "GET POST"] {
+        let defn = literal!({
+          "config": {
+            "url": "http://127.0.0.1:65535",
+            "method": method
+          },
+        });
+        let res = ConnectorHarness::new(
+            function_name!(),
+            &http_impl::client::Builder::default(),
+            &defn,
+        )
+        .await;
+        assert!(res.is_err(), "{method} should be rejected");
+    }
+    Ok(())
+}
+
+// method, url, query and headers from the event metadata take precedence over the config
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_overrides_per_event() -> Result<()> {
+    let _ = env_logger::try_init();
+    let target = find_free_tcp_endpoint_str().await?;
+    let url = format!("http://{target}");
+    let mut fake = TestHttpServer::new(url.clone()).await?;
+    let defn = literal!({
+      "config": {
+        "url": format!("{url}/api/?flork=mork"),
+        "method": "POST",
+        "headers": {"x-snot": "config"},
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    let requests = [
+        (
+            literal!({
+                "method": "PUT",
+                "url": "snot",
+                "query": {"badger": "1"},
+                "headers": {"X-Snot": "event"}
+            }),
+            format!("{url}/api/snot?badger=1"),
+            "PUT",
+            "event",
+        ),
+        (
+            literal!({}),
+            format!("{url}/api/?flork=mork"),
+            "POST",
+            "config",
+        ),
+        (
+            literal!({"method": "delete", "query": {"flork": "badger"}}),
+            format!("{url}/api/?flork=badger"),
+            "DELETE",
+            "config",
+        ),
+    ];
+    for (request, uri, method, header) in requests {
+        let event = Event {
+            data: (
+                literal!("snot"),
+                literal!({"http_client": {"request": request}}),
+            )
+                .into(),
+            transactional: true,
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+        let res = harness.out()?.get_event().await?;
+        let meta = res.data.suffix().meta();
+        let request = meta.get("http_client").get("request");
+        assert_eq!(Some(uri.as_str()), request.get_str("uri"));
+        assert_eq!(Some(method), request.get_str("method"));
+        assert_eq!(
+            Some(&literal!([header])),
+            request.get("headers").get("x-snot")
+        );
+    }
+
+    // unknown methods are rejected
+    let event = Event {
+        data: (
+            literal!("snot"),
+            literal!({"http_client": {"request": {"method": "SNOT"}}}),
+        )
+            .into(),
+        transactional: true,
+        ..Default::default()
+    };
+    harness.send_to_sink(event, IN).await?;
+    let in_pipe = harness.get_pipe(IN)?;
+    loop {
+        let cf = in_pipe.get_contraflow().await?;
+        if cf.cb == CbAction::Fail {
+            break;
+        }
+    }
+
+    fake.stop();
+    let (_out, _err) = harness.stop().await?;
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn http_client_request_override_content_type() -> Result<()> {
     let target = find_free_tcp_endpoint_str().await?;
@@ -474,6 +1301,85 @@ async fn http_client_request_override_content_type() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_codec_per_content_type() -> Result<()> {
+    let _ = env_logger::try_init();
+    let target = find_free_tcp_endpoint_str().await?;
+    // echoes the raw request body without a content-type, so it is decoded with the `binary` codec
+    let server = spawn_slow_server(&target, Duration::ZERO, Arc::default())?;
+    let defn = literal!({
+      "codec": "binary",
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "mime_mapping": {
+            "application/x-snot": "msgpack"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    let value = literal!({"snot": "badger"});
+    let msgpack = MsgPack {}.encode(&value)?;
+    let cases = [
+        (
+            "application/json",
+            value.clone(),
+            b"{\"snot\":\"badger\"}".to_vec(),
+        ),
+        ("application/msgpack", value.clone(), msgpack.clone()),
+        ("application/x-snot", value, msgpack),
+        // unknown content types are encoded with the connector codec
+        (
+            "text/x-badger",
+            Value::Bytes(b"flork".to_vec().into()),
+            b"flork".to_vec(),
+        ),
+    ];
+    for (content_type, data, expected) in cases {
+        let event = Event {
+            data: (
+                data,
+                literal!({
+                    "http_client": {
+                        "request": {
+                            "headers": {"content-type": content_type}
+                        }
+                    }
+                }),
+            )
+                .into(),
+            transactional: true,
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+        let res = harness.out()?.get_event().await?;
+        assert_eq!(&Value::Bytes(expected.into()), res.data.suffix().value());
+        assert_eq!(
+            Some(&literal!([content_type])),
+            res.data
+                .suffix()
+                .meta()
+                .get("http_client")
+                .get("request")
+                .get("headers")
+                .get("content-type")
+        );
+    }
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn http_client_request_auth_none() -> Result<()> {
     let target = find_free_tcp_endpoint_str().await?;
@@ -667,6 +1573,2676 @@ async fn chunked() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_postprocessors() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let url = format!("http://{target}");
+    let defn = literal!({
+      "postprocessors": ["base64"],
+      "config": {
+        "url": url.clone(),
+        "method": "POST",
+        "headers": {"content-type": "text/plain"},
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (
+            literal!("snot badger"),
+            literal!({
+                "correlation": "http_client_request_postprocessors"
+            }),
+        )
+            .into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let res = rtt_with_defn(url, defn, event).await?;
+    // the fake server echoes the body as it was received on the wire
+    assert_eq!(&Value::from("c25vdCBiYWRnZXI="), res.value());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_raw_body() -> Result<()> {
+    // raw bodies given as bytes and as base64 encoded string
+    let raw_bodies = [
+        Value::Bytes(b"flork mork".to_vec().into()),
+        Value::from("ZmxvcmsgbW9yaw=="),
+    ];
+    for raw_body in raw_bodies {
+        let target = find_free_tcp_endpoint_str().await?;
+        let url = format!("http://{target}");
+        let defn = literal!({
+          "postprocessors": ["base64"],
+          "config": {
+            "url": url.clone(),
+            "method": "POST",
+            "headers": {"content-type": "text/plain"},
+            "mime_mapping": {
+                "*/*": "string"
+            }
+          },
+        });
+        let event = Event {
+            data: (
+                literal!("snot badger"),
+                literal!({
+                    "http_client": {
+                        "request": {
+                            "raw_body": raw_body
+                        }
+                    },
+                    "correlation": "http_client_request_raw_body"
+                }),
+            )
+                .into(),
+            transactional: true,
+            ..Default::default()
+        };
+        let res = rtt_with_defn(url, defn, event).await?;
+        // neither the codec nor the base64 postprocessor touched the body
+        assert_eq!(&Value::from("flork mork"), res.value());
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_multipart() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let url = format!("http://{target}");
+    let defn = literal!({
+      "config": {
+        "url": url.clone(),
+        "method": "POST",
+        "headers": {"content-type": "application/json"},
+        "multipart": true,
+        "mime_mapping": {
+            "*/*": "json",
+            "multipart/form-data": "binary"
+        }
+      },
+    });
+    let binary = vec![0_u8, 159, 146, 150, 255];
+    let event = Event {
+        data: (
+            literal!([
+                {"name": "snot", "data": "badger"},
+                {
+                    "name": "file",
+                    "filename": "snot.bin",
+                    "data": Value::Bytes(binary.clone().into())
+                }
+            ]),
+            literal!({}),
+        )
+            .into(),
+        ..Default::default()
+    };
+    let res = rtt_with_defn(url, defn, event).await?;
+    // the echo server responds with the content type of the request
+    let content_type = res
+        .meta()
+        .get("http_client")
+        .get("response")
+        .get("headers")
+        .get("content-type")
+        .get_idx(0)
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let boundary = content_type
+        .strip_prefix("multipart/form-data; boundary=")
+        .unwrap_or_default();
+    assert!(!boundary.is_empty(), "{content_type}");
+    let mut expected = format!(
+        "--{boundary}\r\n\
+        Content-Disposition: form-data; name=\"snot\"\r\n\r\n\
+        badger\r\n\
+        --{boundary}\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"snot.bin\"\r\n\
+        Content-Type: application/octet-stream\r\n\r\n"
+    )
+    .into_bytes();
+    expected.extend_from_slice(&binary);
+    expected.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    assert_eq!(Some(expected.as_slice()), res.value().as_bytes());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_stream_requests() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = spawn_upload_server(&target, requests.clone())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "stream_requests": true,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    let chunks = ["snot", "badger", "flork"];
+    for (i, chunk) in (0_u64..).zip(chunks) {
+        // the last chunk ends the stream
+        let meta = if chunk == "flork" {
+            literal!({"http_client": {"request": {"end_stream": true}}})
+        } else {
+            literal!({})
+        };
+        let event = Event {
+            id: EventId::from_id(1, 42, i),
+            data: (Value::from(chunk), meta).into(),
+            transactional: true,
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+    }
+    let res = harness.out()?.get_event().await?;
+    let (value, meta) = res.data.parts();
+    assert_eq!(Some("snotbadgerflork"), value.as_str());
+    assert_eq!(
+        Some("chunked"),
+        meta.get("http_client")
+            .get("response")
+            .get("headers")
+            .get("x-transfer-encoding")
+            .get_idx(0)
+            .as_str()
+    );
+    // all events are acked together with the single request
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+    for i in 0..3 {
+        assert!(cf.id.is_tracking(&EventId::from_id(1, 42, i)));
+    }
+    assert_eq!(1, requests.load(Ordering::Acquire));
+
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+// a single concurrent request must still be served, see the former off-by-one in the client pool
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_concurrency_one() -> Result<()> {
+    let _ = env_logger::try_init();
+    let target = find_free_tcp_endpoint_str().await?;
+    let url = format!("http://{target}");
+    let mut fake = TestHttpServer::new(url.clone()).await?;
+    let defn = literal!({
+      "config": {
+        "url": url,
+        "method": "POST",
+        "concurrency": 1,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    for data in ["snot", "badger"] {
+        let event = Event {
+            data: (literal!(data), literal!({})).into(),
+            transactional: true,
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+        let res = harness.out()?.get_event().await?;
+        assert_eq!(&Value::from(data), res.data.suffix().value());
+        let in_pipe = harness.get_pipe(IN)?;
+        assert_eq!(CbAction::Ack, in_pipe.get_contraflow().await?.cb);
+    }
+    fake.stop();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_pre_and_postprocessors() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let url = format!("http://{target}");
+    let defn = literal!({
+      "preprocessors": ["base64"],
+      "postprocessors": ["base64"],
+      "config": {
+        "url": url.clone(),
+        "method": "POST",
+        "headers": {"content-type": "text/plain"},
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (
+            literal!("snot badger"),
+            literal!({
+                "correlation": "http_client_request_pre_and_postprocessors"
+            }),
+        )
+            .into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let res = rtt_with_defn(url, defn, event).await?;
+    // encoded on the way out, decoded again on the way in
+    assert_eq!(&Value::from("snot badger"), res.value());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_retry() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = spawn_flaky_server(&target, 2, requests.clone())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "headers": {"content-type": "text/plain"},
+        "mime_mapping": {
+            "*/*": "string"
+        },
+        "retry": {
+            "max_retries": 3,
+            "initial_backoff_ms": 10,
+            "max_backoff_ms": 50,
+            "retry_on": [503]
+        }
+      },
+    });
+    let event = Event {
+        data: (
+            literal!("snot badger"),
+            literal!({
+                "correlation": "http_client_request_retry"
+            }),
+        )
+            .into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.out()?.get_event().await?;
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+
+    assert_eq!(3, requests.load(Ordering::Acquire));
+    assert_eq!(&Value::from("snot badger"), res.data.suffix().value());
+    assert_eq!(
+        Some(200),
+        res.data
+            .suffix()
+            .meta()
+            .get("http_client")
+            .get("response")
+            .get_u16("status")
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_retry_exhausted() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = spawn_flaky_server(&target, 2, requests.clone())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "headers": {"content-type": "text/plain"},
+        "mime_mapping": {
+            "*/*": "string"
+        },
+        "retry": {
+            "max_retries": 1,
+            "initial_backoff_ms": 10,
+            "retry_on": [503]
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!("snot badger"), literal!({})).into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.out()?.get_event().await?;
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+
+    // the last response is emitted once the retries are exhausted
+    assert_eq!(2, requests.load(Ordering::Acquire));
+    assert_eq!(
+        Some(503),
+        res.data
+            .suffix()
+            .meta()
+            .get("http_client")
+            .get("response")
+            .get_u16("status")
+    );
+    Ok(())
+}
+
+/// Send a request with `method` to a server that never responds, returning how many requests it received
+async fn timed_out_requests(id: &str, method: &str, non_idempotent: bool) -> Result<usize> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = spawn_stalling_server(&target, requests.clone())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": method,
+        "timeout": 100_000_000, // 100ms
+        "mime_mapping": {
+            "*/*": "string"
+        },
+        "retry": {
+            "max_retries": 2,
+            "initial_backoff_ms": 10,
+            "max_backoff_ms": 10,
+            "non_idempotent": non_idempotent
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!("snot badger"), literal!({})).into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let mut harness =
+        ConnectorHarness::new(id, &http_impl::client::Builder::default(), &defn).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.err()?.get_event().await?;
+    assert_eq!(
+        Some(504),
+        res.data
+            .suffix()
+            .meta()
+            .get("http_client")
+            .get("response")
+            .get_u16("status")
+    );
+    let in_pipe = harness.get_pipe(IN)?;
+    assert_eq!(CbAction::Fail, in_pipe.get_contraflow().await?.cb);
+    server.abort();
+    let (out, _err) = harness.stop().await?;
+    assert!(out.is_empty());
+    Ok(requests.load(Ordering::Acquire))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_no_retry_post_after_timeout() -> Result<()> {
+    // the server might have processed the request already
+    assert_eq!(
+        1,
+        timed_out_requests(function_name!(), "POST", false).await?
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_retry_put_after_timeout() -> Result<()> {
+    assert_eq!(3, timed_out_requests(function_name!(), "PUT", false).await?);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_retry_non_idempotent_after_timeout() -> Result<()> {
+    assert_eq!(3, timed_out_requests(function_name!(), "POST", true).await?);
+    Ok(())
+}
+
+/// Send a request to a server answering it with `429 Too Many Requests` and `retry_after` first,
+/// returning how long it took to receive the successful response
+async fn retry_after_rtt(
+    id: &str,
+    retry_after: String,
+    max_retry_after_ms: u64,
+) -> Result<Duration> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = spawn_retry_after_server(&target, retry_after, requests.clone())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "mime_mapping": {
+            "*/*": "string"
+        },
+        "retry": {
+            "max_retries": 1,
+            "initial_backoff_ms": 10,
+            "max_backoff_ms": 10,
+            "max_retry_after_ms": max_retry_after_ms
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!("snot badger"), literal!({})).into(),
+        ..Default::default()
+    };
+    let mut harness =
+        ConnectorHarness::new(id, &http_impl::client::Builder::default(), &defn).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    let start = Instant::now();
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.out()?.get_event().await?;
+    let elapsed = start.elapsed();
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+
+    assert_eq!(2, requests.load(Ordering::Acquire));
+    assert_eq!(&Value::from("snot badger"), res.data.suffix().value());
+    Ok(elapsed)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_retry_after_seconds() -> Result<()> {
+    let elapsed = retry_after_rtt(function_name!(), "2".to_string(), 60_000).await?;
+    // the backoff of 10ms is overridden
+    assert!(elapsed >= Duration::from_secs(2));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_retry_after_date() -> Result<()> {
+    let retry_after = (chrono::Utc::now() + chrono::Duration::seconds(3))
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string();
+    let elapsed = retry_after_rtt(function_name!(), retry_after, 60_000).await?;
+    // the date has a resolution of seconds
+    assert!(elapsed >= Duration::from_secs(1));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_retry_after_capped() -> Result<()> {
+    let elapsed = retry_after_rtt(function_name!(), "3600".to_string(), 100).await?;
+    assert!(elapsed >= Duration::from_millis(100));
+    assert!(elapsed < Duration::from_secs(3600));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_retry_config() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "retry": {
+            "initial_backoff_ms": 1000,
+            "max_backoff_ms": 10
+        }
+      },
+    });
+    let res = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await
+    .err()
+    .map(|e| e.to_string())
+    .unwrap_or_default();
+    assert!(res.contains("initial_backoff_ms"));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_circuit_breaker() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = spawn_flaky_server(&target, 2, requests.clone())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "headers": {"content-type": "text/plain"},
+        "mime_mapping": {
+            "*/*": "string"
+        },
+        "circuit_breaker": {
+            "max_consecutive_failures": 2,
+            "cooldown_ms": 500
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    let event = || Event {
+        data: (literal!("snot badger"), literal!({})).into(),
+        ..Default::default()
+    };
+    let status = |event: &Event| {
+        event
+            .data
+            .suffix()
+            .meta()
+            .get("http_client")
+            .get("response")
+            .get_u16("status")
+    };
+
+    // two failing responses trip the breaker
+    for _ in 0..2 {
+        harness.send_to_sink(event(), IN).await?;
+        let res = harness.out()?.get_event().await?;
+        assert_eq!(Some(503), status(&res));
+    }
+    // requests are short-circuited while it is open
+    harness.send_to_sink(event(), IN).await?;
+    let res = harness.err()?.get_event().await?;
+    assert_eq!(Some(503), status(&res));
+    assert_eq!(
+        Some("circuit_breaker"),
+        res.data.suffix().value().get_str("kind")
+    );
+    assert_eq!(2, requests.load(Ordering::Acquire));
+
+    // after the cooldown a probe is let through, closing the breaker again as it succeeds
+    sleep(Duration::from_millis(600)).await;
+    for _ in 0..2 {
+        harness.send_to_sink(event(), IN).await?;
+        let res = harness.out()?.get_event().await?;
+        assert_eq!(Some(200), status(&res));
+    }
+    assert_eq!(4, requests.load(Ordering::Acquire));
+
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_circuit_breaker() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "circuit_breaker": {
+            "error_rate": 1.5
+        }
+      },
+    });
+    let res = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await
+    .err()
+    .map(|e| e.to_string())
+    .unwrap_or_default();
+    assert!(res.contains("error_rate"));
+    Ok(())
+}
+
+/// Send two `GET` requests to a server answering with the given `Cache-Control`,
+/// returning the number of requests it received and answered with `304 Not Modified`
+async fn cached_rtt(
+    id: &str,
+    cache_control: &'static str,
+    cache_max_bytes: u64,
+) -> Result<(usize, usize)> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let requests = Arc::new(AtomicUsize::new(0));
+    let not_modified = Arc::new(AtomicUsize::new(0));
+    let server = spawn_caching_server(
+        &target,
+        cache_control,
+        requests.clone(),
+        not_modified.clone(),
+    )?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "GET",
+        "mime_mapping": {
+            "*/*": "string"
+        },
+        "cache_max_bytes": cache_max_bytes
+      },
+    });
+    let mut harness =
+        ConnectorHarness::new(id, &http_impl::client::Builder::default(), &defn).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    for _ in 0..2 {
+        let event = Event {
+            data: (literal!("snot"), literal!({})).into(),
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+        let res = harness.out()?.get_event().await?;
+        // cached and revalidated responses look like the original one
+        assert_eq!(&Value::from("snot badger"), res.data.suffix().value());
+        assert_eq!(
+            Some(200),
+            res.data
+                .suffix()
+                .meta()
+                .get("http_client")
+                .get("response")
+                .get_u16("status")
+        );
+    }
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok((
+        requests.load(Ordering::Acquire),
+        not_modified.load(Ordering::Acquire),
+    ))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_cache_fresh_hit() -> Result<()> {
+    let (requests, not_modified) = cached_rtt(function_name!(), "max-age=60", 4096).await?;
+    assert_eq!(1, requests);
+    assert_eq!(0, not_modified);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_cache_revalidation() -> Result<()> {
+    let (requests, not_modified) = cached_rtt(function_name!(), "no-cache", 4096).await?;
+    assert_eq!(2, requests);
+    assert_eq!(1, not_modified);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_cache_too_small() -> Result<()> {
+    // the response doesn't fit into the cache
+    let (requests, not_modified) = cached_rtt(function_name!(), "max-age=60", 10).await?;
+    assert_eq!(2, requests);
+    assert_eq!(0, not_modified);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_timeout() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_slow_server(
+        &target,
+        Duration::from_secs(5),
+        Arc::new(AtomicUsize::new(0)),
+    )?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "concurrency": 1,
+        "timeout": 100_000_000, // 100ms
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (
+            literal!("snot badger"),
+            literal!({
+                "correlation": "http_client_request_timeout"
+            }),
+        )
+            .into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.err()?.get_event().await?;
+    let meta = res.data.suffix().meta();
+    assert_eq!(
+        Some(504),
+        meta.get("http_client").get("response").get_u16("status")
+    );
+    assert_eq!(
+        Some("http_client_request_timeout"),
+        meta.get_str("correlation")
+    );
+
+    // the event is failed and the concurrency slot is released again
+    let in_pipe = harness.get_pipe(IN)?;
+    assert_eq!(CbAction::Fail, in_pipe.get_contraflow().await?.cb);
+    assert_eq!(CbAction::Restore, in_pipe.get_contraflow().await?.cb);
+
+    server.abort();
+    let (out, _err) = harness.stop().await?;
+    assert!(out.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_echo_request() -> Result<()> {
+    let body = Value::Bytes(b"snot badger".to_vec().into());
+    for echo_request in [None, Some("headers"), Some("full"), Some("none")] {
+        let target = find_free_tcp_endpoint_str().await?;
+        let url = format!("http://{target}");
+        let mut config = literal!({
+            "url": url.clone(),
+            "method": "POST",
+            "headers": {"content-type": "text/plain"},
+            "mime_mapping": {
+                "*/*": "string"
+            }
+        });
+        if let Some(echo_request) = echo_request {
+            config.try_insert("echo_request", echo_request);
+        }
+        let defn = literal!({ "config": config });
+        let event = Event {
+            data: (
+                literal!("snot badger"),
+                literal!({
+                    "correlation": "http_client_echo_request"
+                }),
+            )
+                .into(),
+            transactional: true,
+            ..Default::default()
+        };
+        let res = rtt_with_defn(url, defn, event).await?;
+        let meta = res.meta().get("http_client");
+        assert!(meta.get_u64("request_id").is_some(), "{echo_request:?}");
+        assert_eq!(Some(200), meta.get("response").get_u16("status"));
+        let request = meta.get("request");
+        match echo_request {
+            Some("none") => assert_eq!(None, request),
+            Some("full") => {
+                assert_eq!(Some("POST"), request.get_str("method"));
+                assert!(request.get("headers").is_some());
+                assert_eq!(Some(&body), request.get("raw_body"));
+            }
+            _ => {
+                // the default
+                assert_eq!(Some("POST"), request.get_str("method"));
+                assert!(request.get("headers").is_some());
+                assert_eq!(None, request.get("raw_body"));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_echo_request_on_error() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_slow_server(
+        &target,
+        Duration::from_secs(5),
+        Arc::new(AtomicUsize::new(0)),
+    )?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "timeout": 100_000_000, // 100ms
+        "echo_request": "full",
+        "headers": {"content-type": "text/plain"},
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (
+            literal!("snot badger"),
+            literal!({
+                "correlation": "http_client_echo_request_on_error"
+            }),
+        )
+            .into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.err()?.get_event().await?;
+    let meta = res.data.suffix().meta().get("http_client");
+    assert_eq!(Some(504), meta.get("response").get_u16("status"));
+    // the body was sent completely before the request timed out
+    let request = meta.get("request");
+    assert_eq!(Some("POST"), request.get_str("method"));
+    assert_eq!(
+        Some(&Value::Bytes(b"snot badger".to_vec().into())),
+        request.get("raw_body")
+    );
+    let in_pipe = harness.get_pipe(IN)?;
+    assert_eq!(CbAction::Fail, in_pipe.get_contraflow().await?.cb);
+
+    server.abort();
+    let (out, _err) = harness.stop().await?;
+    assert!(out.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_split_errors() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_status_server(&target)?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "split_errors": true,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    let event = |path: &str| Event {
+        data: (
+            literal!(null),
+            literal!({"http_client": {"request": {"url": path.to_string()}}}),
+        )
+            .into(),
+        ..Default::default()
+    };
+    harness.send_to_sink(event("/404"), IN).await?;
+    let res = harness.err()?.get_event().await?;
+    let meta = res.data.suffix().meta();
+    assert_eq!(
+        Some(404),
+        meta.get("http_client").get("response").get_u16("status")
+    );
+    harness.send_to_sink(event("/503"), IN).await?;
+    let res = harness.err()?.get_event().await?;
+    let meta = res.data.suffix().meta();
+    assert_eq!(
+        Some(503),
+        meta.get("http_client").get("response").get_u16("status")
+    );
+    // redirects are not followed by default and are no errors
+    for status in ["/200", "/302"] {
+        harness.send_to_sink(event(status), IN).await?;
+        let res = harness.out()?.get_event().await?;
+        let meta = res.data.suffix().meta();
+        assert_eq!(
+            status.trim_start_matches('/').parse::<u16>().ok(),
+            meta.get("http_client").get("response").get_u16("status")
+        );
+    }
+
+    server.abort();
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty());
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_response_codec_by_content_type() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_api_server(&target)?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}/ok"),
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    let expected = [
+        ("/ok", 200, literal!({"snot": "badger"})),
+        ("/error", 500, literal!("<h1>Internal Server Error</h1>")),
+        // the `*/*` codec is used for responses without a valid content type
+        ("/invalid", 200, literal!("badger")),
+    ];
+    for (path, status, body) in expected {
+        let event = Event {
+            data: (
+                Value::null(),
+                literal!({"http_client": {"request": {"url": path}}}),
+            )
+                .into(),
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+        let res = harness.out()?.get_event().await?;
+        let (value, meta) = res.data.parts();
+        assert_eq!(&body, value);
+        assert_eq!(
+            Some(status),
+            meta.get("http_client").get("response").get_u16("status")
+        );
+    }
+
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_no_split_errors() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_status_server(&target)?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}/404"),
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(Event::default(), IN).await?;
+    let res = harness.out()?.get_event().await?;
+    let meta = res.data.suffix().meta();
+    assert_eq!(
+        Some(404),
+        meta.get("http_client").get("response").get_u16("status")
+    );
+
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+async fn saturate(id: &str, overflow: &str) -> Result<(ConnectorHarness, JoinHandle<Result<()>>)> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_slow_server(
+        &target,
+        Duration::from_secs(1),
+        Arc::new(AtomicUsize::new(0)),
+    )?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "concurrency": 1,
+        "overflow": overflow,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness =
+        ConnectorHarness::new(id, &http_impl::client::Builder::default(), &defn).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    for data in ["snot", "badger"] {
+        let event = Event {
+            data: (literal!(data), literal!({ "correlation": data })).into(),
+            transactional: true,
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+    }
+    Ok((harness, server))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_overflow_block() -> Result<()> {
+    let (mut harness, server) = saturate(function_name!(), "block").await?;
+    // both requests are sent, the second one closes the circuit breaker
+    let in_pipe = harness.get_pipe(IN)?;
+    assert_eq!(CbAction::Trigger, in_pipe.get_contraflow().await?.cb);
+    for _ in 0..2 {
+        harness.out()?.get_event().await?;
+    }
+    server.abort();
+    harness.stop().await?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_overflow_drop() -> Result<()> {
+    let (mut harness, server) = saturate(function_name!(), "drop").await?;
+    // the second event is dropped with a synthetic `429` and acked
+    let res = harness.err()?.get_event().await?;
+    let meta = res.data.suffix().meta();
+    assert_eq!(
+        Some(429),
+        meta.get("http_client").get("response").get_u16("status")
+    );
+    assert_eq!(Some("badger"), meta.get_str("correlation"));
+    let in_pipe = harness.get_pipe(IN)?;
+    assert_eq!(CbAction::Ack, in_pipe.get_contraflow().await?.cb);
+    // the first one is sent
+    let res = harness.out()?.get_event().await?;
+    assert_eq!(&Value::from("snot"), res.data.suffix().value());
+    assert_eq!(CbAction::Ack, in_pipe.get_contraflow().await?.cb);
+    server.abort();
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty());
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_overflow_fail() -> Result<()> {
+    let (mut harness, server) = saturate(function_name!(), "fail").await?;
+    // the second event is failed right away, without closing the circuit breaker
+    let in_pipe = harness.get_pipe(IN)?;
+    assert_eq!(CbAction::Fail, in_pipe.get_contraflow().await?.cb);
+    let res = harness.out()?.get_event().await?;
+    assert_eq!(&Value::from("snot"), res.data.suffix().value());
+    assert_eq!(CbAction::Ack, in_pipe.get_contraflow().await?.cb);
+    server.abort();
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty());
+    assert!(err.is_empty());
+    Ok(())
+}
+
+/// Send `events` events to a `http_client` with the given `rate_limit`, returning the harness and server
+async fn rate_limited(
+    id: &str,
+    rate_limit: Value<'static>,
+    events: u64,
+) -> Result<(ConnectorHarness, JoinHandle<Result<()>>)> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_counting_server(&target, Duration::ZERO, false, Arc::default())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "concurrency": 16,
+        "rate_limit": rate_limit,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness =
+        ConnectorHarness::new(id, &http_impl::client::Builder::default(), &defn).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    for i in 0..events {
+        let event = Event {
+            id: EventId::from_id(0, 0, i),
+            data: (literal!(format!("snot{i}")), literal!({})).into(),
+            transactional: true,
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+    }
+    Ok((harness, server))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_rate_limit_delay() -> Result<()> {
+    let start = Instant::now();
+    let (mut harness, server) = rate_limited(
+        function_name!(),
+        literal!({"max_per_sec": 20, "burst": 2}),
+        8,
+    )
+    .await?;
+    for _ in 0..8 {
+        harness.out()?.get_event().await?;
+    }
+    // the burst is sent right away, the other 6 requests one every 50ms
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(290), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+    let in_pipe = harness.get_pipe(IN)?;
+    for _ in 0..8 {
+        assert_eq!(CbAction::Ack, in_pipe.get_contraflow().await?.cb);
+    }
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_rate_limit_drop() -> Result<()> {
+    let (mut harness, server) = rate_limited(
+        function_name!(),
+        literal!({"max_per_sec": 0.5, "burst": 2, "on_limit": "drop"}),
+        5,
+    )
+    .await?;
+    // only the burst is sent, the other requests are dropped with a synthetic `429`
+    for _ in 0..3 {
+        let res = harness.err()?.get_event().await?;
+        let (value, meta) = res.data.suffix().parts();
+        assert_eq!(
+            Some(429),
+            meta.get("http_client").get("response").get_u16("status")
+        );
+        assert!(value
+            .get_str("error")
+            .unwrap_or_default()
+            .starts_with("Rate limit of 0.5 requests per second"));
+    }
+    let mut sent = Vec::new();
+    for _ in 0..2 {
+        let res = harness.out()?.get_event().await?;
+        sent.push(
+            res.data
+                .suffix()
+                .value()
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        );
+    }
+    sent.sort();
+    assert_eq!(vec!["snot0", "snot1"], sent);
+    // dropped events are acked as well
+    let in_pipe = harness.get_pipe(IN)?;
+    for _ in 0..5 {
+        assert_eq!(CbAction::Ack, in_pipe.get_contraflow().await?.cb);
+    }
+    server.abort();
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty());
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_rate_limit() -> Result<()> {
+    let invalid = [
+        literal!({}),
+        literal!({"max_per_sec": 0}),
+        literal!({"max_per_sec": 10, "burst": 0}),
+        literal!({"max_per_sec": 10, "on_limit": "fail"}),
+    ];
+    for rate_limit in invalid {
+        let defn = literal!({
+          "config": {
+            "url": "http://localhost:8080",
+            "rate_limit": rate_limit.clone()
+          }
+        });
+        let res = ConnectorHarness::new(
+            function_name!(),
+            &http_impl::client::Builder::default(),
+            &defn,
+        )
+        .await;
+        assert!(res.is_err(), "{rate_limit} should be rejected");
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_private_ca() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_tls_server(&target)?;
+    let defn = literal!({
+      "config": {
+        "url": format!("https://{target}"),
+        "tls": {
+            "cafile": "./tests/ca.cert"
+        },
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(Event::default(), IN).await?;
+    let res = harness.out()?.get_event().await?;
+    assert_eq!(&Value::from("secure"), res.data.suffix().value());
+    assert_eq!(
+        Some(200),
+        res.data
+            .suffix()
+            .meta()
+            .get("http_client")
+            .get("response")
+            .get_u16("status")
+    );
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_tls_server_name() -> Result<()> {
+    let port = find_free_tcp_port().await?;
+    let server = spawn_tls_server(&format!("127.0.0.1:{port}"))?;
+    // connect by IP, verifying the certificate issued for `localhost`
+    let defn = literal!({
+      "config": {
+        "url": format!("https://127.0.0.1:{port}"),
+        "tls": {
+            "cafile": "./tests/ca.cert",
+            "server_name": "localhost"
+        },
+        "host_header": format!("localhost:{port}"),
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(Event::default(), IN).await?;
+    let res = harness.out()?.get_event().await?;
+    assert_eq!(&Value::from("secure"), res.data.suffix().value());
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_server_name() -> Result<()> {
+    let url = "https://127.0.0.1:65535";
+    let invalid = [
+        literal!({"config": {"url": url, "tls": {"server_name": "snot badger"}}}),
+        literal!({"config": {"url": url, "host_header": ""}}),
+        literal!({"config": {"url": url, "host_header": "snot\nbadger"}}),
+    ];
+    for defn in invalid {
+        let res = ConnectorHarness::new(
+            function_name!(),
+            &http_impl::client::Builder::default(),
+            &defn,
+        )
+        .await;
+        assert!(res.is_err(), "{defn} should be rejected");
+    }
+    Ok(())
+}
+
+/// Send a request that fails to be sent and return the error event and its metadata
+async fn failed_request(
+    id: &str,
+    defn: &Value<'static>,
+) -> Result<(Value<'static>, Value<'static>)> {
+    let event = Event {
+        data: (Value::null(), literal!({ "correlation": id })).into(),
+        transactional: true,
+        ..Event::default()
+    };
+    let mut harness =
+        ConnectorHarness::new(id, &http_impl::client::Builder::default(), defn).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.err()?.get_event().await?;
+    let in_pipe = harness.get_pipe(IN)?;
+    assert_eq!(CbAction::Fail, in_pipe.get_contraflow().await?.cb);
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty());
+    assert!(err.is_empty());
+    let (value, meta) = res.data.suffix().parts();
+    Ok((value.clone_static(), meta.clone_static()))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_untrusted_ca() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_tls_server(&target)?;
+    // the private CA is not part of the system root certificates
+    let defn = literal!({
+      "config": {
+        "url": format!("https://{target}"),
+        "tls": true
+      },
+    });
+    let (value, _meta) = failed_request(function_name!(), &defn).await?;
+    assert_eq!(Some("tls"), value.get_str("kind"));
+    server.abort();
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_connection_refused() -> Result<()> {
+    // nobody is listening here
+    let target = find_free_tcp_endpoint_str().await?;
+    let url = format!("http://{target}/snot");
+    let defn = literal!({
+      "config": {
+        "url": url.clone(),
+      },
+    });
+    let (value, meta) = failed_request(function_name!(), &defn).await?;
+    assert_eq!(Some("connect"), value.get_str("kind"));
+    assert!(value.get_str("error").is_some());
+    assert_eq!(
+        Some(url.as_str()),
+        meta.get("http_client").get("request").get_str("uri")
+    );
+    assert_eq!(
+        Some("http_client_request_connection_refused"),
+        meta.get_str("correlation")
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_unresolvable_host() -> Result<()> {
+    let defn = literal!({
+      "config": {
+        "url": "http://snot.badger.invalid/",
+      },
+    });
+    let (value, meta) = failed_request(function_name!(), &defn).await?;
+    assert_eq!(Some("dns"), value.get_str("kind"));
+    assert_eq!(
+        Some("http://snot.badger.invalid/"),
+        meta.get("http_client").get("request").get_str("uri")
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_pool_connect_timeout() -> Result<()> {
+    // a listener that never accepts, once its backlog is full further connection attempts hang
+    let socket = TcpSocket::new_v4()?;
+    socket.bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+    let target = socket.local_addr()?;
+    let _listener = socket.listen(0)?;
+    let mut backlog = Vec::new();
+    for _ in 0..4 {
+        if let Ok(Ok(stream)) =
+            tokio::time::timeout(Duration::from_millis(100), TcpStream::connect(target)).await
+        {
+            backlog.push(stream);
+        }
+    }
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}/snot"),
+        "timeout": 60_000_000_000_u64,
+        "pool": {
+            "max_idle_per_host": 1,
+            "idle_timeout_ms": 1000,
+            "connect_timeout_ms": 100
+        }
+      },
+    });
+    let start = Instant::now();
+    let (value, _meta) = failed_request(function_name!(), &defn).await?;
+    // the connect timeout hit long before the request timeout
+    assert!(start.elapsed() < Duration::from_secs(30));
+    assert_eq!(Some("connect"), value.get_str("kind"));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_pool_config() -> Result<()> {
+    let invalid = [
+        literal!({"connect_timeout_ms": 0}),
+        literal!({"idle_timeout_ms": 0}),
+        literal!({"max_idle_per_host": -1}),
+        literal!({"snot": "badger"}),
+    ];
+    for pool in invalid {
+        let defn = literal!({
+          "config": {
+            "url": "http://localhost:8080",
+            "pool": pool.clone()
+          }
+        });
+        let res = ConnectorHarness::new(
+            function_name!(),
+            &http_impl::client::Builder::default(),
+            &defn,
+        )
+        .await;
+        assert!(res.is_err(), "{pool} should be rejected");
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_cafile() -> Result<()> {
+    let defn = literal!({
+      "config": {
+        "url": "https://localhost:65535",
+        "tls": {
+            "cafile": "./tests/does-not-exist.cert"
+        }
+      },
+    });
+    let res = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await;
+    assert!(res.is_err());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_stream_response() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_streaming_server(&target)?;
+    let defn = literal!({
+      "preprocessors": ["separate"],
+      "config": {
+        "url": format!("http://{target}"),
+        "stream_response": true,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(Event::default(), IN).await?;
+    let mut stream_ids = Vec::new();
+    // the last line is only emitted when the stream ends
+    for expected in ["snot", "badger", "flork", "mork"] {
+        let event = harness.out()?.get_event().await?;
+        assert_eq!(&Value::from(expected), event.data.suffix().value());
+        assert_eq!(
+            Some(200),
+            event
+                .data
+                .suffix()
+                .meta()
+                .get("http_client")
+                .get("response")
+                .get_u16("status")
+        );
+        stream_ids.push(event.id.stream_id());
+    }
+    stream_ids.dedup();
+    assert_eq!(1, stream_ids.len());
+    server.abort();
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty());
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_stop_drains_in_flight_requests() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let responses = Arc::new(AtomicUsize::new(0));
+    let server = spawn_slow_server(&target, Duration::from_millis(500), responses.clone())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "drain_timeout": 5_000_000_000_u64, // 5s
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    for data in ["snot", "badger"] {
+        let event = Event {
+            data: (literal!(data), literal!({})).into(),
+            transactional: true,
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+    }
+    // the requests are in flight
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(0, responses.load(Ordering::Acquire));
+
+    harness.stop().await?;
+    // stopping waited for both responses
+    assert_eq!(2, responses.load(Ordering::Acquire));
+    server.abort();
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_follow_redirects() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_redirect_server(&target)?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}/a"),
+        "method": "PUT",
+        "headers": {"content-type": "text/plain"},
+        "max_redirects": 2,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!("snot badger"), literal!({})).into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.out()?.get_event().await?;
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+
+    // the body is replayed on every hop of the redirect chain
+    assert_eq!(&Value::from("snot badger"), res.data.suffix().value());
+    let response = res.data.suffix().meta().get("http_client").get("response");
+    assert_eq!(Some(200), response.get_u16("status"));
+    let final_url = format!("http://{target}/c");
+    assert_eq!(Some(final_url.as_str()), response.get_str("url"));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_redirect_loop() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_redirect_server(&target)?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}/loop"),
+        "method": "GET",
+        "max_redirects": 3,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!(""), literal!({})).into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.err()?.get_event().await?;
+    assert_eq!(
+        Some("Exceeded the maximum of 3 HTTP redirects"),
+        res.data.suffix().value().get_str("error")
+    );
+    let in_pipe = harness.get_pipe(IN)?;
+    assert_eq!(CbAction::Fail, in_pipe.get_contraflow().await?.cb);
+
+    server.abort();
+    let (out, _err) = harness.stop().await?;
+    assert!(out.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_digest_auth() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = spawn_digest_server(&target, requests.clone())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}/snot"),
+        "method": "POST",
+        "headers": {"content-type": "text/plain"},
+        "auth": {"digest": {"username": "snot", "password": "badger"}},
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!("snot badger"), literal!({})).into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.out()?.get_event().await?;
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+
+    // initial request, answer to the first challenge and answer to the stale nonce
+    assert_eq!(3, requests.load(Ordering::Acquire));
+    assert_eq!(&Value::from("snot badger"), res.data.suffix().value());
+    let response = res.data.suffix().meta().get("http_client").get("response");
+    assert_eq!(Some(200), response.get_u16("status"));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_no_auth_after_cross_origin_redirect() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let other = find_free_tcp_endpoint_str().await?;
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = spawn_redirect_to_server(&target, format!("http://{other}/snot"))?;
+    let other_server = spawn_digest_server(&other, requests.clone())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}/snot"),
+        "method": "POST",
+        "headers": {"content-type": "text/plain"},
+        "auth": {"digest": {"username": "snot", "password": "badger"}},
+        "max_redirects": 1,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!("snot badger"), literal!({})).into(),
+        transactional: true,
+        ..Default::default()
+    };
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.out()?.get_event().await?;
+    server.abort();
+    other_server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+
+    // the challenge of the other origin is not answered
+    assert_eq!(1, requests.load(Ordering::Acquire));
+    let response = res.data.suffix().meta().get("http_client").get("response");
+    assert_eq!(Some(401), response.get_u16("status"));
+    let final_url = format!("http://{other}/snot");
+    assert_eq!(Some(final_url.as_str()), response.get_str("url"));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_gzip_compression() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_gzip_server(&target)?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}/"),
+        "method": "POST",
+        "compression": "gzip",
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    // an empty body is sent uncompressed
+    for data in ["snot badger", ""] {
+        let event = Event {
+            data: (literal!(data), literal!({})).into(),
+            transactional: true,
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+        let res = harness.out()?.get_event().await?;
+        assert_eq!(&Value::from(data), res.data.suffix().value());
+        let response = res.data.suffix().meta().get("http_client").get("response");
+        assert_eq!(Some(200), response.get_u16("status"));
+        // the emitted body is no longer gzipped
+        assert!(response.get("headers").get("content-encoding").is_none());
+    }
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_response_content_encoding() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_encoding_server(&target)?;
+    // responses are decoded even without request compression
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}/gzip"),
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    for path in ["/gzip", "/br"] {
+        let event = Event {
+            data: (
+                Value::null(),
+                literal!({"http_client": {"request": {"url": path}}}),
+            )
+                .into(),
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+        let res = harness.out()?.get_event().await?;
+        assert_eq!(&Value::from("snot badger"), res.data.suffix().value());
+        let response = res.data.suffix().meta().get("http_client").get("response");
+        assert!(response.get("headers").get("content-encoding").is_none());
+    }
+
+    // an unknown encoding is an error, not a garbled event
+    let event = Event {
+        data: (
+            Value::null(),
+            literal!({"http_client": {"request": {"url": "/unknown"}}}),
+        )
+            .into(),
+        transactional: true,
+        ..Default::default()
+    };
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.err()?.get_event().await?;
+    assert_eq!(
+        Some("Unsupported Content-Encoding snot"),
+        res.data.suffix().value().get_str("error")
+    );
+    let response = res.data.suffix().meta().get("http_client").get("response");
+    assert_eq!(Some(200), response.get_u16("status"));
+    let in_pipe = harness.get_pipe(IN)?;
+    assert_eq!(CbAction::Fail, in_pipe.get_contraflow().await?.cb);
+
+    server.abort();
+    let (out, _err) = harness.stop().await?;
+    assert!(out.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_via_proxy() -> Result<()> {
+    let proxy_target = find_free_tcp_endpoint_str().await?;
+    let proxy = spawn_proxy_server(&proxy_target)?;
+    let defn = literal!({
+      "config": {
+        "url": "http://snot.invalid/badger",
+        "proxy": {
+            "url": format!("http://{proxy_target}"),
+            "username": "snot",
+            "password": "badger"
+        },
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!(null), literal!({})).into(),
+        ..Default::default()
+    };
+    // the target server is never reached
+    let target = find_free_tcp_endpoint_str().await?;
+    let res = rtt_with_defn(format!("http://{target}"), defn, event).await;
+    proxy.abort();
+    // the proxy got the absolute url and the credentials
+    assert_eq!(
+        &Value::from("http://snot.invalid/badger Basic c25vdDpiYWRnZXI="),
+        res?.value()
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_no_proxy() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let proxy_target = find_free_tcp_endpoint_str().await?;
+    let proxy = spawn_proxy_server(&proxy_target)?;
+    let url = format!("http://{target}");
+    let defn = literal!({
+      "config": {
+        "url": url.clone(),
+        "method": "POST",
+        "proxy": {
+            "url": format!("http://{proxy_target}"),
+            "no_proxy": ["example.com", "localhost", "127.0.0.1"]
+        },
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!("snot"), literal!({})).into(),
+        ..Default::default()
+    };
+    let res = rtt_with_defn(url, defn, event).await;
+    proxy.abort();
+    // the request went directly to the echo server
+    assert_eq!(&Value::from("snot"), res?.value());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_http2() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let connections = Arc::new(AtomicUsize::new(0));
+    let server = spawn_counting_server(
+        &target,
+        Duration::from_millis(100),
+        true,
+        connections.clone(),
+    )?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "concurrency": 4,
+        "http_version": "2",
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    for i in 0..4 {
+        let event = Event {
+            data: (Value::from(format!("snot{i}")), literal!({})).into(),
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+    }
+    let mut bodies = Vec::new();
+    for _ in 0..4 {
+        let event = harness.out()?.get_event().await?;
+        let (value, meta) = event.data.parts();
+        assert_eq!(
+            Some("HTTP/2.0"),
+            meta.get("http_client").get("response").get_str("version")
+        );
+        bodies.push(value.as_str().unwrap_or_default().to_string());
+    }
+    bodies.sort();
+    assert_eq!(vec!["snot0", "snot1", "snot2", "snot3"], bodies);
+    // all concurrent requests were multiplexed over a single connection
+    assert_eq!(1, connections.load(Ordering::Acquire));
+
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_response_trailers() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_trailer_server(&target)?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "http_version": "2",
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    let event = Event {
+        data: (Value::from("snot"), literal!({})).into(),
+        ..Default::default()
+    };
+    harness.send_to_sink(event, IN).await?;
+    let event = harness.out()?.get_event().await?;
+    let (value, meta) = event.data.parts();
+    assert_eq!(&Value::from("snot"), value);
+    let response = meta.get("http_client").get("response");
+    // trailers are kept apart from the headers
+    assert_eq!(
+        Some(&literal!({"grpc-status": ["0"]})),
+        response.get("trailers")
+    );
+    assert_eq!(
+        Some(&literal!(["badger"])),
+        response.get("headers").get("x-snot")
+    );
+    assert_eq!(None, response.get("headers").get("grpc-status"));
+
+    server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_http11_to_http2_server() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_counting_server(&target, Duration::ZERO, true, Arc::default())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "POST",
+        "http_version": "1.1",
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    let event = Event {
+        data: (literal!("snot"), literal!({})).into(),
+        ..Default::default()
+    };
+    harness.send_to_sink(event, IN).await?;
+    // the server doesn't understand HTTP/1.1
+    let res = harness.err()?.get_event().await?;
+    assert!(res.data.suffix().value().get_str("error").is_some());
+
+    server.abort();
+    let (out, _err) = harness.stop().await?;
+    assert!(out.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_max_connections_per_host() -> Result<()> {
+    let slow_target = find_free_tcp_endpoint_str().await?;
+    let fast_target = find_free_tcp_endpoint_str().await?;
+    let slow_server = spawn_counting_server(
+        &slow_target,
+        Duration::from_millis(300),
+        false,
+        Arc::default(),
+    )?;
+    let fast_server = spawn_counting_server(&fast_target, Duration::ZERO, false, Arc::default())?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{slow_target}"),
+        "method": "POST",
+        "concurrency": 4,
+        "max_connections_per_host": 1,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+    let start = Instant::now();
+    for body in ["slow1", "slow2"] {
+        let event = Event {
+            data: (Value::from(body), literal!({})).into(),
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+    }
+    let event = Event {
+        data: (
+            literal!("fast"),
+            literal!({
+                "http_client": {
+                    "request": {
+                        "url": format!("http://{fast_target}")
+                    }
+                }
+            }),
+        )
+            .into(),
+        ..Default::default()
+    };
+    harness.send_to_sink(event, IN).await?;
+
+    let mut bodies = Vec::new();
+    for _ in 0..3 {
+        let event = harness.out()?.get_event().await?;
+        bodies.push(
+            event
+                .data
+                .suffix()
+                .value()
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        );
+    }
+    // the fast host isn't held up by the slow one being at its limit
+    assert_eq!(Some("fast"), bodies.first().map(String::as_str));
+    bodies.sort();
+    assert_eq!(vec!["fast", "slow1", "slow2"], bodies);
+    // the requests to the slow host were sent one after the other
+    assert!(start.elapsed() >= Duration::from_millis(600));
+
+    slow_server.abort();
+    fast_server.abort();
+    let (_out, err) = harness.stop().await?;
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_max_connections_per_host() -> Result<()> {
+    let defn = literal!({
+      "config": {
+        "url": "http://localhost:8080",
+        "max_connections_per_host": 0
+      }
+    });
+    let res = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await;
+    assert!(res.is_err());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_http_version() -> Result<()> {
+    let defn = literal!({
+      "config": {
+        "url": "http://localhost:8080",
+        "http_version": "3"
+      }
+    });
+    let res = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await;
+    assert!(res.is_err());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_proxy_config() -> Result<()> {
+    let defn = literal!({
+      "config": {
+        "url": "http://localhost:8080",
+        "proxy": {
+            "url": "https://localhost:3128"
+        }
+      }
+    });
+    let res = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await;
+    assert!(res.is_err());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_request_unix_socket() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let socket = dir.path().join("http.sock");
+    let server = spawn_unix_socket_server(&socket)?;
+    let defn = literal!({
+      "config": {
+        "url": "http://snot.invalid/badger",
+        "method": "GET",
+        "unix_socket": socket.display().to_string(),
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!(null), literal!({})).into(),
+        ..Default::default()
+    };
+    // the host of the url is never resolved nor reached
+    let target = find_free_tcp_endpoint_str().await?;
+    let res = rtt_with_defn(format!("http://{target}"), defn, event).await;
+    server.abort();
+    assert_eq!(&Value::from("GET snot.invalid /badger"), res?.value());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_unix_socket() -> Result<()> {
+    let invalid = [
+        literal!({"url": "http://localhost:8080", "unix_socket": ""}),
+        literal!({
+            "url": "http://localhost:8080",
+            "unix_socket": "/tmp/http.sock",
+            "proxy": {"url": "http://localhost:3128"}
+        }),
+    ];
+    for config in invalid {
+        let defn = literal!({ "config": config });
+        let res = ConnectorHarness::new(
+            function_name!(),
+            &http_impl::client::Builder::default(),
+            &defn,
+        )
+        .await;
+        assert!(res.is_err());
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_profiles() -> Result<()> {
+    let default_target = find_free_tcp_endpoint_str().await?;
+    let snot_target = find_free_tcp_endpoint_str().await?;
+    let badger_target = find_free_tcp_endpoint_str().await?;
+    let secure_target = find_free_tcp_endpoint_str().await?;
+    let servers = [
+        spawn_named_server(&default_target, "default")?,
+        spawn_named_server(&snot_target, "snot")?,
+        spawn_named_server(&badger_target, "badger")?,
+        spawn_tls_server(&secure_target)?,
+    ];
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{default_target}"),
+        "method": "GET",
+        "profiles": {
+            "snot": {
+                "url": format!("http://{snot_target}"),
+                "auth": {"basic": {"username": "snot", "password": "badger"}}
+            },
+            "badger": {
+                "url": format!("http://{badger_target}/"),
+                "auth": {"bearer": "flork"}
+            },
+            "secure": {
+                "url": format!("https://{secure_target}"),
+                "tls": {"cafile": "./tests/ca.cert"}
+            }
+        },
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    let expected = [
+        (None, "default none"),
+        (Some("snot"), "snot Basic c25vdDpiYWRnZXI="),
+        (Some("badger"), "badger Bearer flork"),
+        (Some("secure"), "secure"),
+    ];
+    for (profile, response) in expected {
+        let mut meta = literal!({ "correlation": response });
+        if let Some(profile) = profile {
+            meta.try_insert("http_client", literal!({ "profile": profile }));
+        }
+        let event = Event {
+            data: (Value::null(), meta).into(),
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+        let res = harness.out()?.get_event().await?;
+        assert_eq!(&Value::from(response), res.data.suffix().value());
+        assert_eq!(
+            Some(response),
+            res.data.suffix().meta().get_str("correlation")
+        );
+    }
+
+    // events selecting an unknown profile are failed with an error event
+    let event = Event {
+        id: EventId::from_id(0, 0, 1),
+        data: (
+            Value::null(),
+            literal!({
+                "http_client": {"profile": "flork"},
+                "correlation": "flork"
+            }),
+        )
+            .into(),
+        transactional: true,
+        ..Default::default()
+    };
+    harness.send_to_sink(event, IN).await?;
+    let res = harness.err()?.get_event().await?;
+    assert_eq!(Some("profile"), res.data.suffix().value().get_str("kind"));
+    let meta = res.data.suffix().meta();
+    assert_eq!(Some("flork"), meta.get_str("correlation"));
+    assert_eq!(Some("flork"), meta.get("http_client").get_str("profile"));
+    let in_pipe = harness.get_pipe(IN)?;
+    assert_eq!(CbAction::Fail, in_pipe.get_contraflow().await?.cb);
+
+    for server in servers {
+        server.abort();
+    }
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty());
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_profile() -> Result<()> {
+    let invalid = [
+        literal!({"snot": {}}),
+        literal!({"snot": {"url": "http://localhost:8080", "method": "GET"}}),
+        literal!({"snot": {"url": "http://localhost:8080", "tls": {"server_name": "snot badger"}}}),
+    ];
+    for profiles in invalid {
+        let defn = literal!({
+          "config": {
+            "url": "http://localhost:8080",
+            "profiles": profiles.clone()
+          }
+        });
+        let res = ConnectorHarness::new(
+            function_name!(),
+            &http_impl::client::Builder::default(),
+            &defn,
+        )
+        .await;
+        assert!(res.is_err(), "{profiles} should be rejected");
+    }
+    // the error points at the profile
+    let defn = literal!({
+      "config": {
+        "url": "http://localhost:8080",
+        "profiles": {"snot": {"url": "https://localhost:8443"}}
+      }
+    });
+    let res = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await
+    .err()
+    .map(|e| e.to_string())
+    .unwrap_or_default();
+    assert!(res.contains("Set 'profiles.snot.tls' to 'true'"), "{res}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_cookies() -> Result<()> {
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_cookie_server(&target)?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}/"),
+        "method": "GET",
+        "cookies": true,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      }
+    });
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    let expected = [
+        ("/", "none"),
+        ("/login", "logged in"),
+        ("/", "session=snot"),
+        ("/admin/users", "admin=badger; session=snot"),
+    ];
+    for (path, response) in expected {
+        let meta = literal!({
+            "http_client": {
+                "request": {
+                    "url": format!("http://{target}{path}")
+                }
+            }
+        });
+        let event = Event {
+            data: (Value::null(), meta).into(),
+            ..Default::default()
+        };
+        harness.send_to_sink(event, IN).await?;
+        let res = harness.out()?.get_event().await?;
+        assert_eq!(&Value::from(response), res.data.suffix().value(), "{path}");
+    }
+
+    server.abort();
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty());
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_metrics() -> Result<()> {
+    let defn = literal!({
+      "config": {
+        "url": "http://localhost:8080",
+        "metrics_interval_ms": 0
+      }
+    });
+    let res = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await;
+    assert!(res.is_err());
+
+    let target = find_free_tcp_endpoint_str().await?;
+    let server = spawn_named_server(&target, "snot")?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://{target}"),
+        "method": "GET",
+        "metrics_interval_ms": 100,
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      }
+    });
+    let mut metrics_rx = METRICS_CHANNEL.rx();
+    let mut harness = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    for _ in 0..3 {
+        harness.send_to_sink(Event::default(), IN).await?;
+        let res = harness.out()?.get_event().await?;
+        assert_eq!(&Value::from("snot none"), res.data.suffix().value());
+    }
+
+    // other connectors report to the same channel, wait for a report covering all requests of ours
+    let fields = timeout(Duration::from_secs(10), async {
+        loop {
+            let metric = match metrics_rx.recv().await {
+                Ok(msg) => msg.payload,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            };
+            let metric = metric.suffix().value();
+            if metric.get_str("measurement") == Some("http_client_stats")
+                && metric.get("tags").get_str("connector") == Some("test::http_client_metrics")
+                && metric.get("fields").get_u64("requests") == Some(3)
+            {
+                return metric.get("fields").map(Value::clone_static);
+            }
+        }
+    })
+    .await?
+    .ok_or("metrics channel closed")?;
+    assert_eq!(Some(1), fields.get_u64("clients"));
+    assert_eq!(Some(0), fields.get_u64("failures"));
+    assert_eq!(Some(0), fields.get_u64("in_flight"));
+    assert_eq!(Some(4), fields.get_u64("concurrency"));
+    for field in ["error_rate", "requests_per_sec"] {
+        assert!(
+            fields.get_f64(field).is_some(),
+            "missing {field} in {fields}"
+        );
+    }
+    for field in ["latency_p50", "latency_p99"] {
+        assert!(
+            fields.get_u64(field).is_some(),
+            "missing {field} in {fields}"
+        );
+    }
+
+    server.abort();
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty());
+    assert!(err.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_dns_hosts() -> Result<()> {
+    let port = find_free_tcp_port().await?;
+    let defn = literal!({
+      "config": {
+        "url": format!("http://snot.invalid:{port}/badger"),
+        "method": "POST",
+        "dns": {
+            "cache_ttl_ms": 1000,
+            "hosts": {
+                "snot.invalid": "127.0.0.1"
+            }
+        },
+        "mime_mapping": {
+            "*/*": "string"
+        }
+      },
+    });
+    let event = Event {
+        data: (literal!("snot"), literal!({})).into(),
+        ..Default::default()
+    };
+    // the unresolvable host is sent to the echo server
+    let res = rtt_with_defn(format!("http://127.0.0.1:{port}"), defn, event).await?;
+    assert_eq!(&Value::from("snot"), res.value());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_client_invalid_dns_config() -> Result<()> {
+    let defn = literal!({
+      "config": {
+        "url": "http://localhost:8080",
+        "dns": {
+            "hosts": {
+                "snot": "badger"
+            }
+        }
+      }
+    });
+    let res = ConnectorHarness::new(
+        function_name!(),
+        &http_impl::client::Builder::default(),
+        &defn,
+    )
+    .await;
+    assert!(res.is_err());
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn missing_tls_config_https() -> Result<()> {
     let target = find_free_tcp_endpoint_str().await?;
@@ -387,6 +387,7 @@ async fn https_server_test() -> Result<()> {
         domain: Some("localhost".to_string()),
         cert: None,
         key: None,
+        insecure_skip_verify: false,
     }
     .to_client_config()?;
     let transport = HttpsConnectorBuilder::new()
@@ -147,3 +147,54 @@ async fn tcp_client_test(use_tls: bool) -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tcp_client_batch_timeout() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let free_port = free_port::find_free_tcp_port().await?;
+    let server_addr = format!("localhost:{free_port}");
+    let mut echo_server = EchoServer::new(server_addr.clone(), false);
+    echo_server.run().await?;
+
+    let config = literal!({
+        "codec": "json",
+        "preprocessors": ["separate"],
+        "postprocessors": [
+            {
+                "name": "batch",
+                "config": {
+                    "max_events": 10,
+                    "timeout_ms": 100,
+                    "format": "lines"
+                }
+            },
+            "separate"
+        ],
+        "config": {
+            "url": server_addr
+        }
+    });
+    let mut connector =
+        ConnectorHarness::new(function_name!(), &tcp::client::Builder::default(), &config).await?;
+    connector.start().await?;
+    connector.wait_for_connected().await?;
+    connector.consume_initial_sink_contraflow().await?;
+
+    let event = Event {
+        id: EventId::from_id(1, 1, 1),
+        data: (Value::from("snot badger"), Value::object()).into(),
+        ..Event::default()
+    };
+    connector.send_to_sink(event, IN).await?;
+
+    // the batch is held back until its timeout elapsed and the sink is ticked
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    connector.signal_tick_to_sink().await?;
+    let response = connector.out()?.get_event().await?;
+    assert_eq!(Some("snot badger"), response.data.suffix().value().as_str());
+
+    echo_server.stop().await?;
+    connector.stop().await?;
+    Ok(())
+}
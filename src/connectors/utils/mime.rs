@@ -16,8 +16,10 @@ use halfbrown::HashMap;
 
 use crate::config::NameWithConfig;
 
-const MIME_TYPES: [(&str, &str); 10] = [
+const MIME_TYPES: [(&str, &str); 13] = [
     ("application/json", "json"),
+    ("application/x-ndjson", "json-lines"),
+    ("application/jsonl", "json-lines"),
     ("application/yaml", "yaml"),
     ("text/csv", "csv"),
     ("text/plain", "string"),
@@ -26,17 +28,20 @@ const MIME_TYPES: [(&str, &str); 10] = [
     ("application/msgpack", "msgpack"),
     ("application/x-msgpack", "msgpack"),
     ("application/vnd.msgpack", "msgpack"),
+    ("application/cbor", "cbor"),
     ("application/octet-stream", "binary"),
 ];
 
 /// additional mapping from codec to mime-types
-const CODEC_TO_MIME_TYPES: [(&str, &str); 11] = [
+const CODEC_TO_MIME_TYPES: [(&str, &str); 13] = [
     ("json", "application/json"),
+    ("json-lines", "application/x-ndjson"),
     ("csv", "text/csv"),
     ("string", "text/plain"),
     ("msgpack", "application/msgpack"),
     ("yaml", "application/yaml"),
     ("binary", "application/octet-stream"),
+    ("cbor", "application/cbor"),
     ("syslog", "text/plain"),
     ("influx", "text/plain"),
     ("binflux", "application/octet-stream"),
@@ -52,7 +57,8 @@ pub(crate) struct MimeCodecMap {
 }
 
 impl MimeCodecMap {
-    pub(crate) fn new() -> Self {
+    /// constructs this map from the builtin mime-type and codec mappings
+    pub(crate) fn with_builtin() -> Self {
         let by_mime = MIME_TYPES
             .into_iter()
             .map(|(k, v)| (k.to_string(), v.into()))
@@ -84,6 +90,33 @@ impl MimeCodecMap {
         Self { by_mime, by_codec }
     }
 
+    /// constructs this map from the connector config
+    ///
+    /// The user provided `mime_mapping` is merged onto the builtin mapping, user entries win on conflict.
+    /// With `replace` set, the builtin mime-type mapping is replaced by `mime_mapping` completely.
+    pub(crate) fn from_config(
+        mime_mapping: Option<HashMap<String, NameWithConfig>>,
+        replace: bool,
+    ) -> Self {
+        match mime_mapping {
+            Some(custom) if replace => Self::from_custom(custom),
+            Some(custom) => Self::with_builtin().merge(custom),
+            None => Self::with_builtin(),
+        }
+    }
+
+    /// merges `custom` onto this map, overriding existing mappings of the same mime-type
+    #[must_use]
+    pub(crate) fn merge(mut self, custom: HashMap<String, NameWithConfig>) -> Self {
+        for (mime, codec_name) in custom {
+            if mime != "*/*" {
+                self.by_codec.insert(codec_name.name.clone(), mime.clone());
+            }
+            self.by_mime.insert(mime, codec_name);
+        }
+        self
+    }
+
     /// get codec name from given Content-Type essence (e.g. "application/json")
     /// if the specific codec isn't found provide the default code3c from `*/*`
     pub fn get_codec_name(&self, content_type: &str) -> Option<&NameWithConfig> {
@@ -102,7 +135,7 @@ impl MimeCodecMap {
 
 impl Default for MimeCodecMap {
     fn default() -> Self {
-        Self::new()
+        Self::with_builtin()
     }
 }
 
@@ -115,4 +148,51 @@ mod tests {
         let csv = Some("text/csv".to_string());
         assert_eq!(csv.as_ref(), map.get_mime_type("csv"));
     }
+
+    #[test]
+    fn merge_with_builtin() {
+        let mut custom = HashMap::new();
+        custom.insert("application/x-snot".to_string(), "json".into());
+        custom.insert("text/plain".to_string(), "binary".into());
+        let map = MimeCodecMap::from_config(Some(custom), false);
+        assert_eq!(
+            Some("json"),
+            map.get_codec_name("application/x-snot")
+                .map(|c| c.name.as_str())
+        );
+        // user entries override builtins
+        assert_eq!(
+            Some("binary"),
+            map.get_codec_name("text/plain").map(|c| c.name.as_str())
+        );
+        // builtins are still there
+        assert_eq!(
+            Some("json"),
+            map.get_codec_name("application/json")
+                .map(|c| c.name.as_str())
+        );
+        assert_eq!(
+            Some("csv"),
+            map.get_codec_name("text/csv").map(|c| c.name.as_str())
+        );
+        assert_eq!(
+            Some(&"application/x-snot".to_string()),
+            map.get_mime_type("json")
+        );
+    }
+
+    #[test]
+    fn replace_builtin() {
+        let mut custom = HashMap::new();
+        custom.insert("application/x-snot".to_string(), "json".into());
+        let map = MimeCodecMap::from_config(Some(custom), true);
+        assert_eq!(
+            Some("json"),
+            map.get_codec_name("application/x-snot")
+                .map(|c| c.name.as_str())
+        );
+        assert!(map.get_codec_name("text/csv").is_none());
+        // every codec still maps to a mime type
+        assert_eq!(Some(&"text/csv".to_string()), map.get_mime_type("csv"));
+    }
 }
@@ -132,6 +132,16 @@ pub(crate) fn value_to_prost_value(json: &Value) -> Result<prost_types::Value> {
         Value::Static(StaticNode::U64(v)) => prost_types::Value {
             kind: Some(Kind::NumberValue(*v as f64)),
         },
+        #[cfg(feature = "128bit")]
+        #[allow(clippy::cast_precision_loss)]
+        Value::Static(StaticNode::I128(v)) => prost_types::Value {
+            kind: Some(Kind::NumberValue(*v as f64)),
+        },
+        #[cfg(feature = "128bit")]
+        #[allow(clippy::cast_precision_loss)]
+        Value::Static(StaticNode::U128(v)) => prost_types::Value {
+            kind: Some(Kind::NumberValue(*v as f64)),
+        },
         Value::Static(StaticNode::F64(v)) => prost_types::Value {
             kind: Some(Kind::NumberValue(*v)),
         },
@@ -20,14 +20,19 @@ use hyper::server::{
     accept::Accept,
     conn::{AddrIncoming, AddrStream},
 };
-use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig};
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier},
+    Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig, ServerName,
+    SignatureScheme,
+};
 use rustls_native_certs::load_native_certs;
-use rustls_pemfile::{certs, pkcs8_private_keys, rsa_private_keys, Item};
+use rustls_pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
 use std::{
     io::{self, BufReader},
     net::SocketAddr,
     pin::Pin,
     task::{ready, Context, Poll},
+    time::SystemTime,
 };
 use std::{
     path::{Path, PathBuf},
@@ -59,14 +64,19 @@ pub(crate) struct TLSServerConfig {
 #[derive(Deserialize, Debug, Default, Clone)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct TLSClientConfig {
-    /// Path to the pem-encoded certificate file of the CA to use for verifying the servers certificate
+    /// Path to the pem-encoded certificate file of the CA (or a bundle of CAs) to use for verifying the servers certificate
     pub(crate) cafile: Option<PathBuf>,
-    /// The DNS domain used to verify the server's certificate. If not provided the domain from the connection URL will be used.
+    /// The DNS domain used to verify the server's certificate and sent via SNI, e.g. when connecting by IP address.
+    /// If not provided the domain from the connection URL will be used.
+    #[serde(alias = "server_name")]
     pub(crate) domain: Option<String>,
     /// Path to the pem-encoded certificate (-chain) to use for TLS with client-side certificate
     pub(crate) cert: Option<PathBuf>,
     /// Path to the private key to use for TLS with client-side certificate
     pub(crate) key: Option<PathBuf>,
+    /// Skip verifying the server's certificate, only use this for testing
+    #[serde(default)]
+    pub(crate) insecure_skip_verify: bool,
 }
 
 /// Load the passed certificates file
@@ -92,8 +102,22 @@ fn load_certs(path: &Path) -> Result<Vec<Certificate>> {
         })
 }
 
+/// Load all CA certificates from the passed pem-encoded bundle into a new root store
+fn load_roots(path: &Path) -> Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(&cert).map_err(|e| {
+            Error::from(ErrorKind::TLSError(format!(
+                "Invalid CA certificate in {}: {e}",
+                path.display()
+            )))
+        })?;
+    }
+    Ok(roots)
+}
+
 /// Load the passed private key file
-fn load_keys(path: &Path) -> Result<PrivateKey> {
+pub(crate) fn load_keys(path: &Path) -> Result<PrivateKey> {
     // prefer to load pkcs8 keys
     // this will only error if we have invalid pkcs8 key base64 or we couldnt read the file.
     let keyfile = tremor_common::file::open(path)?;
@@ -130,6 +154,68 @@ fn load_keys(path: &Path) -> Result<PrivateKey> {
         )))
     })
 }
+/// signature schemes we can verify a private key against its certificate with
+static KEY_PAIR_SCHEMES: [(SignatureScheme, &webpki::SignatureAlgorithm); 4] = [
+    (
+        SignatureScheme::RSA_PSS_SHA256,
+        &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+    ),
+    (
+        SignatureScheme::ECDSA_NISTP256_SHA256,
+        &webpki::ECDSA_P256_SHA256,
+    ),
+    (
+        SignatureScheme::ECDSA_NISTP384_SHA384,
+        &webpki::ECDSA_P384_SHA384,
+    ),
+    (SignatureScheme::ED25519, &webpki::ED25519),
+];
+
+/// Ensure the private `key` belongs to the end-entity certificate `cert`
+/// by signing a message with the key and verifying it with the certificate
+fn verify_key_pair(cert: &Certificate, key: &PrivateKey) -> Result<()> {
+    let signing_key = rustls::sign::any_supported_type(key)
+        .map_err(|_e| Error::from(ErrorKind::TLSError("Unsupported private key type".into())))?;
+    let end_entity = webpki::EndEntityCert::try_from(cert.0.as_slice())
+        .map_err(|e| Error::from(ErrorKind::TLSError(format!("Invalid certificate: {e}"))))?;
+    let schemes: Vec<SignatureScheme> = KEY_PAIR_SCHEMES.iter().map(|(s, _)| *s).collect();
+    let (signer, algorithm) = signing_key
+        .choose_scheme(&schemes)
+        .and_then(|signer| {
+            KEY_PAIR_SCHEMES
+                .iter()
+                .find(|(scheme, _)| *scheme == signer.scheme())
+                .map(|(_, algorithm)| (signer, *algorithm))
+        })
+        .ok_or_else(|| Error::from(ErrorKind::TLSError("Unsupported private key type".into())))?;
+    let message = b"tremor key pair check";
+    let signature = signer.sign(message)?;
+    end_entity
+        .verify_signature(algorithm, message, &signature)
+        .map_err(|_e| {
+            Error::from(ErrorKind::TLSError(
+                "The private key does not match the certificate".into(),
+            ))
+        })
+}
+
+/// Accepts any server certificate, for `insecure_skip_verify`
+struct NoServerVerification {}
+
+impl ServerCertVerifier for NoServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
 impl TLSServerConfig {
     pub(crate) fn to_server_config(&self) -> Result<ServerConfig> {
         let certs = load_certs(&self.cert)?;
@@ -146,7 +232,7 @@ impl TLSServerConfig {
     }
 }
 impl TLSClientConfig {
-    /// if we have a cafile configured, we only load it, and no other ca certificates
+    /// if we have a cafile configured, we only load the certificates in it, and no other ca certificates
     /// if there is no cafile configured, we load the default webpki-roots from Mozilla
     pub(crate) fn to_client_connector(&self) -> Result<TlsConnector> {
         let tls_config = self.to_client_config()?;
@@ -154,21 +240,7 @@ impl TLSClientConfig {
     }
     pub(crate) fn to_client_config(&self) -> Result<ClientConfig> {
         let roots = if let Some(cafile) = self.cafile.as_ref() {
-            let mut roots = RootCertStore::empty();
-            let certfile = tremor_common::file::open(cafile)?;
-            let mut reader = BufReader::new(certfile);
-
-            let cert = rustls_pemfile::read_one(&mut reader)?.and_then(|item| match item {
-                Item::X509Certificate(cert) => Some(Certificate(cert)),
-                _ => None,
-            });
-            cert.and_then(|cert| roots.add(&cert).ok()).ok_or_else(|| {
-                Error::from(ErrorKind::TLSError(format!(
-                    "Invalid certificate in {}",
-                    cafile.display()
-                )))
-            })?;
-            roots
+            load_roots(cafile)?
         } else {
             SYSTEM_ROOT_CERTS.clone()
         };
@@ -178,13 +250,36 @@ impl TLSClientConfig {
             .with_root_certificates(roots);
 
         // load client certificate stuff
-        if let (Some(cert), Some(key)) = (self.cert.as_ref(), self.key.as_ref()) {
-            let cert = load_certs(cert)?;
-            let key = load_keys(key)?;
-            Ok(tls_config.with_single_cert(cert, key)?)
-        } else {
-            Ok(tls_config.with_no_client_auth())
+        let mut client_config = match (self.cert.as_ref(), self.key.as_ref()) {
+            (Some(cert_path), Some(key_path)) => {
+                let cert = load_certs(cert_path)?;
+                let key = load_keys(key_path)?;
+                if let Some(end_entity) = cert.first() {
+                    verify_key_pair(end_entity, &key).map_err(|e| {
+                        Error::from(ErrorKind::TLSError(format!(
+                            "Invalid client certificate {} and key {}: {e}",
+                            cert_path.display(),
+                            key_path.display()
+                        )))
+                    })?;
+                }
+                tls_config.with_single_cert(cert, key)?
+            }
+            (None, None) => tls_config.with_no_client_auth(),
+            _ => {
+                return Err(ErrorKind::TLSError(
+                    "Both `cert` and `key` need to be provided for a client certificate".into(),
+                )
+                .into())
+            }
+        };
+        if self.insecure_skip_verify {
+            warn!("TLS server certificate verification is disabled");
+            client_config
+                .dangerous()
+                .set_certificate_verifier(Arc::new(NoServerVerification {}));
         }
+        Ok(client_config)
     }
 }
 
@@ -328,6 +423,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn load_roots_bundle() -> Result<()> {
+        setup_for_tls();
+        let mut bundle = tempfile::NamedTempFile::new()?;
+        bundle.write_all(&std::fs::read("./tests/localhost.cert")?)?;
+        bundle.write_all(&std::fs::read("./tests/ca.cert")?)?;
+        let path = bundle.into_temp_path();
+        assert_eq!(2, load_roots(&path)?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn load_roots_invalid() -> Result<()> {
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(b"-----BEGIN CERTIFICATE-----\nSnotBadger\n-----END CERTIFICATE-----\n")?;
+        let path = file.into_temp_path();
+        assert!(load_roots(&path).is_err());
+        assert!(load_roots(Path::new("./tests/does-not-exist.cert")).is_err());
+        Ok(())
+    }
+
     #[test]
     fn load_keys_invalid() -> Result<()> {
         let mut file = tempfile::NamedTempFile::new()?;
@@ -356,9 +472,52 @@ mod tests {
             domain: Some("hostenschmirtz".to_string()),
             cert: Some(Path::new("./tests/localhost.cert").to_path_buf()),
             key: Some(Path::new("./tests/localhost.key").to_path_buf()),
+            insecure_skip_verify: false,
         };
         let client_config = tls_config.to_client_config()?;
         assert_eq!(true, client_config.client_auth_cert_resolver.has_certs());
         Ok(())
     }
+
+    #[test]
+    fn client_config_key_mismatch() -> Result<()> {
+        use std::process::Command;
+        setup_for_tls();
+
+        // a key that doesn't belong to the fixture certificate
+        let other_key = tempfile::NamedTempFile::new()?.into_temp_path();
+        let status = Command::new("openssl")
+            .args([
+                "genpkey",
+                "-algorithm",
+                "RSA",
+                "-pkeyopt",
+                "rsa_keygen_bits:2048",
+                "-out",
+            ])
+            .arg(&other_key)
+            .status()?;
+        assert!(status.success());
+        let tls_config = TLSClientConfig {
+            cert: Some(Path::new("./tests/localhost.cert").to_path_buf()),
+            key: Some(other_key.to_path_buf()),
+            ..TLSClientConfig::default()
+        };
+        let err = tls_config
+            .to_client_config()
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        assert!(err.contains("The private key does not match the certificate"));
+        Ok(())
+    }
+
+    #[test]
+    fn client_config_incomplete() {
+        let tls_config = TLSClientConfig {
+            cert: Some(Path::new("./tests/localhost.cert").to_path_buf()),
+            ..TLSClientConfig::default()
+        };
+        assert!(tls_config.to_client_config().is_err());
+    }
 }
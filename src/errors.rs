@@ -304,6 +304,14 @@ error_chain! {
             description("TLS error")
                 display("{}", s)
         }
+        TooManyRedirects(max: usize) {
+            description("Too many HTTP redirects")
+                display("Exceeded the maximum of {} HTTP redirects", max)
+        }
+        CircuitBreakerOpen(host: String) {
+            description("Circuit breaker open")
+                display("Circuit breaker open for {}", host)
+        }
         InvalidTremorUrl(msg: String, invalid_url: String) {
             description("Invalid Tremor URL")
                 display("Invalid Tremor URL {}: {}", invalid_url, msg)
@@ -13,9 +13,12 @@
 // limitations under the License.
 
 pub(crate) mod base64;
+mod batch;
 mod chunk;
 pub(crate) mod compress;
+pub(crate) mod encrypt;
 pub(crate) mod gelf_chunking;
+pub(crate) mod hex;
 pub(crate) mod ingest_ns;
 pub(crate) mod length_prefixed;
 pub(crate) mod separate;
@@ -51,6 +54,16 @@ pub trait Postprocessor: Send + Sync {
     fn finish(&mut self, _data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
         Ok(vec![])
     }
+
+    /// Emit data that is due at `now_ns` without any new input, e.g. a batch whose timeout elapsed.
+    ///
+    /// Called periodically by the sink runtime.
+    ///
+    /// # Errors
+    ///   * if the postprocessor could not emit its data
+    fn tick(&mut self, _now_ns: u64) -> Result<Vec<Vec<u8>>> {
+        Ok(vec![])
+    }
 }
 
 /// Lookup a postprocessor via its config
@@ -61,14 +74,21 @@ pub trait Postprocessor: Send + Sync {
 
 pub fn lookup_with_config(config: &PostprocessorConfig) -> Result<Box<dyn Postprocessor>> {
     match config.name.as_str() {
+        "batch" => Ok(Box::new(batch::Batch::from_config(config.config.as_ref())?)),
         "chunk" => Ok(Box::new(chunk::Chunk::from_config(config.config.as_ref())?)),
         "compress" => Ok(Box::new(compress::Compress::from_config(
             config.config.as_ref(),
         )?)),
+        "brotli" => compress::Brotli::from_config(config.config.as_ref()),
+        "gzip" => compress::Gzip::from_config(config.config.as_ref()),
+        "zstd" => compress::Zstd::from_config(config.config.as_ref()),
         "separate" => Ok(Box::new(separate::Separate::from_config(&config.config)?)),
         "base64" => Ok(Box::<base64::Base64>::default()),
+        "hex" => Ok(Box::<hex::Hex>::default()),
         "ingest-ns" => Ok(Box::<ingest_ns::IngestNs>::default()),
-        "length-prefixed" => Ok(Box::<length_prefixed::LengthPrefixed>::default()),
+        "length-prefixed" => Ok(Box::new(length_prefixed::LengthPrefixed::from_config(
+            config.config.as_ref(),
+        )?)),
         "gelf-chunking" => Ok(Box::<gelf_chunking::Gelf>::default()),
         "textual-length-prefixed" => {
             Ok(Box::<textual_length_prefixed::TextualLengthPrefixed>::default())
@@ -164,21 +184,64 @@ pub fn finish(postprocessors: &mut [Box<dyn Postprocessor>], alias: &str) -> Res
     }
 }
 
+/// Canonical way to tick postprocessors
+///
+/// Data emitted by a postprocessor is passed through the postprocessors following it.
+///
+/// # Errors
+///
+/// * If a postprocessor failed
+pub fn tick(
+    postprocessors: &mut [Box<dyn Postprocessor>],
+    now_ns: u64,
+    alias: &str,
+) -> Result<Vec<Vec<u8>>> {
+    let mut res = Vec::new();
+    let mut rest = postprocessors;
+    while let Some((head, tail)) = mem::take(&mut rest).split_first_mut() {
+        let mut data = head.tick(now_ns).map_err(|e| {
+            format!(
+                "[Connector::{alias}] Postprocessor '{}' tick error: {e}",
+                head.name()
+            )
+        })?;
+        for pp in tail.iter_mut() {
+            let mut data1 = Vec::new();
+            for d in &data {
+                data1.append(
+                    &mut pp
+                        .process(now_ns, now_ns, d)
+                        .map_err(|e| format!("[Connector::{alias}] Postprocessor error {e}"))?,
+                );
+            }
+            data = data1;
+        }
+        res.append(&mut data);
+        rest = tail;
+    }
+    Ok(res)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::config::NameWithConfig;
     use tremor_value::literal;
 
-    const LOOKUP_TABLE: [&str; 6] = [
+    const LOOKUP_TABLE: [&str; 11] = [
+        "batch",
+        "brotli",
+        "gzip",
+        "zstd",
         "separate",
         "base64",
+        "hex",
         "gelf-chunking",
         "ingest-ns",
         "length-prefixed",
         "textual-length-prefixed",
     ];
-    const COMPRESSION: [&str; 6] = ["gzip", "zlib", "xz2", "snappy", "lz4", "zstd"];
+    const COMPRESSION: [&str; 7] = ["gzip", "zlib", "xz2", "snappy", "lz4", "zstd", "brotli"];
 
     #[test]
     fn test_lookup() {
@@ -191,6 +254,49 @@ mod test {
         assert!(lookup("bad_lookup").is_err());
     }
 
+    #[test]
+    fn tick_chain() -> Result<()> {
+        let batch = literal!({"name": "batch", "config": {"timeout_ms": 1}});
+        let mut pps = make_postprocessors(&[
+            NameWithConfig::try_from(&batch)?,
+            PostprocessorConfig::from("separate"),
+        ])?;
+        assert!(postprocess(&mut pps, 0, b"1".to_vec(), "test")?.is_empty());
+        assert!(postprocess(&mut pps, 0, b"2".to_vec(), "test")?.is_empty());
+        // the emitted batch runs through the following postprocessors
+        assert_eq!(vec![b"[1,2]\n".to_vec()], tick(&mut pps, u64::MAX, "test")?);
+        assert!(tick(&mut pps, u64::MAX, "test")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn finish_chain() -> Result<()> {
+        use std::io::Read;
+        let batch = literal!({"name": "batch", "config": {"max_events": 3}});
+        let mut pps = make_postprocessors(&[
+            NameWithConfig::try_from(&batch)?,
+            PostprocessorConfig::from("gzip"),
+        ])?;
+        assert!(postprocess(&mut pps, 0, b"1".to_vec(), "test")?.is_empty());
+        assert!(postprocess(&mut pps, 0, b"2".to_vec(), "test")?.is_empty());
+        // the partial batch is compressed when the stream finishes
+        let mut finished = finish(&mut pps, "test")?;
+        assert_eq!(1, finished.len());
+        let mut decompressed = String::new();
+        libflate::gzip::Decoder::new(finished.remove(0).as_slice())?
+            .read_to_string(&mut decompressed)?;
+        assert_eq!("[1,2]", decompressed);
+        assert!(finish(&mut pps, "test")?.is_empty());
+
+        for c in COMPRESSION {
+            let config = literal!({"name": "compress", "config":{"algorithm": c}});
+            let mut pp = lookup_with_config(&NameWithConfig::try_from(&config)?)?;
+            assert!(pp.finish(None)?.is_empty(), "{c}");
+            assert_eq!(1, pp.finish(Some(b"snot"))?.len(), "{c}");
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_lookup_compression() -> Result<()> {
         for c in COMPRESSION {
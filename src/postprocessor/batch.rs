@@ -0,0 +1,314 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A postprocessor collecting serialized events into batches, e.g. for APIs accepting bulk arrays.
+//!
+//! A batch is emitted as a single JSON array of the collected events, or with the events joined by newlines,
+//! as soon as any of the configured limits is hit:
+//!
+//! * it contains `max_events` events
+//! * adding another event would make it exceed `max_bytes`, an event exceeding `max_bytes` on its own is emitted alone
+//! * its first event was collected `timeout_ms` ago, this is checked with every event and periodically by the sink,
+//!   so a partial batch is flushed even if no further events arrive
+//!
+//! A partial batch is emitted when the stream is finished.
+//!
+//! ## Configuration
+//!
+//! | Option       | Description                                                             | Required | Default Value |
+//! |--------------|-------------------------------------------------------------------------|----------|---------------|
+//! | `max_events` | The maximum number of events in a batch.                                | no       | `100`         |
+//! | `max_bytes`  | The maximum size of a batch in bytes.                                   | no       |               |
+//! | `timeout_ms` | The maximum time in milliseconds an event is held back.                 | no       |               |
+//! | `format`     | `array` to emit a JSON array, `lines` to join the events with newlines. | no       | `array`       |
+//!
+//! ## Example
+//!
+//! ```tremor
+//! define connector bulk from tcp_client
+//! with
+//!     codec = "json",
+//!     config = {
+//!         "url": "localhost:4242"
+//!     },
+//!     postprocessors = [
+//!         {
+//!             "name": "batch",
+//!             "config": {
+//!                 "max_events": 500,
+//!                 "max_bytes": 1048576,
+//!                 "timeout_ms": 1000
+//!             }
+//!         }
+//!     ]
+//! end;
+//! ```
+
+use super::Postprocessor;
+use crate::errors::{Error, Kind as ErrorKind, Result};
+use tremor_value::Value;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    #[default]
+    Array,
+    Lines,
+}
+
+fn default_max_events() -> usize {
+    100
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default = "default_max_events")]
+    max_events: usize,
+    #[serde(default = "Default::default")]
+    max_bytes: Option<usize>,
+    #[serde(default = "Default::default")]
+    timeout_ms: Option<u64>,
+    #[serde(default = "Default::default")]
+    format: Format,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_events: default_max_events(),
+            max_bytes: None,
+            timeout_ms: None,
+            format: Format::default(),
+        }
+    }
+}
+
+pub(crate) struct Batch {
+    config: Config,
+    /// the serialized events of the current batch
+    events: Vec<Vec<u8>>,
+    /// size of the current batch when emitted
+    len: usize,
+    /// time the first event of the current batch was collected at
+    started_ns: u64,
+}
+
+impl Batch {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Self> {
+        let config: Config = if let Some(config) = config {
+            tremor_value::structurize(config.clone()).map_err(|e| {
+                let kind = ErrorKind::InvalidConfiguration(
+                    "\"batch\" postprocessor".to_string(),
+                    e.to_string(),
+                );
+                Error::with_chain(e, kind)
+            })?
+        } else {
+            Config::default()
+        };
+        if config.max_events == 0 || config.max_bytes == Some(0) {
+            return Err(ErrorKind::InvalidConfiguration(
+                "\"batch\" postprocessor".to_string(),
+                "`max_events` and `max_bytes` must be > 0".to_string(),
+            )
+            .into());
+        }
+        Ok(Self {
+            config,
+            events: Vec::new(),
+            len: 0,
+            started_ns: 0,
+        })
+    }
+
+    /// size of the current batch with `data` added
+    fn len_with(&self, data: &[u8]) -> usize {
+        match (self.config.format, self.events.is_empty()) {
+            // brackets
+            (Format::Array, true) => data.len() + 2,
+            (Format::Lines, true) => data.len(),
+            // separator
+            (_, false) => self.len + data.len() + 1,
+        }
+    }
+
+    fn timed_out(&self, now_ns: u64) -> bool {
+        self.config.timeout_ms.map_or(false, |timeout_ms| {
+            !self.events.is_empty()
+                && now_ns.saturating_sub(self.started_ns) >= timeout_ms.saturating_mul(1_000_000)
+        })
+    }
+
+    /// emit the current batch, if there is one
+    fn flush(&mut self) -> Option<Vec<u8>> {
+        if self.events.is_empty() {
+            return None;
+        }
+        let mut batch = Vec::with_capacity(self.len);
+        let separator = match self.config.format {
+            Format::Array => {
+                batch.push(b'[');
+                b','
+            }
+            Format::Lines => b'\n',
+        };
+        for (i, event) in self.events.drain(..).enumerate() {
+            if i > 0 {
+                batch.push(separator);
+            }
+            batch.extend_from_slice(&event);
+        }
+        if self.config.format == Format::Array {
+            batch.push(b']');
+        }
+        self.len = 0;
+        Some(batch)
+    }
+}
+
+impl Postprocessor for Batch {
+    fn name(&self) -> &str {
+        "batch"
+    }
+
+    fn process(&mut self, _ingres_ns: u64, egress_ns: u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut res = Vec::new();
+        let exceeds_max_bytes = self
+            .config
+            .max_bytes
+            .map_or(false, |max_bytes| self.len_with(data) > max_bytes);
+        if self.timed_out(egress_ns) || exceeds_max_bytes {
+            res.extend(self.flush());
+        }
+        if self.events.is_empty() {
+            self.started_ns = egress_ns;
+        }
+        self.len = self.len_with(data);
+        self.events.push(data.to_vec());
+        let full = self
+            .config
+            .max_bytes
+            .map_or(false, |max_bytes| self.len >= max_bytes);
+        if self.events.len() >= self.config.max_events || full {
+            res.extend(self.flush());
+        }
+        Ok(res)
+    }
+
+    fn tick(&mut self, now_ns: u64) -> Result<Vec<Vec<u8>>> {
+        if self.timed_out(now_ns) {
+            Ok(self.flush().into_iter().collect())
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn finish(&mut self, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        let mut res = match data {
+            Some(data) if !data.is_empty() => self.process(0, 0, data)?,
+            _ => vec![],
+        };
+        res.extend(self.flush());
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    fn batch(config: &Value) -> Result<Batch> {
+        Batch::from_config(Some(config))
+    }
+
+    #[test]
+    fn from_config() -> Result<()> {
+        assert!(Batch::from_config(None).is_ok());
+        assert!(batch(&literal!({"max_events": 0})).is_err());
+        assert!(batch(&literal!({"max_bytes": 0})).is_err());
+        assert!(batch(&literal!({"format": "snot"})).is_err());
+        assert!(batch(&literal!({"snot": "badger"})).is_err());
+        let pp = batch(&literal!({"max_events": 5, "timeout_ms": 100, "format": "lines"}))?;
+        assert_eq!("batch", pp.name());
+        assert_eq!(5, pp.config.max_events);
+        assert_eq!(Some(100), pp.config.timeout_ms);
+        assert_eq!(Format::Lines, pp.config.format);
+        Ok(())
+    }
+
+    #[test]
+    fn flush_on_max_events() -> Result<()> {
+        let mut pp = batch(&literal!({"max_events": 3}))?;
+        assert!(pp.process(0, 0, b"1")?.is_empty());
+        assert!(pp.process(0, 0, b"{\"snot\":2}")?.is_empty());
+        assert_eq!(
+            vec![b"[1,{\"snot\":2},\"3\"]".to_vec()],
+            pp.process(0, 0, b"\"3\"")?
+        );
+        assert!(pp.process(0, 0, b"4")?.is_empty());
+        // partial batches are emitted on finish
+        assert_eq!(vec![b"[4]".to_vec()], pp.finish(None)?);
+        assert!(pp.finish(None)?.is_empty());
+
+        let mut pp = batch(&literal!({"max_events": 2, "format": "lines"}))?;
+        assert!(pp.process(0, 0, b"snot")?.is_empty());
+        assert_eq!(vec![b"snot\nbadger".to_vec()], pp.process(0, 0, b"badger")?);
+        assert_eq!(vec![b"flork".to_vec()], pp.finish(Some(b"flork"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn flush_on_max_bytes() -> Result<()> {
+        let mut pp = batch(&literal!({"max_bytes": 10}))?;
+        assert!(pp.process(0, 0, b"\"ab\"")?.is_empty());
+        // `["ab","cd"]` would be 11 bytes
+        assert_eq!(vec![b"[\"ab\"]".to_vec()], pp.process(0, 0, b"\"cd\"")?);
+        // `["cd",1,2]` is exactly 10 bytes
+        assert!(pp.process(0, 0, b"1")?.is_empty());
+        assert_eq!(vec![b"[\"cd\",1,2]".to_vec()], pp.process(0, 0, b"2")?);
+        // an event exceeding `max_bytes` on its own is emitted alone
+        assert!(pp.process(0, 0, b"3")?.is_empty());
+        assert_eq!(
+            vec![b"[3]".to_vec(), b"[\"snot badger\"]".to_vec()],
+            pp.process(0, 0, b"\"snot badger\"")?
+        );
+        assert!(pp.finish(None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn flush_on_timeout() -> Result<()> {
+        let mut pp = batch(&literal!({"timeout_ms": 10}))?;
+        assert!(pp.tick(0)?.is_empty());
+        assert!(pp.process(0, 1_000_000, b"1")?.is_empty());
+        assert!(pp.process(0, 5_000_000, b"2")?.is_empty());
+        // the timeout is tracked from the first event of the batch
+        assert!(pp.tick(10_999_999)?.is_empty());
+        assert_eq!(vec![b"[1,2]".to_vec()], pp.tick(11_000_000)?);
+        assert!(pp.tick(100_000_000)?.is_empty());
+
+        // checked with new events as well
+        assert!(pp.process(0, 200_000_000, b"3")?.is_empty());
+        assert_eq!(vec![b"[3]".to_vec()], pp.process(0, 210_000_000, b"4")?);
+        assert_eq!(vec![b"[4]".to_vec()], pp.tick(220_000_000)?);
+
+        // without a timeout ticks never flush
+        let mut pp = batch(&literal!({}))?;
+        assert!(pp.process(0, 0, b"1")?.is_empty());
+        assert!(pp.tick(u64::MAX)?.is_empty());
+        Ok(())
+    }
+}
@@ -20,12 +20,13 @@
 //!
 //! | Name     | Algorithm / Format                                                                 |
 //! |----------|------------------------------------------------------------------------------------|
-//! | `gzip`   | `GZip`                                                                             |
+//! | `gzip`   | `GZip` level 6 (default), 0 stores uncompressed, 1 uses fixed Huffman codes        |
 //! | `zlib`   | `ZLib`                                                                             |
 //! | `xz`     | `Xz2` level 9 (default)                                                            |
 //! | `snappy` | `Snappy`                                                                           |
 //! | `lz4`    | `Lz` level 4 compression (default)                                                 |
 //! | `zstd`   | [`Zstandard`](https://datatracker.ietf.org/doc/html/rfc8878) (defaults to level 0) |
+//! | `brotli` | [`Brotli`](https://www.rfc-editor.org/rfc/rfc7932) quality 11, window 22 (default) |
 //!
 //! Example configuration:
 //!
@@ -57,6 +58,49 @@
 //! ```
 //!
 //! Xz compression when wrong compression level is specified gives an `Err`.
+//!
+//! Zstd compression is also available as the `zstd` postprocessor. Besides the `level` it accepts a `dictionary`
+//! file to compress with, the same dictionary has to be given to the `zstd` preprocessor decompressing the data:
+//!
+//! ```tremor
+//! postprocessors = [
+//!   {
+//!     "name": "zstd",
+//!     "config": {
+//!       "level": 3,
+//!       "dictionary": "/etc/tremor/events.dict"
+//!     }
+//!   }
+//! ]
+//! ```
+//!
+//! Brotli compression is also available as the `brotli` postprocessor. Instead of a `level` it accepts the
+//! `quality`, from 0 to 11, and the `window`, the base 2 logarithm of the window size from 10 to 24:
+//!
+//! ```tremor
+//! postprocessors = [
+//!   {
+//!     "name": "brotli",
+//!     "config": {
+//!       "quality": 5,
+//!       "window": 20
+//!     }
+//!   }
+//! ]
+//! ```
+//!
+//! Gzip compression is also available as the `gzip` postprocessor, which accepts an optional `level`:
+//!
+//! ```tremor
+//! postprocessors = [
+//!   {
+//!     "name": "gzip",
+//!     "config": {
+//!       "level": 1
+//!     }
+//!   }
+//! ]
+//! ```
 
 use super::Postprocessor;
 use crate::errors::Result;
@@ -67,6 +111,15 @@ use std::{
 };
 use tremor_value::Value;
 
+/// Compress the data a preceding postprocessor emitted when finishing, like the last partial
+/// `batch`, the compressors handle every input on its own, so they buffer nothing themselves
+fn compress_remainder(pp: &mut dyn Postprocessor, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+    match data {
+        Some(data) if !data.is_empty() => pp.process(0, 0, data),
+        _ => Ok(vec![]),
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum Algorithm {
     Gzip,
@@ -75,6 +128,7 @@ enum Algorithm {
     Zstd,
     Snappy,
     Lz4,
+    Brotli,
 }
 
 impl FromStr for Algorithm {
@@ -88,6 +142,7 @@ impl FromStr for Algorithm {
             "snappy" => Ok(Algorithm::Snappy),
             "lz4" => Ok(Algorithm::Lz4),
             "zstd" => Ok(Algorithm::Zstd),
+            "brotli" => Ok(Algorithm::Brotli),
             other => Err(format!("Unknown compression algorithm: {other}").into()),
         }
     }
@@ -97,8 +152,9 @@ impl Algorithm {
     pub fn into_postprocessor(self, config: Option<&Value>) -> Result<Box<dyn Postprocessor>> {
         if let Some(compression_level) = config.get_i64("level") {
             match self {
+                Algorithm::Gzip => Gzip::with_config(compression_level),
                 Algorithm::Xz2 => Xz2::with_config(compression_level),
-                Algorithm::Zstd => Zstd::with_config(compression_level),
+                Algorithm::Zstd => Zstd::from_config(config),
                 Algorithm::Lz4 => Lz4::with_config(compression_level),
                 _ => Err("compression level not supported for given algorithm".into()),
             }
@@ -107,29 +163,69 @@ impl Algorithm {
                 Algorithm::Gzip => Box::<Gzip>::default(),
                 Algorithm::Zlib => Box::<Zlib>::default(),
                 Algorithm::Xz2 => Box::<Xz2>::default(),
-                Algorithm::Zstd => Box::<Zstd>::default(),
+                Algorithm::Zstd => Zstd::from_config(config)?,
                 Algorithm::Snappy => Box::<Snappy>::default(),
                 Algorithm::Lz4 => Box::<Lz4>::default(),
+                Algorithm::Brotli => Brotli::from_config(config)?,
             };
             Ok(codec)
         }
     }
 }
 
-#[derive(Default)]
-struct Gzip {}
+pub(crate) struct Gzip {
+    compression_level: u32,
+}
+impl Gzip {
+    fn with_config(level: i64) -> Result<Box<dyn Postprocessor>> {
+        if !(0..=9).contains(&level) {
+            return Err(format!(
+                "Gzip supports compression level between 0 and 9 but {level} was given"
+            )
+            .into());
+        }
+
+        Ok(Box::new(Self {
+            compression_level: level.try_into()?,
+        }))
+    }
+
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Box<dyn Postprocessor>> {
+        config.get_i64("level").map_or_else(
+            || Ok(Box::<Self>::default() as Box<dyn Postprocessor>),
+            Self::with_config,
+        )
+    }
+}
 impl Postprocessor for Gzip {
     fn name(&self) -> &str {
         "gzip"
     }
 
     fn process(&mut self, _ingres_ns: u64, _egress_ns: u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
-        use libflate::gzip::Encoder;
-
-        let mut encoder = Encoder::new(Vec::new())?;
+        use libflate::gzip::{EncodeOptions, Encoder};
+
+        // libflate has no numeric levels, so we map them to what it offers
+        let options = match self.compression_level {
+            0 => EncodeOptions::new().no_compression(),
+            1 => EncodeOptions::new().fixed_huffman_codes(),
+            _ => EncodeOptions::new(),
+        };
+        let mut encoder = Encoder::with_options(Vec::new(), options)?;
         encoder.write_all(data)?;
         Ok(vec![encoder.finish().into_result()?])
     }
+
+    fn finish(&mut self, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        compress_remainder(self, data)
+    }
+}
+impl Default for Gzip {
+    fn default() -> Self {
+        Self {
+            compression_level: 6,
+        }
+    }
 }
 
 #[derive(Default)]
@@ -145,6 +241,10 @@ impl Postprocessor for Zlib {
         encoder.write_all(data)?;
         Ok(vec![encoder.finish().into_result()?])
     }
+
+    fn finish(&mut self, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        compress_remainder(self, data)
+    }
 }
 
 struct Xz2 {
@@ -175,6 +275,10 @@ impl Postprocessor for Xz2 {
         encoder.write_all(data)?;
         Ok(vec![encoder.finish()?])
     }
+
+    fn finish(&mut self, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        compress_remainder(self, data)
+    }
 }
 impl Default for Xz2 {
     fn default() -> Self {
@@ -200,6 +304,10 @@ impl Postprocessor for Snappy {
             .map_err(|e| format!("Snappy compression postprocessor error: {e}"))?;
         Ok(vec![compressed])
     }
+
+    fn finish(&mut self, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        compress_remainder(self, data)
+    }
 }
 
 struct Lz4 {
@@ -230,6 +338,10 @@ impl Postprocessor for Lz4 {
         encoder.write_all(data)?;
         Ok(vec![encoder.finish().0])
     }
+
+    fn finish(&mut self, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        compress_remainder(self, data)
+    }
 }
 impl Default for Lz4 {
     fn default() -> Self {
@@ -240,20 +352,30 @@ impl Default for Lz4 {
 }
 
 #[derive(Clone, Debug, Default)]
-struct Zstd {
+pub(crate) struct Zstd {
     compression_level: i32,
+    /// optional dictionary to compress with, loaded once from the configured file
+    dictionary: Vec<u8>,
 }
 impl Zstd {
-    pub fn with_config(level: i64) -> Result<Box<dyn Postprocessor>> {
-        if !(-7..=22).contains(&level) {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Box<dyn Postprocessor>> {
+        let compression_level = config.get_i64("level").unwrap_or_default();
+        if !(-7..=22).contains(&compression_level) {
             return Err(format!(
-                "Zstd supports compression level between -7 and 22 but {level} was given"
+                "Zstd supports compression level between -7 and 22 but {compression_level} was given"
             )
             .into());
         }
+        let dictionary = if let Some(path) = config.get_str("dictionary") {
+            std::fs::read(path)
+                .map_err(|e| format!("Unable to read zstd dictionary {path}: {e}"))?
+        } else {
+            Vec::new()
+        };
 
         Ok(Box::new(Self {
-            compression_level: level.try_into()?,
+            compression_level: compression_level.try_into()?,
+            dictionary,
         }))
     }
 }
@@ -264,10 +386,80 @@ impl Postprocessor for Zstd {
 
     fn process(&mut self, _ingres_ns: u64, _egress_ns: u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
         // Value of 0 indicates default level for encode.
-        let compressed = zstd::encode_all(data, self.compression_level)?;
+        let compressed = if self.dictionary.is_empty() {
+            zstd::encode_all(data, self.compression_level)?
+        } else {
+            zstd::bulk::Compressor::with_dictionary(self.compression_level, &self.dictionary)?
+                .compress(data)?
+        };
         Ok(vec![compressed])
     }
+
+    fn finish(&mut self, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        compress_remainder(self, data)
+    }
+}
+/// size of the buffer brotli compresses from at once
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Brotli {
+    quality: u32,
+    /// base 2 logarithm of the window size
+    window: u32,
+}
+impl Brotli {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Box<dyn Postprocessor>> {
+        let default = Self::default();
+        let quality = config.get_i64("quality").unwrap_or(default.quality.into());
+        if !(0..=11).contains(&quality) {
+            return Err(format!(
+                "Brotli supports a quality between 0 and 11 but {quality} was given"
+            )
+            .into());
+        }
+        let window = config.get_i64("window").unwrap_or(default.window.into());
+        if !(10..=24).contains(&window) {
+            return Err(format!(
+                "Brotli supports a window between 10 and 24 but {window} was given"
+            )
+            .into());
+        }
+        Ok(Box::new(Self {
+            quality: quality.try_into()?,
+            window: window.try_into()?,
+        }))
+    }
+}
+impl Postprocessor for Brotli {
+    fn name(&self) -> &str {
+        "brotli"
+    }
+
+    fn process(&mut self, _ingres_ns: u64, _egress_ns: u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut encoder = brotli::CompressorWriter::new(
+            Vec::new(),
+            BROTLI_BUFFER_SIZE,
+            self.quality,
+            self.window,
+        );
+        encoder.write_all(data)?;
+        Ok(vec![encoder.into_inner()])
+    }
+
+    fn finish(&mut self, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        compress_remainder(self, data)
+    }
 }
+impl Default for Brotli {
+    fn default() -> Self {
+        Self {
+            quality: 11,
+            window: 22,
+        }
+    }
+}
+
 pub(crate) struct Compress {
     codec: Box<dyn Postprocessor>,
 }
@@ -290,6 +482,10 @@ impl Postprocessor for Compress {
     fn process(&mut self, ingres_ns: u64, egress_ns: u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
         self.codec.process(ingres_ns, egress_ns, data)
     }
+
+    fn finish(&mut self, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        self.codec.finish(data)
+    }
 }
 
 #[cfg(test)]
@@ -322,6 +518,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn brotli_config() -> Result<()> {
+        use tremor_value::literal;
+        let data = "snot badger ".repeat(100);
+        let mut fast = Brotli::from_config(Some(&literal!({ "quality": 0, "window": 10 })))?;
+        let mut best = Brotli::from_config(None)?;
+        let fast = fast.process(0, 0, data.as_bytes())?.remove(0);
+        let best = best.process(0, 0, data.as_bytes())?.remove(0);
+        assert!(best.len() <= fast.len());
+        assert!(fast.len() < data.len());
+
+        for config in [
+            literal!({ "quality": 12 }),
+            literal!({ "quality": -1 }),
+            literal!({ "window": 9 }),
+            literal!({ "window": 25 }),
+        ] {
+            assert!(Brotli::from_config(Some(&config)).is_err(), "{config}");
+        }
+        // brotli has a quality instead of a level
+        let config = literal!({ "algorithm": "brotli", "level": 3 });
+        assert!(Compress::from_config(Some(&config)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn gzip_levels() -> Result<()> {
+        use std::io::Read;
+        let data = "snot badger ".repeat(100);
+        let mut sizes = Vec::new();
+        for level in [0, 1, 6, 9] {
+            let mut pp = Gzip::from_config(Some(&tremor_value::literal!({ "level": level })))?;
+            let compressed = pp.process(0, 0, data.as_bytes())?.remove(0);
+            let mut decompressed = String::new();
+            libflate::gzip::Decoder::new(compressed.as_slice())?
+                .read_to_string(&mut decompressed)?;
+            assert_eq!(data, decompressed);
+            sizes.push(compressed.len());
+        }
+        // storing is larger than the input, compressing is not
+        assert!(sizes[0] > data.len());
+        assert!(sizes[1] < data.len());
+        assert!(sizes[2] < data.len());
+
+        assert!(Gzip::from_config(None).is_ok());
+        assert!(Gzip::from_config(Some(&tremor_value::literal!({"level": 10}))).is_err());
+        assert!(Gzip::from_config(Some(&tremor_value::literal!({"level": -1}))).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_test_xz2() -> Result<()> {
         let _algorithm = Xz2::with_config(-1);
@@ -344,16 +590,38 @@ mod tests {
 
     #[test]
     fn test_test_zstd() -> Result<()> {
-        let _algorithm = Zstd::with_config(-8);
-        assert!(matches!(crate::errors::Error, _algorithm));
-
-        let _algorithm = Zstd::with_config(23);
-        assert!(matches!(crate::errors::Error, _algorithm));
+        let level = |level: i64| tremor_value::literal!({ "level": level });
+        assert!(Zstd::from_config(Some(&level(-8))).is_err());
+        assert!(Zstd::from_config(Some(&level(23))).is_err());
 
-        for level in -7..=22 {
-            let _algorithm = Zstd::with_config(level)?;
+        for l in -7..=22 {
+            let _algorithm = Zstd::from_config(Some(&level(l)))?;
         }
 
         Ok(())
     }
+
+    #[test]
+    fn zstd_dictionary() -> Result<()> {
+        let data = "snot badger flork mork ".repeat(4);
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("zstd.dict");
+        std::fs::write(&path, "flork mork snot badger ".repeat(16))?;
+        let config = tremor_value::literal!({ "dictionary": path.display().to_string() });
+
+        let mut plain = Zstd::from_config(None)?;
+        let mut with_dict = Zstd::from_config(Some(&config))?;
+        let plain = plain.process(0, 0, data.as_bytes())?.remove(0);
+        let compressed = with_dict.process(0, 0, data.as_bytes())?.remove(0);
+        assert!(compressed.len() < plain.len());
+
+        let dictionary = std::fs::read(&path)?;
+        let decompressed = zstd::bulk::Decompressor::with_dictionary(&dictionary)?
+            .decompress(&compressed, 1024)?;
+        assert_eq!(data.as_bytes(), decompressed.as_slice());
+
+        let config = tremor_value::literal!({ "dictionary": "/snot/badger.dict" });
+        assert!(Zstd::from_config(Some(&config)).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,264 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encrypts event data with AES-256-GCM, appending the authentication tag. The `decrypt` preprocessor
+//! with the same configuration decrypts it again.
+//!
+//! ## Configuration
+//!
+//! | Option      | Description                                                                    | Required           | Default Value   |
+//! |-------------|--------------------------------------------------------------------------------|--------------------|-----------------|
+//! | `algorithm` | The encryption algorithm, only `aes-256-gcm` is supported                      | no                 | `aes-256-gcm`   |
+//! | `key`       | The base64 encoded 256 bit key                                                 | if no `key_file`   |                 |
+//! | `key_file`  | Path to a file containing the base64 encoded 256 bit key                       | if no `key`        |                 |
+//! | `nonce`     | `prefix` or `{"fixed": "<base64 encoded 96 bit nonce>"}`                        | no                 | `prefix`        |
+//!
+//! With `prefix` every message is encrypted with a random nonce, which is prepended to the encrypted data.
+//! A `fixed` nonce is not sent along, it must never be used for more than one message with the same key,
+//! as this breaks the confidentiality and authenticity of all messages encrypted with it.
+
+use super::Postprocessor;
+use crate::errors::{Error, Kind as ErrorKind, Result};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce as AeadNonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use tremor_common::base64::BASE64;
+use tremor_value::Value;
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Algorithm {
+    #[default]
+    #[serde(rename = "aes-256-gcm")]
+    Aes256Gcm,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Nonce {
+    #[default]
+    Prefix,
+    Fixed(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default)]
+    algorithm: Algorithm,
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(default)]
+    key_file: Option<String>,
+    #[serde(default)]
+    nonce: Nonce,
+}
+
+/// An AES-256-GCM key with the nonce strategy, shared by the `encrypt` postprocessor and the `decrypt` preprocessor
+pub(crate) struct Cipher {
+    key: LessSafeKey,
+    fixed_nonce: Option<[u8; NONCE_LEN]>,
+    rng: SystemRandom,
+}
+
+impl Cipher {
+    /// The cipher from the `config` of `processor`, e.g. `"encrypt" postprocessor`, which is named in errors
+    pub(crate) fn from_config(config: Option<&Value>, processor: &str) -> Result<Self> {
+        let invalid = |msg: String| -> Error {
+            ErrorKind::InvalidConfiguration(processor.to_string(), msg).into()
+        };
+        let config = config.ok_or_else(|| invalid("Missing configuration".to_string()))?;
+        let config: Config =
+            tremor_value::structurize(config.clone()).map_err(|e| invalid(e.to_string()))?;
+        let algorithm = match config.algorithm {
+            Algorithm::Aes256Gcm => &AES_256_GCM,
+        };
+        let key = match (config.key, config.key_file) {
+            (Some(key), None) => key,
+            (None, Some(key_file)) => std::fs::read_to_string(&key_file)
+                .map_err(|e| invalid(format!("Unable to read `key_file` {key_file}: {e}")))?,
+            _ => {
+                return Err(invalid(
+                    "Exactly one of `key` or `key_file` must be set".to_string(),
+                ))
+            }
+        };
+        let key = BASE64
+            .decode(key.trim())
+            .map_err(|e| invalid(format!("Invalid base64 encoded key: {e}")))?;
+        let key = UnboundKey::new(algorithm, &key)
+            .map_err(|_| invalid("The key must be 256 bits long".to_string()))?;
+        let fixed_nonce = match config.nonce {
+            Nonce::Prefix => None,
+            Nonce::Fixed(nonce) => {
+                let nonce = BASE64
+                    .decode(nonce.trim())
+                    .map_err(|e| invalid(format!("Invalid base64 encoded nonce: {e}")))?;
+                Some(
+                    nonce
+                        .try_into()
+                        .map_err(|_| invalid("The nonce must be 96 bits long".to_string()))?,
+                )
+            }
+        };
+        Ok(Self {
+            key: LessSafeKey::new(key),
+            fixed_nonce,
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// Encrypt `data`, prefixed with the nonce unless it is fixed
+    pub(crate) fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let (nonce, mut res) = if let Some(nonce) = self.fixed_nonce {
+            (nonce, Vec::new())
+        } else {
+            let mut nonce = [0_u8; NONCE_LEN];
+            self.rng
+                .fill(&mut nonce)
+                .map_err(|_| Error::from("Unable to generate a nonce"))?;
+            (nonce, nonce.to_vec())
+        };
+        let mut in_out = data.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                AeadNonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .map_err(|_| Error::from("Unable to encrypt data"))?;
+        res.append(&mut in_out);
+        Ok(res)
+    }
+
+    /// Decrypt `data` and verify its authentication tag, failing if it was tampered with
+    pub(crate) fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let (nonce, ciphertext) = if let Some(nonce) = self.fixed_nonce {
+            (nonce, data)
+        } else {
+            if data.len() < NONCE_LEN {
+                return Err("Encrypted data is too short to contain a nonce".into());
+            }
+            let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+            let mut prefix = [0_u8; NONCE_LEN];
+            prefix.copy_from_slice(nonce);
+            (prefix, ciphertext)
+        };
+        let mut in_out = ciphertext.to_vec();
+        let plaintext_len = self
+            .key
+            .open_in_place(
+                AeadNonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .map_err(|_| {
+                Error::from("Authentication of the encrypted data failed, it is corrupt or was tampered with")
+            })?
+            .len();
+        in_out.truncate(plaintext_len);
+        Ok(in_out)
+    }
+}
+
+pub(crate) struct Encrypt {
+    cipher: Cipher,
+}
+
+impl Encrypt {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Self> {
+        Ok(Self {
+            cipher: Cipher::from_config(config, "\"encrypt\" postprocessor")?,
+        })
+    }
+}
+
+impl Postprocessor for Encrypt {
+    fn name(&self) -> &str {
+        "encrypt"
+    }
+
+    fn process(&mut self, _ingres_ns: u64, _egress_ns: u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        Ok(vec![self.cipher.encrypt(data)?])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    const KEY: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+
+    #[test]
+    fn random_nonces() -> Result<()> {
+        let mut pp = Encrypt::from_config(Some(&literal!({ "key": KEY })))?;
+        let first = pp.process(0, 0, b"snot")?.pop().unwrap_or_default();
+        let second = pp.process(0, 0, b"snot")?.pop().unwrap_or_default();
+        assert_eq!(NONCE_LEN + 4 + AES_256_GCM.tag_len(), first.len());
+        // every message gets its own nonce
+        assert_ne!(first.get(..NONCE_LEN), second.get(..NONCE_LEN));
+        assert_ne!(first, second);
+        Ok(())
+    }
+
+    #[test]
+    fn fixed_nonce() -> Result<()> {
+        let config = literal!({
+            "algorithm": "aes-256-gcm",
+            "key": KEY,
+            "nonce": {"fixed": "AAAAAAAAAAAAAAAA"}
+        });
+        let mut pp = Encrypt::from_config(Some(&config))?;
+        let first = pp.process(0, 0, b"snot")?.pop().unwrap_or_default();
+        assert_eq!(4 + AES_256_GCM.tag_len(), first.len());
+        assert_eq!(first, pp.process(0, 0, b"snot")?.pop().unwrap_or_default());
+        Ok(())
+    }
+
+    #[test]
+    fn key_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("key");
+        std::fs::write(&path, format!("{KEY}\n"))?;
+        let config = literal!({ "key_file": path.display().to_string() });
+        let cipher = Cipher::from_config(Some(&config), "encrypt")?;
+        let other = Cipher::from_config(Some(&literal!({ "key": KEY })), "decrypt")?;
+        assert_eq!(
+            b"badger".to_vec(),
+            other.decrypt(&cipher.encrypt(b"badger")?)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config() {
+        let invalid = [
+            literal!({}),
+            literal!({"key": KEY, "key_file": "/snot/badger"}),
+            literal!({"key_file": "/snot/badger"}),
+            literal!({"key": "snot"}),
+            // 128 bit key
+            literal!({"key": "AAECAwQFBgcICQoLDA0ODw=="}),
+            literal!({"key": KEY, "algorithm": "aes-128-cbc"}),
+            literal!({"key": KEY, "nonce": "random"}),
+            literal!({"key": KEY, "nonce": {"fixed": "AAAA"}}),
+            literal!({"key": KEY, "snot": "badger"}),
+        ];
+        for config in &invalid {
+            assert!(Encrypt::from_config(Some(config)).is_err(), "{config}");
+        }
+        assert!(Encrypt::from_config(None).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encodes raw data into lower case hex encoded bytes.
+
+use super::Postprocessor;
+use crate::Result;
+
+#[derive(Default)]
+pub(crate) struct Hex {}
+impl Postprocessor for Hex {
+    fn name(&self) -> &str {
+        "hex"
+    }
+
+    fn process(&mut self, _ingres_ns: u64, _egress_ns: u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        Ok(vec![::hex::encode(data).into_bytes()])
+    }
+}
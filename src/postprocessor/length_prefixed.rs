@@ -12,26 +12,174 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Prefixes the data with the length of the event data in bytes as an unsigned 64 bit big-endian integer.
+//! Prefixes the data with the length of the event data in bytes as an unsigned integer.
+//!
+//! By default the length is a 64 bit big-endian integer.
+//!
+//! ## Configuration
+//!
+//! | Option       | Description                                                   | Required | Default Value |
+//! |--------------|---------------------------------------------------------------|----------|---------------|
+//! | `width`      | The width of the length prefix in bytes: `1`, `2`, `4` or `8` | no       | `8`           |
+//! | `endianness` | The byte order of the length prefix: `big` or `little`        | no       | `big`         |
+//!
+//! Data that is too long for its length to be expressed in `width` bytes is rejected with an error.
 
 use std::io::Write;
 
-use byteorder::{BigEndian, WriteBytesExt};
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
 
 use super::Postprocessor;
-use crate::Result;
+use crate::errors::{Error, Kind as ErrorKind, Result};
+use tremor_value::Value;
+
+const DEFAULT_WIDTH: usize = 8;
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Endianness {
+    #[default]
+    Big,
+    Little,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default = "default_width")]
+    width: usize,
+    #[serde(default)]
+    endianness: Endianness,
+}
+
+fn default_width() -> usize {
+    DEFAULT_WIDTH
+}
+
+#[derive(Clone)]
+pub(crate) struct LengthPrefixed {
+    width: usize,
+    endianness: Endianness,
+}
+
+impl Default for LengthPrefixed {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_WIDTH,
+            endianness: Endianness::Big,
+        }
+    }
+}
+
+impl LengthPrefixed {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Self> {
+        if let Some(config) = config {
+            let config: Config = tremor_value::structurize(config.clone()).map_err(|e| {
+                let kind = ErrorKind::InvalidConfiguration(
+                    "\"length-prefixed\" postprocessor".to_string(),
+                    e.to_string(),
+                );
+                Error::with_chain(e, kind)
+            })?;
+            if !matches!(config.width, 1 | 2 | 4 | 8) {
+                return Err(ErrorKind::InvalidConfiguration(
+                    "\"length-prefixed\" postprocessor".to_string(),
+                    "`width` must be one of 1, 2, 4 or 8".to_string(),
+                )
+                .into());
+            }
+            Ok(Self {
+                width: config.width,
+                endianness: config.endianness,
+            })
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// the maximum length expressible in `width` bytes
+    fn max_len(&self) -> u64 {
+        u64::MAX >> (64 - 8 * self.width)
+    }
+}
 
-#[derive(Clone, Default)]
-pub(crate) struct LengthPrefixed {}
 impl Postprocessor for LengthPrefixed {
     fn name(&self) -> &str {
         "length-prefix"
     }
 
     fn process(&mut self, _ingres_ns: u64, _egress_ns: u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
-        let mut res = Vec::with_capacity(data.len() + 8);
-        res.write_u64::<BigEndian>(data.len() as u64)?;
+        let len = data.len() as u64;
+        if len > self.max_len() {
+            return Err(format!(
+                "Payload of {len} bytes exceeds the maximum length of {} bytes for a {} byte length prefix",
+                self.max_len(),
+                self.width
+            )
+            .into());
+        }
+        let mut res = Vec::with_capacity(data.len() + self.width);
+        match self.endianness {
+            Endianness::Big => res.write_uint::<BigEndian>(len, self.width)?,
+            Endianness::Little => res.write_uint::<LittleEndian>(len, self.width)?,
+        }
         res.write_all(data)?;
         Ok(vec![res])
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    fn process(config: &Value, data: &[u8]) -> Result<Vec<u8>> {
+        let mut pp = LengthPrefixed::from_config(Some(config))?;
+        Ok(pp.process(0, 0, data)?.pop().unwrap_or_default())
+    }
+
+    #[test]
+    fn two_byte_prefix() -> Result<()> {
+        let config = literal!({"width": 2});
+        assert_eq!(vec![0_u8, 3, 1, 2, 3], process(&config, &[1, 2, 3])?);
+        let config = literal!({"width": 2, "endianness": "little"});
+        assert_eq!(vec![3_u8, 0, 1, 2, 3], process(&config, &[1, 2, 3])?);
+
+        let data = vec![0_u8; 65535];
+        let res = process(&config, &data)?;
+        assert_eq!(Some(&[0xff_u8, 0xff][..]), res.get(0..2));
+        let data = vec![0_u8; 65536];
+        assert!(process(&config, &data).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn four_byte_prefix() -> Result<()> {
+        let data = vec![42_u8; 258];
+        let config = literal!({"width": 4, "endianness": "big"});
+        let res = process(&config, &data)?;
+        assert_eq!(Some(&[0_u8, 0, 1, 2][..]), res.get(0..4));
+        assert_eq!(Some(&data[..]), res.get(4..));
+        let config = literal!({"width": 4, "endianness": "little"});
+        let res = process(&config, &data)?;
+        assert_eq!(Some(&[2_u8, 1, 0, 0][..]), res.get(0..4));
+        assert_eq!(Some(&data[..]), res.get(4..));
+        Ok(())
+    }
+
+    #[test]
+    fn default_prefix() -> Result<()> {
+        let mut pp = LengthPrefixed::from_config(None)?;
+        let res = pp.process(0, 0, &[1_u8])?.pop().unwrap_or_default();
+        assert_eq!(vec![0_u8, 0, 0, 0, 0, 0, 0, 1, 1], res);
+        Ok(())
+    }
+
+    #[test]
+    fn from_config() {
+        assert!(LengthPrefixed::from_config(Some(&literal!({"width": 3}))).is_err());
+        assert!(LengthPrefixed::from_config(Some(&literal!({"endianness": "middle"}))).is_err());
+        assert!(LengthPrefixed::from_config(Some(&literal!({"snot": "badger"}))).is_err());
+        assert!(LengthPrefixed::from_config(Some(&literal!({"width": 1}))).is_ok());
+    }
+}
@@ -12,14 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Appends the separation character at the end of each event's byte stream, the default character is '\n' (newline). It can be overwritten with the `separator` option.
+//! Appends the separator at the end of each event's byte stream, the default separator is '\n' (newline). It can be overwritten with the `separator` option.
 //!
-//! | Option      | Description                                            | Required | Default Value |
-//! |-------------|--------------------------------------------------------|----------|---------------|
-//! | `separator` | The separator to append after each event's byte stream | no       | `\n`          |
+//! The separator can be any sequence of bytes, e.g. `\r\n` or `\0`, or be empty.
+//! With `prefix` set to `true` the separator is written before each event's byte stream instead.
+//!
+//! | Option      | Description                                                          | Required | Default Value |
+//! |-------------|----------------------------------------------------------------------|----------|---------------|
+//! | `separator` | The separator to append after each event's byte stream               | no       | `\n`          |
+//! | `prefix`    | Write the separator before instead of after each event's byte stream | no       | `false`       |
 
 use super::Postprocessor;
-use crate::errors::{Kind as ErrorKind, Result};
+use crate::errors::Result;
 use crate::preprocessor::separate::{default_separator, DEFAULT_SEPARATOR};
 use tremor_pipeline::{ConfigImpl, ConfigMap};
 
@@ -28,41 +32,37 @@ use tremor_pipeline::{ConfigImpl, ConfigMap};
 pub struct Config {
     #[serde(default = "default_separator")]
     separator: String,
+    #[serde(default)]
+    prefix: bool,
 }
 
 impl ConfigImpl for Config {}
 
 pub(crate) struct Separate {
-    separator: u8,
+    separator: Vec<u8>,
+    prefix: bool,
 }
 
 impl Default for Separate {
     fn default() -> Self {
         Self {
-            separator: DEFAULT_SEPARATOR,
+            separator: vec![DEFAULT_SEPARATOR],
+            prefix: false,
         }
     }
 }
 
 impl Separate {
     pub(super) fn from_config(config: &ConfigMap) -> Result<Self> {
-        let separator = if let Some(raw_config) = config {
+        if let Some(raw_config) = config {
             let config = Config::new(raw_config)?;
-            if config.separator.len() != 1 {
-                return Err(ErrorKind::InvalidConfiguration(
-                    String::from("separate postprocessor"),
-                    format!(
-                        "Invalid 'separator': \"{}\", must be 1 byte.",
-                        config.separator
-                    ),
-                )
-                .into());
-            }
-            config.separator.as_bytes()[0]
+            Ok(Self {
+                separator: config.separator.into_bytes(),
+                prefix: config.prefix,
+            })
         } else {
-            DEFAULT_SEPARATOR
-        };
-        Ok(Self { separator })
+            Ok(Self::default())
+        }
     }
 }
 
@@ -72,10 +72,14 @@ impl Postprocessor for Separate {
     }
 
     fn process(&mut self, _ingres_ns: u64, _egress_ns: u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
-        // padding capacity with 1 to account for the new line char we will be pushing
-        let mut framed: Vec<u8> = Vec::with_capacity(data.len() + 1);
-        framed.extend_from_slice(data);
-        framed.push(self.separator);
+        let mut framed: Vec<u8> = Vec::with_capacity(data.len() + self.separator.len());
+        if self.prefix {
+            framed.extend_from_slice(&self.separator);
+            framed.extend_from_slice(data);
+        } else {
+            framed.extend_from_slice(data);
+            framed.extend_from_slice(&self.separator);
+        }
         Ok(vec![framed])
     }
 }
@@ -115,15 +119,80 @@ mod tests {
     }
 
     #[test]
-    fn from_config_invalid_separator() {
+    fn separate_postprocessor_multi_byte() -> Result<()> {
+        let config = Some(literal!({
+            "separator": "\r\n"
+        }));
+        let mut separate = Separate::from_config(&config)?;
+        assert_eq!(
+            Ok(vec![b"foob\r\n".to_vec()]),
+            separate.process(0, 0, b"foob")
+        );
+
+        let config = Some(literal!({
+            "separator": "<EOT>"
+        }));
+        let mut separate = Separate::from_config(&config)?;
+        assert_eq!(
+            Ok(vec![b"foob<EOT>".to_vec()]),
+            separate.process(0, 0, b"foob")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn separate_postprocessor_null_byte() -> Result<()> {
+        let config = Some(literal!({
+            "separator": "\0"
+        }));
+        let mut separate = Separate::from_config(&config)?;
+        assert_eq!(
+            Ok(vec![b"foob\0".to_vec()]),
+            separate.process(0, 0, b"foob")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn separate_postprocessor_empty() -> Result<()> {
         let config = Some(literal!({
-            "separator": "abc"
+            "separator": ""
         }));
-        let res = Separate::from_config(&config)
-            .err()
-            .map(|e| e.to_string())
-            .unwrap_or_default();
+        let mut separate = Separate::from_config(&config)?;
+        assert_eq!(Ok(vec![b"foob".to_vec()]), separate.process(0, 0, b"foob"));
+        Ok(())
+    }
 
-        assert_eq!("Invalid Configuration for separate postprocessor: Invalid 'separator': \"abc\", must be 1 byte.", res);
+    #[test]
+    fn separate_postprocessor_prefix() -> Result<()> {
+        let config = Some(literal!({
+            "separator": "\r\n",
+            "prefix": true
+        }));
+        let mut separate = Separate::from_config(&config)?;
+        assert_eq!(
+            Ok(vec![b"\r\nfoob".to_vec()]),
+            separate.process(0, 0, b"foob")
+        );
+        assert_eq!(Ok(vec![b"\r\n".to_vec()]), separate.process(0, 0, b""));
+
+        let config = Some(literal!({
+            "prefix": true
+        }));
+        let mut separate = Separate::from_config(&config)?;
+        assert_eq!(
+            Ok(vec![b"\nfoob".to_vec()]),
+            separate.process(0, 0, b"foob")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_config_invalid() {
+        let config = Some(literal!({
+            "separator": "|",
+            "snot": "badger"
+        }));
+        assert!(Separate::from_config(&config).is_err());
     }
 }
@@ -13,10 +13,19 @@
 // limitations under the License.
 
 mod base64;
+mod chunk;
+mod count_prefixed;
 mod decompress;
+mod decrypt;
+mod dedup;
+mod fixed_width;
+mod framing;
 pub(crate) mod gelf_chunking;
+mod hex;
 mod ingest_ns;
 mod length_prefixed;
+mod msgpack_stream;
+mod regex_split;
 mod remove_empty;
 pub(crate) mod separate;
 mod textual_length_prefixed;
@@ -44,11 +53,21 @@ pub trait Preprocessor: Sync + Send {
     /// Takes a `data` buffer of input data, that is potentially empty,
     /// especially if this is the first preprocessor in a chain.
     ///
+    /// By default `data`, e.g. the last unterminated line emitted by a preceding
+    /// `separate`, is processed like any other input, so it isn't lost. Preprocessors
+    /// buffering data need to emit their remainder as well.
+    ///
+    /// `ingest_ns` is the ingest time of the end of the stream, it can be updated
+    /// like in `process`, e.g. when extracting it from the remaining data.
+    ///
     /// # Errors
     ///
     /// * if finishing fails for some reason lol
-    fn finish(&mut self, _data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
-        Ok(vec![])
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        match data {
+            Some(data) if !data.is_empty() => self.process(ingest_ns, data),
+            _ => Ok(vec![]),
+        }
     }
 }
 
@@ -60,14 +79,39 @@ pub trait Preprocessor: Sync + Send {
 pub fn lookup_with_config(config: &PreprocessorConfig) -> Result<Box<dyn Preprocessor>> {
     match config.name.as_str() {
         "separate" => Ok(Box::new(separate::Separate::from_config(&config.config)?)),
-        "base64" => Ok(Box::<base64::Base64>::default()),
+        "base64" => Ok(Box::new(base64::Base64::from_config(&config.config)?)),
+        "brotli" => Ok(Box::<decompress::Brotli>::default()),
+        "chunk" => Ok(Box::new(chunk::Chunk::from_config(config.config.as_ref())?)),
+        "count-prefixed" => Ok(Box::new(count_prefixed::CountPrefixed::from_config(
+            config.config.as_ref(),
+        )?)),
         "decompress" => Ok(Box::new(decompress::Decompress::from_config(
             config.config.as_ref(),
         )?)),
+        "decrypt" => Ok(Box::new(decrypt::Decrypt::from_config(
+            config.config.as_ref(),
+        )?)),
+        "decompress-auto" => Ok(Box::<decompress::AutoDecompress>::default()),
+        "dedup" => Ok(Box::new(dedup::Dedup::from_config(config.config.as_ref())?)),
+        "fixed-width" => Ok(Box::new(fixed_width::FixedWidth::from_config(
+            config.config.as_ref(),
+        )?)),
+        "framing" => Ok(Box::new(framing::Framing::from_config(
+            config.config.as_ref(),
+        )?)),
+        "gzip" => Ok(Box::<decompress::Gzip>::default()),
+        "hex" => Ok(Box::new(hex::Hex::from_config(&config.config)?)),
+        "regex-split" => Ok(Box::new(regex_split::RegexSplit::from_config(
+            &config.config,
+        )?)),
         "remove-empty" => Ok(Box::<remove_empty::RemoveEmpty>::default()),
         "gelf-chunking" => Ok(Box::<gelf_chunking::GelfChunking>::default()),
         "ingest-ns" => Ok(Box::<ingest_ns::ExtractIngestTs>::default()),
         "length-prefixed" => Ok(Box::<length_prefixed::LengthPrefixed>::default()),
+        "msgpack-streaming" => Ok(Box::<msgpack_stream::MsgPackStream>::default()),
+        "zstd" => Ok(Box::new(decompress::Zstd::from_config(
+            config.config.as_ref(),
+        )?)),
         "textual-length-prefixed" => {
             Ok(Box::<textual_length_prefixed::TextualLengthPrefixed>::default())
         }
@@ -130,9 +174,13 @@ pub fn preprocess(
 /// # Errors
 ///
 /// * If a preprocessor failed
-pub fn finish(preprocessors: &mut [Box<dyn Preprocessor>], alias: &Alias) -> Result<Vec<Vec<u8>>> {
+pub fn finish(
+    preprocessors: &mut [Box<dyn Preprocessor>],
+    ingest_ns: &mut u64,
+    alias: &Alias,
+) -> Result<Vec<Vec<u8>>> {
     if let Some((head, tail)) = preprocessors.split_first_mut() {
-        let mut data = match head.finish(None) {
+        let mut data = match head.finish(ingest_ns, None) {
             Ok(d) => d,
             Err(e) => {
                 error!(
@@ -146,7 +194,7 @@ pub fn finish(preprocessors: &mut [Box<dyn Preprocessor>], alias: &Alias) -> Res
         for pp in tail {
             data1.clear();
             for d in &data {
-                match pp.finish(Some(d)) {
+                match pp.finish(ingest_ns, Some(d)) {
                     Ok(mut r) => data1.append(&mut r),
                     Err(e) => {
                         error!(
@@ -186,7 +234,7 @@ mod test {
             .pop()
             .ok_or("no data")?;
 
-        assert!(pre_p.finish(None)?.is_empty());
+        assert!(pre_p.finish(&mut 0, None)?.is_empty());
 
         assert_eq!(data, decoded);
         assert_eq!(in_ns, 42);
@@ -345,21 +393,29 @@ mod test {
         let processed = preprocess(pps.as_mut_slice(), &mut it, start.to_vec(), &alias)?;
         assert!(processed.is_empty());
         // not emitted upon finish
-        let finished = finish(pps.as_mut_slice(), &alias)?;
+        let finished = finish(pps.as_mut_slice(), &mut it, &alias)?;
         assert!(finished.is_empty());
 
         Ok(())
     }
 
-    const LOOKUP_TABLE: [&str; 8] = [
+    const LOOKUP_TABLE: [&str; 16] = [
         "separate",
         "base64",
+        "brotli",
+        "count-prefixed",
         "decompress",
+        "decompress-auto",
+        "dedup",
+        "gzip",
+        "hex",
         "remove-empty",
         "gelf-chunking",
         "ingest-ns",
         "length-prefixed",
+        "msgpack-streaming",
         "textual-length-prefixed",
+        "zstd",
     ];
 
     #[test]
@@ -377,14 +433,14 @@ mod test {
     fn test_filter_empty() {
         let mut pre = remove_empty::RemoveEmpty::default();
         assert_eq!(Ok(vec![]), pre.process(&mut 0_u64, &[]));
-        assert_eq!(Ok(vec![]), pre.finish(None));
+        assert_eq!(Ok(vec![]), pre.finish(&mut 0, None));
     }
 
     #[test]
     fn test_filter_null() {
         let mut pre = remove_empty::RemoveEmpty::default();
         assert_eq!(Ok(vec![]), pre.process(&mut 0_u64, &[]));
-        assert_eq!(Ok(vec![]), pre.finish(None));
+        assert_eq!(Ok(vec![]), pre.finish(&mut 0, None));
     }
 
     #[test]
@@ -413,7 +469,7 @@ mod test {
         assert_eq!(out, out2);
 
         // assert empty finish, no leftovers
-        assert!(pre.finish(None)?.is_empty());
+        assert!(pre.finish(&mut 0, None)?.is_empty());
         Ok(())
     }
 
@@ -428,7 +484,7 @@ mod test {
             .map(<[u8]>::to_vec)
             .collect::<Vec<_>>();
         assert_eq!(splitted[..splitted.len() - 1].to_vec(), res);
-        let mut finished = pre.finish(None)?;
+        let mut finished = pre.finish(&mut 0, None)?;
         res.append(&mut finished);
         assert_eq!(splitted, res);
         Ok(())
@@ -522,7 +578,70 @@ mod test {
         assert_eq!(&int, &out);
 
         // assert empty finish, no leftovers
-        assert!(pre.finish(None)?.is_empty());
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex() -> Result<()> {
+        let int = b"snot\x00badger\xff";
+        let mut pre = hex::Hex::default();
+        let mut post = post::hex::Hex::default();
+
+        let mut ingest_ns = 0_u64;
+        let enc = post.process(ingest_ns, 1, int)?.pop().unwrap_or_default();
+        assert_eq!(b"736e6f7400626164676572ff".to_vec(), enc);
+        assert_eq!(vec![int.to_vec()], pre.process(&mut ingest_ns, &enc)?);
+
+        // whitespace separated and prefixed groups, e.g. from a hex dump
+        let config = tremor_value::literal!({"ignore_whitespace": true, "allow_prefix": true});
+        let mut pre = hex::Hex::from_config(&Some(config))?;
+        let dump = b"0x736e6f74 0x00 62616467\n6572ff\n";
+        assert_eq!(vec![int.to_vec()], pre.process(&mut ingest_ns, dump)?);
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() -> Result<()> {
+        fn config(
+            key: &'static str,
+            nonce: tremor_value::Value<'static>,
+        ) -> tremor_value::Value<'static> {
+            tremor_value::literal!({"algorithm": "aes-256-gcm", "key": key, "nonce": nonce})
+        }
+        let key = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+        let other_key = "HxwdHBsaGRgXFhUUExIREA8ODQwLCgkIBwYFBAMCAQA=";
+        let nonces = [
+            tremor_value::Value::from("prefix"),
+            tremor_value::literal!({"fixed": "AAECAwQFBgcICQoL"}),
+        ];
+        let int = b"snot\x00badger\xff";
+        let mut ingest_ns = 0_u64;
+        for nonce in nonces {
+            let mut post = post::encrypt::Encrypt::from_config(Some(&config(key, nonce.clone())))?;
+            let mut pre = decrypt::Decrypt::from_config(Some(&config(key, nonce.clone())))?;
+            let enc = post.process(ingest_ns, 1, int)?.pop().unwrap_or_default();
+            assert_ne!(int.to_vec(), enc);
+            assert_eq!(vec![int.to_vec()], pre.process(&mut ingest_ns, &enc)?);
+
+            // flipping any bit, in the nonce, ciphertext or tag, fails the authentication
+            for i in 0..enc.len() {
+                let mut tampered = enc.clone();
+                if let Some(byte) = tampered.get_mut(i) {
+                    *byte ^= 0x01;
+                }
+                let e = pre
+                    .process(&mut ingest_ns, &tampered)
+                    .err()
+                    .ok_or("tampered data was decrypted")?;
+                assert!(e.to_string().contains("Authentication"), "{e}");
+            }
+            // a different key fails as well
+            let mut other = decrypt::Decrypt::from_config(Some(&config(other_key, nonce)))?;
+            assert!(other.process(&mut ingest_ns, &enc).is_err());
+            assert!(pre.finish(&mut 0, None)?.is_empty());
+        }
         Ok(())
     }
 
@@ -535,7 +654,7 @@ mod test {
         fn process(&mut self, _ingest_ns: &mut u64, _data: &[u8]) -> Result<Vec<Vec<u8>>> {
             Err("chucky".into())
         }
-        fn finish(&mut self, _data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        fn finish(&mut self, _ingest_ns: &mut u64, _data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
             Ok(vec![])
         }
     }
@@ -550,7 +669,7 @@ mod test {
             Ok(vec![])
         }
 
-        fn finish(&mut self, _data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        fn finish(&mut self, _ingest_ns: &mut u64, _data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
             Err("chucky revenge".into())
         }
     }
@@ -564,7 +683,7 @@ mod test {
         fn process(&mut self, _ingest_ns: &mut u64, _data: &[u8]) -> Result<Vec<Vec<u8>>> {
             Ok(vec![b"non".to_vec()])
         }
-        fn finish(&mut self, _data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        fn finish(&mut self, _ingest_ns: &mut u64, _data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
             Ok(vec![b"nein".to_vec()])
         }
     }
@@ -578,7 +697,7 @@ mod test {
         let r = pre.process(&mut ingest_ns, b"foo");
         assert!(r.is_err());
 
-        let r = pre.finish(Some(b"foo"));
+        let r = pre.finish(&mut 0, Some(b"foo"));
         assert!(r.is_ok());
     }
 
@@ -591,7 +710,7 @@ mod test {
         let r = pre.process(&mut ingest_ns, b"foo");
         assert!(r.is_ok());
 
-        let r = pre.finish(Some(b"foo"));
+        let r = pre.finish(&mut 0, Some(b"foo"));
         assert!(r.is_err());
     }
 
@@ -628,14 +747,14 @@ mod test {
             crate::system::flow::Alias::new("chucky"),
             "chucky".to_string(),
         );
-        let r = finish(&mut [pre], &alias);
+        let r = finish(&mut [pre], &mut 0, &alias);
         assert!(r.is_ok());
     }
 
     #[test]
     fn direct_pre_finish_err() {
         let mut pre = Box::new(BadFinisher {});
-        let r = pre.finish(Some(b"foo"));
+        let r = pre.finish(&mut 0, Some(b"foo"));
         assert!(r.is_err());
     }
 
@@ -646,7 +765,7 @@ mod test {
             "chucky".to_string(),
         );
         let pre = Box::new(BadFinisher {});
-        let r = finish(&mut [pre], &alias);
+        let r = finish(&mut [pre], &mut 0, &alias);
         assert!(r.is_err());
     }
 
@@ -658,7 +777,7 @@ mod test {
         );
         let noop = Box::new(NoOp {});
         let pre = Box::new(BadFinisher {});
-        let r = finish(&mut [noop, pre], &alias);
+        let r = finish(&mut [noop, pre], &mut 0, &alias);
         assert!(r.is_err());
     }
 
@@ -671,7 +790,41 @@ mod test {
         let noop1 = Box::new(NoOp {});
         let noop2 = Box::new(NoOp {});
         let noop3 = Box::new(NoOp {});
-        let r = finish(&mut [noop1, noop2, noop3], &alias);
+        let r = finish(&mut [noop1, noop2, noop3], &mut 0, &alias);
         assert!(r.is_ok());
     }
+
+    #[test]
+    fn preprocess_finish_trailing_line() -> Result<()> {
+        let alias = crate::connectors::Alias::new(
+            crate::system::flow::Alias::new("xyz"),
+            "xyz".to_string(),
+        );
+        let mut pps = vec![lookup("separate")?, lookup("base64")?];
+        let mut ingest_ns = 0_u64;
+        let r = preprocess(&mut pps, &mut ingest_ns, b"c25vdA==\nYmFk".to_vec(), &alias)?;
+        assert_eq!(vec![b"snot".to_vec()], r);
+        assert!(preprocess(&mut pps, &mut ingest_ns, b"Z2Vy".to_vec(), &alias)?.is_empty());
+        // the line without a trailing newline is emitted and decoded when the stream ends
+        assert_eq!(vec![b"badger".to_vec()], finish(&mut pps, &mut 0, &alias)?);
+        assert!(finish(&mut pps, &mut 0, &alias)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn finish_updates_ingest_ns() -> Result<()> {
+        let alias = Alias::new("test", "test");
+        let mut post_p = post::ingest_ns::IngestNs {};
+        let encoded = post_p.process(42, 23, b"snot")?.pop().ok_or("no data")?;
+        let mut pps = vec![lookup("separate")?, lookup("ingest-ns")?];
+        let mut ingest_ns = 0_u64;
+        assert!(preprocess(&mut pps, &mut ingest_ns, encoded, &alias)?.is_empty());
+        // the ingest time extracted from the remainder of the stream is kept
+        assert_eq!(
+            vec![b"snot".to_vec()],
+            finish(&mut pps, &mut ingest_ns, &alias)?
+        );
+        assert_eq!(42, ingest_ns);
+        Ok(())
+    }
 }
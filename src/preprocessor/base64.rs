@@ -13,19 +13,129 @@
 // limitations under the License.
 
 //! Decodes base64 encoded data to the raw bytes.
+//!
+//! | Option     | Description                                                                                           | Required | Default Value |
+//! |------------|-------------------------------------------------------------------------------------------------------|----------|---------------|
+//! | `alphabet` | The base64 alphabet, either `standard` or `url_safe`                                                  | no       | `standard`    |
+//! | `padding`  | `true` requires padding, `false` forbids it. If not set, both padded and unpadded data is accepted.   | no       |               |
 use super::Preprocessor;
 use crate::Result;
-use base64::Engine;
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
 use tremor_common::base64::BASE64;
+use tremor_pipeline::{ConfigImpl, ConfigMap};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Alphabet {
+    #[default]
+    Standard,
+    UrlSafe,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default)]
+    alphabet: Alphabet,
+    #[serde(default)]
+    padding: Option<bool>,
+}
+
+impl ConfigImpl for Config {}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Base64 {
+    engine: GeneralPurpose,
+}
+
+impl Default for Base64 {
+    fn default() -> Self {
+        Self { engine: BASE64 }
+    }
+}
+
+impl Base64 {
+    pub(crate) fn from_config(config: &ConfigMap) -> Result<Self> {
+        if let Some(raw_config) = config {
+            let config = Config::new(raw_config)?;
+            let alphabet = match config.alphabet {
+                Alphabet::Standard => &alphabet::STANDARD,
+                Alphabet::UrlSafe => &alphabet::URL_SAFE,
+            };
+            let padding_mode = match config.padding {
+                Some(true) => DecodePaddingMode::RequireCanonical,
+                Some(false) => DecodePaddingMode::RequireNone,
+                None => DecodePaddingMode::Indifferent,
+            };
+            let engine_config = GeneralPurposeConfig::new().with_decode_padding_mode(padding_mode);
+            Ok(Self {
+                engine: GeneralPurpose::new(alphabet, engine_config),
+            })
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
 
-#[derive(Clone, Default, Debug)]
-pub(crate) struct Base64 {}
 impl Preprocessor for Base64 {
     fn name(&self) -> &str {
         "base64"
     }
 
     fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
-        Ok(vec![BASE64.decode(data)?])
+        Ok(vec![self.engine.decode(data)?])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    fn decode(config: &ConfigMap, data: &str) -> Result<Vec<u8>> {
+        let mut pre = Base64::from_config(config)?;
+        let mut ingest_ns = 0_u64;
+        Ok(pre
+            .process(&mut ingest_ns, data.as_bytes())?
+            .pop()
+            .unwrap_or_default())
+    }
+
+    #[test]
+    fn url_safe_unpadded() -> Result<()> {
+        let config = Some(literal!({"alphabet": "url_safe", "padding": false}));
+        // `-` and `_` are only valid in the url safe alphabet
+        assert_eq!(vec![0xfb_u8, 0xff, 0xbf], decode(&config, "-_-_")?);
+        assert_eq!(b"snot badger".to_vec(), decode(&config, "c25vdCBiYWRnZXI")?);
+        assert!(decode(&config, "c25vdCBiYWRnZXI=").is_err());
+        assert!(decode(&config, "+/+/").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn standard_padded() -> Result<()> {
+        let config = Some(literal!({"alphabet": "standard", "padding": true}));
+        assert_eq!(
+            b"snot badger".to_vec(),
+            decode(&config, "c25vdCBiYWRnZXI=")?
+        );
+        assert!(decode(&config, "c25vdCBiYWRnZXI").is_err());
+        assert!(decode(&config, "-_-_").is_err());
+
+        // the default accepts both padded and unpadded data
+        assert_eq!(b"snot badger".to_vec(), decode(&None, "c25vdCBiYWRnZXI=")?);
+        assert_eq!(b"snot badger".to_vec(), decode(&None, "c25vdCBiYWRnZXI")?);
+        assert_eq!(vec![0xfb_u8, 0xff, 0xbf], decode(&None, "+/+/")?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config() {
+        assert!(Base64::from_config(&Some(literal!({"alphabet": "snot"}))).is_err());
+        assert!(Base64::from_config(&Some(literal!({"badger": true}))).is_err());
     }
 }
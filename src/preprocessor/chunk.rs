@@ -0,0 +1,200 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Splits the input into chunks of a fixed number of bytes, e.g. for protocols reading fixed-length records.
+//!
+//! Incoming data is buffered across fragments, every full chunk is emitted as a separate event. A trailing
+//! partial chunk is held back until more data arrives, it is emitted as is when the stream finishes.
+//!
+//! ## Configuration
+//!
+//! | Option | Description                           | Required | Default Value |
+//! |--------|---------------------------------------|----------|---------------|
+//! | `size` | The number of bytes in a single chunk | yes      |               |
+//!
+//! ## Example
+//!
+//! Read 512 byte blocks from a tcp stream:
+//!
+//! ```tremor
+//! define connector blocks from tcp_server
+//! with
+//!     codec = "binary",
+//!     preprocessors = [
+//!         {
+//!             "name": "chunk",
+//!             "config": {
+//!                 "size": 512
+//!             }
+//!         }
+//!     ],
+//!     config = {
+//!         "url": "localhost:4242"
+//!     }
+//! end;
+//! ```
+
+use super::Preprocessor;
+use crate::errors::{Error, Kind as ErrorKind, Result};
+use tremor_value::Value;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    size: usize,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Chunk {
+    size: usize,
+    buffer: Vec<u8>,
+}
+
+impl Chunk {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            buffer: Vec::with_capacity(size),
+        }
+    }
+
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Self> {
+        if let Some(config) = config {
+            let config: Config = tremor_value::structurize(config.clone()).map_err(|e| {
+                let kind = ErrorKind::InvalidConfiguration(
+                    "\"chunk\" preprocessor".to_string(),
+                    e.to_string(),
+                );
+                Error::with_chain(e, kind)
+            })?;
+            if config.size == 0 {
+                return Err(ErrorKind::InvalidConfiguration(
+                    "\"chunk\" preprocessor".to_string(),
+                    "`size` must be > 0".to_string(),
+                )
+                .into());
+            }
+            Ok(Chunk::new(config.size))
+        } else {
+            Err(ErrorKind::MissingConfiguration("\"chunk\" preprocessor".to_string()).into())
+        }
+    }
+}
+
+impl Preprocessor for Chunk {
+    fn name(&self) -> &str {
+        "chunk"
+    }
+
+    fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut data = data;
+        let mut res = Vec::with_capacity((self.buffer.len() + data.len()) / self.size);
+        // complete the buffered partial chunk first
+        if !self.buffer.is_empty() {
+            let missing = self.size - self.buffer.len();
+            if data.len() < missing {
+                self.buffer.extend_from_slice(data);
+                return Ok(res);
+            }
+            let (head, tail) = data.split_at(missing);
+            self.buffer.extend_from_slice(head);
+            res.push(std::mem::replace(
+                &mut self.buffer,
+                Vec::with_capacity(self.size),
+            ));
+            data = tail;
+        }
+        let mut chunks = data.chunks_exact(self.size);
+        res.extend(chunks.by_ref().map(<[u8]>::to_vec));
+        self.buffer.extend_from_slice(chunks.remainder());
+        Ok(res)
+    }
+
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        let mut res = match data {
+            Some(data) => self.process(ingest_ns, data)?,
+            None => vec![],
+        };
+        if !self.buffer.is_empty() {
+            res.push(std::mem::take(&mut self.buffer));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tremor_value::literal;
+
+    #[test]
+    fn from_config() {
+        assert!(Chunk::from_config(None).is_err());
+        assert!(Chunk::from_config(Some(&literal!({"size": 0}))).is_err());
+        assert!(Chunk::from_config(Some(&literal!({"size": "snot"}))).is_err());
+        assert!(Chunk::from_config(Some(&literal!({"size": 4, "snot": 1}))).is_err());
+        assert!(Chunk::from_config(Some(&literal!({"size": 4}))).is_ok());
+    }
+
+    #[test]
+    fn unaligned_fragments() -> Result<()> {
+        let mut pp = Chunk::from_config(Some(&literal!({"size": 4})))?;
+        let mut ingest_ns = 0;
+        assert!(pp.process(&mut ingest_ns, b"sn")?.is_empty());
+        assert!(pp.process(&mut ingest_ns, b"o")?.is_empty());
+        assert_eq!(
+            vec![b"snot".to_vec(), b"badg".to_vec()],
+            pp.process(&mut ingest_ns, b"tbadgerf")?
+        );
+        assert!(pp.process(&mut ingest_ns, b"")?.is_empty());
+        assert_eq!(
+            vec![b"erfl".to_vec(), b"ork!".to_vec()],
+            pp.process(&mut ingest_ns, b"lork!")?
+        );
+        assert!(pp.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn finish_emits_remainder() -> Result<()> {
+        let mut pp = Chunk::from_config(Some(&literal!({"size": 3})))?;
+        let mut ingest_ns = 0;
+        assert_eq!(vec![b"sno".to_vec()], pp.process(&mut ingest_ns, b"snotb")?);
+        assert_eq!(
+            vec![b"tba".to_vec(), b"dge".to_vec(), b"r".to_vec()],
+            pp.finish(&mut 0, Some(b"adger"))?
+        );
+        assert!(pp.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn arbitrary_fragments() -> Result<()> {
+        let data: Vec<u8> = (0..=255).collect();
+        for size in [1, 7, 16, 100, 300] {
+            for fragment in [1, 3, 16, 99, 256] {
+                let mut pp = Chunk::new(size);
+                let mut ingest_ns = 0;
+                let mut chunks = Vec::new();
+                for f in data.chunks(fragment) {
+                    chunks.append(&mut pp.process(&mut ingest_ns, f)?);
+                }
+                chunks.append(&mut pp.finish(&mut 0, None)?);
+                let expected: Vec<Vec<u8>> = data.chunks(size).map(<[u8]>::to_vec).collect();
+                assert_eq!(expected, chunks);
+            }
+        }
+        Ok(())
+    }
+}
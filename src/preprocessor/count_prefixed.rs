@@ -0,0 +1,390 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Splits batches of records prefixed with the number of records they contain, as used by protocols
+//! sending a count of records instead of the length of the batch in bytes.
+//!
+//! Each batch starts with the `count`, either an unsigned LEB128 varint or a fixed width unsigned
+//! big endian integer, followed by that many `records`. Records are either terminated by a newline
+//! or prefixed by their length as a 64 bit unsigned big endian integer, like for the `length-prefixed`
+//! preprocessor. Every record is emitted as a separate event, but only once the whole batch arrived,
+//! so batches are buffered until they are complete.
+//!
+//! A varint count longer than 10 bytes or exceeding 64 bits can't be recovered from, it is reported
+//! as an error and all buffered data is dropped. At the end of the stream, the last record of a batch
+//! of newline terminated records doesn't need a trailing newline, any other incomplete batch is dropped
+//! and reported as an error.
+//!
+//! ## Configuration
+//!
+//! | Option    | Description                                                                   | Required | Default Value |
+//! |-----------|-------------------------------------------------------------------------------|----------|---------------|
+//! | `count`   | The encoding of the count: `varint`, `u8`, `u16`, `u32` or `u64`              | no       | `varint`      |
+//! | `records` | How records are delimited: `lines` if newline terminated or `length-prefixed` | no       | `lines`       |
+//!
+//! ## Example
+//!
+//! ```tremor
+//! define connector batches from tcp_server
+//! with
+//!     codec = "json",
+//!     preprocessors = [
+//!         {
+//!             "name": "count-prefixed",
+//!             "config": {
+//!                 "count": "u16",
+//!                 "records": "lines"
+//!             }
+//!         }
+//!     ],
+//!     config = {
+//!         "url": "localhost:4242"
+//!     }
+//! end;
+//! ```
+
+use super::Preprocessor;
+use crate::errors::{Error, Kind as ErrorKind, Result};
+use byteorder::{BigEndian, ByteOrder};
+use tremor_value::Value;
+
+/// The maximum length of a varint encoding a `u64`
+const MAX_VARINT_LEN: usize = 10;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum Count {
+    #[default]
+    Varint,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl Count {
+    /// The count at the start of `data` and the number of bytes it takes, `None` if it is incomplete
+    fn read(self, data: &[u8]) -> Result<Option<(u64, usize)>> {
+        let width = match self {
+            Self::Varint => return read_varint(data),
+            Self::U8 => 1,
+            Self::U16 => 2,
+            Self::U32 => 4,
+            Self::U64 => 8,
+        };
+        Ok(data
+            .get(..width)
+            .map(|bytes| (BigEndian::read_uint(bytes, width), width)))
+    }
+}
+
+/// An unsigned LEB128 varint at the start of `data`, `None` if it is incomplete
+fn read_varint(data: &[u8]) -> Result<Option<(u64, usize)>> {
+    let mut value = 0_u64;
+    for (i, byte) in data.iter().take(MAX_VARINT_LEN).enumerate() {
+        let bits = u64::from(byte & 0x7f);
+        // the 10th byte holds only the highest bit
+        if i == MAX_VARINT_LEN - 1 && bits > 1 {
+            return Err("Invalid count, the varint exceeds 64 bits".into());
+        }
+        value |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(Some((value, i + 1)));
+        }
+    }
+    if data.len() >= MAX_VARINT_LEN {
+        Err(format!("Invalid count, the varint is longer than {MAX_VARINT_LEN} bytes").into())
+    } else {
+        Ok(None)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+enum Records {
+    #[default]
+    Lines,
+    LengthPrefixed,
+}
+
+impl Records {
+    /// The range of the record at the start of `data` and its total length including the
+    /// delimiter, `None` if it is incomplete
+    fn read(self, data: &[u8]) -> Result<Option<(std::ops::Range<usize>, usize)>> {
+        match self {
+            Self::Lines => Ok(data
+                .iter()
+                .position(|b| *b == b'\n')
+                .map(|end| (0..end, end + 1))),
+            Self::LengthPrefixed => {
+                if data.len() < 8 {
+                    return Ok(None);
+                }
+                let len = usize::try_from(BigEndian::read_u64(data))
+                    .map_err(|_| Error::from("Invalid record, its length exceeds the memory"))?;
+                let end = len
+                    .checked_add(8)
+                    .ok_or_else(|| Error::from("Invalid record, its length exceeds the memory"))?;
+                Ok((data.len() >= end).then_some((8..end, end)))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default = "Default::default")]
+    count: Count,
+    #[serde(default = "Default::default")]
+    records: Records,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CountPrefixed {
+    config: Config,
+    /// the records of the current batch still to come, `None` before its count is read
+    remaining: Option<u64>,
+    /// the records of the current batch read so far
+    records: Vec<Vec<u8>>,
+    /// data not read yet
+    buffer: Vec<u8>,
+}
+
+impl CountPrefixed {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Self> {
+        let config: Config = if let Some(config) = config {
+            tremor_value::structurize(config.clone()).map_err(|e| {
+                let kind = ErrorKind::InvalidConfiguration(
+                    "\"count-prefixed\" preprocessor".to_string(),
+                    e.to_string(),
+                );
+                Error::with_chain(e, kind)
+            })?
+        } else {
+            Config::default()
+        };
+        Ok(Self {
+            config,
+            ..Self::default()
+        })
+    }
+
+    fn reset(&mut self) {
+        self.remaining = None;
+        self.records.clear();
+        self.buffer.clear();
+    }
+
+    /// Reads counts and records from the buffer, returning the records of completed batches
+    fn read(&mut self) -> Result<Vec<Vec<u8>>> {
+        let mut res = Vec::new();
+        let mut start = 0;
+        loop {
+            let data = &self.buffer[start..];
+            match self.remaining {
+                None => match self.config.count.read(data)? {
+                    Some((count, len)) => {
+                        self.remaining = Some(count);
+                        start += len;
+                    }
+                    None => break,
+                },
+                Some(0) => {
+                    res.append(&mut self.records);
+                    self.remaining = None;
+                }
+                Some(remaining) => match self.config.records.read(data)? {
+                    Some((record, len)) => {
+                        self.records.push(data[record].to_vec());
+                        self.remaining = Some(remaining - 1);
+                        start += len;
+                    }
+                    None => break,
+                },
+            }
+        }
+        self.buffer.drain(..start);
+        Ok(res)
+    }
+}
+
+impl Preprocessor for CountPrefixed {
+    fn name(&self) -> &str {
+        "count-prefixed"
+    }
+
+    fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        self.buffer.extend_from_slice(data);
+        self.read().map_err(|e| {
+            // we cannot find the start of the next batch anymore
+            self.reset();
+            e
+        })
+    }
+
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        let mut res = self.process(ingest_ns, data.unwrap_or_default())?;
+        // the last line of the stream might not be terminated
+        if self.config.records == Records::Lines
+            && self.remaining == Some(1)
+            && !self.buffer.is_empty()
+        {
+            self.records.push(std::mem::take(&mut self.buffer));
+            res.append(&mut self.records);
+            self.remaining = None;
+        }
+        if self.remaining.is_some() || !self.buffer.is_empty() {
+            let missing = self.remaining.unwrap_or_default();
+            let left = self.buffer.len();
+            self.reset();
+            return Err(format!(
+                "Incomplete batch at the end of the stream, {missing} records missing and {left} bytes left"
+            )
+            .into());
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    fn count_prefixed(config: &Value) -> Result<CountPrefixed> {
+        CountPrefixed::from_config(Some(config))
+    }
+
+    fn length_prefixed(record: &[u8]) -> Vec<u8> {
+        let mut data = (record.len() as u64).to_be_bytes().to_vec();
+        data.extend_from_slice(record);
+        data
+    }
+
+    #[test]
+    fn from_config() -> Result<()> {
+        let pre = CountPrefixed::from_config(None)?;
+        assert_eq!("count-prefixed", pre.name());
+        assert_eq!(Count::Varint, pre.config.count);
+        assert_eq!(Records::Lines, pre.config.records);
+        let pre = count_prefixed(&literal!({"count": "u32", "records": "length-prefixed"}))?;
+        assert_eq!(Count::U32, pre.config.count);
+        assert_eq!(Records::LengthPrefixed, pre.config.records);
+        assert!(count_prefixed(&literal!({"count": "i32"})).is_err());
+        assert!(count_prefixed(&literal!({"records": "csv"})).is_err());
+        assert!(count_prefixed(&literal!({"snot": "badger"})).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn batch_split_across_reads() -> Result<()> {
+        let data = b"\x03snot\nbadger\n\n\x01flork\n";
+        for split in 0..=data.len() {
+            let mut pre = CountPrefixed::from_config(None)?;
+            let mut res = pre.process(&mut 0, &data[..split])?;
+            // no record is emitted before its batch is complete
+            assert!([0, 3, 4].contains(&res.len()), "split at {split}: {res:?}");
+            res.append(&mut pre.process(&mut 0, &data[split..])?);
+            assert!(pre.finish(&mut 0, None)?.is_empty());
+            assert_eq!(
+                vec![
+                    b"snot".to_vec(),
+                    b"badger".to_vec(),
+                    vec![],
+                    b"flork".to_vec()
+                ],
+                res
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn count_exceeding_data() -> Result<()> {
+        let mut pre = count_prefixed(&literal!({"count": "u16", "records": "length-prefixed"}))?;
+        let mut data = vec![0, 3];
+        data.extend(length_prefixed(b"snot"));
+        data.extend(length_prefixed(b"badger"));
+        // the third record is missing, the batch is buffered
+        assert!(pre.process(&mut 0, &data)?.is_empty());
+        assert_eq!(2, pre.records.len());
+        assert_eq!(
+            vec![b"snot".to_vec(), b"badger".to_vec(), b"flork".to_vec()],
+            pre.process(&mut 0, &length_prefixed(b"flork"))?
+        );
+        // incomplete at the end of the stream
+        assert!(pre.process(&mut 0, &[0, 3])?.is_empty());
+        assert!(pre.finish(&mut 0, Some(&length_prefixed(b"snot"))).is_err());
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn counts() -> Result<()> {
+        let records = b"a\nb\n";
+        for (count, prefix) in [
+            ("varint", vec![2]),
+            ("u8", vec![2]),
+            ("u16", vec![0, 2]),
+            ("u32", vec![0, 0, 0, 2]),
+            ("u64", vec![0, 0, 0, 0, 0, 0, 0, 2]),
+        ] {
+            let mut pre = count_prefixed(&literal!({ "count": count }))?;
+            let mut data = prefix;
+            data.extend_from_slice(records);
+            assert_eq!(
+                vec![b"a".to_vec(), b"b".to_vec()],
+                pre.process(&mut 0, &data)?
+            );
+        }
+        // a multi byte varint
+        let mut pre = CountPrefixed::from_config(None)?;
+        let mut data = vec![0xac, 0x02];
+        data.extend(b"\n".repeat(300));
+        assert_eq!(300, pre.process(&mut 0, &data)?.len());
+        // empty batches
+        assert!(pre.process(&mut 0, &[0, 0])?.is_empty());
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn unterminated_last_line() -> Result<()> {
+        let mut pre = CountPrefixed::from_config(None)?;
+        assert!(pre.process(&mut 0, b"\x02snot\nbadger")?.is_empty());
+        assert_eq!(
+            vec![b"snot".to_vec(), b"badger".to_vec()],
+            pre.finish(&mut 0, None)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_count() -> Result<()> {
+        let mut pre = CountPrefixed::from_config(None)?;
+        assert!(pre.process(&mut 0, &[0xff; 11]).is_err());
+        // the buffered data is dropped
+        assert_eq!(vec![b"snot".to_vec()], pre.process(&mut 0, b"\x01snot\n")?);
+        // exceeding 64 bits
+        let mut data = vec![0xff; 9];
+        data.push(0x02);
+        assert!(pre.process(&mut 0, &data).is_err());
+        let mut data = vec![0xff; 9];
+        data.push(0x01);
+        assert!(pre.process(&mut 0, &data)?.is_empty());
+        assert_eq!(Some(u64::MAX), pre.remaining);
+        Ok(())
+    }
+}
@@ -18,17 +18,26 @@
 //!
 //!Supported formats:
 //!
+//!## brotli
+//!
+//!Decompress [Brotli](https://www.rfc-editor.org/rfc/rfc7932) compressed payload. Brotli has no magic bytes, so it
+//!can't be detected automatically. A stream split across multiple messages is buffered and emitted once it is
+//!complete, invalid streams fail without emitting any of their data. This decompressor is also available as the
+//!`brotli` preprocessor.
+//!
 //!## gzip
 //!
-//!Decompress GZ compressed payload.
+//!Decompress GZ compressed payload, consisting of one or more gzip members. With an explicit `algorithm` a member
+//!split across multiple messages is buffered until it is complete. This decompressor is also available as
+//!the `gzip` preprocessor.
 //!
 //!## lz4
 //!
-//!Decompress Lz4 compressed payload.
+//!Decompress Lz4 compressed payload, either in the lz4 frame format or as a raw block prefixed with its uncompressed size as 32 bit little-endian integer.
 //!
 //!## snappy
 //!
-//!Decompress framed snappy compressed payload (does not support raw snappy).
+//!Decompress framed or raw snappy compressed payload. Raw snappy is only supported with an explicit `algorithm`, it can't be detected automatically.
 //!
 //!## xz
 //!
@@ -40,31 +49,90 @@
 //!
 //!## zstd
 //!
-//!Decompress [Zstandard](https://datatracker.ietf.org/doc/html/rfc8878) compressed payload.
+//!Decompress [Zstandard](https://datatracker.ietf.org/doc/html/rfc8878) compressed payload. With an explicit `algorithm`
+//!a frame split across multiple messages is decompressed as it arrives, the frames can be compressed with the
+//!`dictionary` file given in the config. This decompressor is also available as the `zstd` preprocessor.
 //!
 //!## zlib
 //!
 //!Decompress Zlib (deflate) compressed payload.
+//!
+//!# decompress-auto
+//!
+//!The `decompress-auto` preprocessor detects the format once per stream, from the magic bytes the stream starts
+//!with. Leading bytes are buffered until the format can be told apart. Gzip, zstd, xz and lz4 frames are decompressed,
+//!streams in any other format are passed through unchanged.
 
 use super::Preprocessor;
 use crate::Result;
+use brotli::{enc::StandardAlloc, BrotliDecompressStream, BrotliResult, BrotliState};
 use simd_json::ValueAccess;
-use std::io::{self, Read};
+use std::io::Read;
 use tremor_value::Value;
 
+/// Decompresses one or more gzip members, buffering a trailing partial member
 #[derive(Clone, Default, Debug)]
-struct Gzip {}
+pub(crate) struct Gzip {
+    buffer: Vec<u8>,
+}
+
+/// Decompress the first gzip member in `data`, returning it and the data following it
+fn decompress_gzip_member(data: &[u8]) -> std::io::Result<(Vec<u8>, &[u8])> {
+    use libflate::gzip::Decoder;
+    let mut decoder = Decoder::new(data)?;
+    let mut decompressed = Vec::new();
+    decoder.read_to_end(&mut decompressed)?;
+    Ok((decompressed, decoder.into_inner()))
+}
+
 impl Preprocessor for Gzip {
     fn name(&self) -> &str {
         "gzip"
     }
 
     fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
-        use libflate::gzip::MultiDecoder;
-        let mut decoder = MultiDecoder::new(data)?;
+        self.buffer.extend_from_slice(data);
         let mut decompressed = Vec::new();
-        decoder.read_to_end(&mut decompressed)?;
-        Ok(vec![decompressed])
+        let mut rest = self.buffer.as_slice();
+        let res = loop {
+            if rest.is_empty() {
+                break Ok(());
+            }
+            match decompress_gzip_member(rest) {
+                Ok((mut member, remainder)) => {
+                    decompressed.append(&mut member);
+                    rest = remainder;
+                }
+                // the last member is incomplete, wait for more data
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        let consumed = self.buffer.len() - rest.len();
+        if let Err(e) = res {
+            self.buffer.clear();
+            return Err(e.into());
+        }
+        self.buffer.drain(..consumed);
+        if consumed == 0 {
+            Ok(vec![])
+        } else {
+            Ok(vec![decompressed])
+        }
+    }
+
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        let res = match data {
+            Some(data) => self.process(ingest_ns, data)?,
+            None => vec![],
+        };
+        if self.buffer.is_empty() {
+            Ok(res)
+        } else {
+            let len = self.buffer.len();
+            self.buffer.clear();
+            Err(format!("Truncated gzip member: {len} bytes left undecompressed").into())
+        }
     }
 }
 
@@ -108,12 +176,22 @@ impl Preprocessor for Snappy {
     }
 
     fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        Ok(vec![decompress_snappy(data)?])
+    }
+}
+
+/// the stream identifier chunk every framed snappy stream starts with
+const SNAPPY_STREAM_IDENTIFIER: &[u8] = b"\xff\x06\x00\x00sNaPpY";
+
+fn decompress_snappy(data: &[u8]) -> Result<Vec<u8>> {
+    if data.starts_with(SNAPPY_STREAM_IDENTIFIER) {
         use snap::read::FrameDecoder;
         let mut rdr = FrameDecoder::new(data);
-        let decompressed_len = snap::raw::decompress_len(data)?;
-        let mut decompressed = Vec::with_capacity(decompressed_len);
-        io::copy(&mut rdr, &mut decompressed)?;
-        Ok(vec![decompressed])
+        let mut decompressed = Vec::new();
+        rdr.read_to_end(&mut decompressed)?;
+        Ok(decompressed)
+    } else {
+        Ok(snap::raw::Decoder::new().decompress_vec(data)?)
     }
 }
 
@@ -125,23 +203,222 @@ impl Preprocessor for Lz4 {
     }
 
     fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        Ok(vec![decompress_lz4(data)?])
+    }
+}
+
+const LZ4_MAGIC: [u8; 4] = [0x04, 0x22, 0x4D, 0x18];
+
+fn decompress_lz4(data: &[u8]) -> Result<Vec<u8>> {
+    if data.starts_with(&LZ4_MAGIC) {
         use lz4::Decoder;
         let mut decoder = Decoder::new(data)?;
         let mut decompressed = Vec::new();
         decoder.read_to_end(&mut decompressed)?;
-        Ok(vec![decompressed])
+        // reading a truncated frame just stops, finishing tells us if we saw the whole frame
+        let (_, res) = decoder.finish();
+        res?;
+        Ok(decompressed)
+    } else {
+        // a raw block prefixed with its uncompressed size
+        Ok(lz4::block::decompress(data, None)?)
+    }
+}
+
+/// size of the buffer zstd decompresses into at once
+const ZSTD_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Decompresses zstd frames, a frame may span multiple messages
+#[derive(Default)]
+pub(crate) struct Zstd {
+    /// optional dictionary the data was compressed with
+    dictionary: Vec<u8>,
+    /// created on first use, the zstd context can't be shared between threads
+    decoder: Option<std::sync::Mutex<zstd::stream::raw::Decoder<'static>>>,
+    /// whether the last message ended within a frame
+    in_frame: bool,
+}
+
+impl Zstd {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Self> {
+        let dictionary = if let Some(path) = config.get_str("dictionary") {
+            std::fs::read(path)
+                .map_err(|e| format!("Unable to read zstd dictionary {path}: {e}"))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            dictionary,
+            ..Self::default()
+        })
+    }
+
+    fn decompress(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        use zstd::stream::raw::{Decoder, InBuffer, Operation, OutBuffer};
+        if self.decoder.is_none() {
+            let decoder = if self.dictionary.is_empty() {
+                Decoder::new()?
+            } else {
+                Decoder::with_dictionary(&self.dictionary)?
+            };
+            self.decoder = Some(std::sync::Mutex::new(decoder));
+        }
+        let decoder = self
+            .decoder
+            .as_mut()
+            .ok_or("zstd decoder missing")?
+            .get_mut()
+            .map_err(|_| "zstd decoder poisoned")?;
+        let mut input = InBuffer::around(data);
+        let mut buffer = [0_u8; ZSTD_BUFFER_SIZE];
+        let mut decompressed = Vec::new();
+        loop {
+            let (hint, written) = {
+                let mut output = OutBuffer::around(&mut buffer[..]);
+                (decoder.run(&mut input, &mut output)?, output.pos())
+            };
+            decompressed.extend_from_slice(&buffer[..written]);
+            // a hint of 0 means the frame was decoded completely
+            self.in_frame = hint != 0;
+            if input.pos() == data.len() && written < ZSTD_BUFFER_SIZE {
+                return Ok(decompressed);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        // the decoder is recreated on the next message
+        self.decoder = None;
+        self.in_frame = false;
     }
 }
 
-#[derive(Clone, Debug, Default)]
-struct Zstd {}
 impl Preprocessor for Zstd {
     fn name(&self) -> &str {
-        "ztd"
+        "zstd"
     }
+
     fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
-        let decoded: Vec<u8> = zstd::decode_all(data)?;
-        Ok(vec![decoded])
+        if data.is_empty() {
+            return Ok(vec![]);
+        }
+        match self.decompress(data) {
+            Ok(decompressed) if decompressed.is_empty() => Ok(vec![]),
+            Ok(decompressed) => Ok(vec![decompressed]),
+            Err(e) => {
+                self.reset();
+                Err(e)
+            }
+        }
+    }
+
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        let res = match data {
+            Some(data) => self.process(ingest_ns, data)?,
+            None => vec![],
+        };
+        if self.in_frame {
+            self.reset();
+            Err("Truncated zstd frame at the end of the stream".into())
+        } else {
+            Ok(res)
+        }
+    }
+}
+
+/// size of the buffer brotli decompresses into at once
+const BROTLI_BUFFER_SIZE: usize = 8 * 1024;
+
+type BrotliDecoder = BrotliState<StandardAlloc, StandardAlloc, StandardAlloc>;
+
+/// Decompresses brotli streams, a stream may span multiple messages and is emitted once it is complete
+#[derive(Default)]
+pub(crate) struct Brotli {
+    /// the decoder of the stream in progress, created on its first message
+    decoder: Option<Box<BrotliDecoder>>,
+    /// the data decompressed so far of the stream in progress
+    decompressed: Vec<u8>,
+}
+
+impl Brotli {
+    fn decompress(&mut self, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut res = Vec::new();
+        let mut available_in = data.len();
+        let mut input_offset = 0;
+        let mut buffer = [0_u8; BROTLI_BUFFER_SIZE];
+        loop {
+            let decoder = self.decoder.get_or_insert_with(|| {
+                Box::new(BrotliState::new(
+                    StandardAlloc::default(),
+                    StandardAlloc::default(),
+                    StandardAlloc::default(),
+                ))
+            });
+            let mut available_out = buffer.len();
+            let mut output_offset = 0;
+            let mut total_out = 0;
+            let result = BrotliDecompressStream(
+                &mut available_in,
+                &mut input_offset,
+                data,
+                &mut available_out,
+                &mut output_offset,
+                &mut buffer,
+                &mut total_out,
+                decoder.as_mut(),
+            );
+            self.decompressed
+                .extend_from_slice(&buffer[..output_offset]);
+            match result {
+                BrotliResult::NeedsMoreOutput => (),
+                // all data is consumed, the stream continues with the next message
+                BrotliResult::NeedsMoreInput => return Ok(res),
+                BrotliResult::ResultSuccess => {
+                    // the data may continue with another stream
+                    self.decoder = None;
+                    res.push(std::mem::take(&mut self.decompressed));
+                    if available_in == 0 {
+                        return Ok(res);
+                    }
+                }
+                BrotliResult::ResultFailure => return Err("Invalid brotli stream".into()),
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.decoder = None;
+        self.decompressed.clear();
+    }
+}
+
+impl Preprocessor for Brotli {
+    fn name(&self) -> &str {
+        "brotli"
+    }
+
+    fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        if data.is_empty() {
+            return Ok(vec![]);
+        }
+        self.decompress(data).map_err(|e| {
+            // nothing of a broken stream is emitted
+            self.reset();
+            e
+        })
+    }
+
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        let res = match data {
+            Some(data) => self.process(ingest_ns, data)?,
+            None => vec![],
+        };
+        if self.decoder.is_some() {
+            self.reset();
+            Err("Truncated brotli stream at the end of the stream".into())
+        } else {
+            Ok(res)
+        }
     }
 }
 
@@ -176,21 +453,8 @@ impl Preprocessor for Fingerprinted {
                 decoder.read_to_end(&mut decompressed)?;
                 decompressed
             }
-            Some(b"sNaPpY" | &[0xff, _, _, _, _, _]) => {
-                use snap::read::FrameDecoder;
-                let mut rdr = FrameDecoder::new(data);
-                let decompressed_len = snap::raw::decompress_len(data)?;
-                let mut decompressed = Vec::with_capacity(decompressed_len);
-                io::copy(&mut rdr, &mut decompressed)?;
-                decompressed
-            }
-            Some(&[0x04, 0x22, 0x4D, 0x18, _, _]) => {
-                use lz4::Decoder;
-                let mut decoder = Decoder::new(data)?;
-                let mut decompressed = Vec::new();
-                decoder.read_to_end(&mut decompressed)?;
-                decompressed
-            }
+            Some(b"sNaPpY" | &[0xff, _, _, _, _, _]) => decompress_snappy(data)?,
+            Some(&[0x04, 0x22, 0x4D, 0x18, _, _]) => decompress_lz4(data)?,
             // Zstd Magic : 0xFD2FB528 (but little endian)
             Some(&[0x28, 0xb5, 0x2f, 0xfd, _, _]) => zstd::decode_all(data)?,
             _ => data.to_vec(),
@@ -199,6 +463,91 @@ impl Preprocessor for Fingerprinted {
     }
 }
 
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+// Zstd Magic : 0xFD2FB528 (but little endian)
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: [u8; 6] = [0xfd, b'7', b'z', b'X', b'Z', 0x00];
+
+enum AutoState {
+    /// the leading bytes of the stream, until its format is detected
+    Detecting(Vec<u8>),
+    Decompress(Box<dyn Preprocessor>),
+    Passthrough,
+}
+
+impl Default for AutoState {
+    fn default() -> Self {
+        Self::Detecting(Vec::new())
+    }
+}
+
+/// The state for a stream starting with `data`, `None` if more bytes are needed to tell its format
+fn detect(data: &[u8]) -> Option<AutoState> {
+    let decompressor: Box<dyn Preprocessor> = if data.starts_with(&GZIP_MAGIC) {
+        Box::<Gzip>::default()
+    } else if data.starts_with(&ZSTD_MAGIC) {
+        Box::<Zstd>::default()
+    } else if data.starts_with(&XZ_MAGIC) {
+        Box::<Xz2>::default()
+    } else if data.starts_with(&LZ4_MAGIC) {
+        Box::<Lz4>::default()
+    } else if [&GZIP_MAGIC[..], &ZSTD_MAGIC, &XZ_MAGIC, &LZ4_MAGIC]
+        .iter()
+        .any(|magic| magic.starts_with(data))
+    {
+        return None;
+    } else {
+        return Some(AutoState::Passthrough);
+    };
+    Some(AutoState::Decompress(decompressor))
+}
+
+/// Decompresses a stream in the format detected from its magic bytes
+#[derive(Default)]
+pub(crate) struct AutoDecompress {
+    state: AutoState,
+}
+
+impl Preprocessor for AutoDecompress {
+    fn name(&self) -> &str {
+        "decompress-auto"
+    }
+
+    fn process(&mut self, ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        match &mut self.state {
+            AutoState::Decompress(decompressor) => decompressor.process(ingest_ns, data),
+            AutoState::Passthrough => Ok(vec![data.to_vec()]),
+            AutoState::Detecting(buffer) => {
+                buffer.extend_from_slice(data);
+                if let Some(state) = detect(buffer) {
+                    let buffer = std::mem::take(buffer);
+                    self.state = state;
+                    self.process(ingest_ns, &buffer)
+                } else {
+                    Ok(vec![])
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        // the next stream is detected anew
+        match std::mem::take(&mut self.state) {
+            AutoState::Decompress(mut decompressor) => decompressor.finish(ingest_ns, data),
+            AutoState::Passthrough => Ok(data.map(|data| vec![data.to_vec()]).unwrap_or_default()),
+            AutoState::Detecting(mut buffer) => {
+                buffer.extend_from_slice(data.unwrap_or_default());
+                if buffer.is_empty() {
+                    return Ok(vec![]);
+                }
+                // the stream is too short to tell, so it can't be compressed
+                self.state = detect(&buffer).unwrap_or(AutoState::Passthrough);
+                self.finish(ingest_ns, Some(&buffer))
+            }
+        }
+    }
+}
+
 pub(crate) struct Decompress {
     codec: Box<dyn Preprocessor>,
 }
@@ -210,7 +559,8 @@ impl Decompress {
             Some("xz2") => Box::<Xz2>::default(),
             Some("snappy") => Box::<Snappy>::default(),
             Some("lz4") => Box::<Lz4>::default(),
-            Some("zstd") => Box::<Zstd>::default(),
+            Some("zstd") => Box::new(Zstd::from_config(config)?),
+            Some("brotli") => Box::<Brotli>::default(),
             Some("autodetect") | None => Box::<Fingerprinted>::default(),
             Some(other) => return Err(format!("Unknown decompression algorithm: {other}").into()),
         };
@@ -224,6 +574,9 @@ impl Preprocessor for Decompress {
     fn process(&mut self, ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
         self.codec.process(ingest_ns, data)
     }
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        self.codec.finish(ingest_ns, data)
+    }
 }
 
 #[cfg(test)]
@@ -265,7 +618,7 @@ mod test {
         // Assert actual decoded form is as expected
         assert_eq!(&internal, &out);
         // assert empty finish, no leftovers
-        assert!(pre.finish(None)?.is_empty());
+        assert!(pre.finish(&mut 0, None)?.is_empty());
         Ok(())
     }
 
@@ -290,7 +643,7 @@ mod test {
         // Assert actual decoded form is as expected
         assert_eq!(&internal, &out);
         // assert empty finish, no leftovers
-        assert!(pre.finish(None)?.is_empty());
+        assert!(pre.finish(&mut 0, None)?.is_empty());
         Ok(())
     }
 
@@ -301,6 +654,52 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_gzip_multi_member() -> Result<()> {
+        let mut post = post::compress::Gzip::default();
+        let mut data = post.process(0, 0, b"snot")?.remove(0);
+        data.append(&mut post.process(0, 0, b"badger")?.remove(0));
+
+        let mut pre = Gzip::default();
+        let mut ingest_ns = 0;
+        assert_eq!(
+            vec![b"snotbadger".to_vec()],
+            pre.process(&mut ingest_ns, &data)?
+        );
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gzip_partial_member() -> Result<()> {
+        let mut post = post::compress::Gzip::default();
+        let mut data = post.process(0, 0, b"snot")?.remove(0);
+        let second = post.process(0, 0, b"badger")?.remove(0);
+        let split = second.len() / 2;
+        data.extend_from_slice(&second[..split]);
+
+        let mut pre = Gzip::default();
+        let mut ingest_ns = 0;
+        // the first member is emitted, the partial second one is buffered
+        assert_eq!(vec![b"snot".to_vec()], pre.process(&mut ingest_ns, &data)?);
+        // a partial header is buffered as well
+        assert!(pre
+            .process(&mut ingest_ns, &second[split..split + 1])?
+            .is_empty());
+        assert_eq!(
+            vec![b"badger".to_vec()],
+            pre.process(&mut ingest_ns, &second[split + 1..])?
+        );
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+
+        // a truncated member fails on finish
+        assert!(pre.process(&mut ingest_ns, &second[..split])?.is_empty());
+        assert!(pre.finish(&mut 0, None).is_err());
+        // invalid data fails right away
+        assert!(pre.process(&mut ingest_ns, b"snot badger").is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_gzip_fingerprinted() -> Result<()> {
         let int = "snot".as_bytes();
@@ -377,4 +776,244 @@ mod test {
         assert_fingerprinted_symmetric(int, "zstd")?;
         Ok(())
     }
+
+    fn decompress(algorithm: &str, data: &[u8]) -> Result<Vec<u8>> {
+        let config = literal!({ "algorithm": algorithm });
+        let mut pre = super::Decompress::from_config(Some(&config))?;
+        let mut ingest_ns = 0_u64;
+        Ok(pre.process(&mut ingest_ns, data)?.pop().unwrap_or_default())
+    }
+
+    // "snot" as framed snappy, with a single uncompressed chunk
+    const SNAPPY_FRAMED: [u8; 22] = [
+        0xff, 0x06, 0x00, 0x00, b's', b'N', b'a', b'P', b'p', b'Y', 0x01, 0x08, 0x00, 0x00, 0xe0,
+        0x2d, 0x36, 0x71, b's', b'n', b'o', b't',
+    ];
+    // "snot" as raw snappy, a single literal
+    const SNAPPY_RAW: [u8; 6] = [0x04, 0x0c, b's', b'n', b'o', b't'];
+    // "snot" as a lz4 frame, with a single uncompressed block
+    const LZ4_FRAMED: [u8; 19] = [
+        0x04, 0x22, 0x4d, 0x18, 0x60, 0x40, 0x82, 0x04, 0x00, 0x00, 0x80, b's', b'n', b'o', b't',
+        0x00, 0x00, 0x00, 0x00,
+    ];
+    // "snot" as a raw lz4 block prefixed with its uncompressed size
+    const LZ4_RAW: [u8; 9] = [0x04, 0x00, 0x00, 0x00, 0x40, b's', b'n', b'o', b't'];
+
+    #[test]
+    fn test_snappy_fixtures() -> Result<()> {
+        assert_eq!(b"snot".to_vec(), decompress("snappy", &SNAPPY_FRAMED)?);
+        assert_eq!(b"snot".to_vec(), decompress("snappy", &SNAPPY_RAW)?);
+        assert_eq!(b"snot".to_vec(), decompress("autodetect", &SNAPPY_FRAMED)?);
+        // truncated
+        assert!(decompress("snappy", &SNAPPY_FRAMED[..20]).is_err());
+        assert!(decompress("snappy", &SNAPPY_RAW[..4]).is_err());
+        assert!(decompress("autodetect", &SNAPPY_FRAMED[..20]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zstd_streaming() -> Result<()> {
+        let mut post = post::lookup("zstd")?;
+        let mut data = post.process(0, 0, b"snot")?.remove(0);
+        data.append(&mut post.process(0, 0, b"badger")?.remove(0));
+
+        let mut pre = Zstd::default();
+        let mut ingest_ns = 0;
+        // frames spanning multiple messages are decompressed as they arrive
+        let mut decompressed = Vec::new();
+        for b in &data {
+            decompressed.append(&mut pre.process(&mut ingest_ns, &[*b])?);
+        }
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        assert_eq!(b"snotbadger".to_vec(), decompressed.concat());
+
+        // a truncated frame fails on finish
+        let partial = pre.process(&mut ingest_ns, &data[..data.len() - 1])?;
+        assert!(partial.concat().starts_with(b"snot"));
+        assert!(pre.finish(&mut 0, None).is_err());
+        // and the decoder starts anew
+        assert_eq!(
+            b"snotbadger".to_vec(),
+            pre.process(&mut ingest_ns, &data)?.concat()
+        );
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zstd_dictionary() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("zstd.dict");
+        std::fs::write(&path, "flork mork snot badger ".repeat(16))?;
+        let config = literal!({ "dictionary": path.display().to_string() });
+        let data = "snot badger flork mork ".repeat(4);
+
+        let mut post = post::compress::Zstd::from_config(Some(&config))?;
+        let compressed = post.process(0, 0, data.as_bytes())?.remove(0);
+        let mut pre = Zstd::from_config(Some(&config))?;
+        let mut ingest_ns = 0;
+        let (first, second) = compressed.split_at(compressed.len() / 2);
+        let mut decompressed = pre.process(&mut ingest_ns, first)?;
+        decompressed.append(&mut pre.process(&mut ingest_ns, second)?);
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        assert_eq!(data.as_bytes(), decompressed.concat().as_slice());
+
+        // the compress preprocessor takes the dictionary as well
+        let config = literal!({ "algorithm": "zstd", "dictionary": path.display().to_string() });
+        let mut pre = super::Decompress::from_config(Some(&config))?;
+        assert_eq!(
+            data.as_bytes(),
+            pre.process(&mut ingest_ns, &compressed)?
+                .concat()
+                .as_slice()
+        );
+
+        let config = literal!({ "dictionary": "/snot/badger.dict" });
+        assert!(Zstd::from_config(Some(&config)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_brotli() -> Result<()> {
+        let data = "snot badger ".repeat(100);
+        let config = literal!({ "algorithm": "brotli", "quality": 5, "window": 16 });
+        let mut post = post::compress::Compress::from_config(Some(&config))?;
+        let compressed = post.process(0, 0, data.as_bytes())?.remove(0);
+        assert!(compressed.len() < data.len());
+        let mut pre = super::Decompress::from_config(Some(&config))?;
+        let mut ingest_ns = 0;
+        assert_eq!(
+            vec![data.as_bytes().to_vec()],
+            pre.process(&mut ingest_ns, &compressed)?
+        );
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+
+        let mut post = post::lookup("brotli")?;
+        let mut pre = super::lookup("brotli")?;
+        let compressed = post.process(0, 0, b"")?.remove(0);
+        assert_eq!(
+            vec![Vec::<u8>::new()],
+            pre.process(&mut ingest_ns, &compressed)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_brotli_streaming() -> Result<()> {
+        let mut post = post::lookup("brotli")?;
+        let snot = post.process(0, 0, b"snot")?.remove(0);
+        let badger = post.process(0, 0, b"badger")?.remove(0);
+
+        let mut pre = Brotli::default();
+        let mut ingest_ns = 0;
+        // a stream spanning multiple messages is emitted once it is complete
+        let (last, first) = snot.split_last().ok_or("empty stream")?;
+        for b in first {
+            assert!(pre.process(&mut ingest_ns, &[*b])?.is_empty());
+        }
+        assert_eq!(
+            vec![b"snot".to_vec()],
+            pre.process(&mut ingest_ns, &[*last])?
+        );
+        // a message may contain multiple streams
+        let data = [badger.as_slice(), snot.as_slice()].concat();
+        assert_eq!(
+            vec![b"badger".to_vec(), b"snot".to_vec()],
+            pre.process(&mut ingest_ns, &data)?
+        );
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_brotli_truncated() -> Result<()> {
+        let data = "snot badger ".repeat(100);
+        let mut post = post::lookup("brotli")?;
+        let compressed = post.process(0, 0, data.as_bytes())?.remove(0);
+
+        let mut pre = Brotli::default();
+        let mut ingest_ns = 0;
+        let (first, _) = compressed.split_at(compressed.len() - 2);
+        assert!(pre.process(&mut ingest_ns, first)?.is_empty());
+        assert!(pre.finish(&mut 0, None).is_err());
+        // the decoder starts anew
+        assert_eq!(
+            vec![data.as_bytes().to_vec()],
+            pre.finish(&mut 0, Some(&compressed))?
+        );
+        assert!(pre.finish(&mut 0, Some(first)).is_err());
+
+        // invalid streams fail right away, here a meta-block header with its reserved bit set
+        assert!(pre.process(&mut ingest_ns, b"\x3asnot badger").is_err());
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    fn decompress_auto(pre: &mut AutoDecompress, chunks: &[&[u8]]) -> Result<Vec<u8>> {
+        let mut ingest_ns = 0_u64;
+        let mut res = Vec::new();
+        for chunk in chunks {
+            res.append(&mut pre.process(&mut ingest_ns, chunk)?);
+        }
+        res.append(&mut pre.finish(&mut 0, None)?);
+        Ok(res.concat())
+    }
+
+    #[test]
+    fn test_decompress_auto() -> Result<()> {
+        let mut pre = AutoDecompress::default();
+        let gzip = post::compress::Gzip::default()
+            .process(0, 0, b"snot")?
+            .remove(0);
+        // the format is detected once enough bytes arrived
+        let mut ingest_ns = 0_u64;
+        assert!(pre.process(&mut ingest_ns, &gzip[..1])?.is_empty());
+        assert_eq!(
+            b"snot".to_vec(),
+            decompress_auto(&mut pre, &[&gzip[1..5], &gzip[5..]])?
+        );
+
+        let config = literal!({ "algorithm": "zstd" });
+        let zstd = post::compress::Compress::from_config(Some(&config))?
+            .process(0, 0, b"badger")?
+            .remove(0);
+        assert!(pre.process(&mut ingest_ns, &zstd[..3])?.is_empty());
+        assert_eq!(
+            b"badger".to_vec(),
+            decompress_auto(&mut pre, &[&zstd[3..]])?
+        );
+
+        let config = literal!({ "algorithm": "xz2" });
+        let xz = post::compress::Compress::from_config(Some(&config))?
+            .process(0, 0, b"flork")?
+            .remove(0);
+        assert_eq!(b"flork".to_vec(), decompress_auto(&mut pre, &[&xz])?);
+        assert_eq!(b"snot".to_vec(), decompress_auto(&mut pre, &[&LZ4_FRAMED])?);
+
+        // plain data is passed through unchanged
+        assert_eq!(
+            vec![b"snot".to_vec(), b"badger".to_vec()],
+            pre.process(&mut ingest_ns, b"snot")?
+                .into_iter()
+                .chain(pre.process(&mut ingest_ns, b"badger")?)
+                .collect::<Vec<_>>()
+        );
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        // including streams that start like a magic but are too short to tell
+        assert!(pre.process(&mut ingest_ns, &[0x28, 0xb5])?.is_empty());
+        assert_eq!(vec![vec![0x28_u8, 0xb5]], pre.finish(&mut 0, None)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lz4_fixtures() -> Result<()> {
+        assert_eq!(b"snot".to_vec(), decompress("lz4", &LZ4_FRAMED)?);
+        assert_eq!(b"snot".to_vec(), decompress("lz4", &LZ4_RAW)?);
+        assert_eq!(b"snot".to_vec(), decompress("autodetect", &LZ4_FRAMED)?);
+        // truncated
+        assert!(decompress("lz4", &LZ4_FRAMED[..13]).is_err());
+        assert!(decompress("lz4", &LZ4_RAW[..7]).is_err());
+        assert!(decompress("autodetect", &LZ4_FRAMED[..13]).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,72 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decrypts AES-256-GCM encrypted data as produced by the `encrypt` postprocessor and takes the same
+//! configuration. The authentication tag is verified, data that was tampered with or encrypted with
+//! another key or nonce is rejected with an error.
+//!
+//! | Option      | Description                                                               | Required         | Default Value |
+//! |-------------|---------------------------------------------------------------------------|------------------|---------------|
+//! | `algorithm` | The encryption algorithm, only `aes-256-gcm` is supported                 | no               | `aes-256-gcm` |
+//! | `key`       | The base64 encoded 256 bit key                                            | if no `key_file` |               |
+//! | `key_file`  | Path to a file containing the base64 encoded 256 bit key                  | if no `key`      |               |
+//! | `nonce`     | `prefix` or `{"fixed": "<base64 encoded 96 bit nonce>"}`                  | no               | `prefix`      |
+//!
+//! With `prefix` the nonce is read from the first 12 bytes of the data.
+use super::Preprocessor;
+use crate::postprocessor::encrypt::Cipher;
+use crate::Result;
+use tremor_value::Value;
+
+pub(crate) struct Decrypt {
+    cipher: Cipher,
+}
+
+impl Decrypt {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Self> {
+        Ok(Self {
+            cipher: Cipher::from_config(config, "\"decrypt\" preprocessor")?,
+        })
+    }
+}
+
+impl Preprocessor for Decrypt {
+    fn name(&self) -> &str {
+        "decrypt"
+    }
+
+    fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        Ok(vec![self.cipher.decrypt(data)?])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    #[test]
+    fn invalid_data() -> Result<()> {
+        let config = literal!({"key": "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8="});
+        let mut pre = Decrypt::from_config(Some(&config))?;
+        let mut ingest_ns = 0_u64;
+        // too short for a nonce
+        assert!(pre.process(&mut ingest_ns, b"snot").is_err());
+        // a nonce but no tag
+        assert!(pre.process(&mut ingest_ns, b"snot badger!").is_err());
+        assert!(pre.process(&mut ingest_ns, &[0_u8; 64]).is_err());
+        assert!(Decrypt::from_config(None).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Drops duplicate data, as delivered by noisy sources repeating their frames.
+//!
+//! Data is considered a duplicate if identical data was emitted within the window, the last `count` emitted
+//! buffers that, if `window_ms` is set, were ingested at most `window_ms` milliseconds ago. Only a hash of
+//! each buffer is kept, so memory is bounded by `count`.
+//!
+//! ## Configuration
+//!
+//! | Option      | Description                                                               | Required | Default Value |
+//! |-------------|---------------------------------------------------------------------------|----------|---------------|
+//! | `count`     | The number of most recently emitted buffers duplicates are detected among | no       | `1000`        |
+//! | `window_ms` | The time in milliseconds emitted buffers are remembered for              | no       |               |
+
+use super::Preprocessor;
+use crate::errors::{Error, Kind as ErrorKind, Result};
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+};
+use tremor_value::Value;
+
+const DEFAULT_COUNT: usize = 1000;
+
+fn default_count() -> usize {
+    DEFAULT_COUNT
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default = "default_count")]
+    count: usize,
+    #[serde(default = "Default::default")]
+    window_ms: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            count: DEFAULT_COUNT,
+            window_ms: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Dedup {
+    config: Config,
+    /// hashes of the emitted buffers with their ingest time, oldest first
+    window: VecDeque<(u64, u64)>,
+    seen: HashSet<u64>,
+}
+
+impl Dedup {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Self> {
+        let config: Config = if let Some(config) = config {
+            tremor_value::structurize(config.clone()).map_err(|e| {
+                let kind = ErrorKind::InvalidConfiguration(
+                    "\"dedup\" preprocessor".to_string(),
+                    e.to_string(),
+                );
+                Error::with_chain(e, kind)
+            })?
+        } else {
+            Config::default()
+        };
+        if config.count == 0 || config.window_ms == Some(0) {
+            return Err(ErrorKind::InvalidConfiguration(
+                "\"dedup\" preprocessor".to_string(),
+                "`count` and `window_ms` must be > 0".to_string(),
+            )
+            .into());
+        }
+        Ok(Self {
+            window: VecDeque::with_capacity(config.count + 1),
+            seen: HashSet::with_capacity(config.count + 1),
+            config,
+        })
+    }
+
+    /// Forget the buffers that were ingested too long before `ingest_ns`
+    fn evict_expired(&mut self, ingest_ns: u64) {
+        if let Some(window_ms) = self.config.window_ms {
+            let window_ns = window_ms.saturating_mul(1_000_000);
+            while let Some((hash, seen_ns)) = self.window.front().copied() {
+                if seen_ns.saturating_add(window_ns) > ingest_ns {
+                    break;
+                }
+                self.window.pop_front();
+                self.seen.remove(&hash);
+            }
+        }
+    }
+}
+
+impl Preprocessor for Dedup {
+    fn name(&self) -> &str {
+        "dedup"
+    }
+
+    fn process(&mut self, ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let hash = hasher.finish();
+        self.evict_expired(*ingest_ns);
+        if !self.seen.insert(hash) {
+            return Ok(vec![]);
+        }
+        self.window.push_back((hash, *ingest_ns));
+        if self.window.len() > self.config.count {
+            if let Some((oldest, _)) = self.window.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        Ok(vec![data.to_vec()])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    fn dedup(pre: &mut Dedup, ingest_ms: u64, data: &[u8]) -> Result<bool> {
+        let mut ingest_ns = ingest_ms * 1_000_000;
+        Ok(!pre.process(&mut ingest_ns, data)?.is_empty())
+    }
+
+    #[test]
+    fn count_window() -> Result<()> {
+        let mut pre = Dedup::from_config(Some(&literal!({"count": 2})))?;
+        assert!(dedup(&mut pre, 0, b"snot")?);
+        assert!(!dedup(&mut pre, 0, b"snot")?);
+        assert!(dedup(&mut pre, 0, b"badger")?);
+        // dropped duplicates don't count towards the window
+        assert!(!dedup(&mut pre, 0, b"badger")?);
+        assert!(!dedup(&mut pre, 0, b"snot")?);
+        // `snot` leaves the window
+        assert!(dedup(&mut pre, 0, b"flork")?);
+        assert!(dedup(&mut pre, 0, b"snot")?);
+        assert!(!dedup(&mut pre, 0, b"flork")?);
+        assert!(pre.window.len() <= 2);
+        assert_eq!(pre.window.len(), pre.seen.len());
+        Ok(())
+    }
+
+    #[test]
+    fn time_window() -> Result<()> {
+        let mut pre = Dedup::from_config(Some(&literal!({"window_ms": 100})))?;
+        assert!(dedup(&mut pre, 1000, b"snot")?);
+        assert!(!dedup(&mut pre, 1050, b"snot")?);
+        assert!(dedup(&mut pre, 1060, b"badger")?);
+        assert!(!dedup(&mut pre, 1099, b"snot")?);
+        // identical data outside the window passes again
+        assert!(dedup(&mut pre, 1100, b"snot")?);
+        assert!(!dedup(&mut pre, 1159, b"badger")?);
+        assert!(dedup(&mut pre, 1160, b"badger")?);
+        assert!(!dedup(&mut pre, 1199, b"snot")?);
+        Ok(())
+    }
+
+    #[test]
+    fn bounded() -> Result<()> {
+        let mut pre = Dedup::from_config(None)?;
+        for i in 0..2 * DEFAULT_COUNT {
+            assert!(dedup(&mut pre, 0, i.to_string().as_bytes())?);
+        }
+        assert_eq!(DEFAULT_COUNT, pre.window.len());
+        assert_eq!(DEFAULT_COUNT, pre.seen.len());
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config() {
+        assert!(Dedup::from_config(Some(&literal!({"count": 0}))).is_err());
+        assert!(Dedup::from_config(Some(&literal!({"window_ms": 0}))).is_err());
+        assert!(Dedup::from_config(Some(&literal!({"window_ms": "snot"}))).is_err());
+        assert!(Dedup::from_config(Some(&literal!({"snot": "badger"}))).is_err());
+    }
+}
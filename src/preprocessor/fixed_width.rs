@@ -0,0 +1,270 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parses newline separated records with fixed-width columns, as found in legacy (e.g. COBOL) feeds.
+//!
+//! Every line is split into the configured `fields`, in order, each `width` characters wide. The record
+//! is emitted as a JSON object mapping the field names to their values, with surrounding whitespace
+//! trimmed, so it is meant to be used with the `json` codec. Incomplete lines are buffered until more
+//! data arrives or the stream ends, empty lines are skipped and a trailing `\r` is removed.
+//!
+//! ## Configuration
+//!
+//! | Option   | Description                                                                                      | Required | Default Value |
+//! |----------|--------------------------------------------------------------------------------------------------|----------|---------------|
+//! | `fields` | The columns of a record, a list of `{"name": "<field name>", "width": <width in characters>}`    | yes      |               |
+//! | `strict` | Fail on lines shorter or longer than the record, otherwise pad short lines and ignore the excess | no       | `true`        |
+//!
+//! Example configuration:
+//!
+//! ```tremor
+//! define connector legacy from tcp_server
+//! with
+//!     preprocessors = [
+//!         {
+//!             "name": "fixed-width",
+//!             "config": {
+//!                 "fields": [
+//!                     {"name": "id", "width": 6},
+//!                     {"name": "name", "width": 20},
+//!                     {"name": "amount", "width": 10}
+//!                 ],
+//!                 "strict": false
+//!             }
+//!         }
+//!     ],
+//!     codec = "json",
+//!     ...
+//! end;
+//! ```
+
+use super::Preprocessor;
+use crate::errors::{Error, Kind as ErrorKind, Result};
+use std::collections::HashSet;
+use tremor_value::Value;
+use value_trait::{Builder, Mutable, Writable};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Field {
+    name: String,
+    width: usize,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    fields: Vec<Field>,
+    #[serde(default = "crate::connectors::prelude::default_true")]
+    strict: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct FixedWidth {
+    config: Config,
+    /// total width of a record in characters
+    width: usize,
+    /// an incomplete line
+    buffer: Vec<u8>,
+}
+
+impl FixedWidth {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Self> {
+        let invalid = |msg: String| -> Error {
+            ErrorKind::InvalidConfiguration("\"fixed-width\" preprocessor".to_string(), msg).into()
+        };
+        let config = config.ok_or_else(|| invalid("Missing configuration".to_string()))?;
+        let config: Config =
+            tremor_value::structurize(config.clone()).map_err(|e| invalid(e.to_string()))?;
+        if config.fields.is_empty() {
+            return Err(invalid("`fields` must not be empty".to_string()));
+        }
+        let mut names = HashSet::new();
+        for field in &config.fields {
+            if field.width == 0 {
+                return Err(invalid(format!(
+                    "The width of field `{}` must be > 0",
+                    field.name
+                )));
+            }
+            if !names.insert(field.name.as_str()) {
+                return Err(invalid(format!("Duplicate field `{}`", field.name)));
+            }
+        }
+        let width = config.fields.iter().map(|f| f.width).sum();
+        Ok(Self {
+            config,
+            width,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Parse a single `line` into a JSON encoded record, `None` for empty lines
+    fn parse(&self, line: &[u8]) -> Result<Option<Vec<u8>>> {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            return Ok(None);
+        }
+        let line = std::str::from_utf8(line)?;
+        let len = line.chars().count();
+        if self.config.strict && len != self.width {
+            return Err(format!(
+                "Invalid fixed-width record of {len} characters, expected {}: {line}",
+                self.width
+            )
+            .into());
+        }
+        let mut chars = line.chars();
+        let mut record = Value::object_with_capacity(self.config.fields.len());
+        for field in &self.config.fields {
+            // short lines are padded, so missing fields are empty
+            let value: String = chars.by_ref().take(field.width).collect();
+            record.insert(field.name.clone(), value.trim().to_string())?;
+        }
+        Ok(Some(record.encode().into_bytes()))
+    }
+}
+
+impl Preprocessor for FixedWidth {
+    fn name(&self) -> &str {
+        "fixed-width"
+    }
+
+    fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        self.buffer.extend_from_slice(data);
+        let complete = match self.buffer.iter().rposition(|b| *b == b'\n') {
+            Some(last) => last + 1,
+            None => return Ok(vec![]),
+        };
+        let rest = self.buffer.split_off(complete);
+        let lines = std::mem::replace(&mut self.buffer, rest);
+        let mut res = Vec::new();
+        for line in lines.split(|b| *b == b'\n') {
+            if let Some(record) = self.parse(line)? {
+                res.push(record);
+            }
+        }
+        Ok(res)
+    }
+
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        let mut res = if let Some(data) = data {
+            self.process(ingest_ns, data)?
+        } else {
+            vec![]
+        };
+        // the last line of the stream doesn't need a trailing newline
+        let line = std::mem::take(&mut self.buffer);
+        if let Some(record) = self.parse(&line)? {
+            res.push(record);
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    fn config(strict: bool) -> Value<'static> {
+        literal!({
+            "fields": [
+                {"name": "id", "width": 4},
+                {"name": "name", "width": 8},
+                {"name": "amount", "width": 6}
+            ],
+            "strict": strict
+        })
+    }
+
+    fn records(data: Vec<Vec<u8>>) -> Result<Vec<Value<'static>>> {
+        data.into_iter()
+            .map(|mut d| Ok(tremor_value::parse_to_value(&mut d)?.into_static()))
+            .collect()
+    }
+
+    #[test]
+    fn well_formed() -> Result<()> {
+        let mut pre = FixedWidth::from_config(Some(&config(true)))?;
+        let mut ingest_ns = 0_u64;
+        let res = pre.process(&mut ingest_ns, b"0001snot     12.50\r\n0002bad")?;
+        assert_eq!(
+            vec![literal!({"id": "0001", "name": "snot", "amount": "12.50"})],
+            records(res)?
+        );
+        // the partial line is completed by the next chunk
+        let res = pre.process(&mut ingest_ns, b"ger       7\n\n")?;
+        assert_eq!(
+            vec![literal!({"id": "0002", "name": "badger", "amount": "7"})],
+            records(res)?
+        );
+        // the last line needs no newline
+        assert!(pre
+            .process(&mut ingest_ns, b"0003\xc3\xa4rger     0.01")?
+            .is_empty());
+        assert_eq!(
+            vec![literal!({"id": "0003", "name": "ärger", "amount": "0.01"})],
+            records(pre.finish(&mut 0, None)?)?
+        );
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn short_line_strict() -> Result<()> {
+        let mut pre = FixedWidth::from_config(Some(&literal!({
+            "fields": [{"name": "id", "width": 4}, {"name": "name", "width": 8}]
+        })))?;
+        let mut ingest_ns = 0_u64;
+        assert!(pre.process(&mut ingest_ns, b"0001snot\n").is_err());
+        assert!(pre
+            .process(&mut ingest_ns, b"0001snot    too long\n")
+            .is_err());
+        assert!(pre.finish(&mut 0, Some(b"0002")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn short_line_lenient() -> Result<()> {
+        let mut pre = FixedWidth::from_config(Some(&config(false)))?;
+        let mut ingest_ns = 0_u64;
+        let res = pre.process(&mut ingest_ns, b"0001snot\n00\n0003badger  1     ignored\n")?;
+        assert_eq!(
+            vec![
+                literal!({"id": "0001", "name": "snot", "amount": ""}),
+                literal!({"id": "00", "name": "", "amount": ""}),
+                literal!({"id": "0003", "name": "badger", "amount": "1"}),
+            ],
+            records(res)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config() {
+        let invalid = [
+            literal!({}),
+            literal!({"fields": []}),
+            literal!({"fields": [{"name": "id", "width": 0}]}),
+            literal!({"fields": [{"name": "id", "width": 1}, {"name": "id", "width": 2}]}),
+            literal!({"fields": [{"name": "id"}]}),
+            literal!({"fields": [{"name": "id", "width": 1}], "snot": "badger"}),
+        ];
+        for config in &invalid {
+            assert!(FixedWidth::from_config(Some(config)).is_err(), "{config}");
+        }
+        assert!(FixedWidth::from_config(None).is_err());
+    }
+}
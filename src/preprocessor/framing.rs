@@ -0,0 +1,340 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validates and strips a fixed framing around each payload, as used by binary protocols wrapping their
+//! payloads in a magic header and a trailing checksum.
+//!
+//! A frame consists of the `header`, the payload, an optional checksum and the `footer`. It ends at the first
+//! occurrence of the `footer` after the header, so the payload must not contain the footer. The crc32 checksum
+//! is calculated over the payload and stored as 4 bytes in big endian byte order.
+//!
+//! Frames are buffered until they are complete. A frame with a checksum mismatch is dropped and reported as an
+//! error, frames completed by the same data before it are emitted first. Data not starting with the `header`
+//! is dropped and reported as an error as well.
+//!
+//! ## Configuration
+//!
+//! | Option     | Description                                         | Required | Default Value |
+//! |------------|-----------------------------------------------------|----------|---------------|
+//! | `header`   | The bytes every frame starts with.                  | no       | `[]`          |
+//! | `footer`   | The bytes every frame ends with, must not be empty. | yes      |               |
+//! | `checksum` | The checksum before the footer, `crc32` or `none`.  | no       | `none`        |
+//!
+//! ## Example
+//!
+//! ```tremor
+//! define connector frames from tcp_server
+//! with
+//!     codec = "binary",
+//!     preprocessors = [
+//!         {
+//!             "name": "framing",
+//!             "config": {
+//!                 "header": [202, 254],
+//!                 "footer": [13, 10],
+//!                 "checksum": "crc32"
+//!             }
+//!         }
+//!     ],
+//!     config = {
+//!         "url": "localhost:4242"
+//!     }
+//! end;
+//! ```
+
+use super::Preprocessor;
+use crate::errors::{Error, Kind as ErrorKind, Result};
+use tremor_value::Value;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum Checksum {
+    #[default]
+    None,
+    Crc32,
+}
+
+impl Checksum {
+    /// number of bytes of the checksum
+    fn len(self) -> usize {
+        match self {
+            Self::None => 0,
+            Self::Crc32 => 4,
+        }
+    }
+
+    fn verify(self, payload: &[u8], checksum: &[u8]) -> Result<()> {
+        match self {
+            Self::None => Ok(()),
+            Self::Crc32 => {
+                let actual = crc32fast::hash(payload);
+                let mut expected = [0_u8; 4];
+                expected.copy_from_slice(checksum);
+                let expected = u32::from_be_bytes(expected);
+                if actual == expected {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "Invalid frame, crc32 checksum mismatch: expected {expected:#010x}, got {actual:#010x}"
+                    )
+                    .into())
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default = "Default::default")]
+    header: Vec<u8>,
+    footer: Vec<u8>,
+    #[serde(default = "Default::default")]
+    checksum: Checksum,
+}
+
+/// A complete frame at the start of the buffer
+struct Frame {
+    /// length of the whole frame
+    len: usize,
+    /// the payload, or the error why it is invalid
+    payload: Result<Vec<u8>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Framing {
+    config: Config,
+    buffer: Vec<u8>,
+}
+
+impl Framing {
+    pub(crate) fn from_config(config: Option<&Value>) -> Result<Self> {
+        let config: Config = if let Some(config) = config {
+            tremor_value::structurize(config.clone()).map_err(|e| {
+                let kind = ErrorKind::InvalidConfiguration(
+                    "\"framing\" preprocessor".to_string(),
+                    e.to_string(),
+                );
+                Error::with_chain(e, kind)
+            })?
+        } else {
+            return Err(
+                ErrorKind::MissingConfiguration(String::from("\"framing\" preprocessor")).into(),
+            );
+        };
+        if config.footer.is_empty() {
+            return Err(ErrorKind::InvalidConfiguration(
+                "\"framing\" preprocessor".to_string(),
+                "`footer` must not be empty".to_string(),
+            )
+            .into());
+        }
+        Ok(Self {
+            config,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// The frame at the start of the buffer, `None` if it is not complete yet
+    ///
+    /// Errors if the buffer doesn't start with the header.
+    fn next_frame(&self) -> Result<Option<Frame>> {
+        let header = &self.config.header;
+        let footer = &self.config.footer;
+        let prefix = self.buffer.len().min(header.len());
+        if self.buffer[..prefix] != header[..prefix] {
+            return Err("Invalid frame, missing header".into());
+        }
+        // the footer follows the checksum
+        let checksum_len = self.config.checksum.len();
+        let min_footer_start = header.len() + checksum_len;
+        let footer_start = if let Some(pos) = self
+            .buffer
+            .get(min_footer_start..)
+            .and_then(|rest| rest.windows(footer.len()).position(|w| w == footer))
+        {
+            min_footer_start + pos
+        } else {
+            return Ok(None);
+        };
+        let checksum_start = footer_start - checksum_len;
+        let payload = &self.buffer[header.len()..checksum_start];
+        let payload = self
+            .config
+            .checksum
+            .verify(payload, &self.buffer[checksum_start..footer_start])
+            .map(|()| payload.to_vec());
+        Ok(Some(Frame {
+            len: footer_start + footer.len(),
+            payload,
+        }))
+    }
+}
+
+impl Preprocessor for Framing {
+    fn name(&self) -> &str {
+        "framing"
+    }
+
+    fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        self.buffer.extend_from_slice(data);
+        let mut res = Vec::new();
+        loop {
+            let (len, error) = match self.next_frame() {
+                Ok(Some(Frame {
+                    len,
+                    payload: Ok(payload),
+                })) => {
+                    res.push(payload);
+                    self.buffer.drain(..len);
+                    continue;
+                }
+                Ok(None) => return Ok(res),
+                Ok(Some(Frame {
+                    len,
+                    payload: Err(e),
+                })) => (len, e),
+                // we cannot find the start of the next frame anymore
+                Err(e) => (self.buffer.len(), e),
+            };
+            if !res.is_empty() {
+                // emit the valid frames first, the error is reported with the next data
+                return Ok(res);
+            }
+            self.buffer.drain(..len);
+            return Err(error);
+        }
+    }
+
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        let mut res = self.process(ingest_ns, data.unwrap_or_default())?;
+        // frames held back behind an invalid one
+        while !self.buffer.is_empty() {
+            let frames = self.process(ingest_ns, &[])?;
+            if frames.is_empty() {
+                let left = self.buffer.len();
+                self.buffer.clear();
+                return Err(format!(
+                    "Incomplete frame with {left} bytes left at the end of the stream"
+                )
+                .into());
+            }
+            res.extend(frames);
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    fn framing(config: &Value) -> Result<Framing> {
+        Framing::from_config(Some(config))
+    }
+
+    fn crc32_frame(payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0xca, 0xfe];
+        frame.extend_from_slice(payload);
+        frame.extend_from_slice(&crc32fast::hash(payload).to_be_bytes());
+        frame.extend_from_slice(b"\r\n");
+        frame
+    }
+
+    fn crc32_framing() -> Result<Framing> {
+        framing(&literal!({"header": [0xca, 0xfe], "footer": [13, 10], "checksum": "crc32"}))
+    }
+
+    #[test]
+    fn from_config() -> Result<()> {
+        assert!(Framing::from_config(None).is_err());
+        assert!(framing(&literal!({})).is_err());
+        assert!(framing(&literal!({"footer": []})).is_err());
+        assert!(framing(&literal!({"footer": [10], "checksum": "md5"})).is_err());
+        assert!(framing(&literal!({"footer": [10], "snot": "badger"})).is_err());
+        let pre = crc32_framing()?;
+        assert_eq!("framing", pre.name());
+        assert_eq!(Checksum::Crc32, pre.config.checksum);
+        Ok(())
+    }
+
+    #[test]
+    fn valid_frames() -> Result<()> {
+        let mut pre = crc32_framing()?;
+        let mut data = crc32_frame(b"snot");
+        data.extend(crc32_frame(b""));
+        data.extend(crc32_frame(b"badger"));
+        assert_eq!(
+            vec![b"snot".to_vec(), vec![], b"badger".to_vec()],
+            pre.process(&mut 0, &data)?
+        );
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+
+        // without checksum
+        let mut pre = framing(&literal!({"header": [60], "footer": [62]}))?;
+        assert_eq!(
+            vec![b"snot".to_vec(), b"badger".to_vec()],
+            pre.process(&mut 0, b"<snot><badger>")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_checksum() -> Result<()> {
+        let mut pre = crc32_framing()?;
+        let mut corrupt = crc32_frame(b"snot");
+        corrupt[3] = b'x';
+        assert!(pre.process(&mut 0, &corrupt).is_err());
+        // the corrupt frame is dropped
+        assert_eq!(
+            vec![b"badger".to_vec()],
+            pre.process(&mut 0, &crc32_frame(b"badger"))?
+        );
+
+        // valid frames before a corrupt one are emitted first
+        let mut data = crc32_frame(b"snot");
+        data.extend(&corrupt);
+        data.extend(crc32_frame(b"badger"));
+        assert_eq!(vec![b"snot".to_vec()], pre.process(&mut 0, &data)?);
+        assert!(pre.process(&mut 0, &[]).is_err());
+        assert_eq!(vec![b"badger".to_vec()], pre.finish(&mut 0, None)?);
+        Ok(())
+    }
+
+    #[test]
+    fn frame_split_across_reads() -> Result<()> {
+        let mut data = crc32_frame(b"snot");
+        data.extend(crc32_frame(b"badger"));
+        for split in 0..=data.len() {
+            let mut pre = crc32_framing()?;
+            let mut res = pre.process(&mut 0, &data[..split])?;
+            res.append(&mut pre.process(&mut 0, &data[split..])?);
+            assert!(pre.finish(&mut 0, None)?.is_empty());
+            assert_eq!(vec![b"snot".to_vec(), b"badger".to_vec()], res);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_framing() -> Result<()> {
+        let mut pre = crc32_framing()?;
+        assert!(pre.process(&mut 0, b"snot").is_err());
+        // an incomplete frame at the end of the stream
+        assert!(pre.process(&mut 0, &[0xca, 0xfe, 1, 2])?.is_empty());
+        assert!(pre.finish(&mut 0, None).is_err());
+        Ok(())
+    }
+}
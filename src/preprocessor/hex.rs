@@ -0,0 +1,134 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decodes hex encoded data to the raw bytes, both lower and upper case digits are accepted.
+//!
+//! | Option              | Description                                                                       | Required | Default Value |
+//! |---------------------|-----------------------------------------------------------------------------------|----------|---------------|
+//! | `ignore_whitespace` | Ignore whitespace between the hex digits, e.g. in `de ad be ef`                   | no       | `false`       |
+//! | `allow_prefix`      | Strip a `0x` prefix from the data, or from each whitespace separated group of it  | no       | `false`       |
+use super::Preprocessor;
+use crate::Result;
+use tremor_pipeline::{ConfigImpl, ConfigMap};
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default)]
+    ignore_whitespace: bool,
+    #[serde(default)]
+    allow_prefix: bool,
+}
+
+impl ConfigImpl for Config {}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Hex {
+    config: Config,
+}
+
+impl Hex {
+    pub(crate) fn from_config(config: &ConfigMap) -> Result<Self> {
+        if let Some(raw_config) = config {
+            Ok(Self {
+                config: Config::new(raw_config)?,
+            })
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    fn strip_prefix<'data>(&self, digits: &'data [u8]) -> &'data [u8] {
+        if self.config.allow_prefix {
+            digits
+                .strip_prefix(b"0x")
+                .or_else(|| digits.strip_prefix(b"0X"))
+                .unwrap_or(digits)
+        } else {
+            digits
+        }
+    }
+}
+
+impl Preprocessor for Hex {
+    fn name(&self) -> &str {
+        "hex"
+    }
+
+    fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let decoded = if self.config.ignore_whitespace {
+            let digits: Vec<u8> = data
+                .split(u8::is_ascii_whitespace)
+                .flat_map(|group| self.strip_prefix(group))
+                .copied()
+                .collect();
+            ::hex::decode(digits)
+        } else {
+            ::hex::decode(self.strip_prefix(data))
+        };
+        Ok(vec![decoded.map_err(|e| format!("Invalid hex data: {e}"))?])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    fn decode(config: &ConfigMap, data: &str) -> Result<Vec<u8>> {
+        let mut pre = Hex::from_config(config)?;
+        let mut ingest_ns = 0_u64;
+        Ok(pre
+            .process(&mut ingest_ns, data.as_bytes())?
+            .pop()
+            .unwrap_or_default())
+    }
+
+    #[test]
+    fn strict() -> Result<()> {
+        assert_eq!(vec![0xde_u8, 0xad, 0xbe, 0xef], decode(&None, "deadBEEF")?);
+        assert!(decode(&None, "")?.is_empty());
+        assert!(decode(&None, "dead beef").is_err());
+        assert!(decode(&None, "0xdeadbeef").is_err());
+        assert!(decode(&None, "deadbee").is_err());
+        assert!(decode(&None, "snot").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn whitespace_and_prefixes() -> Result<()> {
+        let config = Some(literal!({"ignore_whitespace": true, "allow_prefix": true}));
+        let expected = vec![0xde_u8, 0xad, 0xbe, 0xef];
+        assert_eq!(expected, decode(&config, "deadbeef")?);
+        assert_eq!(expected, decode(&config, " de ad\tbe\r\nef\n")?);
+        assert_eq!(expected, decode(&config, "0xDEADBEEF")?);
+        assert_eq!(expected, decode(&config, "0xde 0Xad 0xbe ef")?);
+        // digits of a byte may be split by whitespace
+        assert_eq!(expected, decode(&config, "d ead bee f")?);
+        assert!(decode(&config, "de ad be e").is_err());
+        assert!(decode(&config, "de 0x").is_ok());
+        assert!(decode(&config, "dex0ad").is_err());
+
+        let config = Some(literal!({"allow_prefix": true}));
+        assert_eq!(expected, decode(&config, "0xdeadbeef")?);
+        assert!(decode(&config, "0xde 0xad").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config() {
+        assert!(Hex::from_config(&Some(literal!({"ignore_whitespace": "snot"}))).is_err());
+        assert!(Hex::from_config(&Some(literal!({"badger": true}))).is_err());
+    }
+}
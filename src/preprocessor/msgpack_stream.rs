@@ -0,0 +1,245 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Separates a continuous stream of concatenated [MessagePack](https://msgpack.org) values, e.g. from a tcp connection.
+//!
+//! The length of each value is determined from the MessagePack format itself, no extra framing is needed.
+//! Every complete value is emitted as a separate event, to be decoded with the `msgpack` codec.
+//! Partial values are buffered until the rest of their bytes arrive.
+//!
+//! ## Example
+//!
+//! ```tremor
+//! define connector msgpack_in from tcp_server
+//! with
+//!     codec = "msgpack",
+//!     preprocessors = ["msgpack-streaming"],
+//!     config = {
+//!         "url": "localhost:4242"
+//!     }
+//! end;
+//! ```
+
+use super::Preprocessor;
+use crate::Result;
+use bytes::{Buf, BytesMut};
+
+#[derive(Clone, Default, Debug)]
+pub(crate) struct MsgPackStream {
+    buffer: BytesMut,
+}
+
+impl Preprocessor for MsgPackStream {
+    fn name(&self) -> &str {
+        "msgpack-streaming"
+    }
+
+    fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        self.buffer.extend_from_slice(data);
+        let mut res = Vec::new();
+        loop {
+            match value_len(&self.buffer) {
+                Ok(Some(len)) => {
+                    res.push(self.buffer[..len].to_vec());
+                    self.buffer.advance(len);
+                }
+                Ok(None) => return Ok(res),
+                Err(e) => {
+                    // we cannot find the start of the next value anymore
+                    self.buffer.clear();
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        let res = if let Some(data) = data {
+            self.process(ingest_ns, data)?
+        } else {
+            vec![]
+        };
+        if self.buffer.is_empty() {
+            Ok(res)
+        } else {
+            let left = self.buffer.len();
+            self.buffer.clear();
+            Err(format!(
+                "Incomplete MessagePack value with {left} bytes left at the end of the stream"
+            )
+            .into())
+        }
+    }
+}
+
+/// Read a big endian length of `n` bytes at `pos`, `None` if `data` is too short
+fn read_len(data: &[u8], pos: usize, n: usize) -> Option<usize> {
+    let bytes = data.get(pos..pos + n)?;
+    Some(bytes.iter().fold(0, |len, b| (len << 8) | usize::from(*b)))
+}
+
+/// The length of the first complete MessagePack value in `data`
+///
+/// Returns `None` if `data` doesn't contain a complete value yet.
+fn value_len(data: &[u8]) -> Result<Option<usize>> {
+    let mut pos = 0;
+    // number of values still to be read, nested values of arrays and maps are added to it
+    let mut pending: usize = 1;
+    while pending > 0 {
+        pending -= 1;
+        let marker = if let Some(marker) = data.get(pos) {
+            *marker
+        } else {
+            return Ok(None);
+        };
+        pos += 1;
+        // (bytes of the length field, fixed payload size, number of nested values)
+        let (len_bytes, fixed, nested) = match marker {
+            0x00..=0x7f | 0xc0 | 0xc2 | 0xc3 | 0xe0..=0xff => (0, 0, 0),
+            0x80..=0x8f => (0, 0, 2 * usize::from(marker & 0x0f)),
+            0x90..=0x9f => (0, 0, usize::from(marker & 0x0f)),
+            0xa0..=0xbf => (0, usize::from(marker & 0x1f), 0),
+            0xc1 => return Err("Invalid MessagePack marker 0xc1".into()),
+            // bin and str with 8, 16 and 32 bit lengths
+            0xc4 | 0xd9 => (1, 0, 0),
+            0xc5 | 0xda => (2, 0, 0),
+            0xc6 | 0xdb => (4, 0, 0),
+            // ext with 8, 16 and 32 bit lengths, followed by the type byte
+            0xc7 => (1, 1, 0),
+            0xc8 => (2, 1, 0),
+            0xc9 => (4, 1, 0),
+            0xca => (0, 4, 0),
+            0xcb => (0, 8, 0),
+            0xcc | 0xd0 => (0, 1, 0),
+            0xcd | 0xd1 => (0, 2, 0),
+            0xce | 0xd2 => (0, 4, 0),
+            0xcf | 0xd3 => (0, 8, 0),
+            // fixext 1, 2, 4, 8 and 16 plus the type byte
+            0xd4 => (0, 2, 0),
+            0xd5 => (0, 3, 0),
+            0xd6 => (0, 5, 0),
+            0xd7 => (0, 9, 0),
+            0xd8 => (0, 17, 0),
+            // arrays and maps with 16 and 32 bit lengths
+            0xdc | 0xde => (2, 0, 0),
+            0xdd | 0xdf => (4, 0, 0),
+        };
+        let mut size = fixed;
+        let mut nested = nested;
+        if len_bytes > 0 {
+            let len = if let Some(len) = read_len(data, pos, len_bytes) {
+                len
+            } else {
+                return Ok(None);
+            };
+            pos += len_bytes;
+            match marker {
+                0xdc | 0xdd => nested = len,
+                0xde | 0xdf => nested = 2 * len,
+                _ => size += len,
+            }
+        }
+        pos += size;
+        pending += nested;
+        // every nested value takes at least one byte
+        if pos + pending > data.len() {
+            return Ok(None);
+        }
+    }
+    Ok(Some(pos))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codec::{msgpack::MsgPack, Codec};
+    use tremor_value::{literal, Value};
+
+    fn decode(chunks: &[Vec<u8>]) -> Result<Vec<Value<'static>>> {
+        let mut codec = MsgPack {};
+        chunks
+            .iter()
+            .map(|chunk| {
+                let mut chunk = chunk.clone();
+                Ok(codec
+                    .decode(&mut chunk, 0)?
+                    .ok_or("no value")?
+                    .into_static())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn split_across_reads() -> Result<()> {
+        let mut codec = MsgPack {};
+        let first = literal!({
+            "snot": "badger",
+            "numbers": [1, -2, 300, 70000, 5_000_000_000_u64, 1.5],
+            "nested": {"flag": true, "nothing": null}
+        });
+        let second = literal!(["a longer string that does not fit into a fixstr header", {}]);
+        let mut data = codec.encode(&first)?;
+        data.extend(codec.encode(&second)?);
+
+        // feed the stream in every possible pair of parts
+        for split in 0..=data.len() {
+            let mut pre = MsgPackStream::default();
+            let mut res = pre.process(&mut 0, &data[..split])?;
+            res.append(&mut pre.process(&mut 0, &data[split..])?);
+            assert!(pre.finish(&mut 0, None)?.is_empty());
+            assert_eq!(vec![first.clone(), second.clone()], decode(&res)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn byte_by_byte() -> Result<()> {
+        let mut codec = MsgPack {};
+        let mut data = codec.encode(&literal!("snot"))?;
+        data.extend(codec.encode(&literal!(42))?);
+        data.extend(codec.encode(&literal!({"badger": [1, 2, 3]}))?);
+        let mut pre = MsgPackStream::default();
+        let mut res = Vec::new();
+        for b in data {
+            res.append(&mut pre.process(&mut 0, &[b])?);
+        }
+        assert_eq!(
+            vec![
+                literal!("snot"),
+                literal!(42),
+                literal!({"badger": [1, 2, 3]})
+            ],
+            decode(&res)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn leftover() -> Result<()> {
+        let mut codec = MsgPack {};
+        let data = codec.encode(&literal!(["snot", "badger"]))?;
+        let mut pre = MsgPackStream::default();
+        assert!(pre.process(&mut 0, &data[..data.len() - 1])?.is_empty());
+        assert!(pre.finish(&mut 0, None).is_err());
+        // the buffer is cleared after finishing
+        assert_eq!(1, pre.process(&mut 0, &data)?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_marker() {
+        let mut pre = MsgPackStream::default();
+        assert!(pre.process(&mut 0, &[0x01, 0xc1]).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Splits the input into events at the matches of a regular expression, for text protocols delimiting their
+//! records by a pattern rather than a fixed separator, e.g. multi-line log records starting with a timestamp.
+//!
+//! By default every match starts a new record and is kept at its beginning. With `keep_match` set to `false`
+//! the matches are treated as separators and dropped. Empty records are skipped.
+//!
+//! The trailing, possibly incomplete, record is buffered until the next match confirms its end or the stream ends.
+//! A match reaching the end of the received data is only used once more data arrives, as it might still grow.
+//!
+//! | Option       | Description                                                            | Required | Default Value |
+//! |--------------|------------------------------------------------------------------------|----------|---------------|
+//! | `pattern`    | The regular expression to split at, see the `regex` crate for syntax.  | yes      |               |
+//! | `keep_match` | Keep the match at the start of the record it begins, or drop it.       | no       | `true`        |
+//!
+//! Example configuration:
+//!
+//! ```tremor
+//! define connector logs from tcp_server
+//! with
+//!     preprocessors = [
+//!         {
+//!             "name": "regex-split",
+//!             "config": {
+//!                 "pattern": "(?m)^\\d{4}-\\d{2}-\\d{2}T"
+//!             }
+//!         }
+//!     ],
+//!     codec = "string",
+//!     config = {
+//!         "url": "localhost:4242"
+//!     }
+//! end;
+//! ```
+
+use super::Preprocessor;
+use crate::{
+    connectors::prelude::*,
+    errors::{Kind as ErrorKind, Result},
+};
+use regex::bytes::Regex;
+use tremor_pipeline::{ConfigImpl, ConfigMap};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    pattern: String,
+    #[serde(default = "default_true")]
+    keep_match: bool,
+}
+
+impl ConfigImpl for Config {}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RegexSplit {
+    regex: Regex,
+    keep_match: bool,
+    buffer: Vec<u8>,
+}
+
+impl RegexSplit {
+    pub(crate) fn from_config(config: &ConfigMap) -> Result<Self> {
+        let config = if let Some(raw_config) = config {
+            Config::new(raw_config)?
+        } else {
+            return Err(
+                ErrorKind::MissingConfiguration(String::from("regex-split preprocessor")).into(),
+            );
+        };
+        let regex = Regex::new(&config.pattern).map_err(|e| {
+            ErrorKind::InvalidConfiguration(
+                String::from("regex-split preprocessor"),
+                format!("Invalid 'pattern': {e}"),
+            )
+        })?;
+        Ok(Self {
+            regex,
+            keep_match: config.keep_match,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Split off all complete records from the buffer
+    ///
+    /// Matches reaching the end of the buffer are only considered at the end of the stream.
+    fn split(&mut self, at_end: bool) -> Vec<Vec<u8>> {
+        let mut res = Vec::new();
+        let mut record_start = 0;
+        for m in self.regex.find_iter(&self.buffer) {
+            if m.end() >= self.buffer.len() && !at_end {
+                break;
+            }
+            if m.start() > record_start {
+                res.push(self.buffer[record_start..m.start()].to_vec());
+            }
+            record_start = if self.keep_match { m.start() } else { m.end() };
+        }
+        self.buffer.drain(..record_start);
+        res
+    }
+}
+
+impl Preprocessor for RegexSplit {
+    fn name(&self) -> &str {
+        "regex-split"
+    }
+
+    fn process(&mut self, _ingest_ns: &mut u64, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        self.buffer.extend_from_slice(data);
+        Ok(self.split(false))
+    }
+
+    fn finish(&mut self, _ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        if let Some(data) = data {
+            self.buffer.extend_from_slice(data);
+        }
+        let mut res = self.split(true);
+        if !self.buffer.is_empty() {
+            res.push(std::mem::take(&mut self.buffer));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tremor_value::literal;
+
+    const LOGS: &str = "2022-11-01T10:00:00 INFO starting\n\
+                        2022-11-01T10:00:01 ERROR boom\n  at snot.rs:1\n  at badger.rs:2\n\
+                        2022-11-01T10:00:02 INFO done\n";
+
+    fn regex_split(config: &Value<'static>) -> Result<RegexSplit> {
+        RegexSplit::from_config(&Some(config.clone()))
+    }
+
+    #[test]
+    fn from_config() {
+        assert!(RegexSplit::from_config(&None).is_err());
+        assert!(regex_split(&literal!({})).is_err());
+        assert!(regex_split(&literal!({"pattern": "(unclosed"})).is_err());
+        assert!(regex_split(&literal!({"pattern": "\\d", "snot": true})).is_err());
+    }
+
+    #[test]
+    fn split_on_timestamp() -> Result<()> {
+        let mut pre = regex_split(&literal!({"pattern": "(?m)^\\d{4}-\\d{2}-\\d{2}T"}))?;
+        let mut res = pre.process(&mut 0, LOGS.as_bytes())?;
+        // the last record is only complete at the end of the stream
+        assert_eq!(2, res.len());
+        res.append(&mut pre.finish(&mut 0, None)?);
+        assert_eq!(
+            vec![
+                b"2022-11-01T10:00:00 INFO starting\n".to_vec(),
+                b"2022-11-01T10:00:01 ERROR boom\n  at snot.rs:1\n  at badger.rs:2\n".to_vec(),
+                b"2022-11-01T10:00:02 INFO done\n".to_vec(),
+            ],
+            res
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn record_spanning_reads() -> Result<()> {
+        let expected = vec![
+            b"2022-11-01T10:00:00 INFO starting\n".to_vec(),
+            b"2022-11-01T10:00:01 ERROR boom\n  at snot.rs:1\n  at badger.rs:2\n".to_vec(),
+            b"2022-11-01T10:00:02 INFO done\n".to_vec(),
+        ];
+        let data = LOGS.as_bytes();
+        // split within the multi-line record and within the timestamp of the next one
+        for split in [50, 75, 95, 100] {
+            let mut pre = regex_split(&literal!({"pattern": "(?m)^\\d{4}-\\d{2}-\\d{2}T"}))?;
+            let mut res = pre.process(&mut 0, &data[..split])?;
+            assert_eq!(vec![expected[0].clone()], res);
+            res.append(&mut pre.process(&mut 0, &data[split..])?);
+            res.append(&mut pre.finish(&mut 0, None)?);
+            assert_eq!(expected, res);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn drop_match() -> Result<()> {
+        let mut pre = regex_split(&literal!({"pattern": "\\|+", "keep_match": false}))?;
+        assert_eq!(
+            vec![b"snot".to_vec()],
+            pre.process(&mut 0, b"||snot||badger|")?
+        );
+        // the trailing match might still grow
+        assert_eq!(vec![b"badger".to_vec()], pre.process(&mut 0, b"|flork")?);
+        assert_eq!(vec![b"flork".to_vec()], pre.finish(&mut 0, Some(b"|"))?);
+        assert!(pre.finish(&mut 0, None)?.is_empty());
+        Ok(())
+    }
+}
@@ -276,10 +276,9 @@ impl Preprocessor for Separate {
         Ok(events)
     }
 
-    fn finish(&mut self, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
-        let mut tmp = 0_u64;
+    fn finish(&mut self, ingest_ns: &mut u64, data: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
         if let Some(data) = data {
-            self.process(&mut tmp, data).map(|mut processed| {
+            self.process(ingest_ns, data).map(|mut processed| {
                 if !self.buffer.is_empty() {
                     processed.push(self.buffer.split_off(0));
                 }
@@ -367,7 +366,7 @@ mod test {
         assert_eq!(r.pop().unwrap_or_default(), b"0123456789");
         assert_eq!(r.pop().unwrap_or_default(), b"012345");
         assert!(r.is_empty());
-        assert!(pp.finish(None)?.is_empty());
+        assert!(pp.finish(&mut 0, None)?.is_empty());
 
         Ok(())
     }
@@ -437,7 +436,7 @@ mod test {
         assert_eq!(r.pop().unwrap_or_default(), b"0123456789");
         assert_eq!(r.pop().unwrap_or_default(), b"012345");
         assert!(r.is_empty());
-        assert!(pp.finish(None)?.is_empty());
+        assert!(pp.finish(&mut 0, None)?.is_empty());
 
         Ok(())
     }
@@ -551,7 +550,7 @@ mod test {
         assert_eq!(r.pop().unwrap_or_default(), b"0123456789");
         assert_eq!(r.pop().unwrap_or_default(), b"012345");
         assert!(r.is_empty());
-        let r = pp.finish(None)?;
+        let r = pp.finish(&mut 0, None)?;
         assert!(r.is_empty());
 
         Ok(())
@@ -571,7 +570,7 @@ mod test {
         let mut r = pp.process(&mut i, b"\n")?;
         assert_eq!(r.pop().unwrap_or_default(), b"012345");
         assert!(r.is_empty());
-        assert!(pp.finish(None)?.is_empty());
+        assert!(pp.finish(&mut 0, None)?.is_empty());
 
         Ok(())
     }
@@ -591,7 +590,7 @@ mod test {
         assert_eq!(r.pop().unwrap_or_default(), b"012345");
         assert!(r.is_empty());
 
-        let mut r = pp.finish(None)?;
+        let mut r = pp.finish(&mut 0, None)?;
         assert_eq!(r.pop().unwrap_or_default(), b"abc");
         assert!(r.is_empty());
 
@@ -630,7 +629,7 @@ mod test {
         let mut r = pp.process(&mut ingest_ns, data)?;
         assert_eq!(r.pop().unwrap_or_default(), b"4567890123");
         assert!(r.is_empty());
-        let mut r = pp.finish(None)?;
+        let mut r = pp.finish(&mut 0, None)?;
         assert_eq!(r.pop().unwrap_or_default(), b"456");
         assert!(r.is_empty());
         assert!(pp.buffer.is_empty());
@@ -648,7 +647,7 @@ mod test {
         assert_eq!(2, r.len());
         assert_eq!(9998, r[0].len());
         assert_eq!(1, r[1].len());
-        let r = pp.finish(None)?;
+        let r = pp.finish(&mut 0, None)?;
         assert_eq!(0, r.len());
         Ok(())
     }
@@ -669,7 +668,7 @@ mod test {
         assert_eq!(1, r.len());
         assert_eq!(9998, r[0].len());
 
-        let r = pp.finish(None)?;
+        let r = pp.finish(&mut 0, None)?;
         assert_eq!(1, r.len());
         assert_eq!(1, r[0].len());
         Ok(())
@@ -691,7 +690,7 @@ mod test {
         assert_eq!(89, r[0].len());
         assert_eq!(10, r[1].len());
 
-        let r = pp.finish(Some(&[b'|']))?;
+        let r = pp.finish(&mut 0, Some(&[b'|']))?;
         assert_eq!(2, r.len());
         assert_eq!(0, r[0].len());
         assert_eq!(0, r[1].len());
@@ -713,7 +712,7 @@ mod test {
         assert_eq!(1, r.len());
         assert_eq!(89, r[0].len());
 
-        let r = pp.finish(Some(&[b'|', b'A']))?;
+        let r = pp.finish(&mut 0, Some(&[b'|', b'A']))?;
         assert_eq!(2, r.len());
         assert_eq!(10, r[0].len());
         assert_eq!(1, r[1].len());
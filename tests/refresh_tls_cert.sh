@@ -8,3 +8,11 @@ if [ -f "$pwd/localhost.cert" ]; then  rm -f $pwd/localhost.cert; fi
 if [ -f "$pwd/localhost.key" ]; then rm $pwd/localhost.key; fi
 openssl req -newkey rsa:2048 -new -nodes -x509 -days 3650 -out $pwd/localhost.cert -keyout $pwd/localhost.key -subj /CN=localhost -config $pwd/openssl.cfg
 chmod 664 $pwd/localhost.key
+
+# a private CA and a localhost certificate signed by it, only trusted via `cafile`
+rm -f $pwd/ca.cert $pwd/ca.key $pwd/localhost-ca.cert $pwd/localhost-ca.key $pwd/localhost-ca.csr
+openssl req -newkey rsa:2048 -new -nodes -x509 -days 3650 -out $pwd/ca.cert -keyout $pwd/ca.key -subj "/CN=Tremor Test CA"
+openssl req -newkey rsa:2048 -new -nodes -out $pwd/localhost-ca.csr -keyout $pwd/localhost-ca.key -subj /CN=localhost -config $pwd/openssl.cfg
+openssl x509 -req -days 3650 -in $pwd/localhost-ca.csr -CA $pwd/ca.cert -CAkey $pwd/ca.key -CAcreateserial -out $pwd/localhost-ca.cert -extfile $pwd/openssl.cfg -extensions ext
+rm -f $pwd/localhost-ca.csr $pwd/ca.srl
+chmod 664 $pwd/localhost-ca.key
@@ -30,6 +30,8 @@ pub enum Error {
     InvalidTremorUrl(String, String),
     /// Substring out of bounds
     SubstringOutOfBounds,
+    /// Invalid port name
+    InvalidPort(String),
     /// Generic untyped error message
     Generic(String),
 }
@@ -72,6 +74,7 @@ impl Display for Error {
                 write!(w, "Invalid Tremor URL, {reason}: `{detail}`")
             }
             Error::SubstringOutOfBounds => write!(w, "Substring out of bounds"),
+            Error::InvalidPort(port) => write!(w, "Invalid port name `{port}`"),
             Error::Generic(msg) => write!(w, "Error: {msg}"),
         }
     }
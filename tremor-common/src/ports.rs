@@ -12,8 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::borrow::Borrow;
+use std::{borrow::Borrow, str::FromStr};
 
+use crate::Error;
 use beef::Cow;
 use serde::{Deserialize, Serialize};
 
@@ -41,6 +42,25 @@ impl<'port> Port<'port> {
     pub const fn const_str(s: &'port str) -> Self {
         Port::Custom(Cow::const_str(s))
     }
+
+    /// The name of the port
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        self.borrow()
+    }
+
+    /// Turns this port into one that owns its name
+    #[must_use]
+    pub fn into_static(self) -> Port<'static> {
+        match self {
+            Port::In => Port::In,
+            Port::Out => Port::Out,
+            Port::Err => Port::Err,
+            Port::Metrics => Port::Metrics,
+            Port::Overflow => Port::Overflow,
+            Port::Custom(c) => Port::Custom(Cow::owned(c.into_owned())),
+        }
+    }
 }
 
 impl<'port> From<Cow<'port, str>> for Port<'port> {
@@ -86,6 +106,18 @@ impl From<String> for Port<'static> {
     }
 }
 
+/// Parses a port name, only ascii alphanumeric characters and `_` are allowed
+impl FromStr for Port<'static> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        if s.is_empty() || !s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(Error::InvalidPort(s.to_string()));
+        }
+        Ok(Port::from(s.to_string()))
+    }
+}
+
 impl<'port> Borrow<str> for Port<'port> {
     fn borrow(&self) -> &str {
         match self {
@@ -133,3 +165,51 @@ pub const ERR: Port<'static> = Port::Err;
 pub const METRICS: Port<'static> = Port::Metrics;
 /// Overflow
 pub const OVERFLOW: Port<'static> = Port::Overflow;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_ports() {
+        assert_eq!(IN, Port::from("in"));
+        assert_eq!(OUT, Port::from("OUT"));
+        assert_eq!(ERR, Port::from(Cow::from("Err")));
+        assert_eq!(METRICS, Port::from("metrics".to_string()));
+        assert_eq!(OVERFLOW, Port::from("overflow"));
+        for port in [IN, OUT, ERR, METRICS, OVERFLOW] {
+            assert_eq!(Some(port.clone()), port.to_string().parse::<Port>().ok());
+        }
+    }
+
+    #[test]
+    fn custom_ports() -> Result<(), Error> {
+        let port = Port::from("snot");
+        assert_eq!(Port::const_str("snot"), port);
+        assert_eq!("snot", port.as_str());
+        assert_eq!(port, "snot".parse::<Port>()?);
+        assert_eq!(Port::from("snot"), Port::from("SNOT"));
+        assert_eq!(
+            "snot_badger_42",
+            "snot_badger_42".parse::<Port>()?.to_string()
+        );
+
+        assert!("".parse::<Port>().is_err());
+        assert!("snot badger".parse::<Port>().is_err());
+        assert!("snot-badger".parse::<Port>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn custom_ports_are_not_copied() {
+        let name = String::from("snot");
+        let port = Port::from(name.as_str());
+        assert!(matches!(&port, Port::Custom(c) if c.is_borrowed()));
+        assert_eq!(name.as_str(), port.to_string());
+
+        let port = port.into_static();
+        drop(name);
+        assert!(matches!(&port, Port::Custom(c) if !c.is_borrowed()));
+        assert_eq!("snot", port.as_str());
+    }
+}
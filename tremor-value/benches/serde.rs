@@ -0,0 +1,137 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares copying strings via `to_value` with borrowing them when deserializing a `Value`,
+//! both in time and in the number of allocations per iteration
+
+use criterion::{
+    black_box, criterion_group, criterion_main,
+    measurement::{Measurement, ValueFormatter},
+    BenchmarkGroup, Criterion, Throughput,
+};
+use serde::Serialize;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tremor_value::{to_value, Value};
+
+/// Counts allocations, so criterion can measure them via `Allocations`
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/// Measures the allocations done by a benchmark instead of the time it takes
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> Self::Intermediate {
+        ALLOCATIONS.load(Ordering::SeqCst)
+    }
+
+    fn end(&self, start: Self::Intermediate) -> Self::Value {
+        ALLOCATIONS.load(Ordering::SeqCst) - start
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationsFormatter
+    }
+}
+
+struct AllocationsFormatter;
+
+impl ValueFormatter for AllocationsFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        _throughput: &Throughput,
+        _values: &mut [f64],
+    ) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+#[derive(Serialize)]
+struct Payload<'p> {
+    fields: Vec<&'p str>,
+}
+
+fn bench_serde<M: Measurement>(mut group: BenchmarkGroup<M>, throughput: bool) {
+    let field = "snot badger ".repeat(100);
+    let payload = Payload {
+        fields: vec![field.as_str(); 100],
+    };
+    let json = serde_json::to_string(&payload).unwrap_or_default();
+
+    if throughput {
+        group.throughput(Throughput::Bytes(json.len() as u64));
+    }
+    group.bench_function("to_value", |b| b.iter(|| black_box(to_value(&payload))));
+    group.bench_function("deserialize_borrowed", |b| {
+        b.iter(|| black_box(serde_json::from_str::<Value>(&json)))
+    });
+    group.finish();
+}
+
+fn serde_benchmark(c: &mut Criterion) {
+    bench_serde(c.benchmark_group("serde"), true);
+}
+
+fn serde_allocations(c: &mut Criterion<Allocations>) {
+    bench_serde(c.benchmark_group("serde_allocations"), false);
+}
+
+criterion_group!(benches, serde_benchmark);
+criterion_group! {
+    name = allocations;
+    config = Criterion::default().with_measurement(Allocations);
+    targets = serde_allocations
+}
+criterion_main!(benches, allocations);
@@ -17,11 +17,14 @@ pub type Result<T> = std::result::Result<T, Error>;
 use std::fmt;
 
 use fmt::Display;
+use value_trait::{ExtendedValueType, ValueType};
 #[derive(Debug)]
 /// A Error
 pub enum Error {
     /// A map was expected but some other value was found
     ExpectedMap,
+    /// A value of the described type was expected but one of the given type was found
+    ExpectedType(&'static str, ValueType),
     /// A generic serde error
     Serde(String),
     /// A SIMD Json error
@@ -40,6 +43,9 @@ impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::ExpectedMap => write!(f, "Expected a struct, but did not find one"),
+            Error::ExpectedType(expected, found) => {
+                write!(f, "Expected {expected}, but found {}", type_name(*found))
+            }
             Error::Serde(s) | Error::Generic(s) => f.write_str(s),
             Error::SimdJson(e) => write!(f, "SIMD JSON error: {e}"),
         }
@@ -48,6 +54,32 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
+/// The type of a value as users know it, objects are records in tremor
+fn type_name(t: ValueType) -> &'static str {
+    match t {
+        ValueType::Null => "null",
+        ValueType::Bool => "a bool",
+        ValueType::String => "a string",
+        ValueType::I64 | ValueType::U64 | ValueType::I128 | ValueType::U128 => "an integer",
+        ValueType::F64 => "a float",
+        ValueType::Array => "an array",
+        ValueType::Object => "a record",
+        ValueType::Custom(c) => c,
+        ValueType::Extended(e) => match e {
+            ExtendedValueType::I32
+            | ExtendedValueType::I16
+            | ExtendedValueType::I8
+            | ExtendedValueType::U32
+            | ExtendedValueType::U16
+            | ExtendedValueType::U8
+            | ExtendedValueType::Usize => "an integer",
+            ExtendedValueType::F32 => "a float",
+            ExtendedValueType::Char => "a char",
+            ExtendedValueType::None => "nothing",
+        },
+    }
+}
+
 // #[cfg_attr(coverage, no_coverage)] // this is a simple error
 impl serde_ext::de::Error for Error {
     fn custom<T: fmt::Display>(msg: T) -> Self {
@@ -65,9 +97,22 @@ impl serde_ext::ser::Error for Error {
 #[cfg(test)]
 mod test {
     use super::Error;
+    use value_trait::ValueType;
 
     #[test]
     fn from_str() {
         assert_eq!("snot", format!("{}", Error::from("snot")));
     }
+
+    #[test]
+    fn expected_type() {
+        assert_eq!(
+            "Expected a string, but found a record",
+            Error::ExpectedType("a string", ValueType::Object).to_string()
+        );
+        assert_eq!(
+            "Expected a bool, but found null",
+            Error::ExpectedType("a bool", ValueType::Null).to_string()
+        );
+    }
 }
@@ -44,12 +44,15 @@ pub mod prelude;
 mod serde;
 /// The value modules defines a structural module of tremor supported types
 pub mod value;
-pub use crate::serde::structurize;
+pub use crate::serde::{from_value, structurize, Serializer};
 pub use error::*;
 pub use known_key::{Error as KnownKeyError, KnownKey};
 pub use simd_json::{json, json_typed, AlignedBuf, StaticNode};
+pub use value::diff::diff;
 pub use value::from::*;
-pub use value::{parse_to_value, parse_to_value_with_buffers, to_value, Object, Value};
+pub use value::{
+    parse_to_value, parse_to_value_with_buffers, to_sorted_value, to_value, Object, Value,
+};
 
 use simd_json::Node;
 use simd_json_derive::{Deserialize, Serialize, Tape};
@@ -15,6 +15,6 @@
 mod de;
 mod se;
 
-pub use de::structurize;
-pub use se::to_value;
+pub use de::{from_value, structurize};
 pub use se::Serializer;
+pub use se::{to_sorted_value, to_value};
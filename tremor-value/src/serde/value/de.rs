@@ -16,7 +16,8 @@ use crate::{Error, Object, Value};
 use beef::Cow;
 use serde::de::{EnumAccess, IntoDeserializer, VariantAccess};
 use serde_ext::de::{
-    self, Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor,
+    self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer, MapAccess, SeqAccess,
+    Visitor,
 };
 use serde_ext::forward_to_deserialize_any;
 use simd_json::StaticNode;
@@ -275,6 +276,49 @@ impl<'de> Deserialize<'de> for Value<'de> {
 
 struct ValueVisitor;
 
+/// A map key, borrowed from the input when the deserializer allows it
+struct Key<'de>(Cow<'de, str>);
+
+impl<'de> Deserialize<'de> for Key<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(KeyVisitor)
+    }
+}
+
+struct KeyVisitor;
+
+impl<'de> Visitor<'de> for KeyVisitor {
+    type Value = Key<'de>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string key")
+    }
+
+    fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Key(Cow::borrowed(value)))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Key(Cow::owned(value.to_string())))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Key(Cow::owned(value)))
+    }
+}
+
 impl<'de> Visitor<'de> for ValueVisitor {
     type Value = Value<'de>;
 
@@ -509,9 +553,10 @@ impl<'de> Visitor<'de> for ValueVisitor {
         let size = map.size_hint().unwrap_or_default();
 
         let mut m = Object::with_capacity(size);
-        while let Some(k) = map.next_key::<&str>()? {
+        // keys are borrowed where possible, but escaped keys or keys from a reader need to be owned
+        while let Some(Key(k)) = map.next_key()? {
             let v = map.next_value()?;
-            m.insert(k.into(), v);
+            m.insert(k, v);
         }
         Ok(Value::from(m))
     }
@@ -546,13 +591,30 @@ where
     }
 }
 
+/// Converts a `Value` into any type implementing `DeserializeOwned`, the inverse of `to_value`.
+///
+/// Since `T` is required to own all of its data, strings that are borrowed in `value`
+/// are copied into `T` and the result does not borrow from `value`. Use `structurize`
+/// when the target type should borrow strings from the value instead.
+///
+/// # Errors
+///
+/// Will return Err if the DOM value cannot be deserialized to the target type
+pub fn from_value<T>(value: Value) -> crate::error::Result<T>
+where
+    T: DeserializeOwned,
+{
+    structurize(value)
+}
+
 #[cfg(test)]
 mod test {
     use serde::Deserialize;
     use simd_json::ValueAccess;
+    use std::collections::HashMap;
 
     use crate::{error::Result, Value};
-    use crate::{literal, structurize};
+    use crate::{from_value, literal, structurize, to_value};
 
     #[derive(serde::Deserialize, Debug)]
     pub struct SO {}
@@ -643,6 +705,69 @@ mod test {
         Ok(())
     }
 
+    #[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+    struct Inner {
+        name: String,
+        tags: Vec<String>,
+    }
+
+    #[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+    struct Outer {
+        id: u64,
+        inner: HashMap<String, Inner>,
+        nested: HashMap<String, HashMap<String, Vec<i64>>>,
+        maybe: Option<Inner>,
+        nothing: Option<bool>,
+    }
+
+    #[test]
+    fn from_value_round_trip() -> Result<()> {
+        let mut inner = HashMap::new();
+        inner.insert(
+            "first".to_string(),
+            Inner {
+                name: "snot".to_string(),
+                tags: vec!["a".to_string(), "b".to_string()],
+            },
+        );
+        let mut numbers = HashMap::new();
+        numbers.insert("numbers".to_string(), vec![-1, 0, 1]);
+        let mut nested = HashMap::new();
+        nested.insert("level1".to_string(), numbers);
+        let outer = Outer {
+            id: 42,
+            inner,
+            nested,
+            maybe: Some(Inner {
+                name: "badger".to_string(),
+                tags: vec![],
+            }),
+            nothing: None,
+        };
+        let value = to_value(&outer)?;
+        let back: Outer = from_value(value)?;
+        assert_eq!(outer, back);
+        Ok(())
+    }
+
+    #[test]
+    fn from_value_borrowed_input() -> Result<()> {
+        let mut raw_json = br#"{"name": "snot", "tags": ["badger"]}"#.to_vec();
+        let value = crate::parse_to_value(&mut raw_json)?;
+        let inner: Inner = from_value(value)?;
+        // the result owns its strings and outlives the input buffer
+        drop(raw_json);
+        assert_eq!("snot", inner.name);
+        assert_eq!(vec!["badger".to_string()], inner.tags);
+        Ok(())
+    }
+
+    #[test]
+    fn from_value_mismatch() {
+        let res: Result<Inner> = from_value(literal!({"name": 1}));
+        assert!(res.is_err());
+    }
+
     #[test]
     fn variant() {
         #[derive(Clone, Debug, Default)]
@@ -719,4 +844,37 @@ mod test {
         assert_eq!(nac.name, "name");
         assert_eq!(nac.config, None);
     }
+
+    #[test]
+    fn deserialize_borrows_strings() -> Result<()> {
+        let json = r#"{"snot": "badger", "array": ["flork"]}"#;
+        let v: Value =
+            serde_json::from_str(json).map_err(|e| crate::Error::Serde(e.to_string()))?;
+        let in_json = |s: &str| json.as_bytes().as_ptr_range().contains(&s.as_ptr());
+        let (key, value) = v
+            .as_object()
+            .and_then(|o| o.iter().find(|(k, _)| *k == "snot"))
+            .ok_or("no snot")?;
+        assert!(in_json(key));
+        assert!(value.as_str().map_or(false, in_json));
+        assert!(v
+            .get("array")
+            .and_then(|a| a.get_idx(0))
+            .and_then(ValueAccess::as_str)
+            .map_or(false, in_json));
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_owned_keys() -> Result<()> {
+        // escaped keys can't be borrowed
+        let v: Value = serde_json::from_str(r#"{"sn\"ot": "bad\"ger"}"#)
+            .map_err(|e| crate::Error::Serde(e.to_string()))?;
+        assert_eq!(literal!({"sn\"ot": "bad\"ger"}), v);
+        // neither can keys from a reader
+        let mut de = serde_json::Deserializer::from_reader(r#"{"snot": {"badger": 1}}"#.as_bytes());
+        let v = Value::deserialize(&mut de).map_err(|e| crate::Error::Serde(e.to_string()))?;
+        assert_eq!(literal!({"snot": {"badger": 1}}), v);
+        Ok(())
+    }
 }
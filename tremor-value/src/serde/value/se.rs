@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::{value::Bytes, Error, Object, Result, Value};
+use beef::Cow;
 use serde_ext::ser::{
     self, Serialize, SerializeMap as SerializeMapTrait, SerializeSeq as SerializeSeqTrait,
 };
@@ -58,6 +59,11 @@ impl<'value> Serialize for Value<'value> {
 
 /// convert anything implementing `Serialize` into a `Value` using our own `Serializer`.
 ///
+/// The result is always `'static`: serde hands strings to a `Serializer` with a lifetime
+/// that ends with the call, so they have to be copied. To get a `Value` that borrows
+/// strings from its input, deserialize it from that input instead, e.g. via
+/// `serde_json::from_str::<Value>`, which borrows all strings and keys without escapes.
+///
 /// # Errors
 ///
 /// if the given value cannot be serialized
@@ -68,8 +74,124 @@ where
     value.serialize(Serializer::default())
 }
 
-#[derive(Default)]
-pub struct Serializer {}
+/// Like `to_value`, but with the keys of all objects sorted lexicographically, see
+/// `Serializer::sorted`.
+///
+/// # Errors
+///
+/// if the given value cannot be serialized
+pub fn to_sorted_value<T>(value: T) -> Result<Value<'static>>
+where
+    T: Serialize,
+{
+    value.serialize(Serializer::default().sorted(true))
+}
+
+/// Serializer turning anything implementing `Serialize` into a `Value`.
+///
+/// The options are set by chaining, e.g. `Serializer::default().stringify_keys(true).sorted(true)`.
+///
+/// By default only string keys are accepted for maps, use `Serializer::stringify_keys`
+/// to additionally accept integer, bool and char keys by turning them into strings,
+/// the same way `serde_json` does.
+///
+/// Unit enum variants are always accepted as map keys, using their (renamed) variant name.
+/// Use `Serializer::variant_keys` to also accept newtype variants by the key they wrap.
+///
+/// Object keys are kept in the order they are serialized in, use `Serializer::sorted`
+/// for a deterministic, lexicographic order. Unsorted objects with more than 32 keys are
+/// backed by a hash map and lose their order, unless the `preserve_order` feature is enabled.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Serializer {
+    stringify_keys: bool,
+    variant_keys: bool,
+    sort_keys: bool,
+}
+
+impl Serializer {
+    /// Sets whether integer, bool and char map keys are converted into strings instead
+    /// of being rejected.
+    #[must_use]
+    pub fn stringify_keys(mut self, stringify_keys: bool) -> Self {
+        self.stringify_keys = stringify_keys;
+        self
+    }
+
+    /// Sets whether newtype enum variants are accepted as map keys, using the key they
+    /// wrap, e.g. `Key::Named("snot".to_string())` becomes `"snot"`.
+    #[must_use]
+    pub fn variant_keys(mut self, variant_keys: bool) -> Self {
+        self.variant_keys = variant_keys;
+        self
+    }
+
+    /// Sets whether the keys of all objects are sorted lexicographically, e.g. for golden
+    /// files or computing signatures.
+    ///
+    /// Sorted objects are always backed by a vector to keep their order, so key lookups
+    /// in objects with more than 32 keys are linear instead of hashed.
+    #[must_use]
+    pub fn sorted(mut self, sorted: bool) -> Self {
+        self.sort_keys = sorted;
+        self
+    }
+
+    /// Turns a finished map into an object, sorting its keys if requested
+    fn object(self, map: Object<'static>) -> Value<'static> {
+        if self.sort_keys {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            // a hash map would lose the order again
+            let mut sorted = Object::vec_with_capacity(entries.len());
+            for (k, v) in entries {
+                sorted.insert_nocheck(k, v);
+            }
+            Value::from(sorted)
+        } else {
+            Value::from(map)
+        }
+    }
+
+    fn key_serializer(self) -> MapKeySerializer {
+        MapKeySerializer {
+            stringify: self.stringify_keys,
+            variants: self.variant_keys,
+        }
+    }
+}
+
+/// Creates the object a map or struct is serialized into.
+///
+/// With the `preserve_order` feature the object stays backed by a vector no matter how
+/// many keys it gets, so it iterates (and encodes) in insertion order. The price is that
+/// key lookups in objects with more than 32 keys are linear instead of hashed.
+#[cfg(feature = "preserve_order")]
+fn object_with_capacity(capacity: usize) -> Object<'static> {
+    Object::vec_with_capacity(capacity)
+}
+
+#[cfg(not(feature = "preserve_order"))]
+fn object_with_capacity(capacity: usize) -> Object<'static> {
+    Object::with_capacity(capacity)
+}
+
+/// Inserts a serialized entry, replacing the value of an already present key in place.
+///
+/// With the `preserve_order` feature this avoids `insert`, which moves objects with more
+/// than 32 keys to a hash map.
+#[cfg(feature = "preserve_order")]
+fn insert_entry(map: &mut Object<'static>, key: Cow<'static, str>, value: Value<'static>) {
+    if let Some(existing) = map.get_mut(&*key) {
+        *existing = value;
+    } else {
+        map.insert_nocheck(key, value);
+    }
+}
+
+#[cfg(not(feature = "preserve_order"))]
+fn insert_entry(map: &mut Object<'static>, key: Cow<'static, str>, value: Value<'static>) {
+    map.insert(key, value);
+}
 
 impl serde::Serializer for Serializer {
     type Ok = Value<'static>;
@@ -107,6 +229,12 @@ impl serde::Serializer for Serializer {
         Ok(Value::Static(StaticNode::I64(value)))
     }
 
+    #[cfg(feature = "128bit")]
+    #[inline]
+    fn serialize_i128(self, value: i128) -> Result<Value<'static>> {
+        Ok(Value::Static(StaticNode::I128(value)))
+    }
+
     #[inline]
     fn serialize_u8(self, value: u8) -> Result<Value<'static>> {
         self.serialize_u64(u64::from(value))
@@ -123,11 +251,16 @@ impl serde::Serializer for Serializer {
     }
 
     #[inline]
-    #[allow(clippy::cast_possible_wrap)]
     fn serialize_u64(self, value: u64) -> Result<Value<'static>> {
         Ok(Value::Static(StaticNode::U64(value)))
     }
 
+    #[cfg(feature = "128bit")]
+    #[inline]
+    fn serialize_u128(self, value: u128) -> Result<Value<'static>> {
+        Ok(Value::Static(StaticNode::U128(value)))
+    }
+
     #[inline]
     fn serialize_f32(self, value: f32) -> Result<Value<'static>> {
         self.serialize_f64(f64::from(value))
@@ -197,7 +330,7 @@ impl serde::Serializer for Serializer {
         T: Serialize,
     {
         let mut values = Object::with_capacity(1);
-        values.insert(variant.into(), stry!(to_value(value)));
+        values.insert(variant.into(), stry!(value.serialize(self)));
         Ok(Value::from(values))
     }
 
@@ -217,6 +350,7 @@ impl serde::Serializer for Serializer {
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
         Ok(SerializeVec {
             vec: Vec::with_capacity(len.unwrap_or(0)),
+            ser: self,
         })
     }
 
@@ -242,13 +376,15 @@ impl serde::Serializer for Serializer {
         Ok(SerializeTupleVariant {
             name: variant.to_owned(),
             vec: Vec::with_capacity(len),
+            ser: self,
         })
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
         Ok(SerializeMap::Map {
-            map: Object::new(),
+            map: object_with_capacity(len.unwrap_or(0)),
             next_key: None,
+            ser: self,
         })
     }
 
@@ -261,34 +397,44 @@ impl serde::Serializer for Serializer {
         _name: &'static str,
         _variant_index: u32,
         variant: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeStructVariant> {
         Ok(SerializeStructVariant {
             name: variant.to_owned(),
-            map: Object::new(),
+            map: object_with_capacity(len),
+            ser: self,
         })
     }
 }
 
+/// Serializes sequences and tuples into a `Value::Array`
 pub struct SerializeVec {
     vec: Vec<Value<'static>>,
+    ser: Serializer,
 }
 
+/// Serializes tuple variants into a single-key `Value::Object`
 pub struct SerializeTupleVariant {
     name: String,
     vec: Vec<Value<'static>>,
+    ser: Serializer,
 }
 
+/// Serializes maps and structs into a `Value::Object`
 pub enum SerializeMap {
+    /// map in progress, with the key waiting for its value
     Map {
         map: Object<'static>,
         next_key: Option<String>,
+        ser: Serializer,
     },
 }
 
+/// Serializes struct variants into a single-key `Value::Object`
 pub struct SerializeStructVariant {
     name: String,
     map: Object<'static>,
+    ser: Serializer,
 }
 
 impl serde::ser::SerializeSeq for SerializeVec {
@@ -299,7 +445,7 @@ impl serde::ser::SerializeSeq for SerializeVec {
     where
         T: Serialize,
     {
-        self.vec.push(stry!(to_value(value)));
+        self.vec.push(stry!(value.serialize(self.ser)));
         Ok(())
     }
 
@@ -348,7 +494,7 @@ impl serde::ser::SerializeTupleVariant for SerializeTupleVariant {
     where
         T: Serialize,
     {
-        self.vec.push(stry!(to_value(value)));
+        self.vec.push(stry!(value.serialize(self.ser)));
         Ok(())
     }
 
@@ -371,9 +517,11 @@ impl serde::ser::SerializeMap for SerializeMap {
     {
         match *self {
             Self::Map {
-                ref mut next_key, ..
+                ref mut next_key,
+                ser,
+                ..
             } => {
-                *next_key = Some(stry!(key.serialize(MapKeySerializer {})));
+                *next_key = Some(stry!(key.serialize(ser.key_serializer())));
                 Ok(())
             }
         }
@@ -387,11 +535,12 @@ impl serde::ser::SerializeMap for SerializeMap {
             Self::Map {
                 ref mut map,
                 ref mut next_key,
+                ser,
             } => {
                 let key = next_key.take();
                 // ALLOW: Panic because this indicates a bug in the program rather than an expected failure.
                 let key = key.expect("serialize_value called before serialize_key");
-                map.insert(key.into(), stry!(to_value(value)));
+                insert_entry(map, key.into(), stry!(value.serialize(ser)));
                 Ok(())
             }
         }
@@ -399,12 +548,26 @@ impl serde::ser::SerializeMap for SerializeMap {
 
     fn end(self) -> Result<Value<'static>> {
         match self {
-            Self::Map { map, .. } => Ok(Value::from(map)),
+            Self::Map { map, ser, .. } => Ok(ser.object(map)),
         }
     }
 }
 
-struct MapKeySerializer {}
+struct MapKeySerializer {
+    stringify: bool,
+    /// accept newtype variants by the key they wrap
+    variants: bool,
+}
+
+impl MapKeySerializer {
+    fn stringify<T: ToString>(self, value: &T) -> Result<String> {
+        if self.stringify {
+            Ok(value.to_string())
+        } else {
+            Err(key_must_be_a_string())
+        }
+    }
+}
 
 fn key_must_be_a_string() -> Error {
     Error::Serde("Key must be a String.".to_string())
@@ -440,62 +603,52 @@ impl serde_ext::Serializer for MapKeySerializer {
         value.serialize(self)
     }
 
-    fn serialize_bool(self, _value: bool) -> Result<Self::Ok> {
-        Err(key_must_be_a_string())
+    fn serialize_bool(self, value: bool) -> Result<Self::Ok> {
+        self.stringify(&value)
     }
 
-    fn serialize_i8(self, _value: i8) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i8(self, value: i8) -> Result<Self::Ok> {
+        self.stringify(&value)
     }
 
-    fn serialize_i16(self, _value: i16) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i16(self, value: i16) -> Result<Self::Ok> {
+        self.stringify(&value)
     }
 
-    fn serialize_i32(self, _value: i32) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i32(self, value: i32) -> Result<Self::Ok> {
+        self.stringify(&value)
     }
 
-    fn serialize_i64(self, _value: i64) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_i64(self, value: i64) -> Result<Self::Ok> {
+        self.stringify(&value)
     }
 
-    fn serialize_u8(self, _value: u8) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u8(self, value: u8) -> Result<Self::Ok> {
+        self.stringify(&value)
     }
 
-    fn serialize_u16(self, _value: u16) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u16(self, value: u16) -> Result<Self::Ok> {
+        self.stringify(&value)
     }
 
-    fn serialize_u32(self, _value: u32) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u32(self, value: u32) -> Result<Self::Ok> {
+        self.stringify(&value)
     }
 
-    fn serialize_u64(self, _value: u64) -> Result<Self::Ok> {
-        //Ok(value.to_string())
-        Err(key_must_be_a_string())
+    fn serialize_u64(self, value: u64) -> Result<Self::Ok> {
+        self.stringify(&value)
     }
 
     fn serialize_f32(self, _value: f32) -> Result<Self::Ok> {
-        //Err(key_must_be_a_string())
         Err(key_must_be_a_string())
     }
 
     fn serialize_f64(self, _value: f64) -> Result<Self::Ok> {
-        //Err(key_must_be_a_string())
         Err(key_must_be_a_string())
     }
 
-    fn serialize_char(self, _value: char) -> Result<Self::Ok> {
-        Err(key_must_be_a_string())
+    fn serialize_char(self, value: char) -> Result<Self::Ok> {
+        self.stringify(&value)
     }
 
     #[inline]
@@ -520,12 +673,16 @@ impl serde_ext::Serializer for MapKeySerializer {
         _name: &'static str,
         _variant_index: u32,
         _variant: &'static str,
-        _value: &T,
+        value: &T,
     ) -> Result<Self::Ok>
     where
         T: Serialize,
     {
-        Err(key_must_be_a_string())
+        if self.variants {
+            value.serialize(self)
+        } else {
+            Err(key_must_be_a_string())
+        }
     }
 
     fn serialize_none(self) -> Result<Self::Ok> {
@@ -615,14 +772,15 @@ impl serde::ser::SerializeStructVariant for SerializeStructVariant {
     where
         T: Serialize,
     {
-        self.map.insert(key.into(), stry!(to_value(value)));
+        let value = stry!(value.serialize(self.ser));
+        insert_entry(&mut self.map, key.into(), value);
         Ok(())
     }
 
     fn end(self) -> Result<Value<'static>> {
         let mut object = Object::with_capacity(1);
 
-        object.insert(self.name.into(), Value::from(self.map));
+        object.insert(self.name.into(), self.ser.object(self.map));
 
         Ok(Value::from(object))
     }
@@ -765,6 +923,58 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn serialize_u64_max() -> Result<()> {
+        #[derive(Serialize)]
+        struct Big {
+            value: u64,
+        }
+        assert_eq!(Value::from(u64::MAX), to_value(u64::MAX)?);
+
+        let v = to_value(Big { value: u64::MAX })?;
+        assert_eq!(Some(u64::MAX), v.get_u64("value"));
+        assert_eq!(None, v.get_i64("value"));
+        Ok(())
+    }
+
+    #[cfg(feature = "128bit")]
+    #[test]
+    fn serialize_128bit() -> Result<()> {
+        #[derive(Serialize)]
+        struct Huge {
+            unsigned: u128,
+            signed: i128,
+        }
+        assert_eq!(
+            Value::Static(StaticNode::U128(u128::MAX)),
+            to_value(u128::MAX)?
+        );
+        assert_eq!(
+            Value::Static(StaticNode::I128(i128::MIN)),
+            to_value(i128::MIN)?
+        );
+
+        let v = to_value(Huge {
+            unsigned: u128::MAX,
+            signed: i128::MIN,
+        })?;
+        assert_eq!(
+            Some(&Value::Static(StaticNode::U128(u128::MAX))),
+            v.get("unsigned")
+        );
+        assert_eq!(
+            Some(&Value::Static(StaticNode::I128(i128::MIN))),
+            v.get("signed")
+        );
+
+        // and back
+        let unsigned: u128 = crate::structurize(to_value(u128::MAX)?)?;
+        assert_eq!(u128::MAX, unsigned);
+        let signed: i128 = crate::structurize(to_value(i128::MIN)?)?;
+        assert_eq!(i128::MIN, signed);
+        Ok(())
+    }
+
     #[derive(Serialize, Clone)]
     struct NestedStruct {
         key: String,
@@ -934,6 +1144,210 @@ mod tests {
         }
     }
 
+    #[test]
+    fn serialize_map_stringified_int_keys() -> Result<()> {
+        let mut map: std::collections::HashMap<i64, String> =
+            std::collections::HashMap::with_capacity(2);
+        map.insert(-1, "snot".to_string());
+        map.insert(42, "badger".to_string());
+
+        let value = map.serialize(Serializer::default().stringify_keys(true))?;
+        assert_eq!(Some("snot"), value.get_str("-1"));
+        assert_eq!(Some("badger"), value.get_str("42"));
+
+        // nested maps are stringified as well
+        let nested = vec![map];
+        let value = nested.serialize(Serializer::default().stringify_keys(true))?;
+        assert_eq!(
+            Some("badger"),
+            value.get_idx(0).and_then(|m| m.get_str("42"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_map_stringified_bool_keys() -> Result<()> {
+        let mut map: std::collections::HashMap<bool, u8> =
+            std::collections::HashMap::with_capacity(2);
+        map.insert(true, 1);
+        map.insert(false, 0);
+
+        let value = map.serialize(Serializer::default().stringify_keys(true))?;
+        assert_eq!(Some(1), value.get_u64("true"));
+        assert_eq!(Some(0), value.get_u64("false"));
+
+        // the default stays strict
+        assert!(to_value(&map).is_err());
+        let serializer = Serializer::default()
+            .stringify_keys(true)
+            .stringify_keys(false);
+        assert!(map.serialize(serializer).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_chained_options() -> Result<()> {
+        let map: std::collections::BTreeMap<u8, u8> = vec![(2, 2), (10, 10)].into_iter().collect();
+        let serializer = Serializer::default().stringify_keys(true).sorted(true);
+        let value = map.serialize(serializer)?;
+        assert_eq!(vec!["10", "2"], keys(&value));
+        assert_eq!(Some(10), value.get_u64("10"));
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_map_stringified_float_keys_fail() {
+        let res = serde::Serializer::collect_map(
+            Serializer::default().stringify_keys(true),
+            vec![(1.5_f64, 1_u8)],
+        );
+        assert!(res.is_err());
+    }
+
+    #[derive(Serialize, PartialEq, Eq, Hash)]
+    enum Key {
+        Snot,
+        #[serde(rename = "flork")]
+        Badger,
+        Named(String),
+    }
+
+    #[test]
+    fn serialize_map_enum_keys() -> Result<()> {
+        let mut map = std::collections::HashMap::with_capacity(2);
+        map.insert(Key::Snot, 1_u8);
+        map.insert(Key::Badger, 2);
+        // unit variants are always accepted
+        let value = to_value(&map)?;
+        assert_eq!(Some(1), value.get_u64("Snot"));
+        assert_eq!(Some(2), value.get_u64("flork"));
+
+        map.insert(Key::Named("mork".to_string()), 3);
+        assert!(to_value(&map).is_err());
+        let value = map.serialize(Serializer::default().variant_keys(true))?;
+        assert_eq!(Some(3), value.get_u64("mork"));
+        assert_eq!(Some(3), value.as_object().map(halfbrown::HashMap::len));
+
+        // options are still rejected
+        let mut map = std::collections::HashMap::with_capacity(1);
+        map.insert(Some(Key::Snot), 1_u8);
+        assert!(map
+            .serialize(Serializer::default().variant_keys(true))
+            .is_err());
+        Ok(())
+    }
+
+    #[derive(Serialize)]
+    struct Badger {
+        zebra: u8,
+        snot: &'static str,
+        apple: bool,
+        mango: Vec<u8>,
+        banana: std::collections::HashMap<&'static str, u8>,
+    }
+
+    fn keys(value: &Value) -> Vec<String> {
+        value
+            .as_object()
+            .map(|o| o.keys().map(ToString::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn serialize_sorted() -> Result<()> {
+        let badger = Badger {
+            zebra: 1,
+            snot: "badger",
+            apple: true,
+            mango: vec![1, 2],
+            banana: vec![("flork", 1), ("a", 2), ("mork", 3), ("b", 4)]
+                .into_iter()
+                .collect(),
+        };
+        let first = to_sorted_value(&badger)?;
+        let second = badger.serialize(Serializer::default().sorted(true))?;
+        assert_eq!(first, second);
+        assert_eq!(keys(&first), keys(&second));
+        assert_eq!(
+            vec!["apple", "banana", "mango", "snot", "zebra"],
+            keys(&first)
+        );
+        let banana = first.get("banana").map(keys).unwrap_or_default();
+        assert_eq!(vec!["a", "b", "flork", "mork"], banana);
+
+        // struct variants are sorted as well
+        let snot = to_sorted_value(Snot::Struct {
+            snot: None,
+            badger: "snot".to_string(),
+        })?;
+        let inner = snot.get("Struct").map(keys).unwrap_or_default();
+        assert_eq!(vec!["badger", "snot"], inner);
+
+        // the default keeps the field order
+        assert_eq!(
+            vec!["zebra", "snot", "apple", "mango", "banana"],
+            keys(&to_value(&badger)?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_sorted_many_keys() -> Result<()> {
+        /// serializes its entries as a map, in reverse order of their names
+        struct Many(usize);
+        impl Serialize for Many {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: ser::Serializer,
+            {
+                serializer.collect_map((0..self.0).rev().map(|i| (format!("key{i:02}"), i)))
+            }
+        }
+        let value = to_sorted_value(Many(40))?;
+        let expected: Vec<String> = (0..40).map(|i| format!("key{i:02}")).collect();
+        assert_eq!(expected, keys(&value));
+        assert_eq!(Some(&Value::from(39_u64)), value.get("key39"));
+        Ok(())
+    }
+
+    #[cfg(feature = "preserve_order")]
+    #[test]
+    fn serialize_preserve_order() -> Result<()> {
+        /// serializes its entries as a map, in reverse order of their names
+        struct Many(usize);
+        impl Serialize for Many {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: ser::Serializer,
+            {
+                serializer.collect_map((0..self.0).rev().map(|i| (format!("key{i:02}"), i)))
+            }
+        }
+        #[derive(Serialize)]
+        struct Ordered {
+            zebra: u8,
+            snot: &'static str,
+            apple: bool,
+            many: Many,
+        }
+
+        let value = to_value(Ordered {
+            zebra: 1,
+            snot: "badger",
+            apple: true,
+            many: Many(64),
+        })?;
+        assert_eq!(vec!["zebra", "snot", "apple", "many"], keys(&value));
+        let many = value.get("many").map(keys).unwrap_or_default();
+        let expected: Vec<_> = (0..64).rev().map(|i| format!("key{i:02}")).collect();
+        assert_eq!(expected, many);
+        assert_eq!(Some(0), value.get("many").and_then(|m| m.get_u64("key00")));
+        assert!(value.encode().starts_with(
+            r#"{"zebra":1,"snot":"badger","apple":true,"many":{"key63":63,"key62":62"#
+        ));
+        Ok(())
+    }
+
     /*
     not working until rust has specialization
 
@@ -964,6 +1378,27 @@ mod tests {
        }
     */
 
+    #[test]
+    fn serialize_bytes_round_trip() -> Result<()> {
+        /// serializes via `serialize_bytes`, like `serde_bytes` does
+        struct ByteSlice<'a>(&'a [u8]);
+        impl Serialize for ByteSlice<'_> {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: ser::Serializer,
+            {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+        let bytes = [1_u8, 2, 3];
+        let original = Value::Bytes(Cow::borrowed(&bytes));
+        let serialized = to_value(ByteSlice(&bytes))?;
+        assert!(matches!(serialized, Value::Bytes(_)));
+        assert_eq!(original, serialized);
+        assert_eq!(original, to_value(&original)?);
+        Ok(())
+    }
+
     #[test]
     fn serialize_value_bytes() {
         let bytes = Value::Bytes(Cow::borrowed(&[1, 2, 3]));
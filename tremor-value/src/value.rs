@@ -13,6 +13,7 @@
 // limitations under the License.
 
 mod cmp;
+pub(crate) mod diff;
 /// Conversions from other types to the value type
 pub mod from;
 
@@ -32,7 +33,7 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-pub use crate::serde::to_value;
+pub use crate::serde::{to_sorted_value, to_value};
 pub use r#static::StaticValue;
 
 /// Representation of a JSON object
@@ -99,6 +100,44 @@ impl<'value> Value<'value> {
         Value::Array(vec![])
     }
 
+    /// Creates an object value from key value pairs, e.g. a `HashMap<String, String>`
+    ///
+    /// ```
+    /// # use tremor_value::{literal, Value};
+    /// let v = Value::object_from([("snot", Value::object_from([("badger", 42)]))]);
+    /// assert_eq!(literal!({"snot": {"badger": 42}}), v);
+    /// ```
+    #[must_use]
+    pub fn object_from<I, K, V>(pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<Cow<'value, str>>,
+        V: Into<Value<'value>>,
+    {
+        Value::Object(Box::new(
+            pairs
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        ))
+    }
+
+    /// Creates an array value from values, e.g. a `Vec<&str>`
+    ///
+    /// ```
+    /// # use tremor_value::{literal, Value};
+    /// let v = Value::array_from([Value::from("snot"), Value::array_from([1, 2])]);
+    /// assert_eq!(literal!(["snot", [1, 2]]), v);
+    /// ```
+    #[must_use]
+    pub fn array_from<I, V>(values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value<'value>>,
+    {
+        Value::Array(values.into_iter().map(Into::into).collect())
+    }
+
     /// Creates a constant null value
     #[must_use]
     pub const fn const_null() -> Value<'static> {
@@ -207,10 +246,27 @@ impl Ord for Static {
                     (v1 as i64).cmp(&v2)
                 }
             }
+            #[cfg(feature = "128bit")]
+            (v1, v2) => cmp_128bit(v1, v2),
         }
     }
 }
 
+/// Compares two numbers of which at least one is a 128 bit integer
+#[cfg(feature = "128bit")]
+fn cmp_128bit(v1: StaticNode, v2: StaticNode) -> Ordering {
+    if let (Some(i1), Some(i2)) = (v1.as_i128(), v2.as_i128()) {
+        i1.cmp(&i2)
+    } else if let (Some(u1), Some(u2)) = (v1.as_u128(), v2.as_u128()) {
+        u1.cmp(&u2)
+    } else {
+        // one of them is a float, or a negative integer compared to one beyond the `i128` range
+        let f1 = v1.cast_f64().unwrap_or_default();
+        let f2 = v2.cast_f64().unwrap_or_default();
+        f1.partial_cmp(&f2).unwrap_or(Ordering::Equal)
+    }
+}
+
 impl<'value> PartialOrd for Value<'value> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -358,6 +414,304 @@ impl<'value> Value<'value> {
     {
         self.get(k).and_then(Self::as_char)
     }
+
+    /// Looks up a value by a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901),
+    /// e.g. `/snot/0/badger`.
+    ///
+    /// `~1` and `~0` in a reference token stand for `/` and `~`, arrays are indexed by
+    /// their decimal index. Returns `None` if the pointer is malformed or the value
+    /// doesn't exist, the empty pointer references the value itself.
+    #[must_use]
+    pub fn pointer(&self, ptr: &str) -> Option<&Self> {
+        let mut target = self;
+        for token in pointer_tokens(ptr)? {
+            target = match target {
+                Self::Object(o) => o.get(token.as_str())?,
+                Self::Array(a) => a.get(pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    /// Looks up a value by a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901)
+    /// for modification, see `pointer`.
+    #[must_use]
+    pub fn pointer_mut(&mut self, ptr: &str) -> Option<&mut Self> {
+        let mut target = self;
+        for token in pointer_tokens(ptr)? {
+            target = match target {
+                Self::Object(o) => o.get_mut(token.as_str())?,
+                Self::Array(a) => a.get_mut(pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    /// Looks up a value by a dotted path, e.g. `snot.0.badger`, as paths are written in
+    /// tremor-script.
+    ///
+    /// Segments index objects by key and arrays by their decimal index. Returns `None` if
+    /// any segment doesn't exist, the empty path references the value itself. Keys containing
+    /// a `.` can't be addressed this way, use `pointer` for them.
+    ///
+    /// ```
+    /// # use tremor_value::literal;
+    /// let v = literal!({"snot": [{"badger": 42}]});
+    /// assert_eq!(Some(&literal!(42)), v.get_dotted("snot.0.badger"));
+    /// assert_eq!(None, v.get_dotted("snot.1.badger"));
+    /// ```
+    #[must_use]
+    pub fn get_dotted(&self, path: &str) -> Option<&Self> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        let mut target = self;
+        for segment in path.split('.') {
+            target = match target {
+                Self::Object(o) => o.get(segment)?,
+                Self::Array(a) => a.get(pointer_index(segment)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    /// Merges `patch` into this value following
+    /// [JSON Merge Patch](https://www.rfc-editor.org/rfc/rfc7386) semantics.
+    ///
+    /// Objects are merged recursively, keys with a `null` value in the patch are removed.
+    /// Any other patch, including arrays, replaces the value.
+    pub fn merge(&mut self, patch: &Value<'value>) {
+        if let Self::Object(patch) = patch {
+            if !self.is_object() {
+                *self = Self::object_with_capacity(patch.len());
+            }
+            if let Self::Object(target) = self {
+                for (k, v) in patch.iter() {
+                    if v.is_null() {
+                        target.remove(k);
+                    } else if let Some(t) = target.get_mut(k) {
+                        t.merge(v);
+                    } else {
+                        // merge into null so nested nulls are dropped as well
+                        let mut t = Self::null();
+                        t.merge(v);
+                        target.insert(k.clone(), t);
+                    }
+                }
+            }
+        } else {
+            *self = patch.clone();
+        }
+    }
+
+    /// Coerces all integers to a single representation, recursively: `U64` if they are
+    /// non-negative, `I64` otherwise.
+    ///
+    /// The representation of an integer depends on where the value came from, e.g. parsed
+    /// JSON holds `I64`s while `to_value` produces `U64`s for unsigned fields. Normalized
+    /// values that are structurally equal are represented identically, so code inspecting
+    /// the representation, like `value_type`, treats them the same.
+    ///
+    /// ```
+    /// # use tremor_value::literal;
+    /// # use value_trait::{ValueAccess, ValueType};
+    /// let mut v = literal!({"snot": [1, -1]});
+    /// v.normalize_numbers();
+    /// let value_type = |path| v.get_dotted(path).map(ValueAccess::value_type);
+    /// assert_eq!(Some(ValueType::U64), value_type("snot.0"));
+    /// assert_eq!(Some(ValueType::I64), value_type("snot.1"));
+    /// ```
+    pub fn normalize_numbers(&mut self) {
+        match self {
+            Self::Static(StaticNode::I64(i)) => {
+                if let Ok(u) = u64::try_from(*i) {
+                    *self = Self::Static(StaticNode::U64(u));
+                }
+            }
+            #[cfg(feature = "128bit")]
+            Self::Static(StaticNode::I128(i)) => {
+                if let Ok(u) = u64::try_from(*i) {
+                    *self = Self::Static(StaticNode::U64(u));
+                } else if let Ok(i) = i64::try_from(*i) {
+                    *self = Self::Static(StaticNode::I64(i));
+                }
+            }
+            #[cfg(feature = "128bit")]
+            Self::Static(StaticNode::U128(u)) => {
+                if let Ok(u) = u64::try_from(*u) {
+                    *self = Self::Static(StaticNode::U64(u));
+                }
+            }
+            Self::Array(a) => a.iter_mut().for_each(Self::normalize_numbers),
+            Self::Object(o) => o.values_mut().for_each(Self::normalize_numbers),
+            Self::Static(_) | Self::String(_) | Self::Bytes(_) => (),
+        }
+    }
+
+    /// Calls `f` on this value and then, depth-first, on every value nested in it.
+    ///
+    /// A value is visited before its elements, so these are the ones `f` left in place,
+    /// e.g. values `f` replaced are not descended into but their replacements are.
+    ///
+    /// ```
+    /// # use tremor_value::{literal, Value};
+    /// let mut v = literal!({"snot": ["badger", 1]});
+    /// v.visit_mut(|v| {
+    ///     if let Value::String(s) = v {
+    ///         *s = s.to_uppercase().into();
+    ///     }
+    /// });
+    /// assert_eq!(literal!({"snot": ["BADGER", 1]}), v);
+    /// ```
+    pub fn visit_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut Self),
+    {
+        self.visit_mut_with(&mut f);
+    }
+
+    fn visit_mut_with<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&mut Self),
+    {
+        f(self);
+        match self {
+            Self::Array(a) => a.iter_mut().for_each(|v| v.visit_mut_with(f)),
+            Self::Object(o) => o.values_mut().for_each(|v| v.visit_mut_with(f)),
+            Self::Static(_) | Self::String(_) | Self::Bytes(_) => (),
+        }
+    }
+
+    /// Replaces the values of all object fields named like one of `keys`, at any depth,
+    /// with `"***"`, e.g. to keep secrets out of logs.
+    ///
+    /// ```
+    /// # use tremor_value::literal;
+    /// let mut v = literal!({"user": "snot", "auth": {"password": "badger"}});
+    /// v.redact(&["password"]);
+    /// assert_eq!(literal!({"user": "snot", "auth": {"password": "***"}}), v);
+    /// ```
+    pub fn redact(&mut self, keys: &[&str]) {
+        self.visit_mut(|value| {
+            if let Self::Object(o) = value {
+                for (k, v) in o.iter_mut() {
+                    let k: &str = k;
+                    if keys.contains(&k) {
+                        *v = Self::from("***");
+                    }
+                }
+            }
+        });
+    }
+
+    /// Moves the value out and leaves `null` in its place, like `std::mem::take`
+    ///
+    /// This gets an owned value out of a mutable one, e.g. a field of an event obtained via
+    /// `get_mut`, without the deep copy of `clone` or `clone_static`. Borrowed strings and
+    /// bytes stay borrowed, so only call `into_static` on the taken value where it needs
+    /// to outlive the event.
+    #[inline]
+    #[must_use]
+    pub fn take(&mut self) -> Self {
+        std::mem::take(self)
+    }
+
+    /// The value as `bool`
+    ///
+    /// # Errors
+    /// if the value isn't a bool, naming the type it is instead
+    pub fn try_as_bool(&self) -> Result<bool> {
+        self.as_bool().ok_or_else(|| self.expected("a bool"))
+    }
+
+    /// The value as `i64`
+    ///
+    /// # Errors
+    /// if the value isn't an integer that fits into an `i64`
+    pub fn try_as_i64(&self) -> Result<i64> {
+        self.as_i64()
+            .ok_or_else(|| self.expected("a signed 64 bit integer"))
+    }
+
+    /// The value as `u64`
+    ///
+    /// # Errors
+    /// if the value isn't a non-negative integer that fits into an `u64`
+    pub fn try_as_u64(&self) -> Result<u64> {
+        self.as_u64()
+            .ok_or_else(|| self.expected("an unsigned 64 bit integer"))
+    }
+
+    /// The value as `usize`, e.g. for sizes or counts in configurations
+    ///
+    /// # Errors
+    /// if the value isn't a non-negative integer that fits into an `usize`
+    pub fn try_as_usize(&self) -> Result<usize> {
+        self.as_usize()
+            .ok_or_else(|| self.expected("an unsigned integer"))
+    }
+
+    /// The value as `f64`, integers are accepted and converted
+    ///
+    /// # Errors
+    /// if the value is neither a float nor an integer
+    pub fn try_as_f64(&self) -> Result<f64> {
+        self.cast_f64().ok_or_else(|| self.expected("a float"))
+    }
+
+    /// The value as `&str`
+    ///
+    /// # Errors
+    /// if the value isn't a string, naming the type it is instead
+    pub fn try_as_str(&self) -> Result<&str> {
+        self.as_str().ok_or_else(|| self.expected("a string"))
+    }
+
+    /// The value as array
+    ///
+    /// # Errors
+    /// if the value isn't an array, naming the type it is instead
+    pub fn try_as_array(&self) -> Result<&Vec<Self>> {
+        self.as_array().ok_or_else(|| self.expected("an array"))
+    }
+
+    /// The value as object
+    ///
+    /// # Errors
+    /// if the value isn't an object, naming the type it is instead
+    pub fn try_as_object(&self) -> Result<&Object<'value>> {
+        self.as_object().ok_or_else(|| self.expected("a record"))
+    }
+
+    fn expected(&self, expected: &'static str) -> Error {
+        Error::ExpectedType(expected, self.value_type())
+    }
+}
+
+/// The unescaped reference tokens of a JSON Pointer, `None` if it doesn't start with `/`
+fn pointer_tokens(ptr: &str) -> Option<Vec<String>> {
+    if ptr.is_empty() {
+        return Some(Vec::new());
+    }
+    let tokens = ptr.strip_prefix('/')?;
+    Some(
+        tokens
+            .split('/')
+            .map(|t| t.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// An array index of a JSON Pointer, which has neither signs nor leading zeros
+fn pointer_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.len() > 1 && token.starts_with('0')) {
+        None
+    } else {
+        token.parse().ok()
+    }
 }
 
 impl<'value> Builder<'value> for Value<'value> {
@@ -630,6 +984,114 @@ mod test {
     use crate::literal;
     use proptest::proptest;
 
+    #[test]
+    fn object_and_array_from() {
+        let tags = vec!["snot", "badger"];
+        let mut attributes = std::collections::BTreeMap::new();
+        attributes.insert("flork".to_string(), "flark".to_string());
+        let v = Value::object_from([
+            ("tags", Value::array_from(tags)),
+            ("attributes", Value::object_from(attributes)),
+            (
+                "nested",
+                Value::array_from([
+                    Value::object_from([("a", 1_u64)]),
+                    Value::array_from(Vec::<Value>::new()),
+                    Value::null(),
+                ]),
+            ),
+        ]);
+        assert_eq!(
+            literal!({
+                "tags": ["snot", "badger"],
+                "attributes": {"flork": "flark"},
+                "nested": [{"a": 1}, [], null]
+            }),
+            v
+        );
+        assert_eq!(
+            Value::object(),
+            Value::object_from(Vec::<(&str, u8)>::new())
+        );
+        assert_eq!(Value::array(), Value::array_from(Vec::<u8>::new()));
+    }
+
+    #[test]
+    fn try_as() -> Result<()> {
+        let v = literal!({
+            "concurrency": 4,
+            "ratio": 0.5,
+            "offset": -1,
+            "tls": true,
+            "url": "http://localhost",
+            "headers": ["snot"]
+        });
+        assert_eq!(6, v.try_as_object()?.len());
+        assert_eq!(4, v["concurrency"].try_as_usize()?);
+        assert_eq!(4, v["concurrency"].try_as_u64()?);
+        assert_eq!(-1, v["offset"].try_as_i64()?);
+        assert!((v["ratio"].try_as_f64()? - 0.5).abs() < f64::EPSILON);
+        assert!((v["concurrency"].try_as_f64()? - 4.0).abs() < f64::EPSILON);
+        assert!(v["tls"].try_as_bool()?);
+        assert_eq!("http://localhost", v["url"].try_as_str()?);
+        assert_eq!(&vec![Value::from("snot")], v["headers"].try_as_array()?);
+
+        let message = |r: Result<_>| r.err().map(|e: Error| e.to_string());
+        assert_eq!(
+            Some("Expected an unsigned integer, but found a string".to_string()),
+            message(v["url"].try_as_usize().map(|_| ()))
+        );
+        assert_eq!(
+            Some("Expected an unsigned 64 bit integer, but found an integer".to_string()),
+            message(v["offset"].try_as_u64().map(|_| ()))
+        );
+        assert_eq!(
+            Some("Expected a signed 64 bit integer, but found a float".to_string()),
+            message(v["ratio"].try_as_i64().map(|_| ()))
+        );
+        assert_eq!(
+            Some("Expected a float, but found a bool".to_string()),
+            message(v["tls"].try_as_f64().map(|_| ()))
+        );
+        assert_eq!(
+            Some("Expected a bool, but found null".to_string()),
+            message(Value::null().try_as_bool().map(|_| ()))
+        );
+        assert_eq!(
+            Some("Expected a string, but found an array".to_string()),
+            message(v["headers"].try_as_str().map(|_| ()))
+        );
+        assert_eq!(
+            Some("Expected an array, but found a record".to_string()),
+            message(v.try_as_array().map(|_| ()))
+        );
+        assert_eq!(
+            Some("Expected a record, but found bytes".to_string()),
+            message(Value::Bytes(vec![1_u8].into()).try_as_object().map(|_| ()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn take() {
+        let mut v = literal!({
+            "snot": ["badger", 1, {"flork": null}],
+            "bytes": Value::Bytes(vec![1_u8, 2].into())
+        });
+        let expected = v.clone();
+        let taken = v.take();
+        assert_eq!(Value::Static(StaticNode::Null), v);
+        assert_eq!(expected, taken);
+
+        // take a field out of an object, borrowed strings stay borrowed
+        let raw = String::from("badger");
+        let mut v = literal!({"snot": Value::from(raw.as_str()), "flork": 1});
+        let field = v.get_mut("snot").map(Value::take);
+        assert_eq!(Some(Value::from("badger")), field);
+        assert!(matches!(&field, Some(Value::String(s)) if s.is_borrowed()));
+        assert_eq!(literal!({"snot": null, "flork": 1}), v);
+    }
+
     #[test]
     fn test_cmp_map() {
         let mut o1 = Object::new();
@@ -646,6 +1108,268 @@ mod test {
         assert_eq!(cmp_map(&o1, &o2), Ordering::Greater);
     }
 
+    #[test]
+    fn pointer() {
+        let v = literal!({
+            "snot": {"badger": [1, {"flork": "mork"}, 3]},
+            "a/b": 1,
+            "m~n": 2,
+            "": 3,
+            "0": 4
+        });
+        assert_eq!(Some(&v), v.pointer(""));
+        assert_eq!(
+            Some(&literal!([1, {"flork": "mork"}, 3])),
+            v.pointer("/snot/badger")
+        );
+        assert_eq!(Some(&literal!("mork")), v.pointer("/snot/badger/1/flork"));
+        assert_eq!(Some(&literal!(1)), v.pointer("/snot/badger/0"));
+        assert_eq!(Some(&literal!(1)), v.pointer("/a~1b"));
+        assert_eq!(Some(&literal!(2)), v.pointer("/m~0n"));
+        assert_eq!(Some(&literal!(3)), v.pointer("/"));
+        assert_eq!(Some(&literal!(4)), v.pointer("/0"));
+
+        // out of bounds, missing keys and invalid pointers
+        assert_eq!(None, v.pointer("/snot/badger/3"));
+        assert_eq!(None, v.pointer("/snot/badger/01"));
+        assert_eq!(None, v.pointer("/snot/badger/+1"));
+        assert_eq!(None, v.pointer("/snot/badger/-"));
+        assert_eq!(None, v.pointer("/snot/flork"));
+        assert_eq!(None, v.pointer("/a~1b/snot"));
+        assert_eq!(None, v.pointer("snot"));
+    }
+
+    #[test]
+    fn pointer_mut() {
+        let mut v = literal!({"snot": {"badger": [1, 2]}, "~/": {}});
+        if let Some(x) = v.pointer_mut("/snot/badger/1") {
+            *x = literal!("two");
+        }
+        if let Some(x) = v.pointer_mut("/~0~1") {
+            *x = literal!(true);
+        }
+        assert_eq!(literal!({"snot": {"badger": [1, "two"]}, "~/": true}), v);
+        assert!(v.pointer_mut("/snot/badger/2").is_none());
+        assert!(v.pointer_mut("/snot/flork").is_none());
+    }
+
+    #[test]
+    fn get_dotted() {
+        let v = literal!({
+            "snot": {"badger": [1, {"flork": "mork"}, [3]]},
+            "0": 4,
+            "a.b": 5
+        });
+        assert_eq!(Some(&v), v.get_dotted(""));
+        assert_eq!(
+            Some(&literal!([1, {"flork": "mork"}, [3]])),
+            v.get_dotted("snot.badger")
+        );
+        assert_eq!(Some(&literal!("mork")), v.get_dotted("snot.badger.1.flork"));
+        assert_eq!(Some(&literal!(1)), v.get_dotted("snot.badger.0"));
+        assert_eq!(Some(&literal!(3)), v.get_dotted("snot.badger.2.0"));
+        // numeric segments are keys of objects
+        assert_eq!(Some(&literal!(4)), v.get_dotted("0"));
+
+        // out of bounds, missing keys and descending into scalars
+        assert_eq!(None, v.get_dotted("snot.badger.3"));
+        assert_eq!(None, v.get_dotted("snot.badger.01"));
+        assert_eq!(None, v.get_dotted("snot.badger.-1"));
+        assert_eq!(None, v.get_dotted("snot.badger.flork"));
+        assert_eq!(None, v.get_dotted("snot.flork"));
+        assert_eq!(None, v.get_dotted("snot.badger.0.flork"));
+        assert_eq!(None, v.get_dotted("snot."));
+        assert_eq!(None, v.get_dotted("badger"));
+        // keys with dots are not addressable
+        assert_eq!(None, v.get_dotted("a.b"));
+        assert_eq!(Some(&literal!(5)), v.pointer("/a.b"));
+    }
+
+    #[test]
+    fn visit_mut() {
+        let mut v = literal!({
+            "snot": "badger",
+            "nested": [{"flork": "mork"}, ["deep"], 1],
+            "Key": null
+        });
+        let mut visited = 0;
+        v.visit_mut(|v| {
+            visited += 1;
+            if let Value::String(s) = v {
+                *s = s.to_uppercase().into();
+            }
+        });
+        // keys are left alone
+        assert_eq!(
+            literal!({
+                "snot": "BADGER",
+                "nested": [{"flork": "MORK"}, ["DEEP"], 1],
+                "Key": null
+            }),
+            v
+        );
+        assert_eq!(9, visited);
+
+        // replacements are visited instead of the values they replace
+        let mut v = literal!([[1, 2], 3]);
+        v.visit_mut(|v| {
+            if let Some(a) = v.as_array() {
+                if a.len() == 2 && a[0].is_array() {
+                    *v = literal!([4, [5]]);
+                }
+            } else if let Some(i) = v.as_i64() {
+                *v = Value::from(i * 10);
+            }
+        });
+        assert_eq!(literal!([40, [50]]), v);
+    }
+
+    #[test]
+    fn redact() {
+        let mut v = literal!({
+            "user": "snot",
+            "password": "badger",
+            "auth": {
+                "token": {"value": "secret"},
+                "tokens": [{"token": 1}, {"password": null, "user": "flork"}]
+            },
+            "passwords": ["password"]
+        });
+        v.redact(&["password", "token"]);
+        assert_eq!(
+            literal!({
+                "user": "snot",
+                "password": "***",
+                "auth": {
+                    "token": "***",
+                    "tokens": [{"token": "***"}, {"password": "***", "user": "flork"}]
+                },
+                "passwords": ["password"]
+            }),
+            v
+        );
+        let mut v = literal!({"snot": "badger"});
+        v.redact(&[]);
+        assert_eq!(literal!({"snot": "badger"}), v);
+    }
+
+    #[test]
+    fn normalize_numbers() -> Result<()> {
+        #[derive(serde::Serialize)]
+        struct Badger {
+            id: u64,
+            age: u8,
+            offset: i32,
+            scores: Vec<u16>,
+            weight: f64,
+        }
+        let badger = Badger {
+            id: 42,
+            age: 7,
+            offset: -3,
+            scores: vec![1, 2],
+            weight: 1.5,
+        };
+        let value_type = |v: &Value, path| v.get_dotted(path).map(ValueAccess::value_type);
+        let mut from_struct = crate::to_value(&badger)?;
+        let mut json = br#"{"id":42,"age":7,"offset":-3,"scores":[1,2],"weight":1.5}"#.to_vec();
+        let mut from_json = parse_to_value(&mut json)?;
+        // parsed non-negative integers are `I64`s, serialized unsigned ones `U64`s
+        assert_eq!(Some(ValueType::I64), value_type(&from_json, "id"));
+        assert_eq!(Some(ValueType::U64), value_type(&from_struct, "id"));
+
+        from_struct.normalize_numbers();
+        from_json.normalize_numbers();
+        assert_eq!(from_json, from_struct);
+        for v in [&from_json, &from_struct] {
+            for path in ["id", "age", "scores.0", "scores.1"] {
+                assert_eq!(Some(ValueType::U64), value_type(v, path), "{v} {path}");
+            }
+            assert_eq!(Some(ValueType::I64), value_type(v, "offset"), "{v}");
+            assert_eq!(Some(ValueType::F64), value_type(v, "weight"), "{v}");
+        }
+
+        // integers beyond the range of `I64` and other values are kept as they are
+        let mut v = literal!([u64::MAX, i64::MIN, "1", null, true, 0.0]);
+        let expected = v.clone();
+        v.normalize_numbers();
+        assert_eq!(expected, v);
+        assert_eq!(Some(ValueType::U64), value_type(&v, "0"));
+        assert_eq!(Some(ValueType::I64), value_type(&v, "1"));
+        assert_eq!(Some(ValueType::F64), value_type(&v, "5"));
+        Ok(())
+    }
+
+    #[test]
+    fn merge() {
+        // examples from RFC 7386 appendix A
+        let cases = [
+            (
+                literal!({"a": "b"}),
+                literal!({"a": "c"}),
+                literal!({"a": "c"}),
+            ),
+            (
+                literal!({"a": "b"}),
+                literal!({"b": "c"}),
+                literal!({"a": "b", "b": "c"}),
+            ),
+            (literal!({"a": "b"}), literal!({ "a": null }), literal!({})),
+            (
+                literal!({"a": "b", "b": "c"}),
+                literal!({ "a": null }),
+                literal!({"b": "c"}),
+            ),
+            (
+                literal!({"a": ["b"]}),
+                literal!({"a": "c"}),
+                literal!({"a": "c"}),
+            ),
+            (
+                literal!({"a": "c"}),
+                literal!({"a": ["b"]}),
+                literal!({"a": ["b"]}),
+            ),
+            (
+                literal!({"a": {"b": "c"}}),
+                literal!({"a": {"b": "d", "c": null}}),
+                literal!({"a": {"b": "d"}}),
+            ),
+            (
+                literal!({"a": [{"b": "c"}]}),
+                literal!({"a": [1]}),
+                literal!({"a": [1]}),
+            ),
+            (
+                literal!(["a", "b"]),
+                literal!(["c", "d"]),
+                literal!(["c", "d"]),
+            ),
+            (literal!({"a": "b"}), literal!(["c"]), literal!(["c"])),
+            (literal!({"a": "foo"}), literal!(null), literal!(null)),
+            (literal!({"a": "foo"}), literal!("bar"), literal!("bar")),
+            (
+                literal!({ "e": null }),
+                literal!({"a": 1}),
+                literal!({"e": null, "a": 1}),
+            ),
+            (
+                literal!([1, 2]),
+                literal!({"a": "b", "c": null}),
+                literal!({"a": "b"}),
+            ),
+            (
+                literal!({}),
+                literal!({"a": {"bb": {"ccc": null}}}),
+                literal!({"a": {"bb": {}}}),
+            ),
+        ];
+        for (mut target, patch, expected) in cases {
+            target.merge(&patch);
+            assert_eq!(expected, target);
+        }
+    }
+
     #[test]
     fn obj_eq() {
         let o1: Value = literal!({"k": 1, "v":2});
@@ -723,6 +1447,45 @@ mod test {
         );
     }
 
+    #[cfg(feature = "128bit")]
+    #[test]
+    fn nuber_ord_128bit() {
+        use std::cmp::Ordering;
+        assert_eq!(
+            Value::from(1_i128).cmp(&Value::from(1_u64)),
+            Ordering::Equal
+        );
+        assert_eq!(
+            Value::from(1_u128).cmp(&Value::from(1_i64)),
+            Ordering::Equal
+        );
+        assert_eq!(
+            Value::from(1_u128).cmp(&Value::from(1_i128)),
+            Ordering::Equal
+        );
+        assert_eq!(
+            Value::from(1_i128).cmp(&Value::from(1_f64)),
+            Ordering::Equal
+        );
+
+        assert_eq!(
+            Value::from(u128::MAX).cmp(&Value::from(i128::MAX)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Value::from(i128::MIN).cmp(&Value::from(u128::MAX)),
+            Ordering::Less
+        );
+        assert_eq!(
+            Value::from(i128::MIN).cmp(&Value::from(i64::MIN)),
+            Ordering::Less
+        );
+        assert_eq!(
+            Value::from(u128::MAX).cmp(&Value::from(1e100_f64)),
+            Ordering::Less
+        );
+    }
+
     #[test]
     fn nuber_ord_i64() {
         use std::cmp::Ordering;
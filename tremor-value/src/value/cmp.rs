@@ -14,7 +14,7 @@
 
 use super::Value;
 use base64::Engine;
-use simd_json::{prelude::*, BorrowedValue, OwnedValue};
+use simd_json::{prelude::*, BorrowedValue, OwnedValue, StaticNode};
 use tremor_common::base64::BASE64;
 
 #[allow(clippy::cast_sign_loss, clippy::default_trait_access)]
@@ -301,10 +301,134 @@ where
     }
 }
 
+impl<'value> Value<'value> {
+    /// Compares values like `==`, but numbers are equal if they are numerically equal,
+    /// regardless of their representation, e.g. `1`, `1_u64` and `1.0`.
+    ///
+    /// `PartialEq` stays strict, this is meant for comparing e.g. parsed JSON to constructed
+    /// values in tests and assertions.
+    ///
+    /// ```
+    /// # use tremor_value::{literal, Value};
+    /// assert!(literal!({"snot": [1, 2.0]}).eq_loose(&literal!({"snot": [1.0, 2_u64]})));
+    /// assert!(!Value::from(1).eq_loose(&Value::from(1.000_001)));
+    /// ```
+    #[must_use]
+    pub fn eq_loose(&self, other: &Self) -> bool {
+        self.eq_loose_within(other, 0.0)
+    }
+
+    /// Like `eq_loose`, but numbers are equal if they differ by at most `epsilon`
+    ///
+    /// ```
+    /// # use tremor_value::Value;
+    /// assert!(Value::from(1).eq_loose_within(&Value::from(1.000_001), 0.001));
+    /// ```
+    #[must_use]
+    pub fn eq_loose_within(&self, other: &Self, epsilon: f64) -> bool {
+        match (self, other) {
+            (Self::Static(s1), Self::Static(s2)) => {
+                numbers_eq_within(s1, s2, epsilon).unwrap_or_else(|| s1 == s2)
+            }
+            (Self::Array(a1), Self::Array(a2)) => {
+                a1.len() == a2.len()
+                    && a1
+                        .iter()
+                        .zip(a2)
+                        .all(|(v1, v2)| v1.eq_loose_within(v2, epsilon))
+            }
+            (Self::Object(o1), Self::Object(o2)) => {
+                o1.len() == o2.len()
+                    && o1.iter().all(|(key, v1)| {
+                        let key: &str = key;
+                        o2.get(key)
+                            .map_or(false, |v2| v1.eq_loose_within(v2, epsilon))
+                    })
+            }
+            _ => self == other,
+        }
+    }
+}
+
+/// The value of an integer, `None` for any other node
+fn integer(node: &StaticNode) -> Option<i128> {
+    match node {
+        StaticNode::I64(i) => Some(i128::from(*i)),
+        StaticNode::U64(u) => Some(i128::from(*u)),
+        #[cfg(feature = "128bit")]
+        StaticNode::I128(i) => Some(*i),
+        #[cfg(feature = "128bit")]
+        StaticNode::U128(u) => i128::try_from(*u).ok(),
+        _ => None,
+    }
+}
+
+/// Compares two numbers exactly, or within `epsilon` if it is positive, `None` unless both are numbers
+// integers are only converted to floats for the comparison within `epsilon`
+#[allow(
+    clippy::float_cmp,
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation
+)]
+fn numbers_eq_within(n1: &StaticNode, n2: &StaticNode, epsilon: f64) -> Option<bool> {
+    let close = |d: f64| epsilon > 0.0 && d.abs() <= epsilon;
+    Some(match (n1, n2) {
+        (StaticNode::F64(f1), StaticNode::F64(f2)) => f1 == f2 || close(f1 - f2),
+        (StaticNode::F64(f), n) | (n, StaticNode::F64(f)) => {
+            let i = integer(n)?;
+            // a float equals an integer only if it has no fraction and is within the range of integers
+            let exact = f.fract() == 0.0
+                && *f >= i128::MIN as f64
+                && *f < i128::MAX as f64
+                && *f as i128 == i;
+            exact || close(i as f64 - f)
+        }
+        _ => {
+            let (i1, i2) = (integer(n1)?, integer(n2)?);
+            i1 == i2 || close(i1 as f64 - i2 as f64)
+        }
+    })
+}
+
 #[cfg(test)]
 mod test {
     use crate::{literal, Value};
 
+    #[test]
+    fn eq_loose() {
+        let (signed, unsigned, float) = (Value::from(1), Value::from(1_u64), Value::from(1.0));
+        for (v1, v2) in [(&signed, &unsigned), (&signed, &float), (&unsigned, &float)] {
+            assert!(v1.eq_loose(v2), "{v1:?} {v2:?}");
+            assert!(v2.eq_loose(v1), "{v2:?} {v1:?}");
+        }
+        // `==` stays strict
+        assert_ne!(signed, float);
+        assert_ne!(unsigned, float);
+
+        assert!(!signed.eq_loose(&Value::from(1.5)));
+        assert!(!signed.eq_loose(&Value::from(-1)));
+        assert!(!signed.eq_loose(&Value::from("1")));
+        assert!(!Value::from(f64::NAN).eq_loose(&Value::from(f64::NAN)));
+        // no precision is lost comparing large integers
+        let (big, big_float) = (2_u64.pow(53), 9_007_199_254_740_992.0);
+        assert!(!Value::from(big + 1).eq_loose(&Value::from(big_float)));
+        assert!(Value::from(big).eq_loose(&Value::from(big_float)));
+        assert!(!Value::from(u64::MAX).eq_loose(&Value::from(u64::MAX - 1)));
+
+        // nested values
+        assert!(literal!({"snot": [1, {"badger": 2.0}], "flork": null})
+            .eq_loose(&literal!({"flork": null, "snot": [1.0, {"badger": 2_u64}]})));
+        assert!(!literal!({"snot": [1, 2]}).eq_loose(&literal!({"snot": [1, 2, 3]})));
+        assert!(!literal!({"snot": 1}).eq_loose(&literal!({"badger": 1})));
+        assert!(!literal!([1]).eq_loose(&literal!({"snot": 1})));
+
+        // within an epsilon
+        assert!(Value::from(0.1 + 0.2).eq_loose_within(&Value::from(0.3), f64::EPSILON));
+        assert!(!Value::from(0.1 + 0.2).eq_loose(&Value::from(0.3)));
+        assert!(signed.eq_loose_within(&Value::from(1.05), 0.1));
+        assert!(!signed.eq_loose_within(&Value::from(2), 0.1));
+    }
+
     #[test]
     fn diff_types() {
         assert_ne!(Value::from(1), Value::from("snot"));
@@ -0,0 +1,241 @@
+// Copyright 2022, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::Value;
+use crate::literal;
+
+/// The structural difference between `old` and `new` as a
+/// [JSON Patch](https://www.rfc-editor.org/rfc/rfc6902), an array of `add`, `remove` and
+/// `replace` operations that transform `old` into `new` when applied in order.
+///
+/// Objects are compared key by key, so only changed keys show up. Arrays are compared
+/// element by element after skipping their common start and end, so inserting or removing
+/// elements only adds or removes those. Keys of objects are visited in sorted order, so
+/// the patch is deterministic.
+///
+/// ```
+/// # use tremor_value::{diff, literal};
+/// let old = literal!({"snot": "badger", "flork": [1, 2]});
+/// let new = literal!({"snot": "badger", "flork": [1, 3, 2]});
+/// assert_eq!(
+///     literal!([{"op": "add", "path": "/flork/1", "value": 3}]),
+///     diff(&old, &new)
+/// );
+/// ```
+#[must_use]
+pub fn diff(old: &Value, new: &Value) -> Value<'static> {
+    let mut ops = Vec::new();
+    diff_into(old, new, &mut String::new(), &mut ops);
+    Value::Array(ops)
+}
+
+fn diff_into(old: &Value, new: &Value, path: &mut String, ops: &mut Vec<Value<'static>>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut removed: Vec<_> = old.keys().filter(|k| !new.contains_key(*k)).collect();
+            removed.sort();
+            for key in removed {
+                ops.push(op("remove", &child(path, key), None));
+            }
+            let mut keys: Vec<_> = new.keys().collect();
+            keys.sort();
+            for key in keys {
+                let len = path.len();
+                push_token(path, key);
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_into(old, new, path, ops),
+                    (None, Some(new)) => ops.push(op("add", path, Some(new))),
+                    _ => (),
+                }
+                path.truncate(len);
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            let min_len = old.len().min(new.len());
+            let prefix = old.iter().zip(new).take_while(|(o, n)| o == n).count();
+            let suffix = old
+                .iter()
+                .rev()
+                .zip(new.iter().rev())
+                .take(min_len - prefix)
+                .take_while(|(o, n)| o == n)
+                .count();
+            let old_changed = &old[prefix..old.len() - suffix];
+            let new_changed = &new[prefix..new.len() - suffix];
+            for (i, (old, new)) in old_changed.iter().zip(new_changed).enumerate() {
+                let len = path.len();
+                push_token(path, &(prefix + i).to_string());
+                diff_into(old, new, path, ops);
+                path.truncate(len);
+            }
+            for (i, new) in new_changed.iter().enumerate().skip(old_changed.len()) {
+                ops.push(op(
+                    "add",
+                    &child(path, &(prefix + i).to_string()),
+                    Some(new),
+                ));
+            }
+            // removed from the back, so the indices of the remaining elements stay valid
+            for i in (new_changed.len()..old_changed.len()).rev() {
+                ops.push(op("remove", &child(path, &(prefix + i).to_string()), None));
+            }
+        }
+        _ => ops.push(op("replace", path, Some(new))),
+    }
+}
+
+fn op(op: &'static str, path: &str, value: Option<&Value>) -> Value<'static> {
+    let mut res = literal!({"op": op, "path": path.to_string()});
+    if let (Some(value), Value::Object(res)) = (value, &mut res) {
+        res.insert("value".into(), value.clone_static());
+    }
+    res
+}
+
+/// Append the escaped reference token for `key` to the JSON Pointer `path`
+fn push_token(path: &mut String, key: &str) {
+    path.push('/');
+    path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+}
+
+fn child(path: &str, key: &str) -> String {
+    let mut res = path.to_string();
+    push_token(&mut res, key);
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    /// Apply the JSON Patch `patch` to `value`
+    fn apply(value: &mut Value<'static>, patch: &Value) -> Option<()> {
+        for op in patch.as_array()? {
+            let pointer = op.get_str("path")?;
+            let (parent, key) = pointer.rsplit_once('/').unwrap_or(("", ""));
+            let key = key.replace("~1", "/").replace("~0", "~");
+            match op.get_str("op")? {
+                "replace" if pointer.is_empty() => *value = op.get("value")?.clone_static(),
+                "replace" => *value.pointer_mut(pointer)? = op.get("value")?.clone_static(),
+                "add" => match value.pointer_mut(parent)? {
+                    Value::Object(o) => {
+                        o.insert(key.into(), op.get("value")?.clone_static());
+                    }
+                    Value::Array(a) => {
+                        a.insert(key.parse().ok()?, op.get("value")?.clone_static());
+                    }
+                    _ => return None,
+                },
+                "remove" => match value.pointer_mut(parent)? {
+                    Value::Object(o) => {
+                        o.remove(key.as_str())?;
+                    }
+                    Value::Array(a) => {
+                        a.remove(key.parse().ok()?);
+                    }
+                    _ => return None,
+                },
+                _ => return None,
+            }
+        }
+        Some(())
+    }
+
+    fn assert_reconstructs(old: &Value<'static>, new: &Value<'static>) -> Value<'static> {
+        let patch = diff(old, new);
+        let mut patched = old.clone();
+        assert_eq!(Some(()), apply(&mut patched, &patch), "{patch}");
+        assert_eq!(new, &patched, "{patch}");
+        patch
+    }
+
+    #[test]
+    fn objects() {
+        let old = literal!({
+            "snot": "badger",
+            "unchanged": {"deep": [1, 2, 3]},
+            "nested": {"flork": 1, "gone": true, "a/b~c": null},
+            "removed": [1, 2],
+            "type": [1]
+        });
+        let new = literal!({
+            "snot": "badger",
+            "unchanged": {"deep": [1, 2, 3]},
+            "nested": {"flork": 2, "new": {"x": null}, "a/b~c": false},
+            "added": "yes",
+            "type": {"0": 1}
+        });
+        let patch = assert_reconstructs(&old, &new);
+        assert_eq!(
+            literal!([
+                {"op": "remove", "path": "/removed"},
+                {"op": "add", "path": "/added", "value": "yes"},
+                {"op": "remove", "path": "/nested/gone"},
+                {"op": "replace", "path": "/nested/a~1b~0c", "value": false},
+                {"op": "replace", "path": "/nested/flork", "value": 2},
+                {"op": "add", "path": "/nested/new", "value": {"x": null}},
+                {"op": "replace", "path": "/type", "value": {"0": 1}}
+            ]),
+            patch
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        // insertions and removals only touch the affected elements
+        let patch = assert_reconstructs(&literal!([1, 2, 3, 4]), &literal!([1, 5, 2, 3, 4]));
+        assert_eq!(literal!([{"op": "add", "path": "/1", "value": 5}]), patch);
+        let patch = assert_reconstructs(&literal!([1, 2, 3, 4]), &literal!([1, 4]));
+        assert_eq!(
+            literal!([{"op": "remove", "path": "/2"}, {"op": "remove", "path": "/1"}]),
+            patch
+        );
+        // changed elements are diffed themselves
+        let patch = assert_reconstructs(
+            &literal!([{"snot": 1}, "badger"]),
+            &literal!([{"snot": 2}, "badger"]),
+        );
+        assert_eq!(
+            literal!([{"op": "replace", "path": "/0/snot", "value": 2}]),
+            patch
+        );
+
+        assert_reconstructs(&literal!([1, 1, 1]), &literal!([1, 1]));
+        assert_reconstructs(&literal!([]), &literal!([1, [2], {"3": 4}]));
+        assert_reconstructs(&literal!([1, 2, 3]), &literal!([3, 2, 1, 0]));
+        assert_reconstructs(&literal!([[1, 2], [3]]), &literal!([[2], [3, 4], []]));
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(
+            Value::array(),
+            diff(&literal!({"snot": [1]}), &literal!({"snot": [1]}))
+        );
+        let patch = assert_reconstructs(&literal!("snot"), &literal!(["badger"]));
+        assert_eq!(
+            literal!([{"op": "replace", "path": "", "value": ["badger"]}]),
+            patch
+        );
+        assert_reconstructs(&Value::null(), &literal!(1.5));
+        assert_reconstructs(
+            &Value::Bytes(vec![1_u8].into()),
+            &Value::Bytes(vec![2_u8].into()),
+        );
+    }
+}
@@ -156,6 +156,85 @@ trait Generator: BaseGenerator {
     }
 }
 
+impl<'value> Value<'value> {
+    /// Estimates the length of the JSON encoding of this value without encoding it or allocating.
+    ///
+    /// The estimate is exact, except for floats, which are off by at most a few bytes each.
+    #[must_use]
+    pub fn estimated_json_len(&self) -> usize {
+        match self {
+            Value::Static(StaticNode::Null | StaticNode::Bool(true)) => 4,
+            Value::Static(StaticNode::Bool(false)) => 5,
+            Value::Static(StaticNode::I64(n)) => {
+                int_len(u128::from(n.unsigned_abs())) + usize::from(*n < 0)
+            }
+            #[cfg(feature = "128bit")]
+            Value::Static(StaticNode::I128(n)) => int_len(n.unsigned_abs()) + usize::from(*n < 0),
+            Value::Static(StaticNode::U64(n)) => int_len(u128::from(*n)),
+            #[cfg(feature = "128bit")]
+            Value::Static(StaticNode::U128(n)) => int_len(*n),
+            Value::Static(StaticNode::F64(f)) => float_len(*f),
+            Value::String(s) => string_len(s),
+            Value::Array(a) => {
+                2 + a.len().saturating_sub(1)
+                    + a.iter().map(Value::estimated_json_len).sum::<usize>()
+            }
+            Value::Object(o) => {
+                2 + o.len().saturating_sub(1)
+                    + o.iter()
+                        .map(|(k, v)| string_len(k) + 1 + v.estimated_json_len())
+                        .sum::<usize>()
+            }
+            // base64 encoded with padding
+            Value::Bytes(b) => 2 + (b.len() + 2) / 3 * 4,
+        }
+    }
+}
+
+/// number of decimal digits of `n`
+fn int_len(mut n: u128) -> usize {
+    let mut len = 1;
+    while n >= 10 {
+        n /= 10;
+        len += 1;
+    }
+    len
+}
+
+/// length of a float, printed in decimal notation for moderate exponents and in scientific notation otherwise
+fn float_len(f: f64) -> usize {
+    /// counts the bytes written to it
+    struct Counter(usize);
+    impl std::fmt::Write for Counter {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
+    }
+    let mut counter = Counter(0);
+    let abs = f.abs();
+    let res = if abs == 0.0 || (1e-5..1e16).contains(&abs) {
+        std::fmt::Write::write_fmt(&mut counter, format_args!("{f}"))
+    } else {
+        std::fmt::Write::write_fmt(&mut counter, format_args!("{f:e}"))
+    };
+    // integral floats get a `.0` suffix
+    let suffix = if f.fract() == 0.0 && abs < 1e16 { 2 } else { 0 };
+    res.map_or(0, |()| counter.0 + suffix)
+}
+
+/// length of a quoted string including escapes
+fn string_len(s: &str) -> usize {
+    2 + s
+        .bytes()
+        .map(|b| match b {
+            b'"' | b'\\' | b'\n' | b'\r' | b'\t' | 0x08 | 0x0c => 2,
+            0..=0x1f => 6,
+            _ => 1,
+        })
+        .sum::<usize>()
+}
+
 trait FastGenerator: BaseGenerator {
     type T: Write;
 
@@ -264,6 +343,7 @@ where
 #[cfg(test)]
 mod test {
     use super::Value;
+    use crate::literal;
     use simd_json::prelude::*;
     use simd_json::StaticNode;
 
@@ -325,6 +405,55 @@ mod test {
         );
     }
 
+    #[test]
+    fn estimated_json_len() {
+        let exact = [
+            literal!(null),
+            literal!(true),
+            literal!(false),
+            literal!(0),
+            literal!(-1),
+            literal!(i64::MIN),
+            literal!(i64::MAX),
+            literal!(u64::MAX),
+            literal!(""),
+            literal!("snot \"badger\" \\ \n\t\u{1} \u{7f} \u{e4}\u{1f980}"),
+            literal!([]),
+            literal!([1, "two", [3, [], {}], null]),
+            literal!({}),
+            literal!({"snot": "badger", "k\"ey": {"nested": [1, 2, {"deeper": true}]}}),
+            Value::Bytes(Vec::<u8>::new().into()),
+            Value::Bytes(vec![1_u8].into()),
+            Value::Bytes(vec![1_u8, 2].into()),
+            Value::Bytes(vec![1_u8, 2, 3, 4, 5, 6, 7].into()),
+        ];
+        for v in &exact {
+            assert_eq!(v.encode().len(), v.estimated_json_len(), "{v}");
+        }
+        let floats = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.5,
+            0.1,
+            1.0 / 3.0,
+            123_456.789,
+            1e15,
+            1e16,
+            1.234e-7,
+            -2.5e300,
+            f64::MIN_POSITIVE,
+            f64::MAX,
+        ];
+        for f in floats {
+            let v = literal!({ "float": f, "in": [f, f] });
+            let len = v.encode().len();
+            let estimate = v.estimated_json_len();
+            // at most a few bytes per float
+            assert!(len.abs_diff(estimate) <= 9, "{v}: {len} != {estimate}");
+        }
+    }
+
     fn assert_str(from: &str, to: &str) {
         assert_eq!(Value::String(from.into()).encode(), to);
     }